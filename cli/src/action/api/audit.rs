@@ -0,0 +1,118 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A local record of the changes made to a node's roles.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::error::CliError;
+
+/// A file to which a JSON line is appended for each successful role change.
+pub struct AuditLog {
+    path: PathBuf,
+    identity: String,
+}
+
+impl AuditLog {
+    /// Creates an audit log which records the changes as made by `identity`, the public key of the
+    /// signer used to authorize the requests.
+    pub fn new(path: PathBuf, identity: String) -> Self {
+        Self { path, identity }
+    }
+
+    /// Appends an entry for an operation that changed the role with the given ID.
+    pub fn record(&self, operation: &str, role_id: &str) -> Result<(), CliError> {
+        let entry = AuditEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+            operation,
+            role_id,
+            identity: &self.identity,
+        };
+        let line = serde_json::to_string(&entry).map_err(|err| {
+            CliError::ActionError(format!("Cannot format audit log entry into json: {}", err))
+        })?;
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|err| {
+                CliError::EnvironmentError(format!(
+                    "Unable to record {} of role {} in audit log {}: {}",
+                    operation,
+                    role_id,
+                    self.path.display(),
+                    err
+                ))
+            })
+    }
+}
+
+#[derive(Serialize)]
+struct AuditEntry<'a> {
+    /// Seconds since the Unix epoch
+    timestamp: u64,
+    operation: &'a str,
+    role_id: &'a str,
+    identity: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use tempfile::Builder;
+
+    /// Verify that each recorded operation is appended to the log as a JSON line.
+    #[test]
+    fn test_audit_log_record() {
+        let temp_dir = Builder::new()
+            .prefix("test_audit_log_record")
+            .tempdir()
+            .expect("Unable to create temp dir");
+        let path = temp_dir.path().join("audit.log");
+
+        let audit_log = AuditLog::new(path.clone(), "0123abcd".into());
+        audit_log
+            .record("create_role", "role-a")
+            .expect("Unable to record entry");
+        audit_log
+            .record("delete_role", "role-a")
+            .expect("Unable to record entry");
+
+        let contents = fs::read_to_string(&path).expect("Unable to read audit log");
+        let entries = contents
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("Invalid entry"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["operation"], "create_role");
+        assert_eq!(entries[0]["role_id"], "role-a");
+        assert_eq!(entries[0]["identity"], "0123abcd");
+        assert!(entries[0]["timestamp"].is_u64());
+        assert_eq!(entries[1]["operation"], "delete_role");
+    }
+}
@@ -14,26 +14,43 @@
 
 //! Provides convenient functions for sending REST API requests to a splinter node.
 
+#[cfg(feature = "authorization-handler-rbac")]
+mod audit;
+mod protocol;
 #[cfg(feature = "authorization-handler-rbac")]
 mod rbac;
 
+#[cfg(feature = "authorization-handler-rbac")]
+use std::collections::{BTreeSet, HashMap};
+
 use reqwest::blocking::Client;
 use serde::Deserialize;
 
 use super::CliError;
 
+use protocol::{ProtocolVersion, PROTOCOL_VERSION_HEADER};
+
+static PERMISSIONS_PROTOCOL_VERSION: ProtocolVersion =
+    ProtocolVersion::new(1, 2, "/authorization/permissions");
+
+#[cfg(feature = "authorization-handler-rbac")]
+pub use audit::AuditLog;
 #[cfg(feature = "authorization-handler-rbac")]
 pub use rbac::{
     assignments::{
         Assignment, AssignmentBuilder, AssignmentUpdate, AssignmentUpdateBuilder, Identity,
     },
-    roles::{Role, RoleBuilder, RoleUpdate, RoleUpdateBuilder},
+    roles::{Role, RoleBuilder, RoleDiff, RoleUpdate, RoleUpdateBuilder},
 };
 
 #[derive(Default)]
 pub struct SplinterRestClientBuilder {
     pub url: Option<String>,
     pub auth: Option<String>,
+    pub dry_run: bool,
+    pub http_client: Option<Client>,
+    #[cfg(feature = "authorization-handler-rbac")]
+    pub audit_log: Option<AuditLog>,
 }
 
 impl SplinterRestClientBuilder {
@@ -51,6 +68,26 @@ impl SplinterRestClientBuilder {
         self
     }
 
+    /// Sets the HTTP client used to send requests, so that its connection pool can be shared by
+    /// several REST clients. A new HTTP client is created if one is not provided.
+    pub fn with_http_client(mut self, http_client: Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Sets the audit log in which each successful role change is recorded.
+    #[cfg(feature = "authorization-handler-rbac")]
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Sets whether role changes are printed instead of being sent to the node.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
     pub fn build(self) -> Result<SplinterRestClient, CliError> {
         Ok(SplinterRestClient {
             url: self.url.ok_or_else(|| {
@@ -61,6 +98,12 @@ impl SplinterRestClientBuilder {
                     "Failed to build client, jwt authorization not provided".to_string(),
                 )
             })?,
+            dry_run: self.dry_run,
+            http_client: self.http_client.unwrap_or_else(Client::new),
+            #[cfg(feature = "authorization-handler-rbac")]
+            audit_log: self.audit_log,
+            #[cfg(feature = "authorization-handler-rbac")]
+            retry_policy: rbac::RetryPolicy::default(),
         })
     }
 }
@@ -69,12 +112,22 @@ impl SplinterRestClientBuilder {
 pub struct SplinterRestClient {
     pub url: String,
     pub auth: String,
+    /// When true, the requests that create, update or delete roles are printed instead of sent
+    pub dry_run: bool,
+    /// The HTTP client used to send requests; cloning it shares its connection pool
+    pub http_client: Client,
+    /// Where successful role changes are recorded, if anywhere
+    #[cfg(feature = "authorization-handler-rbac")]
+    pub audit_log: Option<AuditLog>,
+    /// How role requests are retried after a transient failure
+    #[cfg(feature = "authorization-handler-rbac")]
+    pub retry_policy: rbac::RetryPolicy,
 }
 
 impl SplinterRestClient {
     /// Gets the Splinter node's status.
     pub fn get_node_status(&self) -> Result<NodeStatus, CliError> {
-        Client::new()
+        self.http_client
             .get(&format!("{}/status", self.url))
             .header("Authorization", &self.auth)
             .send()
@@ -110,7 +163,7 @@ impl SplinterRestClient {
     /// Checks whether or not maintenance mode is enabled for the Splinter node.
     #[cfg(feature = "authorization-handler-maintenance")]
     pub fn is_maintenance_mode_enabled(&self) -> Result<bool, CliError> {
-        Client::new()
+        self.http_client
             .get(&format!("{}/authorization/maintenance", self.url))
             .header("Authorization", &self.auth)
             .send()
@@ -156,7 +209,7 @@ impl SplinterRestClient {
     /// Turns maintenance mode on or off for the Splinter node.
     #[cfg(feature = "authorization-handler-maintenance")]
     pub fn set_maintenance_mode(&self, enabled: bool) -> Result<(), CliError> {
-        Client::new()
+        self.http_client
             .post(&format!("{}/authorization/maintenance", self.url))
             .query(&[("enabled", enabled)])
             .header("Authorization", &self.auth)
@@ -188,10 +241,21 @@ impl SplinterRestClient {
             })
     }
 
-    /// Lists all REST API permissions for a Splinter node.
-    pub fn list_permissions(&self) -> Result<Vec<Permission>, CliError> {
-        Client::new()
-            .get(&format!("{}/authorization/permissions", self.url))
+    /// Lists all REST API permissions for a Splinter node. If `search` is provided, only the
+    /// permissions whose ID, display name or description contains it, ignoring case, are listed.
+    pub fn list_permissions(&self, search: Option<&str>) -> Result<Vec<Permission>, CliError> {
+        let mut request = self
+            .http_client
+            .get(&format!("{}/authorization/permissions", self.url));
+        if let Some(search) = search {
+            request = request.query(&[("search", search)]);
+        }
+
+        request
+            .header(
+                PROTOCOL_VERSION_HEADER,
+                PERMISSIONS_PROTOCOL_VERSION.negotiate(&self.url, &self.auth)?,
+            )
             .header("Authorization", &self.auth)
             .send()
             .map_err(|err| CliError::ActionError(format!("Failed to get permissions: {}", err)))
@@ -200,6 +264,12 @@ impl SplinterRestClient {
                 if status.is_success() {
                     res.json::<PermissionsResponse>()
                         .map(|mut response| {
+                            // Nodes that do not support searching ignore the parameter and return
+                            // every permission, so the search is also applied here
+                            if let Some(search) = search {
+                                let search = search.to_lowercase();
+                                response.data.retain(|perm| perm.matches(&search));
+                            }
                             response.data.sort_by(|a, b| {
                                 // Unwrapping because comparing strings always returns `Some(_)`
                                 a.permission_id.partial_cmp(&b.permission_id).unwrap()
@@ -231,6 +301,16 @@ impl SplinterRestClient {
             })
     }
 
+    /// Returns the IDs of all REST API permissions for a Splinter node.
+    #[cfg(feature = "authorization-handler-rbac")]
+    pub fn permission_ids(&self) -> Result<BTreeSet<String>, CliError> {
+        Ok(self
+            .list_permissions(None)?
+            .into_iter()
+            .map(|permission| permission.permission_id)
+            .collect())
+    }
+
     #[cfg(feature = "authorization-handler-rbac")]
     pub fn list_roles(&self) -> Result<rbac::PagingIter<Role>, CliError> {
         Ok(rbac::PagingIter::new(
@@ -240,24 +320,83 @@ impl SplinterRestClient {
         ))
     }
 
+    /// Lists the IDs of the roles which grant each permission, keyed by permission ID.
+    #[cfg(feature = "authorization-handler-rbac")]
+    pub fn list_roles_by_permission(&self) -> Result<HashMap<String, Vec<String>>, CliError> {
+        rbac::roles::permission_index(self.list_roles()?)
+    }
+
     #[cfg(feature = "authorization-handler-rbac")]
     pub fn get_role(&self, role_id: &str) -> Result<Option<Role>, CliError> {
-        rbac::roles::get_role(&self.url, &self.auth, role_id)
+        rbac::roles::get_role(&self.url, &self.auth, &self.retry_policy, role_id)
     }
 
     #[cfg(feature = "authorization-handler-rbac")]
     pub fn create_role(&self, role: Role) -> Result<(), CliError> {
-        rbac::roles::create_role(&self.url, &self.auth, role)
+        let role_id = role.role_id.clone();
+        rbac::roles::create_role(
+            &self.url,
+            &self.auth,
+            &self.retry_policy,
+            role,
+            self.dry_run,
+        )?;
+        self.audit("create_role", &role_id)
+    }
+
+    /// Creates the role `new_role_id` with the permissions of the role `source_role_id`.
+    #[cfg(feature = "authorization-handler-rbac")]
+    pub fn clone_role(
+        &self,
+        source_role_id: &str,
+        new_role_id: &str,
+        new_display_name: Option<&str>,
+    ) -> Result<(), CliError> {
+        rbac::roles::clone_role(
+            &self.url,
+            &self.auth,
+            &self.retry_policy,
+            source_role_id,
+            new_role_id,
+            new_display_name,
+            self.dry_run,
+        )?;
+        self.audit("clone_role", new_role_id)
     }
 
     #[cfg(feature = "authorization-handler-rbac")]
     pub fn update_role(&self, role_update: RoleUpdate) -> Result<(), CliError> {
-        rbac::roles::update_role(&self.url, &self.auth, role_update)
+        let role_id = role_update.role_id().to_string();
+        rbac::roles::update_role(
+            &self.url,
+            &self.auth,
+            &self.retry_policy,
+            role_update,
+            self.dry_run,
+        )?;
+        self.audit("update_role", &role_id)
     }
 
     #[cfg(feature = "authorization-handler-rbac")]
     pub fn delete_role(&self, role_id: &str) -> Result<(), CliError> {
-        rbac::roles::delete_role(&self.url, &self.auth, role_id)
+        rbac::roles::delete_role(
+            &self.url,
+            &self.auth,
+            &self.retry_policy,
+            role_id,
+            self.dry_run,
+        )?;
+        self.audit("delete_role", role_id)
+    }
+
+    /// Records a successful role change in the audit log, if there is one. Dry runs do not change
+    /// anything, so they are not recorded.
+    #[cfg(feature = "authorization-handler-rbac")]
+    fn audit(&self, operation: &str, role_id: &str) -> Result<(), CliError> {
+        match &self.audit_log {
+            Some(audit_log) if !self.dry_run => audit_log.record(operation, role_id),
+            _ => Ok(()),
+        }
     }
 
     #[cfg(feature = "authorization-handler-rbac")]
@@ -315,3 +454,17 @@ pub struct Permission {
     pub permission_display_name: String,
     pub permission_description: String,
 }
+
+impl Permission {
+    /// Returns whether the permission's ID, display name or description contains the given
+    /// lowercase search text, ignoring case.
+    fn matches(&self, search: &str) -> bool {
+        [
+            &self.permission_id,
+            &self.permission_display_name,
+            &self.permission_description,
+        ]
+        .iter()
+        .any(|field| field.to_lowercase().contains(search))
+    }
+}
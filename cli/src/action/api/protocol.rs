@@ -0,0 +1,146 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Negotiation of the `SplinterProtocolVersion` header sent with REST API requests.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use reqwest::{blocking::Client, StatusCode};
+use serde::Deserialize;
+
+use crate::error::CliError;
+
+/// The header used to request a protocol version from the REST API.
+pub(super) const PROTOCOL_VERSION_HEADER: &str = "SplinterProtocolVersion";
+
+/// The protocol version used for a group of REST API resources.
+///
+/// When the client supports a range of versions, the first call to `negotiate` sends the
+/// preferred version to the probe path. If the server rejects it, the rejection reports the
+/// newest version the server supports, which is used instead if it is within the client's range.
+/// The selected version is cached for all subsequent requests.
+pub(super) struct ProtocolVersion {
+    min: u32,
+    preferred: u32,
+    probe_path: &'static str,
+    // Zero until a version has been negotiated
+    negotiated: AtomicU32,
+}
+
+impl ProtocolVersion {
+    /// Creates a protocol version for resources supporting versions `min` through `preferred`.
+    ///
+    /// `probe_path` must be a resource which accepts `GET` requests and checks the protocol
+    /// version.
+    pub const fn new(min: u32, preferred: u32, probe_path: &'static str) -> Self {
+        Self {
+            min,
+            preferred,
+            probe_path,
+            negotiated: AtomicU32::new(0),
+        }
+    }
+
+    /// Returns the protocol version to send to the node at `base_url`, negotiating it on the
+    /// first call.
+    pub fn negotiate(&self, base_url: &str, auth: &str) -> Result<u32, CliError> {
+        match self.negotiated.load(Ordering::SeqCst) {
+            0 => {
+                let version = if self.min == self.preferred {
+                    self.preferred
+                } else {
+                    self.probe(base_url, auth)?
+                };
+                self.negotiated.store(version, Ordering::SeqCst);
+                Ok(version)
+            }
+            version => Ok(version),
+        }
+    }
+
+    fn probe(&self, base_url: &str, auth: &str) -> Result<u32, CliError> {
+        let res = Client::new()
+            .get(&format!("{}{}", base_url, self.probe_path))
+            .header(PROTOCOL_VERSION_HEADER, self.preferred)
+            .header("Authorization", auth)
+            .send()
+            .map_err(|err| {
+                CliError::ActionError(format!("Failed to negotiate protocol version: {}", err))
+            })?;
+
+        if res.status() != StatusCode::BAD_REQUEST {
+            return Ok(self.preferred);
+        }
+
+        // Only protocol version rejections report the server's version; any other bad request
+        // is left for the actual request to report.
+        match res.json::<VersionRejection>() {
+            Ok(VersionRejection {
+                splinter_protocol: Some(supported),
+            }) => self.select(supported),
+            _ => Ok(self.preferred),
+        }
+    }
+
+    fn select(&self, supported: u32) -> Result<u32, CliError> {
+        if supported >= self.preferred {
+            Ok(self.preferred)
+        } else if supported >= self.min {
+            Ok(supported)
+        } else {
+            Err(CliError::ActionError(format!(
+                "Node supports protocol version {}, but version {} or greater is required",
+                supported, self.min
+            )))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct VersionRejection {
+    splinter_protocol: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that the newest version supported by both the client and the server is selected,
+    /// and that an error is returned if the server only supports older versions.
+    #[test]
+    fn test_select() {
+        let version = ProtocolVersion::new(2, 4, "/");
+
+        assert_eq!(version.select(5).expect("Failed to select version"), 4);
+        assert_eq!(version.select(4).expect("Failed to select version"), 4);
+        assert_eq!(version.select(3).expect("Failed to select version"), 3);
+        assert_eq!(version.select(2).expect("Failed to select version"), 2);
+        assert!(version.select(1).is_err());
+    }
+
+    /// Verify that a single supported version is used without contacting the node, and that the
+    /// result is cached.
+    #[test]
+    fn test_negotiate_single_version() {
+        let version = ProtocolVersion::new(1, 1, "/");
+
+        assert_eq!(
+            version
+                .negotiate("http://localhost:0", "")
+                .expect("Failed to negotiate version"),
+            1
+        );
+        assert_eq!(version.negotiated.load(Ordering::SeqCst), 1);
+    }
+}
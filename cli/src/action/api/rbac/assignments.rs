@@ -18,7 +18,7 @@ use serde::{Deserialize, Serialize};
 use crate::action::api::ServerError;
 use crate::error::CliError;
 
-use super::{Pageable, RBAC_PROTOCOL_VERSION};
+use super::{Pageable, PROTOCOL_VERSION_HEADER, RBAC_PROTOCOL_VERSION};
 
 #[derive(Clone, Deserialize, Serialize)]
 #[serde(tag = "identity_type", content = "identity")]
@@ -155,7 +155,10 @@ pub fn create_assignment(
 ) -> Result<(), CliError> {
     Client::new()
         .post(&format!("{}/authorization/assignments", base_url))
-        .header("SplinterProtocolVersion", RBAC_PROTOCOL_VERSION)
+        .header(
+            PROTOCOL_VERSION_HEADER,
+            RBAC_PROTOCOL_VERSION.negotiate(base_url, auth)?,
+        )
         .header("Authorization", auth)
         .json(&assignment)
         .send()
@@ -200,7 +203,10 @@ pub fn get_assignment(
             "{}/authorization/assignments/{}/{}",
             base_url, id_type, id_value
         ))
-        .header("SplinterProtocolVersion", RBAC_PROTOCOL_VERSION)
+        .header(
+            PROTOCOL_VERSION_HEADER,
+            RBAC_PROTOCOL_VERSION.negotiate(base_url, auth)?,
+        )
         .header("Authorization", auth)
         .send()
         .map_err(|err| {
@@ -252,7 +258,10 @@ pub fn update_assignment(
 
     Client::new()
         .patch(&format!("{}/authorization/assignments/{}/{}", base_url, id_type, id_value))
-        .header("SplinterProtocolVersion", RBAC_PROTOCOL_VERSION)
+        .header(
+            PROTOCOL_VERSION_HEADER,
+            RBAC_PROTOCOL_VERSION.negotiate(base_url, auth)?,
+        )
         .header("Authorization", auth)
         .json(&assignment_update)
         .send()
@@ -298,7 +307,10 @@ pub fn delete_assignment(base_url: &str, auth: &str, identity: &Identity) -> Res
             "{}/authorization/assignments/{}/{}",
             base_url, id_type, id_value
         ))
-        .header("SplinterProtocolVersion", RBAC_PROTOCOL_VERSION)
+        .header(
+            PROTOCOL_VERSION_HEADER,
+            RBAC_PROTOCOL_VERSION.negotiate(base_url, auth)?,
+        )
         .header("Authorization", auth)
         .send()
         .map_err(|err| CliError::ActionError(format!("Failed to delete assignment: {}", err)))
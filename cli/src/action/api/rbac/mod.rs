@@ -16,13 +16,17 @@ pub mod assignments;
 pub mod roles;
 
 use std::collections::VecDeque;
+use std::thread;
+use std::time::Duration;
 
-use reqwest::blocking::Client;
-use serde::{de::DeserializeOwned, Deserialize};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::CliError;
 
-const RBAC_PROTOCOL_VERSION: u32 = 1;
+use super::protocol::{ProtocolVersion, PROTOCOL_VERSION_HEADER};
+
+static RBAC_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::new(1, 1, "/authorization/roles");
 
 #[derive(Deserialize)]
 struct Page<T: DeserializeOwned> {
@@ -137,7 +141,10 @@ where
 {
     Client::new()
         .get(&format!("{}{}", base_url, link))
-        .header("SplinterProtocolVersion", RBAC_PROTOCOL_VERSION)
+        .header(
+            PROTOCOL_VERSION_HEADER,
+            RBAC_PROTOCOL_VERSION.negotiate(base_url, auth)?,
+        )
         .header("Authorization", auth)
         .send()
         .map_err(|err| CliError::ActionError(format!("Failed to fetch {} page: {}", label, err)))
@@ -168,3 +175,232 @@ where
             }
         })
 }
+
+/// How requests to the REST API are retried after a transient failure.
+///
+/// Connection errors, timeouts and 5xx responses are retried, waiting twice as long before each
+/// retry as before the previous one. 4xx responses are never retried.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    attempts: u32,
+    base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Constructs a new `RetryPolicy`.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempts` - The total number of times a request is sent, including the first; at least 1
+    /// * `base_delay` - The time waited before the first retry
+    pub fn new(attempts: u32, base_delay: Duration) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            base_delay,
+        }
+    }
+
+    /// Sends the request constructed by `request`, constructing it again for each retry.
+    ///
+    /// Returns the last response or error once the request succeeds, fails with a
+    /// non-transient error, or runs out of attempts.
+    fn send<F>(&self, request: F) -> Result<Response, reqwest::Error>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut delay = self.base_delay;
+        let mut attempt = 1;
+        loop {
+            let result = request().send();
+            let transient = match &result {
+                Ok(res) => res.status().is_server_error(),
+                Err(err) => err.is_connect() || err.is_timeout(),
+            };
+
+            if !transient || attempt >= self.attempts {
+                return result;
+            }
+
+            match &result {
+                Ok(res) => debug!(
+                    "Request failed with status code '{}', retrying in {:?}",
+                    res.status(),
+                    delay
+                ),
+                Err(err) => debug!("Request failed: {}, retrying in {:?}", err, delay),
+            }
+            thread::sleep(delay);
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3, Duration::from_millis(250))
+    }
+}
+
+/// Prints the request that would be sent to the REST API, for a dry run. The authorization
+/// header is redacted.
+fn print_request<B: Serialize>(
+    method: &str,
+    url: &str,
+    base_url: &str,
+    auth: &str,
+    body: Option<&B>,
+) -> Result<(), CliError> {
+    let protocol_version = RBAC_PROTOCOL_VERSION.negotiate(base_url, auth)?;
+    println!("{}", describe_request(method, url, protocol_version, body)?);
+    Ok(())
+}
+
+fn describe_request<B: Serialize>(
+    method: &str,
+    url: &str,
+    protocol_version: u32,
+    body: Option<&B>,
+) -> Result<String, CliError> {
+    let mut request = format!(
+        "{} {}\n{}: {}\nAuthorization: <redacted>",
+        method, url, PROTOCOL_VERSION_HEADER, protocol_version
+    );
+
+    if let Some(body) = body {
+        let body = serde_json::to_string_pretty(body).map_err(|err| {
+            CliError::ActionError(format!("Cannot format request body into json: {}", err))
+        })?;
+        request.push_str("\nContent-Type: application/json\n\n");
+        request.push_str(&body);
+    }
+
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    use super::roles::RoleBuilder;
+
+    /// Starts a server that answers each request with the next of the given status codes, and
+    /// returns its URL along with the thread that returns the number of requests it answered.
+    fn start_mock_server(statuses: Vec<u16>) -> (String, thread::JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Unable to bind mock server");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("Unable to get address")
+        );
+
+        let handle = thread::spawn(move || {
+            let mut answered = 0;
+            for status in statuses {
+                let (mut stream, _) = listener.accept().expect("Unable to accept connection");
+
+                // Read the request headers; none of the test requests have a body
+                let mut request = vec![];
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = stream.read(&mut buf).expect("Unable to read request");
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..read]);
+                }
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                )
+                .expect("Unable to write response");
+                answered += 1;
+            }
+            answered
+        });
+
+        (url, handle)
+    }
+
+    /// Verify that a request is retried after 5xx responses until it succeeds.
+    #[test]
+    fn test_retry_policy_retries_server_errors() {
+        let (url, server) = start_mock_server(vec![503, 500, 200]);
+
+        let res = RetryPolicy::new(3, Duration::from_millis(1))
+            .send(|| Client::new().get(&url))
+            .expect("Unable to send request");
+
+        assert_eq!(res.status().as_u16(), 200);
+        assert_eq!(server.join().expect("Mock server panicked"), 3);
+    }
+
+    /// Verify that a 4xx response is returned without being retried, and that the last 5xx
+    /// response is returned once the attempts run out.
+    #[test]
+    fn test_retry_policy_limits() {
+        let (url, server) = start_mock_server(vec![404]);
+        let res = RetryPolicy::new(3, Duration::from_millis(1))
+            .send(|| Client::new().get(&url))
+            .expect("Unable to send request");
+        assert_eq!(res.status().as_u16(), 404);
+        assert_eq!(server.join().expect("Mock server panicked"), 1);
+
+        let (url, server) = start_mock_server(vec![503, 502]);
+        let res = RetryPolicy::new(2, Duration::from_millis(1))
+            .send(|| Client::new().get(&url))
+            .expect("Unable to send request");
+        assert_eq!(res.status().as_u16(), 502);
+        assert_eq!(server.join().expect("Mock server panicked"), 2);
+    }
+
+    /// Verify that a described request includes the method, URL, headers and body, without the
+    /// authorization value.
+    #[test]
+    fn test_describe_request() {
+        let role = RoleBuilder::default()
+            .with_role_id("role-a".into())
+            .with_display_name("Role A".into())
+            .with_permissions(vec!["a.read".into()])
+            .build()
+            .expect("Unable to build role");
+
+        let request = describe_request(
+            "POST",
+            "http://localhost:8080/authorization/roles",
+            1,
+            Some(&role),
+        )
+        .expect("Unable to describe request");
+
+        assert_eq!(
+            request,
+            "POST http://localhost:8080/authorization/roles\n\
+            SplinterProtocolVersion: 1\n\
+            Authorization: <redacted>\n\
+            Content-Type: application/json\n\
+            \n\
+            {\n  \"role_id\": \"role-a\",\n  \"display_name\": \"Role A\",\n  \
+            \"permissions\": [\n    \"a.read\"\n  ]\n}"
+        );
+
+        let request = describe_request::<()>(
+            "DELETE",
+            "http://localhost:8080/authorization/roles/role-a",
+            1,
+            None,
+        )
+        .expect("Unable to describe request");
+
+        assert_eq!(
+            request,
+            "DELETE http://localhost:8080/authorization/roles/role-a\n\
+            SplinterProtocolVersion: 1\n\
+            Authorization: <redacted>"
+        );
+    }
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 
 use reqwest::blocking::Client;
@@ -20,9 +21,9 @@ use serde::{Deserialize, Serialize};
 use crate::action::api::ServerError;
 use crate::error::CliError;
 
-use super::{Pageable, RBAC_PROTOCOL_VERSION};
+use super::{print_request, Pageable, RetryPolicy, PROTOCOL_VERSION_HEADER, RBAC_PROTOCOL_VERSION};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Role {
     pub role_id: String,
     pub display_name: String,
@@ -55,12 +56,13 @@ pub struct RoleBuilder {
     role_id: Option<String>,
     display_name: Option<String>,
     permissions: Vec<String>,
+    known_permissions: Option<BTreeSet<String>>,
 }
 
 impl RoleBuilder {
     /// Sets the role id of the resulting Role.
     ///
-    /// Must not be empty.
+    /// Must not be empty or only whitespace.
     pub fn with_role_id(mut self, role_id: String) -> Self {
         self.role_id = Some(role_id);
         self
@@ -74,18 +76,31 @@ impl RoleBuilder {
 
     /// Sets the permissions included in the resulting Role.
     ///
-    /// Must not be empty.
+    /// Must not be empty, and each permission must not be blank.
     pub fn with_permissions(mut self, permissions: Vec<String>) -> Self {
         self.permissions = permissions;
         self
     }
 
+    /// Sets the IDs of the permissions known to the node, such as those returned by
+    /// `SplinterRestClient::permission_ids`.
+    ///
+    /// If set, every permission of the resulting Role must be one of the known permissions.
+    pub fn with_known_permissions(mut self, known_permissions: BTreeSet<String>) -> Self {
+        self.known_permissions = Some(known_permissions);
+        self
+    }
+
     /// Constructs the Role.
+    ///
+    /// Leading and trailing whitespace is trimmed from the role ID and display name, so a role ID
+    /// that consists only of whitespace is rejected as blank.
     pub fn build(self) -> Result<Role, CliError> {
         let RoleBuilder {
             role_id,
             display_name,
             permissions,
+            known_permissions,
         } = self;
 
         if permissions.is_empty() {
@@ -93,15 +108,37 @@ impl RoleBuilder {
                 "A role must have at least one permission".into(),
             ));
         }
+        check_permissions_not_blank(&permissions)?;
 
-        let role_id =
-            role_id.ok_or_else(|| CliError::ActionError("A role must have a role ID".into()))?;
+        if let Some(known_permissions) = known_permissions {
+            let unknown_permissions = permissions
+                .iter()
+                .filter(|perm| !known_permissions.contains(*perm))
+                .map(|perm| perm.as_str())
+                .collect::<BTreeSet<_>>();
+            if !unknown_permissions.is_empty() {
+                return Err(CliError::ActionError(format!(
+                    "Unrecognized permissions: {}",
+                    unknown_permissions
+                        .into_iter()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            }
+        }
+
+        let role_id = role_id
+            .ok_or_else(|| CliError::ActionError("A role must have a role ID".into()))?
+            .trim()
+            .to_string();
         if role_id.is_empty() {
             return Err(CliError::ActionError("A role ID must not be blank".into()));
         }
 
         let display_name = display_name
-            .ok_or_else(|| CliError::ActionError("A role must have a display name".into()))?;
+            .ok_or_else(|| CliError::ActionError("A role must have a display name".into()))?
+            .trim()
+            .to_string();
 
         Ok(Role {
             role_id,
@@ -122,11 +159,105 @@ pub struct RoleUpdate {
     permissions: Option<Vec<String>>,
 }
 
+impl RoleUpdate {
+    /// Returns the ID of the role to be updated.
+    pub fn role_id(&self) -> &str {
+        &self.role_id
+    }
+}
+
+/// The changes a role update makes to a role.
+#[derive(Debug, Default, PartialEq)]
+pub struct RoleDiff {
+    pub role_id: String,
+    pub added_permissions: Vec<String>,
+    pub removed_permissions: Vec<String>,
+    /// The current and updated display names, if the display name changes
+    pub display_name: Option<(String, String)>,
+}
+
+impl RoleDiff {
+    /// Computes the changes that applying `update` makes to the `current` role.
+    ///
+    /// Permissions are compared as sets and listed in sorted order.
+    pub fn new(current: &Role, update: &RoleUpdate) -> Self {
+        let display_name = update
+            .display_name
+            .as_ref()
+            .filter(|display_name| **display_name != current.display_name)
+            .map(|display_name| (current.display_name.clone(), display_name.clone()));
+
+        let (added_permissions, removed_permissions) = match &update.permissions {
+            Some(permissions) => {
+                let current = current.permissions.iter().collect::<BTreeSet<_>>();
+                let updated = permissions.iter().collect::<BTreeSet<_>>();
+                (
+                    updated
+                        .difference(&current)
+                        .map(|s| s.to_string())
+                        .collect(),
+                    current
+                        .difference(&updated)
+                        .map(|s| s.to_string())
+                        .collect(),
+                )
+            }
+            None => (vec![], vec![]),
+        };
+
+        Self {
+            role_id: current.role_id.clone(),
+            added_permissions,
+            removed_permissions,
+            display_name,
+        }
+    }
+
+    /// Returns true if the update does not change the role.
+    pub fn is_empty(&self) -> bool {
+        self.added_permissions.is_empty()
+            && self.removed_permissions.is_empty()
+            && self.display_name.is_none()
+    }
+}
+
+impl fmt::Display for RoleDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "Role {} is unchanged", self.role_id);
+        }
+
+        write!(f, "Role {} changes:", self.role_id)?;
+        if let Some((current, updated)) = &self.display_name {
+            write!(f, "\n    Name: {} -> {}", current, updated)?;
+        }
+        if !self.added_permissions.is_empty() {
+            write!(
+                f,
+                "\n    Added permissions: {}",
+                self.added_permissions.join(", ")
+            )?;
+        }
+        if !self.removed_permissions.is_empty() {
+            write!(
+                f,
+                "\n    Removed permissions: {}",
+                self.removed_permissions.join(", ")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Default)]
 pub struct RoleUpdateBuilder {
     role_id: Option<String>,
     display_name: Option<String>,
     permissions: Option<Vec<String>>,
+    add_permissions: Vec<String>,
+    remove_permissions: Vec<String>,
+    current_role: Option<Role>,
 }
 
 impl RoleUpdateBuilder {
@@ -146,18 +277,48 @@ impl RoleUpdateBuilder {
 
     /// Sets the permissions included in the resulting Role.
     ///
-    /// Must not be empty.
+    /// Must not be empty, and each permission must not be blank.
     pub fn with_permissions(mut self, permissions: Option<Vec<String>>) -> Self {
         self.permissions = permissions;
         self
     }
 
+    /// Sets the permissions to be granted in addition to those the role currently has.
+    ///
+    /// Requires the current role, and must not be combined with `with_permissions`.
+    pub fn with_add_permissions(mut self, permissions: Vec<String>) -> Self {
+        self.add_permissions = permissions;
+        self
+    }
+
+    /// Sets the permissions to be removed from those the role currently has. Each must currently
+    /// belong to the role.
+    ///
+    /// Requires the current role, and must not be combined with `with_permissions`.
+    pub fn with_remove_permissions(mut self, permissions: Vec<String>) -> Self {
+        self.remove_permissions = permissions;
+        self
+    }
+
+    /// Sets the role as it currently exists on the node, to which permissions are added or from
+    /// which they are removed.
+    pub fn with_current_role(mut self, role: Role) -> Self {
+        self.current_role = Some(role);
+        self
+    }
+
     /// Constructs the Role.
+    ///
+    /// If permissions are added or removed, the resulting permissions are those of the current
+    /// role, less the removed permissions, plus the added permissions, without duplicates.
     pub fn build(self) -> Result<RoleUpdate, CliError> {
         let RoleUpdateBuilder {
             role_id,
             display_name,
             permissions,
+            add_permissions,
+            remove_permissions,
+            current_role,
         } = self;
 
         let role_id =
@@ -166,12 +327,33 @@ impl RoleUpdateBuilder {
             return Err(CliError::ActionError("A role ID must not be blank".into()));
         }
 
+        let permissions = if add_permissions.is_empty() && remove_permissions.is_empty() {
+            permissions
+        } else {
+            if permissions.is_some() {
+                return Err(CliError::ActionError(
+                    "Permissions cannot be both replaced and added or removed".into(),
+                ));
+            }
+            let current_role = current_role.ok_or_else(|| {
+                CliError::ActionError(
+                    "The current role is required to add or remove permissions".into(),
+                )
+            })?;
+            Some(merge_permissions(
+                current_role.permissions,
+                add_permissions,
+                remove_permissions,
+            )?)
+        };
+
         if let Some(permissions) = permissions.as_ref() {
             if permissions.is_empty() {
                 return Err(CliError::ActionError(
                     "A role must have at least one permission".into(),
                 ));
             }
+            check_permissions_not_blank(permissions)?;
         }
 
         Ok(RoleUpdate {
@@ -182,18 +364,83 @@ impl RoleUpdateBuilder {
     }
 }
 
+/// Removes and adds the given permissions to a role's current permissions, returning the result
+/// sorted and without duplicates.
+///
+/// Returns an error if a permission is both added and removed, or if a removed permission does not
+/// belong to the role.
+fn merge_permissions(
+    current: Vec<String>,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Vec<String>, CliError> {
+    let add = add.into_iter().collect::<BTreeSet<_>>();
+    let mut remove = remove.into_iter().collect::<BTreeSet<_>>();
+
+    if add.intersection(&remove).next().is_some() {
+        return Err(CliError::ActionError(format!(
+            "Cannot add and remove the same permissions: {}",
+            add.intersection(&remove)
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+
+    let mut permissions = current
+        .into_iter()
+        .filter(|perm| !remove.remove(perm))
+        .collect::<BTreeSet<_>>();
+
+    if !remove.is_empty() {
+        return Err(CliError::ActionError(format!(
+            "Cannot remove permissions that do not belong to the role: {}",
+            remove
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+
+    permissions.extend(add);
+
+    Ok(permissions.into_iter().collect())
+}
+
+/// Verifies that none of the given permissions are empty or only whitespace.
+fn check_permissions_not_blank(permissions: &[String]) -> Result<(), CliError> {
+    if let Some(index) = permissions.iter().position(|perm| perm.trim().is_empty()) {
+        return Err(CliError::ActionError(format!(
+            "A permission must not be blank (permission at index {})",
+            index
+        )));
+    }
+
+    Ok(())
+}
+
 #[derive(Deserialize)]
 struct RoleGet {
     #[serde(rename = "data")]
     role: Role,
 }
 
-pub fn get_role(base_url: &str, auth: &str, role_id: &str) -> Result<Option<Role>, CliError> {
-    Client::new()
-        .get(&format!("{}/authorization/roles/{}", base_url, role_id))
-        .header("SplinterProtocolVersion", RBAC_PROTOCOL_VERSION)
-        .header("Authorization", auth)
-        .send()
+pub fn get_role(
+    base_url: &str,
+    auth: &str,
+    retry: &RetryPolicy,
+    role_id: &str,
+) -> Result<Option<Role>, CliError> {
+    let url = format!("{}/authorization/roles/{}", base_url, role_id);
+    let protocol_version = RBAC_PROTOCOL_VERSION.negotiate(base_url, auth)?;
+    retry
+        .send(|| {
+            Client::new()
+                .get(&url)
+                .header(PROTOCOL_VERSION_HEADER, protocol_version)
+                .header("Authorization", auth)
+        })
         .map_err(|err| CliError::ActionError(format!("Failed to fetch role {}: {}", role_id, err)))
         .and_then(|res| {
             let status = res.status();
@@ -229,13 +476,28 @@ pub fn get_role(base_url: &str, auth: &str, role_id: &str) -> Result<Option<Role
         })
 }
 
-pub fn create_role(base_url: &str, auth: &str, role: Role) -> Result<(), CliError> {
-    Client::new()
-        .post(&format!("{}/authorization/roles", base_url))
-        .header("SplinterProtocolVersion", RBAC_PROTOCOL_VERSION)
-        .header("Authorization", auth)
-        .json(&role)
-        .send()
+/// Creates the given role. If `dry_run` is true, the request is printed instead of being sent.
+pub fn create_role(
+    base_url: &str,
+    auth: &str,
+    retry: &RetryPolicy,
+    role: Role,
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let url = format!("{}/authorization/roles", base_url);
+    if dry_run {
+        return print_request("POST", &url, base_url, auth, Some(&role));
+    }
+
+    let protocol_version = RBAC_PROTOCOL_VERSION.negotiate(base_url, auth)?;
+    retry
+        .send(|| {
+            Client::new()
+                .post(&url)
+                .header(PROTOCOL_VERSION_HEADER, protocol_version)
+                .header("Authorization", auth)
+                .json(&role)
+        })
         .map_err(|err| CliError::ActionError(format!("Failed to create role: {}", err)))
         .and_then(|res| {
             let status = res.status();
@@ -263,16 +525,81 @@ pub fn create_role(base_url: &str, auth: &str, role: Role) -> Result<(), CliErro
         })
 }
 
-pub fn update_role(base_url: &str, auth: &str, role_update: RoleUpdate) -> Result<(), CliError> {
-    Client::new()
-        .patch(&format!(
-            "{}/authorization/roles/{}",
-            base_url, role_update.role_id
-        ))
-        .header("SplinterProtocolVersion", RBAC_PROTOCOL_VERSION)
-        .header("Authorization", auth)
-        .json(&role_update)
-        .send()
+/// Creates the role `new_role_id` with the permissions of the role `source_role_id`.
+///
+/// The new role has the display name `new_display_name`, or the source role's display name if none
+/// is given. An error is returned if the source role does not exist or a role with the new ID
+/// already exists. If `dry_run` is true, the request is printed instead of being sent.
+pub fn clone_role(
+    base_url: &str,
+    auth: &str,
+    retry: &RetryPolicy,
+    source_role_id: &str,
+    new_role_id: &str,
+    new_display_name: Option<&str>,
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let source = get_role(base_url, auth, retry, source_role_id)?
+        .ok_or_else(|| CliError::ActionError(format!("Role {} does not exist", source_role_id)))?;
+
+    let role = cloned_role(source, new_role_id, new_display_name)?;
+    if get_role(base_url, auth, retry, &role.role_id)?.is_some() {
+        return Err(CliError::ActionError(format!(
+            "A Role with ID {} already exists",
+            role.role_id
+        )));
+    }
+
+    create_role(base_url, auth, retry, role, dry_run)
+}
+
+/// Builds a copy of the given role with a new ID and, optionally, a new display name.
+fn cloned_role(
+    source: Role,
+    new_role_id: &str,
+    new_display_name: Option<&str>,
+) -> Result<Role, CliError> {
+    if source.role_id == new_role_id.trim() {
+        return Err(CliError::ActionError(format!(
+            "A role cannot be cloned to its own ID {}",
+            source.role_id
+        )));
+    }
+
+    RoleBuilder::default()
+        .with_role_id(new_role_id.into())
+        .with_display_name(
+            new_display_name
+                .map(String::from)
+                .unwrap_or(source.display_name),
+        )
+        .with_permissions(source.permissions)
+        .build()
+}
+
+/// Updates a role with the given changes. If `dry_run` is true, the request is printed instead of
+/// being sent.
+pub fn update_role(
+    base_url: &str,
+    auth: &str,
+    retry: &RetryPolicy,
+    role_update: RoleUpdate,
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let url = format!("{}/authorization/roles/{}", base_url, role_update.role_id);
+    if dry_run {
+        return print_request("PATCH", &url, base_url, auth, Some(&role_update));
+    }
+
+    let protocol_version = RBAC_PROTOCOL_VERSION.negotiate(base_url, auth)?;
+    retry
+        .send(|| {
+            Client::new()
+                .patch(&url)
+                .header(PROTOCOL_VERSION_HEADER, protocol_version)
+                .header("Authorization", auth)
+                .json(&role_update)
+        })
         .map_err(|err| CliError::ActionError(format!("Failed to update role: {}", err)))
         .and_then(|res| {
             let status = res.status();
@@ -305,12 +632,28 @@ pub fn update_role(base_url: &str, auth: &str, role_update: RoleUpdate) -> Resul
         })
 }
 
-pub fn delete_role(base_url: &str, auth: &str, role_id: &str) -> Result<(), CliError> {
-    Client::new()
-        .delete(&format!("{}/authorization/roles/{}", base_url, role_id))
-        .header("SplinterProtocolVersion", RBAC_PROTOCOL_VERSION)
-        .header("Authorization", auth)
-        .send()
+/// Deletes the role with the given ID. If `dry_run` is true, the request is printed instead of
+/// being sent.
+pub fn delete_role(
+    base_url: &str,
+    auth: &str,
+    retry: &RetryPolicy,
+    role_id: &str,
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let url = format!("{}/authorization/roles/{}", base_url, role_id);
+    if dry_run {
+        return print_request::<()>("DELETE", &url, base_url, auth, None);
+    }
+
+    let protocol_version = RBAC_PROTOCOL_VERSION.negotiate(base_url, auth)?;
+    retry
+        .send(|| {
+            Client::new()
+                .delete(&url)
+                .header(PROTOCOL_VERSION_HEADER, protocol_version)
+                .header("Authorization", auth)
+        })
         .map_err(|err| CliError::ActionError(format!("Failed to delete role {}: {}", role_id, err)))
         .and_then(|res| {
             let status = res.status();
@@ -338,6 +681,28 @@ pub fn delete_role(base_url: &str, auth: &str, role_id: &str) -> Result<(), CliE
         })
 }
 
+/// Builds an inverted index of permission IDs to the IDs of the roles which grant them.
+///
+/// Role IDs are listed in the order the roles are provided. A role is only listed once for a
+/// permission, even if the permission appears more than once in the role.
+pub fn permission_index<I>(roles: I) -> Result<HashMap<String, Vec<String>>, CliError>
+where
+    I: IntoIterator<Item = Result<Role, CliError>>,
+{
+    let mut index: HashMap<String, Vec<String>> = HashMap::new();
+    for role in roles {
+        let role = role?;
+        for permission in role.permissions {
+            let role_ids = index.entry(permission).or_insert_with(Vec::new);
+            if !role_ids.contains(&role.role_id) {
+                role_ids.push(role.role_id.clone());
+            }
+        }
+    }
+
+    Ok(index)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -403,6 +768,50 @@ mod tests {
         assert!(res.is_err());
     }
 
+    /// Tests that the role builder only accepts known permissions when they are given
+    /// 1. Construct a valid role from known permissions
+    /// 2. Fail with permissions that are not known, listing each of them once
+    /// 3. Succeed with unknown permissions when the known permissions are not given
+    #[test]
+    fn test_role_builder_known_permissions() {
+        let known_permissions = vec!["circuit.read".to_string(), "circuit.write".to_string()]
+            .into_iter()
+            .collect::<BTreeSet<_>>();
+
+        RoleBuilder::default()
+            .with_role_id("known".into())
+            .with_display_name("Known".into())
+            .with_permissions(vec!["circuit.read".to_string()])
+            .with_known_permissions(known_permissions.clone())
+            .build()
+            .expect("could not build a role with known permissions");
+
+        let res = RoleBuilder::default()
+            .with_role_id("unknown".into())
+            .with_display_name("Unknown".into())
+            .with_permissions(vec![
+                "circuit.read".to_string(),
+                "circuit.raed".to_string(),
+                "registry.read".to_string(),
+                "circuit.raed".to_string(),
+            ])
+            .with_known_permissions(known_permissions)
+            .build();
+        match res {
+            Err(CliError::ActionError(msg)) => {
+                assert_eq!(msg, "Unrecognized permissions: circuit.raed, registry.read")
+            }
+            _ => panic!("expected unknown permissions to be rejected"),
+        }
+
+        RoleBuilder::default()
+            .with_role_id("unchecked".into())
+            .with_display_name("Unchecked".into())
+            .with_permissions(vec!["circuit.raed".to_string()])
+            .build()
+            .expect("could not build a role without known permissions");
+    }
+
     /// Tests the role update builder in both Ok and Err scenarios
     /// 1. Construct a valid update with all items
     /// 2. Construct a valid update with no permission changes
@@ -467,4 +876,227 @@ mod tests {
             .build();
         assert!(res.is_err());
     }
+
+    /// Tests that the role update builder adds and removes permissions from the current role
+    /// 1. Add and remove permissions, deduplicating the result
+    /// 2. Fail to add and remove the same permission
+    /// 3. Fail to remove a permission the role does not have
+    /// 4. Fail to add permissions without the current role
+    /// 5. Fail to both replace and add permissions
+    #[test]
+    fn test_role_update_builder_add_remove() {
+        let current_role = || Role {
+            role_id: "valid_role".into(),
+            display_name: "Valid Role".into(),
+            permissions: vec!["b".to_string(), "a".to_string(), "c".to_string()],
+        };
+
+        let role_update = RoleUpdateBuilder::default()
+            .with_role_id("valid_role".into())
+            .with_current_role(current_role())
+            .with_add_permissions(vec!["d".to_string(), "a".to_string(), "d".to_string()])
+            .with_remove_permissions(vec!["c".to_string()])
+            .build()
+            .expect("could not build a valid role update");
+        assert_eq!(
+            Some(vec!["a".to_string(), "b".to_string(), "d".to_string()]),
+            role_update.permissions
+        );
+
+        let res = RoleUpdateBuilder::default()
+            .with_role_id("valid_role".into())
+            .with_current_role(current_role())
+            .with_add_permissions(vec!["d".to_string()])
+            .with_remove_permissions(vec!["d".to_string()])
+            .build();
+        match &res {
+            Err(CliError::ActionError(msg)) => assert!(msg.contains("add and remove")),
+            _ => panic!("Expected an ActionError, but got {:?}", res),
+        }
+
+        let res = RoleUpdateBuilder::default()
+            .with_role_id("valid_role".into())
+            .with_current_role(current_role())
+            .with_remove_permissions(vec!["z".to_string()])
+            .build();
+        assert!(res.is_err());
+
+        let res = RoleUpdateBuilder::default()
+            .with_role_id("valid_role".into())
+            .with_add_permissions(vec!["d".to_string()])
+            .build();
+        assert!(res.is_err());
+
+        let res = RoleUpdateBuilder::default()
+            .with_role_id("valid_role".into())
+            .with_current_role(current_role())
+            .with_permissions(Some(vec!["a".to_string()]))
+            .with_add_permissions(vec!["d".to_string()])
+            .build();
+        assert!(res.is_err());
+    }
+
+    /// Tests that the role diff lists the changes an update makes
+    /// 1. Diff an update that renames the role and changes its permissions
+    /// 2. Diff an update that keeps the display name and permissions
+    #[test]
+    fn test_role_diff() {
+        let current = Role {
+            role_id: "valid_role".into(),
+            display_name: "Valid Role".into(),
+            permissions: vec!["a".to_string(), "b".to_string()],
+        };
+
+        let update = RoleUpdateBuilder::default()
+            .with_role_id("valid_role".into())
+            .with_display_name(Some("Renamed Role".into()))
+            .with_permissions(Some(vec!["c".to_string(), "a".to_string()]))
+            .build()
+            .expect("could not build a valid role update");
+        let diff = RoleDiff::new(&current, &update);
+        assert_eq!(vec!["c".to_string()], diff.added_permissions);
+        assert_eq!(vec!["b".to_string()], diff.removed_permissions);
+        assert_eq!(
+            Some(("Valid Role".to_string(), "Renamed Role".to_string())),
+            diff.display_name
+        );
+        assert!(diff
+            .to_string()
+            .contains("Name: Valid Role -> Renamed Role"));
+
+        let update = RoleUpdateBuilder::default()
+            .with_role_id("valid_role".into())
+            .with_display_name(Some("Valid Role".into()))
+            .with_permissions(Some(vec!["b".to_string(), "a".to_string()]))
+            .build()
+            .expect("could not build a valid role update");
+        let diff = RoleDiff::new(&current, &update);
+        assert!(diff.is_empty());
+        assert_eq!("Role valid_role is unchanged", diff.to_string());
+    }
+
+    /// Tests that both role builders reject blank permissions
+    /// 1. Fail to build a role with an empty permission
+    /// 2. Fail to build a role with a whitespace-only permission
+    /// 3. Fail to build a role update with an empty permission
+    #[test]
+    fn test_role_builders_blank_permission() {
+        let res = RoleBuilder::default()
+            .with_role_id("blank_permission".into())
+            .with_display_name("Blank Permission".into())
+            .with_permissions(vec!["".to_string(), "valid".to_string()])
+            .build();
+        match &res {
+            Err(CliError::ActionError(msg)) => assert!(msg.contains("index 0")),
+            _ => panic!("Expected an ActionError, but got {:?}", res),
+        }
+
+        let res = RoleBuilder::default()
+            .with_role_id("whitespace_permission".into())
+            .with_display_name("Whitespace Permission".into())
+            .with_permissions(vec!["valid".to_string(), "  ".to_string()])
+            .build();
+        match &res {
+            Err(CliError::ActionError(msg)) => assert!(msg.contains("index 1")),
+            _ => panic!("Expected an ActionError, but got {:?}", res),
+        }
+
+        let res = RoleUpdateBuilder::default()
+            .with_role_id("blank_permission_update".into())
+            .with_permissions(Some(vec!["valid".to_string(), "".to_string()]))
+            .build();
+        match &res {
+            Err(CliError::ActionError(msg)) => assert!(msg.contains("index 1")),
+            _ => panic!("Expected an ActionError, but got {:?}", res),
+        }
+    }
+
+    /// Tests that the role builder normalizes whitespace in the role ID and display name
+    /// 1. Fail with a whitespace-only role_id
+    /// 2. Trim the role_id and display name of a valid role
+    #[test]
+    fn test_role_builder_trims_whitespace() {
+        let res = RoleBuilder::default()
+            .with_role_id(" ".into())
+            .with_display_name("Whitespace ID Role".into())
+            .with_permissions(vec!["a".to_string()])
+            .build();
+        assert!(res.is_err());
+
+        let role = RoleBuilder::default()
+            .with_role_id("  valid_role\t".into())
+            .with_display_name(" Valid Role ".into())
+            .with_permissions(vec!["a".to_string()])
+            .build()
+            .expect("could not build a valid role");
+
+        assert_eq!("valid_role", &role.role_id);
+        assert_eq!("Valid Role", &role.display_name);
+    }
+
+    /// Tests that a cloned role copies the source role's permissions
+    /// 1. Clone a role with a new display name
+    /// 2. Clone a role, keeping the source role's display name
+    /// 3. Fail to clone a role to its own ID
+    #[test]
+    fn test_cloned_role() {
+        let source = || Role {
+            role_id: "circuit_admin".into(),
+            display_name: "Circuit Admin".into(),
+            permissions: vec!["circuit.read".into(), "circuit.write".into()],
+        };
+
+        let role = cloned_role(source(), "circuit_admin_2", Some("Second Circuit Admin"))
+            .expect("could not clone the role");
+        assert_eq!("circuit_admin_2", &role.role_id);
+        assert_eq!("Second Circuit Admin", &role.display_name);
+        assert_eq!(source().permissions, role.permissions);
+
+        let role =
+            cloned_role(source(), "circuit_admin_2", None).expect("could not clone the role");
+        assert_eq!("Circuit Admin", &role.display_name);
+
+        assert!(cloned_role(source(), " circuit_admin ", None).is_err());
+    }
+
+    /// Tests that the permission index maps each permission to the roles granting it
+    /// 1. Build an index from three roles, one of which repeats a permission
+    /// 2. Validate each permission lists its granting roles, in order and without duplicates
+    /// 3. Validate that an error from the roles is returned
+    #[test]
+    fn test_permission_index() {
+        let roles = vec![
+            Ok(Role {
+                role_id: "admin".into(),
+                display_name: "Admin".into(),
+                permissions: vec!["circuit.read".into(), "circuit.write".into()],
+            }),
+            Ok(Role {
+                role_id: "reader".into(),
+                display_name: "Reader".into(),
+                permissions: vec!["circuit.read".into(), "circuit.read".into()],
+            }),
+            Ok(Role {
+                role_id: "registry".into(),
+                display_name: "Registry".into(),
+                permissions: vec!["registry.read".into()],
+            }),
+        ];
+
+        let index = permission_index(roles).expect("could not build the index");
+
+        assert_eq!(3, index.len());
+        assert_eq!(
+            Some(&vec!["admin".to_string(), "reader".to_string()]),
+            index.get("circuit.read")
+        );
+        assert_eq!(Some(&vec!["admin".to_string()]), index.get("circuit.write"));
+        assert_eq!(
+            Some(&vec!["registry".to_string()]),
+            index.get("registry.read")
+        );
+
+        let roles = vec![Err(CliError::ActionError("failed to list roles".into()))];
+        assert!(permission_index(roles).is_err());
+    }
 }
@@ -0,0 +1,199 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the `splinter circuit export` command, which writes a circuit's full topology as a
+//! single JSON document.
+
+use std::collections::BTreeMap;
+
+use clap::ArgMatches;
+use serde::Serialize;
+use splinter::admin::messages::CircuitStatus;
+
+use crate::error::CliError;
+use crate::signing::{create_cylinder_jwt_auth, load_signer};
+
+use super::api::{CircuitMembers, CircuitServiceSlice, CircuitSlice};
+use super::{
+    Action, SplinterRestClientBuilder, DEFAULT_SPLINTER_REST_API_URL, SPLINTER_REST_API_URL_ENV,
+};
+
+/// The portable export format for a circuit's topology.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct CircuitTopology {
+    circuit_id: String,
+    display_name: Option<String>,
+    management_type: String,
+    circuit_version: i32,
+    circuit_status: Option<CircuitStatus>,
+    nodes: Vec<NodeTopology>,
+    services: Vec<ServiceTopology>,
+}
+
+/// A member node of an exported circuit.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct NodeTopology {
+    node_id: String,
+    endpoints: Vec<String>,
+    public_key: Option<String>,
+}
+
+/// A service of an exported circuit.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ServiceTopology {
+    service_id: String,
+    service_type: String,
+    node_id: String,
+    arguments: BTreeMap<String, String>,
+}
+
+impl From<CircuitSlice> for CircuitTopology {
+    fn from(circuit: CircuitSlice) -> Self {
+        CircuitTopology {
+            circuit_id: circuit.id,
+            display_name: circuit.display_name,
+            management_type: circuit.management_type,
+            circuit_version: circuit.circuit_version,
+            circuit_status: circuit.circuit_status,
+            nodes: circuit
+                .members
+                .into_iter()
+                .map(NodeTopology::from)
+                .collect(),
+            services: circuit
+                .roster
+                .into_iter()
+                .map(ServiceTopology::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<CircuitMembers> for NodeTopology {
+    fn from(member: CircuitMembers) -> Self {
+        NodeTopology {
+            node_id: member.node_id,
+            endpoints: member.endpoints,
+            public_key: member.public_key,
+        }
+    }
+}
+
+impl From<CircuitServiceSlice> for ServiceTopology {
+    fn from(service: CircuitServiceSlice) -> Self {
+        ServiceTopology {
+            service_id: service.service_id,
+            service_type: service.service_type,
+            node_id: service.node_id,
+            arguments: service.arguments,
+        }
+    }
+}
+
+pub struct CircuitExportAction;
+
+impl Action for CircuitExportAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or(CliError::RequiresArgs)?;
+
+        let url = args
+            .value_of("url")
+            .map(ToOwned::to_owned)
+            .or_else(|| std::env::var(SPLINTER_REST_API_URL_ENV).ok())
+            .unwrap_or_else(|| DEFAULT_SPLINTER_REST_API_URL.to_string());
+        let circuit_id = args
+            .value_of("circuit")
+            .ok_or_else(|| CliError::ActionError("'circuit' argument is required".to_string()))?;
+
+        let signer = load_signer(args.value_of("private_key_file"))?;
+
+        let client = SplinterRestClientBuilder::new()
+            .with_url(url)
+            .with_auth(create_cylinder_jwt_auth(signer)?)
+            .build()?;
+
+        let circuit = client.fetch_circuit(circuit_id)?.ok_or_else(|| {
+            CliError::ActionError(format!("Circuit '{}' does not exist", circuit_id))
+        })?;
+
+        let topology = CircuitTopology::from(circuit);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&topology).map_err(|err| CliError::ActionError(
+                format!("Cannot format circuit topology into json: {}", err)
+            ))?
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    /// Verify that a circuit is converted into the expected export document, with members
+    /// listed as nodes and the roster listed as services.
+    #[test]
+    fn test_circuit_topology_json() {
+        let mut arguments = BTreeMap::new();
+        arguments.insert("peer_services".to_string(), "[\"b000\"]".to_string());
+
+        let circuit = CircuitSlice {
+            id: "0z2C4-hheAY".to_string(),
+            members: vec![CircuitMembers {
+                node_id: "n20959".to_string(),
+                endpoints: vec!["tcp://127.0.0.1:18044".to_string()],
+                public_key: Some("0372a7ee".to_string()),
+            }],
+            roster: vec![CircuitServiceSlice {
+                service_id: "a000".to_string(),
+                service_type: "scabbard".to_string(),
+                node_id: "n20959".to_string(),
+                arguments,
+            }],
+            management_type: "scabbard".to_string(),
+            display_name: None,
+            circuit_version: 2,
+            circuit_status: Some(CircuitStatus::Active),
+        };
+
+        let topology = serde_json::to_value(CircuitTopology::from(circuit))
+            .expect("Unable to serialize topology");
+
+        assert_eq!(
+            topology,
+            json!({
+                "circuit_id": "0z2C4-hheAY",
+                "display_name": null,
+                "management_type": "scabbard",
+                "circuit_version": 2,
+                "circuit_status": "Active",
+                "nodes": [{
+                    "node_id": "n20959",
+                    "endpoints": ["tcp://127.0.0.1:18044"],
+                    "public_key": "0372a7ee",
+                }],
+                "services": [{
+                    "service_id": "a000",
+                    "service_type": "scabbard",
+                    "node_id": "n20959",
+                    "arguments": {"peer_services": "[\"b000\"]"},
+                }],
+            })
+        );
+    }
+}
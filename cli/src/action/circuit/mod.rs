@@ -14,6 +14,7 @@
 
 mod api;
 mod builder;
+mod export;
 mod payload;
 #[cfg(feature = "circuit-template")]
 pub mod template;
@@ -39,12 +40,13 @@ use crate::template::CircuitTemplate;
 
 use super::api::SplinterRestClientBuilder;
 use super::{
-    msg_from_io_error, print_table, Action, DEFAULT_SPLINTER_REST_API_URL,
+    msg_from_io_error, print_table, resolve_output_format, Action, DEFAULT_SPLINTER_REST_API_URL,
     SPLINTER_REST_API_URL_ENV,
 };
 
 use api::{CircuitMembers, CircuitServiceSlice, CircuitSlice};
 pub(crate) use builder::CreateCircuitMessageBuilder;
+pub use export::CircuitExportAction;
 use payload::make_signed_payload;
 
 pub struct CircuitProposeAction;
@@ -958,19 +960,11 @@ impl Action for CircuitListAction {
         let member_filter = arg_matches.and_then(|args| args.value_of("member"));
         let status_filter = arg_matches.and_then(|args| args.value_of("circuit_status"));
 
-        let format = arg_matches
-            .and_then(|args| {
-                if let Some(val) = args.value_of("hidden_format") {
-                    Some(val)
-                } else {
-                    args.value_of("format")
-                }
-            })
-            .unwrap_or("human");
+        let format = resolve_output_format(arg_matches, &["human", "csv"])?;
 
         let signer = load_signer(arg_matches.and_then(|args| args.value_of("private_key_file")))?;
 
-        list_circuits(&url, member_filter, status_filter, format, signer)
+        list_circuits(&url, member_filter, status_filter, &format, signer)
     }
 }
 
@@ -1140,19 +1134,11 @@ impl Action for CircuitProposalsAction {
 
         let member_filter = arg_matches.and_then(|args| args.value_of("member"));
 
-        let format = arg_matches
-            .and_then(|args| {
-                if let Some(val) = args.value_of("hidden_format") {
-                    Some(val)
-                } else {
-                    args.value_of("format")
-                }
-            })
-            .unwrap_or("human");
+        let format = resolve_output_format(arg_matches, &["human", "csv"])?;
 
         let signer = load_signer(arg_matches.and_then(|args| args.value_of("private_key_file")))?;
 
-        list_proposals(&url, management_type_filter, member_filter, format, signer)
+        list_proposals(&url, management_type_filter, member_filter, &format, signer)
     }
 }
 
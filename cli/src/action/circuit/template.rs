@@ -17,7 +17,7 @@ use clap::ArgMatches;
 use crate::error::CliError;
 use crate::template::CircuitTemplate;
 
-use super::Action;
+use super::{resolve_output_format, Action};
 
 pub struct ListCircuitTemplates;
 
@@ -26,15 +26,7 @@ impl Action for ListCircuitTemplates {
         // Collect list of template file stems and full paths to the associated file stem
         let templates = CircuitTemplate::list_available_templates()?;
 
-        let format = arg_matches
-            .and_then(|args| {
-                if let Some(val) = args.value_of("hidden_format") {
-                    Some(val)
-                } else {
-                    args.value_of("format")
-                }
-            })
-            .unwrap_or("human");
+        let format = resolve_output_format(arg_matches, &["human", "csv"])?;
 
         if format == "csv" {
             print!("TEMPLATE,PATH,");
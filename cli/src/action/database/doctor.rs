@@ -0,0 +1,303 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the `splinter database doctor` command, which reports on the health of the database
+//! used by Splinter.
+
+#[cfg(feature = "sqlite")]
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use clap::ArgMatches;
+#[cfg(feature = "postgres")]
+use diesel::pg::PgConnection;
+#[cfg(feature = "sqlite")]
+use diesel::sqlite::SqliteConnection;
+use diesel::{
+    connection::SimpleConnection,
+    r2d2::{ConnectionManager, Pool},
+    Connection,
+};
+use serde::Serialize;
+
+use crate::action::resolve_output_format;
+use crate::error::CliError;
+
+use super::{get_default_database, Action, ConnectionUri};
+
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct DoctorAction;
+
+impl Action for DoctorAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let format = resolve_output_format(arg_matches, &["human", "json"])?;
+        let url = match arg_matches.and_then(|args| args.value_of("connect")) {
+            Some(url) => url.to_owned(),
+            None => get_default_database()?,
+        };
+
+        let report = match ConnectionUri::from_str(&url)? {
+            #[cfg(feature = "postgres")]
+            ConnectionUri::Postgres(url) => check_postgres(&url),
+            #[cfg(feature = "sqlite")]
+            ConnectionUri::Sqlite(connection_string) => check_sqlite(&connection_string),
+        };
+
+        if format == "json" {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).map_err(|err| CliError::ActionError(
+                    format!("Cannot format database report into json: {}", err)
+                ))?
+            );
+        } else {
+            report.print_human();
+        }
+
+        if !report.healthy {
+            Err(CliError::ActionError(
+                "The database health check failed".to_string(),
+            ))
+        } else if !report.pending_migrations.is_empty() {
+            Err(CliError::ActionError(
+                "The database has pending migrations; run `splinter database migrate` to \
+                apply them"
+                    .to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The result of checking a database.
+#[derive(Debug, Serialize)]
+struct DoctorReport {
+    backend: &'static str,
+    healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latency_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pool: Option<PoolStats>,
+    /// The components whose migrations have not been applied to the database
+    pending_migrations: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+struct PoolStats {
+    max_size: u32,
+    connections: u32,
+    idle_connections: u32,
+}
+
+impl DoctorReport {
+    fn unhealthy(backend: &'static str, error: String) -> Self {
+        DoctorReport {
+            backend,
+            healthy: false,
+            error: Some(error),
+            latency_ms: None,
+            pool: None,
+            pending_migrations: vec![],
+        }
+    }
+
+    fn print_human(&self) {
+        println!("Backend: {}", self.backend);
+        match (&self.error, self.latency_ms) {
+            (Some(err), _) => println!("Health: FAILED ({})", err),
+            (None, Some(latency_ms)) => println!("Health: OK ({} ms)", latency_ms),
+            (None, None) => println!("Health: OK"),
+        }
+        if let Some(pool) = &self.pool {
+            println!(
+                "Pool: {} of {} connections open, {} idle",
+                pool.connections, pool.max_size, pool.idle_connections
+            );
+        }
+        if self.healthy {
+            if self.pending_migrations.is_empty() {
+                println!("Migrations: up to date");
+            } else {
+                println!(
+                    "Migrations: pending for {}",
+                    self.pending_migrations.join(", ")
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn check_postgres(url: &str) -> DoctorReport {
+    check_database(
+        "PostgreSQL",
+        ConnectionManager::<PgConnection>::new(url),
+        |conn| {
+            let mut pending = vec![];
+            // `any_pending_*_migrations` returns true when the database is up to date
+            if !splinter::migrations::any_pending_postgres_migrations(conn)
+                .map_err(|err| err.to_string())?
+            {
+                pending.push("splinter");
+            }
+            if !scabbard::migrations::any_pending_postgres_migrations(conn)
+                .map_err(|err| err.to_string())?
+            {
+                pending.push("scabbard");
+            }
+            Ok(pending)
+        },
+    )
+}
+
+#[cfg(feature = "sqlite")]
+fn check_sqlite(connection_string: &str) -> DoctorReport {
+    // Connecting would create a missing database file, so check for it first
+    if connection_string != ":memory:" && !Path::new(connection_string).exists() {
+        return DoctorReport::unhealthy(
+            "SQLite",
+            format!("Database file '{}' does not exist", connection_string),
+        );
+    }
+
+    check_database(
+        "SQLite",
+        ConnectionManager::<SqliteConnection>::new(connection_string),
+        |conn| {
+            let mut pending = vec![];
+            // `any_pending_*_migrations` returns true when the database is up to date
+            if !splinter::migrations::any_pending_sqlite_migrations(conn)
+                .map_err(|err| err.to_string())?
+            {
+                pending.push("splinter");
+            }
+            if !scabbard::migrations::any_pending_sqlite_migrations(conn)
+                .map_err(|err| err.to_string())?
+            {
+                pending.push("scabbard");
+            }
+            Ok(pending)
+        },
+    )
+}
+
+/// Connects to the database and runs a trivial query, so that a database that cannot be reached is
+/// reported with the reason before any work that depends on it begins.
+pub(super) fn probe_database(url: &str) -> Result<(), CliError> {
+    let result = match ConnectionUri::from_str(url)? {
+        #[cfg(feature = "postgres")]
+        ConnectionUri::Postgres(url) => probe(ConnectionManager::<PgConnection>::new(url)),
+        #[cfg(feature = "sqlite")]
+        ConnectionUri::Sqlite(connection_string) => {
+            // Connecting would create a missing database file, so check for it first
+            if connection_string != ":memory:" && !Path::new(&connection_string).exists() {
+                Err(format!(
+                    "Database file '{}' does not exist",
+                    connection_string
+                ))
+            } else {
+                probe(ConnectionManager::<SqliteConnection>::new(
+                    connection_string,
+                ))
+            }
+        }
+    };
+
+    result.map_err(|reason| {
+        CliError::ActionError(format!("Cannot connect to database {}: {}", url, reason))
+    })
+}
+
+fn probe<C>(connection_manager: ConnectionManager<C>) -> Result<(), String>
+where
+    C: Connection + Send + 'static,
+{
+    let pool = Pool::builder()
+        .max_size(1)
+        .connection_timeout(CONNECTION_TIMEOUT)
+        .build(connection_manager)
+        .map_err(|err| format!("Unable to build connection pool: {}", err))?;
+    let conn = pool
+        .get()
+        .map_err(|err| format!("Unable to get connection: {}", err))?;
+    conn.batch_execute("SELECT 1")
+        .map_err(|err| format!("Unable to query database: {}", err))
+}
+
+/// Builds a connection pool, runs a trivial query on one of its connections and checks for
+/// pending migrations.
+fn check_database<C, F>(
+    backend: &'static str,
+    connection_manager: ConnectionManager<C>,
+    pending_migrations: F,
+) -> DoctorReport
+where
+    C: Connection + Send + 'static,
+    F: Fn(&C) -> Result<Vec<&'static str>, String>,
+{
+    let pool = match Pool::builder()
+        .connection_timeout(CONNECTION_TIMEOUT)
+        .build(connection_manager)
+    {
+        Ok(pool) => pool,
+        Err(err) => {
+            return DoctorReport::unhealthy(
+                backend,
+                format!("Unable to build connection pool: {}", err),
+            )
+        }
+    };
+
+    let start = Instant::now();
+    let conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(err) => {
+            return DoctorReport::unhealthy(backend, format!("Unable to get connection: {}", err))
+        }
+    };
+    if let Err(err) = conn.batch_execute("SELECT 1") {
+        return DoctorReport::unhealthy(backend, format!("Unable to query database: {}", err));
+    }
+    let latency_ms = start.elapsed().as_millis();
+
+    let state = pool.state();
+    let pool_stats = PoolStats {
+        max_size: pool.max_size(),
+        connections: state.connections,
+        idle_connections: state.idle_connections,
+    };
+
+    match pending_migrations(&*conn) {
+        Ok(pending_migrations) => DoctorReport {
+            backend,
+            healthy: true,
+            error: None,
+            latency_ms: Some(latency_ms),
+            pool: Some(pool_stats),
+            pending_migrations,
+        },
+        Err(err) => DoctorReport {
+            pool: Some(pool_stats),
+            ..DoctorReport::unhealthy(
+                backend,
+                format!("Unable to check for pending migrations: {}", err),
+            )
+        },
+    }
+}
@@ -0,0 +1,276 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the `splinter database check` command, which reports inconsistencies in the admin
+//! service store.
+
+use std::str::FromStr;
+
+use clap::ArgMatches;
+use serde::Serialize;
+use splinter::admin::store::{AdminServiceStore, Circuit};
+
+use crate::action::resolve_output_format;
+use crate::error::CliError;
+
+use super::{
+    get_default_database,
+    stores::{new_upgrade_stores, UpgradeStores},
+    Action, ConnectionUri,
+};
+
+pub struct StoreIntegrityCheckAction;
+
+impl Action for StoreIntegrityCheckAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let format = resolve_output_format(arg_matches, &["human", "json"])?;
+        let url = match arg_matches.and_then(|args| args.value_of("connect")) {
+            Some(url) => url.to_owned(),
+            None => get_default_database()?,
+        };
+
+        let stores = new_upgrade_stores(&ConnectionUri::from_str(&url)?)
+            .map_err(|err| CliError::ActionError(format!("Unable to open database: {}", err)))?;
+        let report = check_store(&*stores.new_admin_service_store())?;
+
+        if format == "json" {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).map_err(|err| CliError::ActionError(
+                    format!("Cannot format integrity report into json: {}", err)
+                ))?
+            );
+        } else {
+            report.print_human();
+        }
+
+        let issues = report.issue_count();
+        if issues > 0 {
+            Err(CliError::ActionError(format!(
+                "Found {} integrity issues in the admin service store",
+                issues
+            )))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The integrity issues found in an admin service store.
+#[derive(Debug, Default, Serialize)]
+struct IntegrityReport {
+    /// The circuit and service IDs of missing services that still have arguments
+    orphaned_service_arguments: Vec<OrphanedService>,
+    /// The endpoints registered for more than one node
+    duplicate_endpoints: Vec<DuplicateEndpoint>,
+    /// The IDs of circuits that have no members
+    circuits_without_members: Vec<String>,
+    /// The services run by a node that is not a member of the service's circuit
+    services_with_unknown_nodes: Vec<UnknownNodeService>,
+}
+
+#[derive(Debug, Serialize)]
+struct OrphanedService {
+    circuit_id: String,
+    service_id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DuplicateEndpoint {
+    endpoint: String,
+    node_ids: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct UnknownNodeService {
+    circuit_id: String,
+    service_id: String,
+    node_id: String,
+}
+
+impl IntegrityReport {
+    fn issue_count(&self) -> usize {
+        self.orphaned_service_arguments.len()
+            + self.duplicate_endpoints.len()
+            + self.circuits_without_members.len()
+            + self.services_with_unknown_nodes.len()
+    }
+
+    fn print_human(&self) {
+        if self.orphaned_service_arguments.is_empty() {
+            println!("Orphaned service arguments: none");
+        } else {
+            println!("Orphaned service arguments:");
+            for orphan in &self.orphaned_service_arguments {
+                println!("    {}::{}", orphan.circuit_id, orphan.service_id);
+            }
+        }
+
+        if self.duplicate_endpoints.is_empty() {
+            println!("Duplicate endpoints: none");
+        } else {
+            println!("Duplicate endpoints:");
+            for duplicate in &self.duplicate_endpoints {
+                println!(
+                    "    {} ({})",
+                    duplicate.endpoint,
+                    duplicate.node_ids.join(", ")
+                );
+            }
+        }
+
+        if self.circuits_without_members.is_empty() {
+            println!("Circuits without members: none");
+        } else {
+            println!("Circuits without members:");
+            for circuit_id in &self.circuits_without_members {
+                println!("    {}", circuit_id);
+            }
+        }
+
+        if self.services_with_unknown_nodes.is_empty() {
+            println!("Services with unknown nodes: none");
+        } else {
+            println!("Services with unknown nodes:");
+            for service in &self.services_with_unknown_nodes {
+                println!(
+                    "    {}::{} (node {})",
+                    service.circuit_id, service.service_id, service.node_id
+                );
+            }
+        }
+    }
+}
+
+/// Runs every integrity check against the store.
+fn check_store(store: &dyn AdminServiceStore) -> Result<IntegrityReport, CliError> {
+    let store_error =
+        |err| CliError::ActionError(format!("Unable to check the admin service store: {}", err));
+
+    let mut report = IntegrityReport {
+        orphaned_service_arguments: store
+            .find_orphaned_service_arguments()
+            .map_err(store_error)?
+            .into_iter()
+            .map(|(circuit_id, service_id)| OrphanedService {
+                circuit_id,
+                service_id,
+            })
+            .collect(),
+        duplicate_endpoints: store
+            .find_duplicate_endpoints()
+            .map_err(store_error)?
+            .into_iter()
+            .map(|(endpoint, node_ids)| DuplicateEndpoint { endpoint, node_ids })
+            .collect(),
+        ..Default::default()
+    };
+
+    let circuits = store.list_all_circuits().map_err(store_error)?;
+    check_circuits(circuits, &mut report);
+
+    Ok(report)
+}
+
+/// Checks the members and roster of each circuit, in the order the circuits are given.
+fn check_circuits<I>(circuits: I, report: &mut IntegrityReport)
+where
+    I: IntoIterator<Item = Circuit>,
+{
+    for circuit in circuits {
+        if circuit.members().is_empty() {
+            report
+                .circuits_without_members
+                .push(circuit.circuit_id().to_string());
+        }
+
+        for service in circuit.roster() {
+            if !circuit
+                .members()
+                .iter()
+                .any(|member| member.node_id() == service.node_id())
+            {
+                report.services_with_unknown_nodes.push(UnknownNodeService {
+                    circuit_id: circuit.circuit_id().to_string(),
+                    service_id: service.service_id().to_string(),
+                    node_id: service.node_id().to_string(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use splinter::admin::store::{
+        CircuitBuilder, CircuitNode, CircuitNodeBuilder, Service, ServiceBuilder,
+    };
+
+    /// Verify that circuits without members and services run by nodes outside of their circuit
+    /// are reported.
+    #[test]
+    fn test_check_circuits() {
+        let member = CircuitNodeBuilder::default()
+            .with_node_id("acme-node-000")
+            .with_endpoints(&["tcps://splinterd-node-acme:8044".to_string()])
+            .build()
+            .expect("Unable to build node");
+
+        let circuits = vec![
+            circuit("WBKLF-AAAAA", vec![member.clone()], "acme-node-000"),
+            circuit("WBKLF-BBBBB", vec![member], "bubba-node-000"),
+            circuit("WBKLF-CCCCC", vec![], "acme-node-000"),
+        ];
+
+        let mut report = IntegrityReport::default();
+        check_circuits(circuits, &mut report);
+
+        assert_eq!(report.circuits_without_members, vec!["WBKLF-CCCCC"]);
+        assert_eq!(
+            report.services_with_unknown_nodes,
+            vec![
+                UnknownNodeService {
+                    circuit_id: "WBKLF-BBBBB".into(),
+                    service_id: "a000".into(),
+                    node_id: "bubba-node-000".into(),
+                },
+                UnknownNodeService {
+                    circuit_id: "WBKLF-CCCCC".into(),
+                    service_id: "a000".into(),
+                    node_id: "acme-node-000".into(),
+                },
+            ]
+        );
+        assert_eq!(report.issue_count(), 3);
+    }
+
+    fn circuit(circuit_id: &str, members: Vec<CircuitNode>, service_node_id: &str) -> Circuit {
+        let service: Service = ServiceBuilder::default()
+            .with_service_id("a000")
+            .with_service_type("scabbard")
+            .with_node_id(service_node_id)
+            .build()
+            .expect("Unable to build service");
+
+        CircuitBuilder::default()
+            .with_circuit_id(circuit_id)
+            .with_roster(&[service])
+            .with_members(&members)
+            .with_circuit_management_type("test")
+            .build()
+            .expect("Unable to build circuit")
+    }
+}
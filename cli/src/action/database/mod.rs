@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod doctor;
+mod integrity;
 #[cfg(feature = "postgres")]
 mod postgres;
 #[cfg(feature = "sqlite")]
@@ -28,11 +30,13 @@ use std::{env, fs};
 
 use clap::ArgMatches;
 
+pub use self::doctor::DoctorAction;
+pub use self::integrity::StoreIntegrityCheckAction;
 #[cfg(not(feature = "sqlite"))]
 use self::postgres::get_default_database;
 #[cfg(feature = "sqlite")]
 use self::sqlite::{get_default_database, sqlite_migrations};
-pub use self::state::StateMigrateAction;
+pub use self::state::{StateLeavesAction, StateMigrateAction};
 #[cfg(feature = "upgrade")]
 pub use self::upgrade::UpgradeAction;
 use crate::error::CliError;
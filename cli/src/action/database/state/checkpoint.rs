@@ -0,0 +1,156 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the checkpoint file used to resume an interrupted migration
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use splinter::admin::store::ServiceId;
+
+use crate::error::CliError;
+
+/// A service whose state has been completely copied
+#[derive(Debug, Deserialize, Serialize)]
+struct CheckpointEntry {
+    circuit_id: String,
+    service_id: String,
+    complete: bool,
+    /// The state root of the copied tree
+    commit_hash: String,
+}
+
+/// The services a migration has completed, so that a migration that is run again after being
+/// interrupted does not copy them again.
+pub struct MigrationCheckpoint {
+    path: PathBuf,
+    entries: Vec<CheckpointEntry>,
+}
+
+impl MigrationCheckpoint {
+    /// Opens the checkpoint file at the given path, which is empty if the file does not exist.
+    pub fn open(path: &Path) -> Result<Self, CliError> {
+        let entries = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|err| {
+                CliError::EnvironmentError(format!(
+                    "Checkpoint file {} is invalid: {}",
+                    path.display(),
+                    err
+                ))
+            })?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+            Err(err) => {
+                return Err(CliError::EnvironmentError(format!(
+                    "Unable to read checkpoint file {}: {}",
+                    path.display(),
+                    err
+                )))
+            }
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// Returns the ending commit hash of the service's copy, if the copy was completed.
+    pub fn completed(&self, service_id: &ServiceId) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| {
+                entry.complete
+                    && entry.circuit_id == service_id.circuit()
+                    && entry.service_id == service_id.service_id()
+            })
+            .map(|entry| entry.commit_hash.as_str())
+    }
+
+    /// Records that the service's state was copied, ending at the given commit hash, replacing any
+    /// earlier record for the service.
+    pub fn record_complete(
+        &mut self,
+        service_id: &ServiceId,
+        commit_hash: &str,
+    ) -> Result<(), CliError> {
+        self.entries.retain(|entry| {
+            entry.circuit_id != service_id.circuit() || entry.service_id != service_id.service_id()
+        });
+        self.entries.push(CheckpointEntry {
+            circuit_id: service_id.circuit().to_string(),
+            service_id: service_id.service_id().to_string(),
+            complete: true,
+            commit_hash: commit_hash.to_string(),
+        });
+        self.write()
+    }
+
+    fn write(&self) -> Result<(), CliError> {
+        let contents = serde_json::to_string_pretty(&self.entries).map_err(|err| {
+            CliError::ActionError(format!("Cannot format checkpoint into json: {}", err))
+        })?;
+
+        // Write to a temporary file first, so that a crash while writing never leaves a partial
+        // checkpoint behind
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        fs::write(&temp_path, contents)
+            .and_then(|_| fs::rename(&temp_path, &self.path))
+            .map_err(|err| {
+                CliError::EnvironmentError(format!(
+                    "Unable to write checkpoint file {}: {}",
+                    self.path.display(),
+                    err
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::Builder;
+
+    /// Verify that completed services are persisted, and that recording a service again replaces
+    /// its earlier record.
+    #[test]
+    fn test_checkpoint() {
+        let temp_dir = Builder::new()
+            .prefix("test_checkpoint")
+            .tempdir()
+            .expect("Unable to create temp dir");
+        let path = temp_dir.path().join("checkpoint.json");
+
+        let service_a = ServiceId::new("abcde-01234".to_string(), "a000".to_string());
+        let service_b = ServiceId::new("abcde-01234".to_string(), "b000".to_string());
+
+        let mut checkpoint = MigrationCheckpoint::open(&path).expect("Unable to open checkpoint");
+        assert!(checkpoint.completed(&service_a).is_none());
+
+        checkpoint
+            .record_complete(&service_a, "root-a")
+            .expect("Unable to record");
+        checkpoint
+            .record_complete(&service_a, "root-b")
+            .expect("Unable to record");
+
+        let checkpoint = MigrationCheckpoint::open(&path).expect("Unable to reopen checkpoint");
+        assert_eq!(checkpoint.completed(&service_a), Some("root-b"));
+        assert!(checkpoint.completed(&service_b).is_none());
+        assert_eq!(checkpoint.entries.len(), 1);
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides a debugging command for listing the leaves of a scabbard service's state
+
+use std::str::FromStr;
+
+use clap::ArgMatches;
+use scabbard::store::transact::factory::LmdbDatabaseFactory;
+use splinter::admin::store::ServiceId;
+use transact::state::Reader;
+
+use crate::action::database::{
+    stores::{new_upgrade_stores, TransactionalUpgradeStores, UpgradeStoresWithLmdb},
+    ConnectionUri,
+};
+
+use super::{get_state_dir, Action, CliError};
+
+pub struct StateLeavesAction;
+
+impl Action for StateLeavesAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or(CliError::RequiresArgs)?;
+
+        let database_uri = args
+            .value_of("connect")
+            .ok_or_else(|| CliError::ActionError("'connect' argument is required".to_string()))?;
+        let circuit_id = args
+            .value_of("circuit_id")
+            .ok_or_else(|| CliError::ActionError("'circuit' argument is required".to_string()))?;
+        let service_id = args
+            .value_of("service_id")
+            .ok_or_else(|| CliError::ActionError("'service' argument is required".to_string()))?;
+        let prefix = args.value_of("prefix");
+
+        let service_id = ServiceId::new(circuit_id.to_string(), service_id.to_string());
+
+        let upgrade_stores =
+            new_upgrade_stores(&ConnectionUri::from_str(database_uri)?).map_err(|e| {
+                CliError::ActionError(format!(
+                    "Unable to get stores for database {}: {}",
+                    database_uri, e
+                ))
+            })?;
+
+        let upgrade_stores: Box<dyn TransactionalUpgradeStores> = if args.is_present("lmdb") {
+            let state_dir = get_state_dir(arg_matches)?;
+            Box::new(UpgradeStoresWithLmdb::new(
+                upgrade_stores,
+                LmdbDatabaseFactory::new_state_db_factory(&state_dir, None),
+            ))
+        } else {
+            upgrade_stores
+        };
+
+        let commit_hash = upgrade_stores
+            .new_commit_hash_store(&service_id)
+            .get_current_commit_hash()
+            .map_err(|e| CliError::ActionError(format!("{}", e)))?
+            .ok_or_else(|| {
+                CliError::ActionError(format!("No commit hash found for {}", service_id))
+            })?;
+
+        let merkle_state = upgrade_stores
+            .get_merkle_state(&service_id, false)
+            .map_err(|e| CliError::ActionError(e.to_string()))?;
+
+        // Both iterators produce the same set of leaves; only the order differs
+        let leaves = if args.is_present("reverse") {
+            merkle_state.filter_iter_reversed(&commit_hash, prefix)
+        } else {
+            merkle_state.filter_iter(&commit_hash, prefix)
+        }
+        .map_err(|e| CliError::ActionError(format!("Unable to get leaves: {}", e)))?;
+
+        for leaf in leaves {
+            let (address, value) =
+                leaf.map_err(|e| CliError::ActionError(format!("Unable to read leaf: {}", e)))?;
+            println!("{} ({} bytes)", address, value.len());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the lock file that prevents concurrent state migrations in a state directory
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::CliError;
+
+const LOCK_FILE_NAME: &str = "state-migrate.lock";
+
+/// An exclusive lock on a state directory, held for the duration of a migration.
+///
+/// The lock file records the ID of the process holding it and when it was acquired. The file is
+/// removed when the lock is dropped.
+pub struct MigrationLock {
+    path: PathBuf,
+}
+
+impl MigrationLock {
+    /// Acquires the lock for the given state directory.
+    ///
+    /// A lock held by a process that is no longer running is replaced. If `force_unlock` is
+    /// true, an existing lock is removed regardless of whether its holder is still running.
+    pub fn acquire(state_dir: &Path, force_unlock: bool) -> Result<Self, CliError> {
+        let path = state_dir.join(LOCK_FILE_NAME);
+
+        if let Some(holder) = LockHolder::read(&path)? {
+            if force_unlock {
+                warn!(
+                    "Removing state migration lock held by process {} since {}",
+                    holder.pid, holder.acquired
+                );
+                remove_lock_file(&path)?;
+            } else if !holder.is_running() {
+                warn!(
+                    "Removing stale state migration lock held by process {} since {}; the \
+                    process is no longer running",
+                    holder.pid, holder.acquired
+                );
+                remove_lock_file(&path)?;
+            }
+        }
+
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                let holder = LockHolder::read(&path)?
+                    .map(|holder| format!(" by process {} since {}", holder.pid, holder.acquired))
+                    .unwrap_or_default();
+                return Err(CliError::EnvironmentError(format!(
+                    "Another state migration is in progress; {} is held{}. If that migration \
+                    is no longer running, use --force-unlock to remove the lock",
+                    path.display(),
+                    holder
+                )));
+            }
+            Err(err) => {
+                return Err(CliError::EnvironmentError(format!(
+                    "Unable to create lock file {}: {}",
+                    path.display(),
+                    err
+                )))
+            }
+        };
+
+        let acquired = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        writeln!(file, "pid={}\nacquired={}", process::id(), acquired)
+            .and_then(|_| file.sync_all())
+            .map_err(|err| {
+                // The lock is unusable without its holder, so do not leave it behind
+                let _ = fs::remove_file(&path);
+                CliError::EnvironmentError(format!(
+                    "Unable to write lock file {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+
+        Ok(Self { path })
+    }
+}
+
+impl Drop for MigrationLock {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!(
+                "Unable to remove lock file {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+/// The contents of an existing lock file
+struct LockHolder {
+    pid: u32,
+    /// Seconds since the Unix epoch
+    acquired: u64,
+}
+
+impl LockHolder {
+    /// Reads the lock file at the given path, returning `None` if it does not exist.
+    fn read(path: &Path) -> Result<Option<Self>, CliError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(CliError::EnvironmentError(format!(
+                    "Unable to read lock file {}: {}",
+                    path.display(),
+                    err
+                )))
+            }
+        };
+
+        let mut pid = None;
+        let mut acquired = None;
+        for line in contents.lines() {
+            match line.split_once('=') {
+                Some(("pid", value)) => pid = value.trim().parse().ok(),
+                Some(("acquired", value)) => acquired = value.trim().parse().ok(),
+                _ => (),
+            }
+        }
+
+        match (pid, acquired) {
+            (Some(pid), Some(acquired)) => Ok(Some(Self { pid, acquired })),
+            _ => Err(CliError::EnvironmentError(format!(
+                "Lock file {} is invalid; if no other state migration is running, use \
+                --force-unlock to remove it",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Returns whether the process holding the lock is still running.
+    fn is_running(&self) -> bool {
+        // Signal 0 only checks whether the process exists; permission errors mean it does
+        let result = unsafe { libc::kill(self.pid as libc::pid_t, 0) };
+        result == 0 || io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+    }
+}
+
+fn remove_lock_file(path: &Path) -> Result<(), CliError> {
+    fs::remove_file(path).map_err(|err| {
+        CliError::EnvironmentError(format!(
+            "Unable to remove lock file {}: {}",
+            path.display(),
+            err
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::Builder;
+
+    /// Verify that the lock cannot be acquired twice, and that it is released when dropped.
+    #[test]
+    fn test_lock_exclusive() {
+        let temp_dir = Builder::new()
+            .prefix("test_lock_exclusive")
+            .tempdir()
+            .expect("Unable to create temp dir");
+
+        let lock = MigrationLock::acquire(temp_dir.path(), false).expect("Unable to lock");
+        assert!(temp_dir.path().join(LOCK_FILE_NAME).exists());
+
+        match MigrationLock::acquire(temp_dir.path(), false) {
+            Err(CliError::EnvironmentError(msg)) => {
+                assert!(msg.contains(&format!("by process {}", process::id())))
+            }
+            Err(err) => panic!("Unexpected error: {}", err),
+            Ok(_) => panic!("Lock was acquired twice"),
+        }
+
+        drop(lock);
+        assert!(!temp_dir.path().join(LOCK_FILE_NAME).exists());
+
+        MigrationLock::acquire(temp_dir.path(), false).expect("Unable to lock after release");
+    }
+
+    /// Verify that a lock held by a process that is no longer running is replaced, and that a
+    /// lock held by a running process is only replaced with `force_unlock`.
+    #[test]
+    fn test_lock_stale_and_forced() {
+        let temp_dir = Builder::new()
+            .prefix("test_lock_stale_and_forced")
+            .tempdir()
+            .expect("Unable to create temp dir");
+        let path = temp_dir.path().join(LOCK_FILE_NAME);
+
+        // No process can have the largest possible ID
+        fs::write(&path, format!("pid={}\nacquired=0\n", libc::pid_t::MAX))
+            .expect("Unable to write lock file");
+        let lock = MigrationLock::acquire(temp_dir.path(), false).expect("Unable to lock");
+        drop(lock);
+
+        fs::write(&path, format!("pid={}\nacquired=0\n", process::id()))
+            .expect("Unable to write lock file");
+        assert!(MigrationLock::acquire(temp_dir.path(), false).is_err());
+        MigrationLock::acquire(temp_dir.path(), true).expect("Unable to force unlock");
+    }
+}
@@ -17,8 +17,9 @@ mod lmdb;
 use std::collections::HashMap;
 
 use diesel::r2d2::{ConnectionManager, Pool};
+use scabbard::state_migration::MigrationState;
 use scabbard::store::transact::factory::LmdbDatabaseFactory;
-use splinter::error::InternalError;
+use splinter::{admin::store::ServiceId, error::InternalError};
 use transact::state::{
     merkle::sql::{
         backend,
@@ -149,6 +150,25 @@ impl<'a> MerkleState<'a> {
                 .map_err(|e| CliError::ActionError(format!("{}", e))),
         }
     }
+
+    /// Returns the same leaves as `filter_iter`, in reverse key order.
+    ///
+    /// The underlying merkle trees only iterate forward, so all matching leaves, values included,
+    /// are read into memory before the first one is returned; memory use grows with the number
+    /// and size of the matching leaves. This is intended for debugging; prefer `filter_iter` when
+    /// the order does not matter, and provide a filter when reading a large tree.
+    pub fn filter_iter_reversed(
+        &self,
+        state_id: &str,
+        filter: Option<&str>,
+    ) -> ValueIterResult<ValueIter<(String, Vec<u8>)>> {
+        let mut leaves = self
+            .filter_iter(&state_id.to_string(), filter)?
+            .collect::<Vec<_>>();
+        leaves.reverse();
+
+        Ok(Box::new(leaves.into_iter()))
+    }
 }
 
 impl<'a> State for MerkleState<'a> {
@@ -262,7 +282,51 @@ impl<'a> Pruner for MerkleState<'a> {
     }
 }
 
-#[cfg(any(feature = "postgres", feature = "sqlite"))]
+impl<'a> MigrationState for MerkleState<'a> {
+    fn state_root(&self) -> Result<String, InternalError> {
+        self.get_state_root()
+            .map_err(|e| InternalError::from_source(Box::new(e)))
+    }
+
+    fn leaves(
+        &self,
+        state_id: &str,
+        prefix: Option<&str>,
+    ) -> ValueIterResult<ValueIter<(String, Vec<u8>)>> {
+        self.filter_iter(&state_id.to_string(), prefix)
+    }
+
+    fn get_leaves(
+        &self,
+        state_id: &str,
+        addresses: &[String],
+    ) -> Result<HashMap<String, Vec<u8>>, StateError> {
+        self.get(&state_id.to_string(), addresses)
+    }
+
+    fn commit_changes(
+        &self,
+        state_id: &str,
+        state_changes: &[StateChange],
+    ) -> Result<String, StateError> {
+        self.commit(&state_id.to_string(), state_changes)
+    }
+
+    fn prune_state(&self, state_id: &str) -> Result<(), InternalError> {
+        self.prune(vec![state_id.to_string()]).map_err(|e| {
+            InternalError::with_message(format!("Unable to purge previous commit hash {}", e))
+        })?;
+
+        self.remove_pruned_entries().map_err(|e| {
+            InternalError::with_message(format!("Unable to remove pruned entries {}", e))
+        })
+    }
+
+    fn delete_tree(self: Box<Self>) -> Result<(), InternalError> {
+        MerkleState::delete_tree(*self).map_err(|e| InternalError::from_source(Box::new(e)))
+    }
+}
+
 pub struct DieselStateTreeStore<C: diesel::Connection + 'static> {
     pool: Pool<ConnectionManager<C>>,
 }
@@ -276,9 +340,9 @@ impl<C: diesel::Connection + 'static> DieselStateTreeStore<C> {
 
 #[cfg(feature = "sqlite")]
 impl StateTreeStore for DieselStateTreeStore<diesel::SqliteConnection> {
-    fn has_tree(&self, circuit_id: &str, service_id: &str) -> Result<bool, InternalError> {
+    fn has_tree(&self, service_id: &ServiceId) -> Result<bool, InternalError> {
         let sqlite_backend = backend::SqliteBackend::from(self.pool.clone());
-        let tree_name = format!("{}::{}", circuit_id, service_id);
+        let tree_name = service_id.to_string();
         let iter = SqlMerkleRadixStore::new(&sqlite_backend)
             .list_trees()
             .map_err(|e| InternalError::from_source(Box::new(e)))?;
@@ -294,9 +358,9 @@ impl StateTreeStore for DieselStateTreeStore<diesel::SqliteConnection> {
 
 #[cfg(feature = "postgres")]
 impl StateTreeStore for DieselStateTreeStore<diesel::pg::PgConnection> {
-    fn has_tree(&self, circuit_id: &str, service_id: &str) -> Result<bool, InternalError> {
+    fn has_tree(&self, service_id: &ServiceId) -> Result<bool, InternalError> {
         let postgres_backend = backend::PostgresBackend::from(self.pool.clone());
-        let tree_name = format!("{}::{}", circuit_id, service_id);
+        let tree_name = service_id.to_string();
         let iter = SqlMerkleRadixStore::new(&postgres_backend)
             .list_trees()
             .map_err(|e| InternalError::from_source(Box::new(e)))?;
@@ -324,9 +388,9 @@ impl<'a, C: diesel::Connection> DieselInTransactionStateTreeStore<'a, C> {
 
 #[cfg(feature = "postgres")]
 impl<'a> StateTreeStore for DieselInTransactionStateTreeStore<'a, diesel::pg::PgConnection> {
-    fn has_tree(&self, circuit_id: &str, service_id: &str) -> Result<bool, InternalError> {
+    fn has_tree(&self, service_id: &ServiceId) -> Result<bool, InternalError> {
         let postgres_backend = backend::InTransactionPostgresBackend::from(self.conn);
-        let tree_name = format!("{}::{}", circuit_id, service_id);
+        let tree_name = service_id.to_string();
         let iter = SqlMerkleRadixStore::new(&postgres_backend)
             .list_trees()
             .map_err(|e| InternalError::from_source(Box::new(e)))?;
@@ -342,9 +406,9 @@ impl<'a> StateTreeStore for DieselInTransactionStateTreeStore<'a, diesel::pg::Pg
 
 #[cfg(feature = "sqlite")]
 impl<'a> StateTreeStore for DieselInTransactionStateTreeStore<'a, diesel::SqliteConnection> {
-    fn has_tree(&self, circuit_id: &str, service_id: &str) -> Result<bool, InternalError> {
+    fn has_tree(&self, service_id: &ServiceId) -> Result<bool, InternalError> {
         let sqlite_backend = backend::InTransactionSqliteBackend::from(self.conn);
-        let tree_name = format!("{}::{}", circuit_id, service_id);
+        let tree_name = service_id.to_string();
         let iter = SqlMerkleRadixStore::new(&sqlite_backend)
             .list_trees()
             .map_err(|e| InternalError::from_source(Box::new(e)))?;
@@ -369,13 +433,87 @@ impl LmdbStateTreeStore {
 }
 
 impl StateTreeStore for LmdbStateTreeStore {
-    fn has_tree(&self, circuit_id: &str, service_id: &str) -> Result<bool, InternalError> {
+    fn has_tree(&self, service_id: &ServiceId) -> Result<bool, InternalError> {
         let path = self
             .lmdb_db_factory
-            .compute_path(circuit_id, service_id)
+            .compute_path(service_id.circuit(), service_id.service_id())
             .map_err(|e| InternalError::from_source(Box::new(e)))?
             .with_extension("lmdb");
 
         Ok(path.is_file())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that the reversed iterator returns the leaves matching the filter, in descending
+    /// key order.
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_filter_iter_reversed() {
+        use diesel::SqliteConnection;
+        use transact::state::merkle::sql::{
+            migration::run_sqlite_migrations, SqlMerkleStateBuilder,
+        };
+
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Unable to build connection pool");
+        run_sqlite_migrations(&*pool.get().expect("Unable to get connection"))
+            .expect("Unable to run migrations");
+
+        let state = MerkleState::Sqlite {
+            state: SqlMerkleStateBuilder::new()
+                .with_backend(backend::SqliteBackend::from(pool))
+                .with_tree("test".to_string())
+                .create_tree_if_necessary()
+                .build()
+                .expect("Unable to build merkle state"),
+        };
+
+        let initial_root = state.get_state_root().expect("Unable to get state root");
+        let keys = (0..4u32)
+            .flat_map(|i| vec![format!("aa{:068x}", i), format!("bb{:068x}", i)])
+            .collect::<Vec<_>>();
+        let state_root = state
+            .commit(
+                &initial_root,
+                &keys
+                    .iter()
+                    .map(|key| StateChange::Set {
+                        key: key.clone(),
+                        value: key.as_bytes().to_vec(),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .expect("Unable to commit leaves");
+
+        let reversed = state
+            .filter_iter_reversed(&state_root, Some("aa"))
+            .expect("Unable to iterate leaves")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Unable to read leaves");
+
+        let mut expected = keys
+            .iter()
+            .filter(|key| key.starts_with("aa"))
+            .map(|key| (key.clone(), key.as_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        expected.sort();
+        expected.reverse();
+        assert_eq!(reversed, expected);
+
+        let all = state
+            .filter_iter_reversed(&state_root, None)
+            .expect("Unable to iterate leaves")
+            .map(|leaf| leaf.expect("Unable to read leaf").0)
+            .collect::<Vec<_>>();
+        let mut expected = keys;
+        expected.sort();
+        expected.reverse();
+        assert_eq!(all, expected);
+    }
+}
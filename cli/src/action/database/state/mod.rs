@@ -14,57 +14,204 @@
 
 //! Provides scabbard state migration functionality
 
+mod checkpoint;
+mod leaves;
+mod lock;
+mod manifest;
 mod merkle;
+mod progress;
+mod report;
+mod snapshot;
 
+use std::cell::{Cell, RefCell};
+use std::env;
+use std::fs;
 use std::io;
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use clap::ArgMatches;
+use scabbard::state_migration::{
+    migrate_service, verify_copy, Batching, CopyObserver, ServiceCopy, DEFAULT_BATCH_SIZE,
+};
 use scabbard::store::transact::factory::LmdbDatabaseFactory;
-use splinter::error::InternalError;
-use transact::state::{Committer, Pruner, Reader, StateChange};
+use splinter::{admin::store::ServiceId, error::InternalError};
+use transact::state::Reader;
 
 use crate::action::database::{
-    stores::{new_upgrade_stores, UpgradeStoresWithLmdb},
+    doctor::probe_database,
+    get_default_database,
+    stores::{
+        new_upgrade_stores, MigrationStores, TransactionalUpgradeStores, UpgradeStores,
+        UpgradeStoresWithLmdb,
+    },
     ConnectionUri, SplinterEnvironment,
 };
+use crate::config::CliConfig;
 
 use super::{Action, CliError};
 
+use self::checkpoint::MigrationCheckpoint;
+use self::lock::MigrationLock;
+use self::manifest::{MigrationJob, MigrationManifest};
+use self::progress::ProgressEvents;
+use self::report::{
+    print_report, JobOutcome, MigrationReport, ReportFormat, ServiceMigrationResult,
+    ServiceReadiness, SkipReason, SkippedService, VerificationResult,
+};
+use self::snapshot::SnapshotLog;
+
+pub use self::leaves::StateLeavesAction;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
 pub use self::merkle::{DieselInTransactionStateTreeStore, DieselStateTreeStore};
 pub use self::merkle::{LazyLmdbMerkleState, LmdbStateTreeStore, MerkleState};
 
 /// A source of available trees
 pub trait StateTreeStore {
-    fn has_tree(&self, circuit_id: &str, service_id: &str) -> Result<bool, InternalError>;
+    fn has_tree(&self, service_id: &ServiceId) -> Result<bool, InternalError>;
 }
 
 pub struct StateMigrateAction;
 
 impl Action for StateMigrateAction {
     fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
-        let state_dir =
+        let default_state_dir =
             get_state_dir(arg_matches).map_err(|e| CliError::ActionError(format!("{}", e)))?;
-        let lmdb_db_factory = LmdbDatabaseFactory::new_state_db_factory(&state_dir, None);
 
         let args = arg_matches.ok_or(CliError::RequiresArgs)?;
-        let mut in_database = args
-            .value_of("in")
-            .ok_or_else(|| CliError::ActionError("'in' argument is required".to_string()))?;
 
-        let mut out_database = args
-            .value_of("out")
-            .ok_or_else(|| CliError::ActionError("'out' argument is required".to_string()))?;
+        let report_format = ReportFormat::from_arg(args.value_of("report_format"))?;
 
-        info!(
-            "Attempting to migrate scabbard state from {} to {}",
-            in_database, out_database
-        );
+        let deadline = args.value_of("deadline").map(parse_deadline).transpose()?;
+
+        let prefix_file = args
+            .value_of("prefix_file")
+            .map(|path| read_prefix_file(Path::new(path)))
+            .transpose()?;
+        let prefixes = match &prefix_file {
+            Some(prefixes) => prefixes.iter().map(String::as_str).collect(),
+            None => args.value_of("prefix").into_iter().collect(),
+        };
 
-        if !args.is_present("yes") && !args.is_present("dry_run") {
+        // Progress events are streamed to a supervising process as the migration runs
+        let progress_events = match (
+            args.value_of("progress_fd"),
+            args.value_of("progress_socket"),
+        ) {
+            (Some(fd), _) => Some(ProgressEvents::from_fd(fd)?),
+            (None, Some(path)) => Some(ProgressEvents::connect(Path::new(path))?),
+            (None, None) => None,
+        };
+
+        let mut options = MigrateOptions {
+            dry_run: args.is_present("dry_run"),
+            force: args.is_present("force"),
+            skip_existing: args.is_present("skip_existing"),
+            progress: args.is_present("progress"),
+            prefixes,
+            skip_root_check: args.is_present("skip_root_check"),
+            verify: args.is_present("verify"),
+            detail: LogDetail::from_arg(args.value_of("detail"))?,
+            force_unlock: args.is_present("force_unlock"),
+            restore_root: args.value_of("restore_root"),
+            in_state_dir: args.value_of("in_state_dir").map(Path::new),
+            out_state_dir: args.value_of("out_state_dir").map(Path::new),
+            batching: Batching {
+                size: args
+                    .value_of("batch_size")
+                    .map(|size| match size.parse::<usize>() {
+                        Ok(size) if (1..=MAX_BATCH_SIZE).contains(&size) => Ok(size),
+                        _ => Err(CliError::ActionError(format!(
+                            "--batch-size must be between 1 and {}: {}",
+                            MAX_BATCH_SIZE, size
+                        ))),
+                    })
+                    .transpose()?
+                    .unwrap_or(DEFAULT_BATCH_SIZE),
+                flush_idle: args
+                    .value_of("flush_idle_ms")
+                    .map(|millis| {
+                        millis
+                            .parse::<u64>()
+                            .map(Duration::from_millis)
+                            .map_err(|_| {
+                                CliError::ActionError(format!(
+                                    "--flush-idle-ms must be a number of milliseconds: {}",
+                                    millis
+                                ))
+                            })
+                    })
+                    .transpose()?,
+                paranoid: args.is_present("paranoid"),
+            },
+            progress_interval: match args.value_of("progress_interval") {
+                Some(secs) => match secs.parse::<u64>() {
+                    Ok(0) => None,
+                    Ok(secs) => Some(Duration::from_secs(secs)),
+                    Err(_) => {
+                        return Err(CliError::ActionError(format!(
+                            "--progress-interval must be a number of seconds: {}",
+                            secs
+                        )))
+                    }
+                },
+                None => Some(DEFAULT_PROGRESS_INTERVAL),
+            },
+            verify_jobs: args
+                .value_of("jobs")
+                .map(|jobs| match jobs.parse::<usize>() {
+                    Ok(jobs) if jobs > 0 => Ok(jobs),
+                    _ => Err(CliError::ActionError(format!(
+                        "--jobs must be a positive integer: {}",
+                        jobs
+                    ))),
+                })
+                .transpose()?,
+            deadline: None,
+            confirm_count: false,
+            progress_events: progress_events.as_ref(),
+        };
+
+        // A prefix copy only copies part of each tree and never purges the input state
+        if options.skip_root_check && options.prefixes.is_empty() {
+            return Err(CliError::ActionError(
+                "--skip-root-check can only be used with --prefix or --prefix-file".to_string(),
+            ));
+        }
+
+        let batch = args.value_of("manifest").is_some();
+        let jobs = match args.value_of("manifest") {
+            Some(manifest) => MigrationManifest::from_path(Path::new(manifest))?.into_jobs(),
+            None => vec![MigrationJob::from_args(args)?],
+        };
+        let continue_on_error = args.is_present("continue_on_error");
+
+        let mut report = MigrationReport::start(options.dry_run);
+
+        // Services completed by an earlier run with the same checkpoint file are not copied again
+        let mut checkpoint = args
+            .value_of("checkpoint_file")
+            .map(|path| MigrationCheckpoint::open(Path::new(path)))
+            .transpose()?;
+
+        // A restore only writes to the in database, so it does not purge anything
+        let purges =
+            !options.dry_run && options.prefixes.is_empty() && options.restore_root.is_none();
+
+        // The `--yes` and `--confirm-count` flags take precedence over the environment and the
+        // config file, which are only consulted when the migration would otherwise prompt
+        let confirm_count = args.is_present("confirm_count");
+        let assume_yes =
+            args.is_present("yes") || (purges && !confirm_count && configured_assume_yes()?);
+
+        if !assume_yes && purges && confirm_count {
+            // The number of services is only known once each job's circuits have been read, so
+            // each job asks for it before migrating
+            options.confirm_count = true;
+        } else if !assume_yes && purges {
             warn!(
                 "Warning: This will purge the data from `--in` and only the current state \
                 root is stored, the rest are purged."
@@ -88,204 +235,1011 @@ impl Action for StateMigrateAction {
             }
         }
 
-        // used to check for LMDBM regardless of capitalization
-        let lower_in_database = in_database.to_string().to_lowercase();
-        let lower_out_database = out_database.to_string().to_lowercase();
+        // The deadline covers the migration itself, not the time spent at the prompt
+        options.deadline = deadline.map(|deadline| Instant::now() + deadline);
+
+        // Jobs run one at a time; with `--continue-on-error`, a failed job does not stop the
+        // jobs after it
+        for job in &jobs {
+            if batch {
+                info!("Running migration job {}", job.name());
+            }
+
+            let result = migrate_job(
+                job,
+                &default_state_dir,
+                &options,
+                checkpoint.as_mut(),
+                &mut report,
+            );
+
+            match result {
+                Ok(()) => {
+                    if batch {
+                        report.record_job(JobOutcome::succeeded(job.name()));
+                    }
+                }
+                Err(err) if batch && continue_on_error => {
+                    error!("Migration job {} failed: {}", job.name(), err);
+                    report.record_job(JobOutcome::failed(job.name(), err.to_string()));
+                }
+                Err(err) => {
+                    if batch {
+                        report.record_job(JobOutcome::failed(job.name(), err.to_string()));
+                    }
+                    if let Some(events) = &progress_events {
+                        events.run_finished(report.finish(), Some(&err.to_string()));
+                    }
+                    print_report(report_format, report)?;
+                    return Err(err);
+                }
+            }
+        }
+
+        let total_jobs = report.jobs().len();
+        let failed_jobs = report
+            .jobs()
+            .iter()
+            .filter(|outcome| !outcome.is_success())
+            .count();
+
+        if let Some(events) = &progress_events {
+            let error = match failed_jobs {
+                0 => None,
+                _ => Some(format!(
+                    "{} of {} migration jobs failed",
+                    failed_jobs, total_jobs
+                )),
+            };
+            events.run_finished(report.finish(), error.as_deref());
+        }
+
+        print_report(report_format, report)?;
+
+        if failed_jobs > 0 {
+            return Err(CliError::ActionError(format!(
+                "{} of {} migration jobs failed",
+                failed_jobs, total_jobs
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// How much the migration logs about each service
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+enum LogDetail {
+    /// Only the start and finish of each migration are logged
+    Summary,
+    /// Each service is logged as it is migrated or skipped
+    Service,
+    /// Each batch of leaves written is also logged
+    Batch,
+}
+
+impl LogDetail {
+    fn from_arg(detail: Option<&str>) -> Result<Self, CliError> {
+        match detail {
+            Some("summary") => Ok(LogDetail::Summary),
+            None | Some("service") => Ok(LogDetail::Service),
+            Some("batch") => Ok(LogDetail::Batch),
+            Some(detail) => Err(CliError::ActionError(format!(
+                "Unsupported log detail: {}",
+                detail
+            ))),
+        }
+    }
+}
+
+/// The options that apply to every migration job
+struct MigrateOptions<'a> {
+    dry_run: bool,
+    force: bool,
+    skip_existing: bool,
+    progress: bool,
+    /// If not empty, only the leaves whose address starts with one of these prefixes are copied,
+    /// and the input state is never deleted
+    prefixes: Vec<&'a str>,
+    skip_root_check: bool,
+    verify: bool,
+    detail: LogDetail,
+    force_unlock: bool,
+    /// The state root to restore to the in database, instead of migrating
+    restore_root: Option<&'a str>,
+    /// The state directory of the LMDB files read when `--in` is `lmdb`, if it is not the job's
+    /// state directory
+    in_state_dir: Option<&'a Path>,
+    /// The state directory of the LMDB files written when `--out` is `lmdb`, if it is not the
+    /// job's state directory
+    out_state_dir: Option<&'a Path>,
+    /// When the pending batch of leaves is written while copying a tree
+    batching: Batching,
+    /// How often the number of leaves copied so far is logged while copying a tree; `None` if
+    /// it is never logged
+    progress_interval: Option<Duration>,
+    /// The number of threads verifying copies once all services have been copied; `None` if
+    /// each copy is verified as it is made
+    verify_jobs: Option<usize>,
+    /// The time after which no further services or batches of leaves are copied
+    deadline: Option<Instant>,
+    /// Whether the operator must type the number of services a job will migrate before the job
+    /// migrates them
+    confirm_count: bool,
+    /// Where progress events are streamed as the migration runs, if anywhere
+    progress_events: Option<&'a ProgressEvents>,
+}
+
+impl<'a> MigrateOptions<'a> {
+    fn deadline_passed(&self) -> bool {
+        deadline_passed(self.deadline)
+    }
+}
+
+fn deadline_passed(deadline: Option<Instant>) -> bool {
+    matches!(deadline, Some(deadline) if Instant::now() >= deadline)
+}
+
+/// Asks the operator to type the number of services that will be migrated, returning an error
+/// if the number typed does not match `service_count`
+fn confirm_service_count(service_count: usize) -> Result<(), CliError> {
+    warn!(
+        "Warning: This will purge the data from `--in` for {} scabbard services and only the \
+        current state root is stored, the rest are purged.",
+        service_count
+    );
+    warn!("Type the number of services to migrate to continue:");
+
+    let stdin = io::stdin();
+    let line = stdin.lock().lines().next();
+    match line {
+        Some(Ok(input)) if input.trim() == service_count.to_string() => Ok(()),
+        Some(Ok(input)) => Err(CliError::ActionError(format!(
+            "Migration cancelled: {} services would be migrated, not {}",
+            service_count,
+            input.trim()
+        ))),
+        _ => Err(CliError::ActionError(
+            "Unable to get prompt response".to_string(),
+        )),
+    }
+}
+
+/// Returns whether the migration should proceed without prompting, as set by the
+/// `SPLINTER_STATE_MIGRATE_ASSUME_YES` environment variable or, if it is not set, by the
+/// `state_migrate.assume_yes` setting in the CLI config file
+fn configured_assume_yes() -> Result<bool, CliError> {
+    match env::var(STATE_MIGRATE_ASSUME_YES_ENV) {
+        Ok(value) => value.parse::<bool>().map_err(|_| {
+            CliError::EnvironmentError(format!(
+                "{} must be true or false: {}",
+                STATE_MIGRATE_ASSUME_YES_ENV, value
+            ))
+        }),
+        Err(_) => Ok(CliConfig::load_or_default().state_migrate().assume_yes()),
+    }
+}
+
+/// Parses the value of `--deadline`: a number of seconds, optionally followed by a unit of `s`,
+/// `m` or `h`, e.g. `90`, `45m` or `2h`
+fn parse_deadline(deadline: &str) -> Result<Duration, CliError> {
+    let (number, multiplier) = match deadline.char_indices().last() {
+        Some((index, 's')) => (&deadline[..index], 1),
+        Some((index, 'm')) => (&deadline[..index], 60),
+        Some((index, 'h')) => (&deadline[..index], 60 * 60),
+        _ => (deadline, 1),
+    };
+
+    match number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+    {
+        Some(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+        _ => Err(CliError::ActionError(format!(
+            "--deadline must be a positive number of seconds, minutes (m) or hours (h): {}",
+            deadline
+        ))),
+    }
+}
 
-        // Get the database uri that will be used for getting the circuit information. If lmdb
-        // is the target directory, we need to use the URI for the in database, otherwise the
-        // out database is used.
-        let database_uri = match (lower_in_database.as_str(), lower_out_database.as_str()) {
+/// Migrates the state of the local scabbard services for a single job, recording the result of
+/// each service in the given report. A dry run records the readiness of each service instead,
+/// and a migration verified with `--jobs` also records the verification of each service.
+///
+/// If a checkpoint is provided, each service is recorded in it once migrated, and services it
+/// already records as complete are skipped.
+fn migrate_job(
+    job: &MigrationJob,
+    default_state_dir: &Path,
+    options: &MigrateOptions,
+    mut checkpoint: Option<&mut MigrationCheckpoint>,
+    report: &mut MigrationReport,
+) -> Result<(), CliError> {
+    let job_state_dir = job.state_dir().unwrap_or(default_state_dir);
+    let in_state_dir = options.in_state_dir.unwrap_or(job_state_dir);
+    let out_state_dir = options.out_state_dir.unwrap_or(job_state_dir);
+    // With `--jobs`, copies are verified in parallel once every service has been copied, so the
+    // input trees are kept until then
+    let deferred_verify = options.verify && options.verify_jobs.is_some();
+    let mut pending_verification = vec![];
+
+    let mut in_database = job.in_database();
+    let mut out_database = job.out_database();
+
+    info!(
+        "Attempting to migrate scabbard state from {} to {}",
+        in_database, out_database
+    );
+
+    // used to check for LMDBM regardless of capitalization
+    let lower_in_database = in_database.to_string().to_lowercase();
+    let lower_out_database = out_database.to_string().to_lowercase();
+
+    // Get the database uri that will be used for getting the circuit information. If lmdb
+    // is the target directory, we need to use the URI for the in database, otherwise the
+    // out database is used.
+    //
+    // The state directories holding the LMDB files are also collected; the first is the one in
+    // which the snapshot log is kept.
+    let (database_uri, lmdb_state_dirs) =
+        match (lower_in_database.as_str(), lower_out_database.as_str()) {
+            // The circuit information for an LMDB to LMDB migration comes from the node's database
             ("lmdb", "lmdb") => {
-                return Err(CliError::ActionError(
-                    "LMDB to LMDB is not supported".to_string(),
-                ))
+                in_database = lower_in_database.as_str();
+                out_database = lower_out_database.as_str();
+                if same_dir(in_state_dir, out_state_dir) {
+                    return Err(CliError::ActionError(format!(
+                    "LMDB to LMDB requires different in and out state directories; both are {}. \
+                    Use --in-state-dir and --out-state-dir",
+                    in_state_dir.display()
+                )));
+                }
+                (get_default_database()?, vec![in_state_dir, out_state_dir])
             }
             (_, "lmdb") => {
                 out_database = lower_out_database.as_str();
-                in_database.to_string()
+                (in_database.to_string(), vec![out_state_dir])
             }
             ("lmdb", _) => {
                 in_database = lower_in_database.as_str();
-                out_database.to_string()
+                (out_database.to_string(), vec![in_state_dir])
             }
+            // Neither side is LMDB, e.g. postgres to sqlite
             (_, _) => {
-                return Err(CliError::ActionError(
-                    "Command only supports moving state to or from LMDB".to_string(),
-                ))
+                return Err(CliError::ActionError(format!(
+                    "Direct database-to-database migration ({} to {}) is not supported; \
+                state can only be moved to or from LMDB. Migrate the state to `lmdb` \
+                first, then from `lmdb` to the new database",
+                    database_kind(in_database),
+                    database_kind(out_database)
+                )))
             }
         };
 
-        let in_upgrade_stores = match in_database {
-            "lmdb" => {
-                let upgrade_stores = new_upgrade_stores(&ConnectionUri::from_str(&database_uri)?)
-                    .map_err(|e| {
-                    CliError::ActionError(format!(
-                        "Unable to get stores to fetch circuit information {}",
-                        e
-                    ))
-                })?;
-                Box::new(UpgradeStoresWithLmdb::new(
-                    upgrade_stores,
-                    lmdb_db_factory.clone(),
-                ))
+    // Fail early if the database cannot be reached, rather than with a store error part way
+    // through; a dry run is checked too. The side of the migration that is not LMDB is the
+    // database holding the circuit information, so this covers the in and out databases as well
+    probe_database(&database_uri)?;
+
+    // At least one side of the migration is LMDB, so the state directories must be usable
+    // before any circuits are enumerated
+    for lmdb_state_dir in &lmdb_state_dirs {
+        check_state_dir(lmdb_state_dir)?;
+    }
+    let state_dir = lmdb_state_dirs[0];
+
+    // Hold the state directories for the rest of the job, so that concurrent migrations cannot
+    // write to the same trees; a dry run does not write, so it does not need the locks
+    let _locks = if !options.dry_run {
+        lmdb_state_dirs
+            .iter()
+            .map(|lmdb_state_dir| MigrationLock::acquire(lmdb_state_dir, options.force_unlock))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        vec![]
+    };
+
+    let stores_config = StoresConfig {
+        in_database: in_database.to_string(),
+        out_database: out_database.to_string(),
+        database_uri: database_uri.clone(),
+        in_lmdb_db_factory: LmdbDatabaseFactory::new_state_db_factory(in_state_dir, None),
+        out_lmdb_db_factory: LmdbDatabaseFactory::new_state_db_factory(out_state_dir, None),
+    };
+    let in_upgrade_stores = stores_config.open_in()?;
+    let out_upgrade_stores = stores_config.open_out()?;
+
+    // The root of each service's state is recorded before it is purged, so that it can be
+    // restored with `--restore-root`
+    let mut snapshots = SnapshotLog::open(state_dir)?;
+
+    if let Some(root) = options.restore_root {
+        return restore_root(
+            root,
+            &mut snapshots,
+            &*in_upgrade_stores,
+            &*out_upgrade_stores,
+            in_database,
+            options,
+        );
+    }
+
+    // Get the database that will be used to get circuit information
+    let upgrade_stores =
+        new_upgrade_stores(&ConnectionUri::from_str(&database_uri)?).map_err(|e| {
+            CliError::ActionError(format!(
+                "Unable to get stores to fetch circuit information {}",
+                e
+            ))
+        })?;
+
+    let node_id = if let Some(node_id) = upgrade_stores
+        .new_node_id_store()
+        .get_node_id()
+        .map_err(|e| CliError::ActionError(format!("{}", e)))?
+    {
+        node_id
+    } else {
+        // This node has not even set a node id, so it cannot have any circuits.
+        info!("Skipping scabbard state migrate, no local node ID found");
+        return Ok(());
+    };
+
+    let local_services = upgrade_stores
+        .list_local_services(&node_id)
+        .map_err(|e| CliError::ActionError(format!("{}", e)))?;
+
+    if local_services.is_empty() {
+        info!("Skipping scabbard state migrate, no local services found");
+        report.record_no_scabbard_services();
+        return Ok(());
+    }
+
+    let (scabbard_services, other_services): (Vec<_>, Vec<_>) = local_services
+        .into_iter()
+        .filter(|service| job.includes_circuit(service.service_id().circuit()))
+        .partition(|service| service.is_scabbard());
+
+    for service in other_services {
+        debug!(
+            "Skipping {}, service type {} is not scabbard",
+            service.service_id(),
+            service.service_type()
+        );
+        report.record_skipped(SkippedService::new(
+            service.into_service_id(),
+            SkipReason::ServiceTypeMismatch,
+        ));
+    }
+
+    // The circuits may only run other types of services, in which case there is nothing to
+    // migrate and no success to report
+    if scabbard_services.is_empty() {
+        info!("Skipping scabbard state migrate, no local scabbard services to migrate");
+        report.record_no_scabbard_services();
+        return Ok(());
+    }
+
+    if options.confirm_count {
+        confirm_service_count(scabbard_services.len())?;
+    }
+
+    let mut existing_trees = 0;
+    let mut unreadable_trees = 0;
+    let mut interrupted = false;
+    // The report may already hold the services of earlier jobs from a manifest
+    let not_attempted_before = report.not_attempted();
+    for service in scabbard_services {
+        let commit_hash = service.commit_hash().map(ToOwned::to_owned);
+        let service_id = service.into_service_id();
+
+        if options.detail >= LogDetail::Service {
+            if !options.dry_run {
+                info!("Migrating state data for {}", service_id);
+            } else {
+                info!(
+                    "Checking if state data for {} could be migrated",
+                    service_id
+                );
             }
-            _ => new_upgrade_stores(&ConnectionUri::from_str(in_database)?).map_err(|e| {
-                CliError::ActionError(format!(
-                    "Unable to get stores for `--in` database {}: {}",
-                    in_database, e
-                ))
-            })?,
-        };
+        }
 
-        let out_upgrade_stores = match out_database {
-            "lmdb" => {
-                let upgrade_stores = new_upgrade_stores(&ConnectionUri::from_str(&database_uri)?)
-                    .map_err(|e| {
-                    CliError::ActionError(format!(
-                        "Unable to get stores to fetch circuit information {}",
-                        e
-                    ))
-                })?;
-                Box::new(UpgradeStoresWithLmdb::new(upgrade_stores, lmdb_db_factory))
+        let commit_hash = match commit_hash {
+            Some(commit_hash) => commit_hash,
+            None => {
+                if options.detail >= LogDetail::Service {
+                    info!("Skipping {}, no commit hash for service", service_id);
+                }
+                report.record_skipped(SkippedService::new(service_id, SkipReason::NoCommitHash));
+                continue;
             }
-            _ => new_upgrade_stores(&ConnectionUri::from_str(out_database)?).map_err(|e| {
-                CliError::ActionError(format!(
-                    "Unable to get stores for `--out` database {}: {}",
-                    out_database, e
-                ))
-            })?,
         };
 
-        // Get the database that will be used to get circuit information
-        let upgrade_stores =
-            new_upgrade_stores(&ConnectionUri::from_str(&database_uri)?).map_err(|e| {
-                CliError::ActionError(format!(
-                    "Unable to get stores to fetch circuit information {}",
-                    e
-                ))
-            })?;
-
-        let node_id = if let Some(node_id) = upgrade_stores
-            .new_node_id_store()
-            .get_node_id()
-            .map_err(|e| CliError::ActionError(format!("{}", e)))?
+        // A service completed by an earlier run is skipped, as long as its copy still ends at
+        // the recorded root
+        if let Some(recorded_root) = checkpoint
+            .as_deref()
+            .and_then(|checkpoint| checkpoint.completed(&service_id))
         {
-            node_id
-        } else {
-            // This node has not even set a node id, so it cannot have any circuits.
-            info!("Skipping scabbard state migrate, no local node ID found");
-            return Ok(());
-        };
+            let root = out_upgrade_stores
+                .get_merkle_state(&service_id, false)
+                .map_err(|e| CliError::ActionError(e.to_string()))?
+                .get_state_root()?;
+            if root != recorded_root {
+                return Err(CliError::ActionError(format!(
+                    "State root {} for {} in {} does not match the root {} recorded in the \
+                    checkpoint file",
+                    root, service_id, out_database, recorded_root
+                )));
+            }
 
-        let circuits = upgrade_stores
-            .new_admin_service_store()
-            .list_circuits(&[])
-            .map_err(|e| CliError::ActionError(format!("{}", e)))?;
+            if options.detail >= LogDetail::Service {
+                info!(
+                    "Skipping {}, already migrated according to the checkpoint file",
+                    service_id
+                );
+            }
+            report.record_skipped(SkippedService::new(service_id, SkipReason::Checkpointed));
+            continue;
+        }
+
+        // Once the deadline has passed, the remaining services are only reported
+        if options.deadline_passed() {
+            if options.detail >= LogDetail::Service {
+                info!("Not attempting {}, the deadline has passed", service_id);
+            }
+            report.record_service(ServiceMigrationResult::not_attempted(
+                service_id,
+                commit_hash,
+            ));
+            interrupted = true;
+            continue;
+        }
 
-        if circuits.len() == 0 {
-            info!("Skipping scabbard state migrate, no circuits found");
-            Ok(())
+        // A dry run always checks for an existing tree, so that it can be reported
+        let target_exists = if !options.force || options.dry_run {
+            out_upgrade_stores
+                .new_state_tree_store()
+                .has_tree(&service_id)
+                .map_err(|e| CliError::ActionError(e.to_string()))?
         } else {
-            let local_services = circuits.into_iter().flat_map(|circuit| {
-                circuit
-                    .roster()
-                    .iter()
-                    .filter_map(|svc| {
-                        if svc.node_id() == node_id && svc.service_type() == "scabbard" {
-                            Some((
-                                circuit.circuit_id().to_string(),
-                                svc.service_id().to_string(),
-                            ))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<_>>()
-            });
+            false
+        };
 
-            for (circuit_id, service_id) in local_services {
-                if !args.is_present("dry_run") {
-                    info!("Migrating state data for {}::{}", circuit_id, service_id);
-                } else {
+        // check if the tree already exists and error if so unless force is set, or skip it
+        // if skip_existing is set
+        if target_exists && !options.force {
+            if options.skip_existing {
+                if options.detail >= LogDetail::Service {
                     info!(
-                        "Checking if state data for {}::{} could be migrated",
-                        circuit_id, service_id
+                        "Skipping {}, merkle tree already exists in {}",
+                        service_id, out_database
                     );
                 }
+                report.record_skipped(SkippedService::new(service_id, SkipReason::TreeExists));
+                continue;
+            }
+
+            if !options.dry_run {
+                return Err(CliError::ActionError(format!(
+                    "Merkle Tree for {} in {} already exists",
+                    service_id, out_database
+                )));
+            }
 
-                let commit_hash_store =
-                    upgrade_stores.new_commit_hash_store(&circuit_id, &service_id);
-                let commit_hash = commit_hash_store
-                    .get_current_commit_hash()
-                    .map_err(|e| CliError::ActionError(format!("{}", e)))?
-                    .ok_or_else(|| {
-                        CliError::ActionError(format!(
-                            "No commit hash for service {}::{}",
-                            circuit_id, service_id,
-                        ))
-                    })?;
-
-                let state_reader = in_upgrade_stores
-                    .get_merkle_state(&circuit_id, &service_id, false)
-                    .map_err(|e| CliError::ActionError(e.to_string()))?;
-
-                // check if the tree already exists and error if so unless force is set
-                if !args.is_present("force")
-                    && out_upgrade_stores
-                        .new_state_tree_store()
-                        .has_tree(&circuit_id, &service_id)
+            // The dry run continues, so that every service is assessed
+            warn!(
+                "Merkle Tree for {} in {} already exists",
+                service_id, out_database
+            );
+            existing_trees += 1;
+        }
+
+        let state_reader = in_upgrade_stores
+            .get_merkle_state(&service_id, false)
+            .map_err(|e| CliError::ActionError(e.to_string()))?;
+
+        // If dry_run, do not actually attempt to move the data
+        if !options.dry_run {
+            let started = Instant::now();
+
+            // Counting the leaves requires an extra pass over the tree, so it is only done
+            // when progress was requested
+            let total_leaves = if options.progress {
+                Some(
+                    measure_leaves(&state_reader, &commit_hash)
                         .map_err(|e| CliError::ActionError(e.to_string()))?
-                {
-                    return Err(CliError::ActionError(format!(
-                        "Merkle Tree for {}::{} in {} already exists",
-                        circuit_id, service_id, out_database
-                    )));
+                        .count,
+                )
+            } else {
+                None
+            };
+
+            if let Some(events) = options.progress_events {
+                events.service_started(&service_id, &commit_hash, total_leaves);
+            }
+
+            let result = migrate_service(
+                Box::new(state_reader),
+                &MigrationStores::new(&*out_upgrade_stores),
+                &service_id,
+                &commit_hash,
+                &ServiceCopy {
+                    prefixes: &options.prefixes,
+                    verify_root: !options.skip_root_check,
+                    verify: options.verify && !deferred_verify,
+                    // delete the existing scabbard state, unless this is a partial copy whose
+                    // root was not verified against the commit hash, or the copy has yet to be
+                    // verified
+                    purge: options.prefixes.is_empty()
+                        && !options.skip_root_check
+                        && !deferred_verify,
+                    batching: &options.batching,
+                },
+                &RecordSnapshots {
+                    progress: CopyProgress {
+                        service_id: &service_id,
+                        total_leaves,
+                        log_batches: options.detail == LogDetail::Batch,
+                        deadline: options.deadline,
+                        interval: options.progress_interval,
+                        last_report: Cell::new(Duration::default()),
+                        events: options.progress_events,
+                    },
+                    snapshots: RefCell::new(&mut snapshots),
+                },
+            );
+
+            let migrated = match result {
+                Ok(migrated) => migrated,
+                Err(err) => {
+                    record_service(
+                        report,
+                        options,
+                        ServiceMigrationResult::failed(
+                            service_id,
+                            commit_hash,
+                            started.elapsed(),
+                            err.to_string(),
+                        ),
+                    );
+                    // The copy was interrupted by the deadline and its tree removed, so the
+                    // remaining services are reported as not attempted
+                    if options.deadline_passed() {
+                        warn!("{}", err);
+                        interrupted = true;
+                        continue;
+                    }
+                    return Err(CliError::ActionError(err.to_string()));
                 }
+            };
+            let leaves_copied = migrated.leaves_copied();
+            let ending_root = migrated.state_root().to_string();
 
-                // If dry_run, do not actually attempt to move the data
-                if !args.is_present("dry_run") {
-                    out_upgrade_stores
-                        .in_transaction(Box::new(|out_upgrade_stores| {
-                            let state_writer = out_upgrade_stores.get_merkle_state(
-                                &circuit_id,
-                                &service_id,
-                                true,
-                            )?;
-
-                            match copy_state(&state_reader, commit_hash.to_string(), &state_writer)
-                            {
-                                Ok(()) => {
-                                    // delete the existing scabbard state
-                                    state_reader
-                                        .delete_tree()
-                                        .map_err(|e| InternalError::from_source(Box::new(e)))?;
-                                }
-                                Err(err) => {
-                                    // delete the target scabbard state, so that it doesn't exist.
-                                    state_writer
-                                        .delete_tree()
-                                        .map_err(|e| InternalError::from_source(Box::new(e)))?;
-                                    return Err(err);
-                                }
-                            }
-
-                            Ok(())
-                        }))
-                        .map_err(|e| CliError::ActionError(e.to_string()))?;
+            if deferred_verify {
+                pending_verification.push(PendingVerification {
+                    service_id,
+                    commit_hash,
+                    ending_root,
+                    leaves_copied,
+                    duration: started.elapsed(),
+                });
+            } else {
+                if let Some(checkpoint) = checkpoint.as_deref_mut() {
+                    checkpoint.record_complete(&service_id, &ending_root)?;
                 }
+                record_service(
+                    report,
+                    options,
+                    ServiceMigrationResult::migrated(
+                        service_id,
+                        commit_hash,
+                        leaves_copied,
+                        started.elapsed(),
+                    ),
+                );
             }
-            if !args.is_present("dry_run") {
-                info!("Scabbard state successfully migrated to {}", out_database);
-            } else {
-                info!("Dry run was successful for {}", out_database);
+        } else {
+            // Read every leaf, as the migration would, so that a tree that cannot be read to the
+            // end fails the dry run rather than the migration
+            match measure_leaves(&state_reader, &commit_hash) {
+                Ok(leaf_stats) => {
+                    if options.detail >= LogDetail::Service {
+                        info!("Read {} leaves for {}", leaf_stats.count, service_id);
+                    }
+                    report.record_readiness(
+                        ServiceReadiness::new(
+                            service_id,
+                            leaf_stats.count,
+                            target_exists,
+                            leaf_stats.bytes,
+                        ),
+                        !target_exists || options.force,
+                    );
+                }
+                Err(err) => {
+                    error!("Unable to read the state of {}: {}", service_id, err);
+                    unreadable_trees += 1;
+                    report.record_readiness(
+                        ServiceReadiness::unreadable(service_id, target_exists, err.to_string()),
+                        false,
+                    );
+                }
             }
+        }
+    }
+
+    if unreadable_trees > 0 {
+        return Err(CliError::ActionError(format!(
+            "The state of {} services could not be read; the migration to {} would fail",
+            unreadable_trees, out_database
+        )));
+    }
+
+    if existing_trees > 0 {
+        return Err(CliError::ActionError(format!(
+            "Merkle Trees for {} services in {} already exist",
+            existing_trees, out_database
+        )));
+    }
 
-            Ok(())
+    // Copies made before the deadline passed are still verified, so that their input state can
+    // be deleted
+    if let (Some(jobs), false) = (options.verify_jobs, pending_verification.is_empty()) {
+        let results = verify_in_parallel(&stores_config, &pending_verification, jobs)?;
+
+        let mut failed = 0;
+        for (pending, result) in pending_verification.into_iter().zip(results) {
+            match result.error.clone() {
+                None => {
+                    // The copy matches, so the input state can now be deleted
+                    snapshots.record(&pending.service_id, &pending.commit_hash)?;
+                    in_upgrade_stores
+                        .get_merkle_state(&pending.service_id, false)
+                        .map_err(|e| CliError::ActionError(e.to_string()))?
+                        .delete_tree()?;
+                    if let Some(checkpoint) = checkpoint.as_deref_mut() {
+                        checkpoint.record_complete(&pending.service_id, &pending.ending_root)?;
+                    }
+                    record_service(
+                        report,
+                        options,
+                        ServiceMigrationResult::migrated(
+                            pending.service_id,
+                            pending.commit_hash,
+                            pending.leaves_copied,
+                            pending.duration,
+                        ),
+                    );
+                }
+                Some(err) => {
+                    // Keep the input state and remove the copy that does not match it
+                    error!("{}", err);
+                    out_upgrade_stores
+                        .get_merkle_state(&pending.service_id, false)
+                        .map_err(|e| CliError::ActionError(e.to_string()))?
+                        .delete_tree()?;
+                    record_service(
+                        report,
+                        options,
+                        ServiceMigrationResult::failed(
+                            pending.service_id,
+                            pending.commit_hash,
+                            pending.duration,
+                            err,
+                        ),
+                    );
+                    failed += 1;
+                }
+            }
+            report.record_verification(result);
+        }
+
+        if failed > 0 {
+            return Err(CliError::ActionError(format!(
+                "Verification failed for {} services migrated to {}",
+                failed, out_database
+            )));
+        }
+    }
+
+    if interrupted {
+        return Err(CliError::ActionError(format!(
+            "The deadline passed before all services were migrated to {}; {} services were not \
+            attempted, see the report for which",
+            out_database,
+            report.not_attempted() - not_attempted_before
+        )));
+    }
+
+    if !options.dry_run {
+        info!("Scabbard state successfully migrated to {}", out_database);
+    } else {
+        info!("Dry run was successful for {}", out_database);
+    }
+
+    Ok(())
+}
+
+/// Records the result of a service whose state was copied, streaming it as a progress event if
+/// requested
+fn record_service(
+    report: &mut MigrationReport,
+    options: &MigrateOptions,
+    result: ServiceMigrationResult,
+) {
+    if let Some(events) = options.progress_events {
+        events.service_finished(&result);
+    }
+    report.record_service(result);
+}
+
+/// Restores the state of the services whose state at `root` was purged from the in database by an
+/// earlier migration, copying it back from the out database.
+fn restore_root(
+    root: &str,
+    snapshots: &mut SnapshotLog,
+    in_upgrade_stores: &dyn TransactionalUpgradeStores,
+    out_upgrade_stores: &dyn TransactionalUpgradeStores,
+    in_database: &str,
+    options: &MigrateOptions,
+) -> Result<(), CliError> {
+    let service_ids = snapshots.services_with_root(root);
+    if service_ids.is_empty() {
+        return Err(CliError::ActionError(format!(
+            "No purged state with root {} is recorded in {}",
+            root,
+            snapshots.path().display()
+        )));
+    }
+
+    for service_id in service_ids {
+        info!("Restoring state root {} for {}", root, service_id);
+
+        if !options.force
+            && in_upgrade_stores
+                .new_state_tree_store()
+                .has_tree(&service_id)
+                .map_err(|e| CliError::ActionError(e.to_string()))?
+        {
+            return Err(CliError::ActionError(format!(
+                "Merkle Tree for {} in {} already exists",
+                service_id, in_database
+            )));
+        }
+
+        let state_reader = out_upgrade_stores
+            .get_merkle_state(&service_id, false)
+            .map_err(|e| CliError::ActionError(e.to_string()))?;
+
+        // The copy must end at the recorded root, or it is not the purged state
+        migrate_service(
+            Box::new(state_reader),
+            &MigrationStores::new(in_upgrade_stores),
+            &service_id,
+            root,
+            &ServiceCopy {
+                prefixes: &[],
+                verify_root: true,
+                verify: false,
+                purge: false,
+                batching: &options.batching,
+            },
+            &CopyProgress {
+                service_id: &service_id,
+                total_leaves: None,
+                log_batches: options.detail == LogDetail::Batch,
+                deadline: None,
+                interval: options.progress_interval,
+                last_report: Cell::new(Duration::default()),
+                events: None,
+            },
+        )
+        .map_err(|err| {
+            CliError::ActionError(format!(
+                "Unable to restore state root {} for {}: {}",
+                root, service_id, err
+            ))
+        })?;
+    }
+
+    snapshots.remove(root)?;
+
+    info!(
+        "State root {} successfully restored to {}",
+        root, in_database
+    );
+
+    Ok(())
+}
+
+/// Returns a human-readable name for the type of database the given URI refers to
+fn database_kind(database_uri: &str) -> &'static str {
+    if database_uri.starts_with("postgres://") {
+        "PostgreSQL"
+    } else {
+        "SQLite"
+    }
+}
+
+/// The databases a migration job moves state between, from which each side's stores are opened
+#[derive(Clone)]
+struct StoresConfig {
+    in_database: String,
+    out_database: String,
+    /// The database holding the circuit information, used for the LMDB side
+    database_uri: String,
+    in_lmdb_db_factory: LmdbDatabaseFactory,
+    out_lmdb_db_factory: LmdbDatabaseFactory,
+}
+
+impl StoresConfig {
+    fn open_in(&self) -> Result<Box<dyn TransactionalUpgradeStores>, CliError> {
+        self.open(&self.in_database, "--in", &self.in_lmdb_db_factory)
+    }
+
+    fn open_out(&self) -> Result<Box<dyn TransactionalUpgradeStores>, CliError> {
+        self.open(&self.out_database, "--out", &self.out_lmdb_db_factory)
+    }
+
+    fn open(
+        &self,
+        database: &str,
+        arg: &str,
+        lmdb_db_factory: &LmdbDatabaseFactory,
+    ) -> Result<Box<dyn TransactionalUpgradeStores>, CliError> {
+        match database {
+            "lmdb" => {
+                let upgrade_stores = new_upgrade_stores(&ConnectionUri::from_str(
+                    &self.database_uri,
+                )?)
+                .map_err(|e| {
+                    CliError::ActionError(format!(
+                        "Unable to get stores to fetch circuit information {}",
+                        e
+                    ))
+                })?;
+                Ok(Box::new(UpgradeStoresWithLmdb::new(
+                    upgrade_stores,
+                    lmdb_db_factory.clone(),
+                )))
+            }
+            _ => new_upgrade_stores(&ConnectionUri::from_str(database)?).map_err(|e| {
+                CliError::ActionError(format!(
+                    "Unable to get stores for `{}` database {}: {}",
+                    arg, database, e
+                ))
+            }),
         }
     }
 }
 
+/// A service whose state has been copied, but whose copy has yet to be verified
+struct PendingVerification {
+    service_id: ServiceId,
+    commit_hash: String,
+    /// The state root of the copied tree
+    ending_root: String,
+    leaves_copied: usize,
+    /// The time spent copying the service's state
+    duration: Duration,
+}
+
+/// Verifies the copies of the given services using `jobs` threads, returning a result for each
+/// service in the order given.
+///
+/// Verification only reads the trees, so each thread opens its own stores and verifies an equal
+/// share of the services.
+fn verify_in_parallel(
+    stores_config: &StoresConfig,
+    pending: &[PendingVerification],
+    jobs: usize,
+) -> Result<Vec<VerificationResult>, CliError> {
+    let jobs = jobs.min(pending.len());
+    info!(
+        "Verifying the copied state of {} services using {} threads",
+        pending.len(),
+        jobs
+    );
+
+    let mut shares = vec![vec![]; jobs];
+    for (index, pending) in pending.iter().enumerate() {
+        shares[index % jobs].push((
+            index,
+            pending.service_id.clone(),
+            pending.commit_hash.clone(),
+        ));
+    }
+
+    let handles = shares
+        .into_iter()
+        .map(|share| {
+            let stores_config = stores_config.clone();
+            thread::spawn(move || verify_share(&stores_config, share))
+        })
+        .collect::<Vec<_>>();
+
+    let mut results = vec![];
+    for handle in handles {
+        results.extend(handle.join().map_err(|_| {
+            CliError::ActionError("A verification thread terminated unexpectedly".to_string())
+        })?);
+    }
+    results.sort_by_key(|(index, _)| *index);
+
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+/// Verifies each of the given services, tagging each result with the service's index
+fn verify_share(
+    stores_config: &StoresConfig,
+    share: Vec<(usize, ServiceId, String)>,
+) -> Vec<(usize, VerificationResult)> {
+    let stores = stores_config
+        .open_in()
+        .and_then(|in_stores| Ok((in_stores, stores_config.open_out()?)));
+
+    share
+        .into_iter()
+        .map(|(index, service_id, commit_hash)| {
+            let result = match &stores {
+                Ok((in_stores, out_stores)) => verify_service(
+                    in_stores.as_upgrade_stores(),
+                    out_stores.as_upgrade_stores(),
+                    &service_id,
+                    &commit_hash,
+                ),
+                Err(err) => Err(format!(
+                    "Unable to verify copied state for {}: {}",
+                    service_id, err
+                )),
+            };
+            (index, VerificationResult::new(service_id, result.err()))
+        })
+        .collect()
+}
+
+/// Verifies that the copied state of a service matches its original state
+fn verify_service(
+    in_stores: &dyn UpgradeStores,
+    out_stores: &dyn UpgradeStores,
+    service_id: &ServiceId,
+    commit_hash: &str,
+) -> Result<(), String> {
+    let state_reader = in_stores
+        .get_merkle_state(service_id, false)
+        .map_err(|e| e.to_string())?;
+    let state_writer = out_stores
+        .get_merkle_state(service_id, false)
+        .map_err(|e| e.to_string())?;
+
+    verify_copy(&state_reader, &state_writer, commit_hash, service_id).map_err(|e| e.to_string())
+}
+
+/// Checks that the state directory exists and can be read, so that a missing or inaccessible
+/// directory is reported before any trees are opened
+fn check_state_dir(state_dir: &Path) -> Result<(), CliError> {
+    let readable = state_dir.is_dir() && fs::read_dir(state_dir).is_ok();
+    if !readable {
+        return Err(CliError::EnvironmentError(format!(
+            "State directory '{}' does not exist or is not readable",
+            state_dir.display()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns whether the two paths are the same directory, comparing their canonical paths when
+/// they exist
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
 /// Gets the path of splinterd's state directory
 ///
 ///
@@ -314,89 +1268,346 @@ fn get_state_dir(arg_matches: Option<&ArgMatches>) -> Result<PathBuf, CliError>
     }
 }
 
-/// Copy existing scabbard state for the current commit hash from state reader MerkleState to
-/// state writer MerkleState
-///
-/// # Arguments
-///
-/// * `state_reader` - The MerkleState that holds the state that should be moved
-/// * `current_commit_hash` - The current state root hash for the in database
-/// * `state_writer` - The MerkleState that the state should be moved to
-///
-/// # Returns
-///
-/// * Ok if the state was successfully copied and results in the correct state root hash, otherwise
-/// a CliError is returned
-fn copy_state(
+/// Reads the address prefixes for `--prefix-file`, one per line. Blank lines and lines starting
+/// with `#` are ignored
+fn read_prefix_file(path: &Path) -> Result<Vec<String>, CliError> {
+    let contents = fs::read_to_string(path).map_err(|err| {
+        CliError::ActionError(format!(
+            "Unable to read prefix file {}: {}",
+            path.display(),
+            err
+        ))
+    })?;
+
+    let prefixes = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    // An empty list would copy every leaf, which is not what a prefix file asks for
+    if prefixes.is_empty() {
+        return Err(CliError::ActionError(format!(
+            "Prefix file {} does not contain any prefixes",
+            path.display()
+        )));
+    }
+
+    Ok(prefixes)
+}
+
+const MAX_BATCH_SIZE: usize = 1_000_000;
+
+const STATE_MIGRATE_ASSUME_YES_ENV: &str = "SPLINTER_STATE_MIGRATE_ASSUME_YES";
+
+/// How often `copy_state` logs its progress, unless `--progress-interval` is provided
+const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The number of leaves in a tree and their total size
+struct LeafStats {
+    count: usize,
+    /// The total size of the leaves' addresses and values, in bytes
+    bytes: u64,
+}
+
+/// Counts and sizes the leaves in the tree at the given commit hash
+fn measure_leaves(
     state_reader: &MerkleState,
-    current_commit_hash: String,
-    state_writer: &MerkleState,
-) -> Result<(), InternalError> {
-    let state_changes_iter = state_reader
-        .filter_iter(&current_commit_hash, None)
+    commit_hash: &str,
+) -> Result<LeafStats, InternalError> {
+    let leaves = state_reader
+        .filter_iter(&commit_hash.to_string(), None)
         .map_err(|e| {
             InternalError::with_message(format!("Unable to get leaves for commit hash: {}", e))
         })?;
 
-    let mut count = 0;
-    let mut last_state_id = state_writer
-        .get_state_root()
-        .map_err(|e| InternalError::from_source(Box::new(e)))?;
-    let mut state_changes = vec![];
-    for state_change in state_changes_iter {
-        match state_change {
-            Ok((key, value)) => {
-                state_changes.push(StateChange::Set { key, value });
-                count += 1;
-
-                if count > 1000 {
-                    last_state_id =
-                        write_and_prune_with_cleanup(state_writer, &last_state_id, &state_changes)?;
-
-                    count = 0;
-                    state_changes.clear()
-                }
-            }
-            Err(err) => {
-                return Err(InternalError::with_message(format!(
-                    "Cannot get state change: {}",
-                    err
-                )))
+    let mut stats = LeafStats { count: 0, bytes: 0 };
+    for leaf in leaves {
+        let (address, value) =
+            leaf.map_err(|e| InternalError::with_message(format!("Cannot get leaf: {}", e)))?;
+        stats.count += 1;
+        stats.bytes += (address.len() + value.len()) as u64;
+    }
+
+    Ok(stats)
+}
+
+/// Logs the progress of copying a single service's state, and bounds how long the copy may run
+struct CopyProgress<'a> {
+    service_id: &'a ServiceId,
+    /// The number of leaves in the tree, if known. When provided, the percentage of the leaves
+    /// that have been copied is logged after each batch
+    total_leaves: Option<usize>,
+    /// Whether to log each batch of leaves as it is written
+    log_batches: bool,
+    /// The time after which no further batches of leaves are copied
+    deadline: Option<Instant>,
+    /// How often the number of leaves read so far is logged; `None` if it is never logged
+    interval: Option<Duration>,
+    /// The time since the copy started at which the number of leaves read was last logged
+    last_report: Cell<Duration>,
+    /// Where each batch of leaves written is streamed as a progress event, if anywhere
+    events: Option<&'a ProgressEvents>,
+}
+
+impl<'a> CopyObserver for CopyProgress<'a> {
+    /// Logs the number of leaves read so far if the interval has passed since it was last logged.
+    fn leaves_read(&self, read: usize, elapsed: Duration) {
+        if let Some(interval) = self.interval {
+            if elapsed >= self.last_report.get() + interval {
+                info!(
+                    "Read {} leaves for {} in {}s",
+                    read,
+                    self.service_id,
+                    elapsed.as_secs()
+                );
+                self.last_report.set(elapsed);
             }
         }
     }
 
-    last_state_id = write_and_prune_with_cleanup(state_writer, &last_state_id, &state_changes)?;
+    fn batch_written(&self, batch_size: usize, copied: usize) {
+        if self.log_batches && batch_size > 0 {
+            info!(
+                "Wrote {} leaves for {} ({} leaves copied)",
+                batch_size, self.service_id, copied
+            );
+        }
 
-    if last_state_id != current_commit_hash {
-        return Err(InternalError::with_message(format!(
-            "Ending commit hash did not match expected {} != {}",
-            last_state_id, current_commit_hash
-        )));
+        if let Some(total_leaves) = self.total_leaves {
+            log_progress(self.service_id, copied, total_leaves);
+        }
+
+        if let Some(events) = self.events.filter(|_| batch_size > 0) {
+            events.batch_flushed(self.service_id, batch_size, copied);
+        }
     }
 
-    Ok(())
+    /// Stops the copy once a batch is written after the deadline has passed
+    fn check_continue(&self, copied: usize) -> Result<(), InternalError> {
+        if deadline_passed(self.deadline) {
+            return Err(InternalError::with_message(format!(
+                "Deadline passed after copying {} leaves for {}",
+                copied, self.service_id
+            )));
+        }
+
+        Ok(())
+    }
 }
 
-fn write_and_prune_with_cleanup(
-    merkle_state: &MerkleState,
-    state_id: &str,
-    state_changes: &[StateChange],
-) -> Result<String, InternalError> {
-    let next_state_id = merkle_state
-        .commit(&state_id.to_string(), state_changes)
-        .map_err(|e| {
-            InternalError::with_message(format!("Unable to commit state changes {}", e))
-        })?;
-    merkle_state
-        .prune(vec![state_id.to_string()])
-        .map_err(|e| {
-            InternalError::with_message(format!("Unable to purge previous commit hash {}", e))
-        })?;
+/// Logs the progress of a copy, and records the root of the service's state in the snapshot log
+/// before it is purged, so that it can be restored with `--restore-root`
+struct RecordSnapshots<'a> {
+    progress: CopyProgress<'a>,
+    snapshots: RefCell<&'a mut SnapshotLog>,
+}
 
-    merkle_state.remove_pruned_entries().map_err(|e| {
-        InternalError::with_message(format!("Unable to remove pruned entries {}", e))
-    })?;
+impl<'a> CopyObserver for RecordSnapshots<'a> {
+    fn leaves_read(&self, read: usize, elapsed: Duration) {
+        self.progress.leaves_read(read, elapsed)
+    }
+
+    fn batch_written(&self, batch_size: usize, copied: usize) {
+        self.progress.batch_written(batch_size, copied)
+    }
+
+    fn check_continue(&self, copied: usize) -> Result<(), InternalError> {
+        self.progress.check_continue(copied)
+    }
+
+    fn before_purge(&self, service_id: &ServiceId, commit_hash: &str) -> Result<(), InternalError> {
+        self.snapshots
+            .borrow_mut()
+            .record(service_id, commit_hash)
+            .map_err(|e| InternalError::from_source(Box::new(e)))
+    }
+}
+
+fn log_progress(service_id: &ServiceId, copied: usize, total_leaves: usize) {
+    let percent = if total_leaves == 0 {
+        100
+    } else {
+        copied * 100 / total_leaves
+    };
+
+    info!(
+        "Migrated {}% ({}/{} leaves) for {}",
+        percent, copied, total_leaves, service_id
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use scabbard::state_migration::copy_state;
+    use tempfile::Builder;
+    use transact::state::{Committer, StateChange};
+
+    /// Verify that an existing state directory passes the check and a missing one is reported.
+    #[test]
+    fn test_check_state_dir() {
+        let directory = Builder::new()
+            .prefix("test")
+            .tempdir()
+            .expect("could not create temp directory");
+
+        assert!(check_state_dir(directory.path()).is_ok());
+
+        let missing = directory.path().join("missing");
+        match check_state_dir(&missing) {
+            Err(CliError::EnvironmentError(msg)) => assert_eq!(
+                msg,
+                format!(
+                    "State directory '{}' does not exist or is not readable",
+                    missing.display()
+                )
+            ),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    /// Verify that two paths to the same directory are recognized, so that an LMDB to LMDB
+    /// migration cannot read and write the same files.
+    #[test]
+    fn test_same_dir() {
+        let directory = Builder::new()
+            .prefix("test_same_dir")
+            .tempdir()
+            .expect("could not create temp directory");
+        let other = directory.path().join("other");
+        fs::create_dir(&other).expect("could not create directory");
+
+        assert!(same_dir(directory.path(), &other.join("..")));
+        assert!(!same_dir(directory.path(), &other));
+        assert!(same_dir(Path::new("missing"), Path::new("missing")));
+        assert!(!same_dir(directory.path(), Path::new("missing")));
+    }
+
+    /// Verify that copying a tree results in the same state root whether its leaves are written
+    /// one at a time or all in one batch, and whether or not each batch is read back.
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_copy_state_batch_sizes() {
+        use diesel::r2d2::{ConnectionManager, Pool};
+        use diesel::SqliteConnection;
+        use transact::state::merkle::sql::{
+            backend, migration::run_sqlite_migrations, SqlMerkleStateBuilder,
+        };
+
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Unable to build connection pool");
+        run_sqlite_migrations(&*pool.get().expect("Unable to get connection"))
+            .expect("Unable to run migrations");
+
+        let merkle_state = |tree: &str| MerkleState::Sqlite {
+            state: SqlMerkleStateBuilder::new()
+                .with_backend(backend::SqliteBackend::from(pool.clone()))
+                .with_tree(tree.to_string())
+                .create_tree_if_necessary()
+                .build()
+                .expect("Unable to build merkle state"),
+        };
+
+        let source = merkle_state("source");
+        let initial_root = source.get_state_root().expect("Unable to get state root");
+        let leaves = (0..25u32)
+            .map(|i| StateChange::Set {
+                key: format!("{:070x}", i),
+                value: i.to_le_bytes().to_vec(),
+            })
+            .collect::<Vec<_>>();
+        let source_root = source
+            .commit(&initial_root, &leaves)
+            .expect("Unable to commit leaves");
+
+        let service_id = ServiceId::new("abcde-01234".to_string(), "a000".to_string());
+        let progress = CopyProgress {
+            service_id: &service_id,
+            total_leaves: None,
+            log_batches: false,
+            deadline: None,
+            interval: None,
+            last_report: Cell::new(Duration::default()),
+            events: None,
+        };
+
+        for (tree, size, paranoid) in &[
+            ("one-at-a-time", 1, false),
+            ("all-at-once", 10_000, false),
+            ("paranoid", 7, true),
+        ] {
+            let target = merkle_state(*tree);
+            let copied = copy_state(
+                &source,
+                &source_root,
+                &target,
+                &service_id,
+                &ServiceCopy {
+                    prefixes: &[],
+                    verify_root: true,
+                    verify: false,
+                    purge: false,
+                    batching: &Batching {
+                        size: *size,
+                        flush_idle: None,
+                        paranoid: *paranoid,
+                    },
+                },
+                &progress,
+            )
+            .expect("Unable to copy state");
 
-    Ok(next_state_id)
+            assert_eq!(copied, leaves.len());
+            assert_eq!(
+                target.get_state_root().expect("Unable to get state root"),
+                source_root
+            );
+        }
+    }
+
+    /// Verify that a prefix file is read without comments or blank lines, and that an empty prefix
+    /// file is rejected.
+    #[test]
+    fn test_prefix_file() {
+        let directory = Builder::new()
+            .prefix("test_prefix_file")
+            .tempdir()
+            .expect("could not create temp directory");
+
+        let path = directory.path().join("prefixes");
+        fs::write(
+            &path,
+            "# contracts\n00ec01\n\n  00ec00  \n00ec0100\n00ec01\n",
+        )
+        .expect("could not write prefix file");
+        let prefixes = read_prefix_file(&path).expect("could not read prefix file");
+        assert_eq!(prefixes, vec!["00ec01", "00ec00", "00ec0100", "00ec01"]);
+
+        fs::write(&path, "# nothing to copy\n\n").expect("could not write prefix file");
+        assert!(read_prefix_file(&path).is_err());
+        assert!(read_prefix_file(&directory.path().join("missing")).is_err());
+    }
+
+    /// Verify that deadlines are parsed with and without a unit, and that invalid deadlines are
+    /// rejected.
+    #[test]
+    fn test_parse_deadline() {
+        assert_eq!(parse_deadline("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_deadline("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_deadline("45m").unwrap(), Duration::from_secs(45 * 60));
+        assert_eq!(
+            parse_deadline("2h").unwrap(),
+            Duration::from_secs(2 * 60 * 60)
+        );
+
+        assert!(parse_deadline("0").is_err());
+        assert!(parse_deadline("h").is_err());
+        assert!(parse_deadline("2d").is_err());
+        assert!(parse_deadline("-5m").is_err());
+    }
 }
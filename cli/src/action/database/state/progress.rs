@@ -0,0 +1,212 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Machine-readable progress events for the scabbard state migration, streamed as it runs
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Write;
+use std::os::unix::io::FromRawFd;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+
+use serde::Serialize;
+use splinter::admin::store::ServiceId;
+
+use crate::error::CliError;
+
+use super::report::{MigrationSummary, ServiceMigrationResult};
+
+/// An event written to the progress stream, as a single line of JSON
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum ProgressEvent<'a> {
+    ServiceStarted {
+        circuit_id: &'a str,
+        service_id: &'a str,
+        commit_hash: &'a str,
+        /// The number of leaves in the tree, if they were counted with `--progress`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total_leaves: Option<usize>,
+    },
+    BatchFlushed {
+        circuit_id: &'a str,
+        service_id: &'a str,
+        /// The number of leaves written by this batch
+        batch_leaves: usize,
+        /// The number of leaves written for the service so far
+        leaves_copied: usize,
+    },
+    ServiceFinished(&'a ServiceMigrationResult),
+    RunFinished {
+        #[serde(flatten)]
+        summary: &'a MigrationSummary,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<&'a str>,
+    },
+}
+
+/// Writes progress events as newline-delimited JSON to a file descriptor or Unix socket given by
+/// a supervising process.
+///
+/// The events are only informational, so a failure to write one is logged and no further events
+/// are written, rather than failing the migration.
+pub struct ProgressEvents {
+    out: RefCell<Option<Box<dyn Write>>>,
+}
+
+impl ProgressEvents {
+    /// Writes the events to a file descriptor inherited from the parent process
+    pub fn from_fd(fd: &str) -> Result<Self, CliError> {
+        let fd = fd
+            .parse::<i32>()
+            .ok()
+            .filter(|fd| *fd >= 0)
+            .ok_or_else(|| {
+                CliError::ActionError(format!("--progress-fd must be a file descriptor: {}", fd))
+            })?;
+        // The descriptor was opened by the parent process for this command, which now owns it
+        let file = unsafe { File::from_raw_fd(fd) };
+        Ok(Self::new(Box::new(file)))
+    }
+
+    /// Writes the events to the Unix socket listening at the given path
+    pub fn connect(path: &Path) -> Result<Self, CliError> {
+        let stream = UnixStream::connect(path).map_err(|err| {
+            CliError::ActionError(format!(
+                "Unable to connect to progress socket {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        Ok(Self::new(Box::new(stream)))
+    }
+
+    fn new(out: Box<dyn Write>) -> Self {
+        Self {
+            out: RefCell::new(Some(out)),
+        }
+    }
+
+    pub fn service_started(
+        &self,
+        service_id: &ServiceId,
+        commit_hash: &str,
+        total_leaves: Option<usize>,
+    ) {
+        self.emit(&ProgressEvent::ServiceStarted {
+            circuit_id: service_id.circuit(),
+            service_id: service_id.service_id(),
+            commit_hash,
+            total_leaves,
+        })
+    }
+
+    pub fn batch_flushed(&self, service_id: &ServiceId, batch_leaves: usize, leaves_copied: usize) {
+        self.emit(&ProgressEvent::BatchFlushed {
+            circuit_id: service_id.circuit(),
+            service_id: service_id.service_id(),
+            batch_leaves,
+            leaves_copied,
+        })
+    }
+
+    pub fn service_finished(&self, result: &ServiceMigrationResult) {
+        self.emit(&ProgressEvent::ServiceFinished(result))
+    }
+
+    /// Writes the final event of the run, with the error that stopped it, if any
+    pub fn run_finished(&self, summary: &MigrationSummary, error: Option<&str>) {
+        self.emit(&ProgressEvent::RunFinished { summary, error })
+    }
+
+    fn emit(&self, event: &ProgressEvent) {
+        let mut out = self.out.borrow_mut();
+        if let Some(writer) = out.as_mut() {
+            let written = serde_json::to_vec(event)
+                .map_err(|err| err.to_string())
+                .and_then(|mut line| {
+                    line.push(b'\n');
+                    writer
+                        .write_all(&line)
+                        .and_then(|_| writer.flush())
+                        .map_err(|err| err.to_string())
+                });
+            if let Err(err) = written {
+                warn!(
+                    "Unable to write progress event, no more will be written: {}",
+                    err
+                );
+                *out = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixListener;
+    use std::time::Duration;
+
+    use crate::action::database::state::report::MigrationReport;
+
+    /// Verify that events are written to a Unix socket as one JSON object per line
+    #[test]
+    fn test_progress_events_socket() {
+        let dir = tempfile::tempdir().expect("Unable to create temp dir");
+        let path = dir.path().join("progress.sock");
+        let listener = UnixListener::bind(&path).expect("Unable to bind socket");
+
+        let events = ProgressEvents::connect(&path).expect("Unable to connect");
+        let (stream, _) = listener.accept().expect("Unable to accept connection");
+
+        let service_id = ServiceId::new("abcde-01234".into(), "a000".into());
+        events.service_started(&service_id, "root", Some(3));
+        events.batch_flushed(&service_id, 2, 2);
+        let result = ServiceMigrationResult::migrated(
+            service_id,
+            "root".to_string(),
+            3,
+            Duration::from_millis(5),
+        );
+        events.service_finished(&result);
+        let mut report = MigrationReport::start(false);
+        report.record_service(result);
+        events.run_finished(report.finish(), None);
+        drop(events);
+
+        let lines = BufReader::new(stream)
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<serde_json::Value>(&line.expect("Unable to read line"))
+                    .expect("Line is not JSON")
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["event"], "service_started");
+        assert_eq!(lines[0]["circuit_id"], "abcde-01234");
+        assert_eq!(lines[0]["total_leaves"], 3);
+        assert_eq!(lines[1]["event"], "batch_flushed");
+        assert_eq!(lines[1]["batch_leaves"], 2);
+        assert_eq!(lines[2]["event"], "service_finished");
+        assert_eq!(lines[2]["outcome"], "migrated");
+        assert_eq!(lines[3]["event"], "run_finished");
+        assert_eq!(lines[3]["migrated"], 1);
+        assert!(lines[3].get("error").is_none());
+    }
+}
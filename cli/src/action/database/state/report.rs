@@ -0,0 +1,839 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structured reporting for the scabbard state migration
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use splinter::admin::store::ServiceId;
+
+use crate::action::print_table;
+use crate::error::CliError;
+
+/// Whether the state of a service was moved
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceOutcome {
+    Migrated,
+    Failed,
+    /// The migration's deadline passed before the service's state was copied
+    NotAttempted,
+}
+
+impl ServiceOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServiceOutcome::Migrated => "migrated",
+            ServiceOutcome::Failed => "failed",
+            ServiceOutcome::NotAttempted => "not_attempted",
+        }
+    }
+}
+
+/// The result of migrating the state of a single service
+#[derive(Debug, Serialize)]
+pub struct ServiceMigrationResult {
+    pub circuit_id: String,
+    pub service_id: String,
+    pub commit_hash: String,
+    /// The number of leaves copied; 0 if the copy failed or was not attempted
+    pub leaves_copied: usize,
+    /// The time spent copying the service's state, in milliseconds
+    pub duration_ms: u64,
+    pub outcome: ServiceOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ServiceMigrationResult {
+    pub fn migrated(
+        service_id: ServiceId,
+        commit_hash: String,
+        leaves_copied: usize,
+        duration: Duration,
+    ) -> Self {
+        let (circuit_id, service_id) = service_id.into_parts();
+        Self {
+            circuit_id,
+            service_id,
+            commit_hash,
+            leaves_copied,
+            duration_ms: duration_ms(duration),
+            outcome: ServiceOutcome::Migrated,
+            error: None,
+        }
+    }
+
+    pub fn failed(
+        service_id: ServiceId,
+        commit_hash: String,
+        duration: Duration,
+        error: String,
+    ) -> Self {
+        let (circuit_id, service_id) = service_id.into_parts();
+        Self {
+            circuit_id,
+            service_id,
+            commit_hash,
+            leaves_copied: 0,
+            duration_ms: duration_ms(duration),
+            outcome: ServiceOutcome::Failed,
+            error: Some(error),
+        }
+    }
+
+    pub fn not_attempted(service_id: ServiceId, commit_hash: String) -> Self {
+        let (circuit_id, service_id) = service_id.into_parts();
+        Self {
+            circuit_id,
+            service_id,
+            commit_hash,
+            leaves_copied: 0,
+            duration_ms: 0,
+            outcome: ServiceOutcome::NotAttempted,
+            error: None,
+        }
+    }
+}
+
+/// The reason a local service was intentionally not migrated
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The service has no current commit hash, so there is no state to move
+    NoCommitHash,
+    /// The tree already exists in the out database and `--skip-existing` was provided
+    TreeExists,
+    /// The service is not a scabbard service
+    ServiceTypeMismatch,
+    /// The checkpoint file records that the service was migrated by an earlier run
+    Checkpointed,
+}
+
+impl SkipReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::NoCommitHash => "no_commit_hash",
+            SkipReason::TreeExists => "tree_exists",
+            SkipReason::ServiceTypeMismatch => "service_type_mismatch",
+            SkipReason::Checkpointed => "checkpointed",
+        }
+    }
+}
+
+/// A local service that was skipped by the migration
+#[derive(Debug, Serialize)]
+pub struct SkippedService {
+    pub circuit_id: String,
+    pub service_id: String,
+    pub reason: SkipReason,
+}
+
+impl SkippedService {
+    pub fn new(service_id: ServiceId, reason: SkipReason) -> Self {
+        let (circuit_id, service_id) = service_id.into_parts();
+        Self {
+            circuit_id,
+            service_id,
+            reason,
+        }
+    }
+}
+
+/// The readiness of a local service to be migrated, as assessed by a dry run
+#[derive(Debug, Serialize)]
+pub struct ServiceReadiness {
+    pub circuit_id: String,
+    pub service_id: String,
+    /// The number of leaves in the service's current state
+    pub source_leaves: usize,
+    /// Whether the out database already has a tree for the service
+    pub target_exists: bool,
+    /// The total size of the leaves' addresses and values, in bytes
+    pub estimated_bytes: u64,
+    /// Why the service's current state could not be read to the end, if it could not
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ServiceReadiness {
+    pub fn new(
+        service_id: ServiceId,
+        source_leaves: usize,
+        target_exists: bool,
+        estimated_bytes: u64,
+    ) -> Self {
+        let (circuit_id, service_id) = service_id.into_parts();
+        Self {
+            circuit_id,
+            service_id,
+            source_leaves,
+            target_exists,
+            estimated_bytes,
+            error: None,
+        }
+    }
+
+    /// Creates the readiness of a service whose current state could not be read, which would
+    /// fail to migrate
+    pub fn unreadable(service_id: ServiceId, target_exists: bool, error: String) -> Self {
+        let (circuit_id, service_id) = service_id.into_parts();
+        Self {
+            circuit_id,
+            service_id,
+            source_leaves: 0,
+            target_exists,
+            estimated_bytes: 0,
+            error: Some(error),
+        }
+    }
+}
+
+/// The result of verifying a service's copied state, when verification is run with `--jobs`
+#[derive(Debug, Serialize)]
+pub struct VerificationResult {
+    pub circuit_id: String,
+    pub service_id: String,
+    /// Why the copy does not match the original state, if it failed verification
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl VerificationResult {
+    pub fn new(service_id: ServiceId, error: Option<String>) -> Self {
+        let (circuit_id, service_id) = service_id.into_parts();
+        Self {
+            circuit_id,
+            service_id,
+            error,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// The result of one job from a migration manifest
+#[derive(Debug, Serialize)]
+pub struct JobOutcome {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl JobOutcome {
+    pub fn succeeded(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            error: None,
+        }
+    }
+
+    pub fn failed(name: &str, error: String) -> Self {
+        Self {
+            name: name.to_string(),
+            error: Some(error),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// The format used to display the results of a migration
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    /// Results are only logged as the migration runs
+    Human,
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn from_arg(format: Option<&str>) -> Result<Self, CliError> {
+        match format {
+            None | Some("human") => Ok(ReportFormat::Human),
+            Some("json") => Ok(ReportFormat::Json),
+            Some("csv") => Ok(ReportFormat::Csv),
+            Some(format) => Err(CliError::ActionError(format!(
+                "Unsupported report format: {}",
+                format
+            ))),
+        }
+    }
+}
+
+/// Aggregate results of a migration run
+#[derive(Debug, Serialize)]
+pub struct MigrationSummary {
+    dry_run: bool,
+    /// The number of services migrated, or that would be migrated in a dry run
+    migrated: usize,
+    skipped: usize,
+    failed: usize,
+    /// The number of services left alone because the deadline passed
+    #[serde(skip_serializing_if = "is_zero")]
+    not_attempted: usize,
+    /// The number of leaves copied; always 0 in a dry run
+    leaves_copied: usize,
+    /// Whether a migration job found no local scabbard services to migrate
+    no_scabbard_services: bool,
+    elapsed_ms: u64,
+    #[serde(skip)]
+    start: Instant,
+}
+
+impl MigrationSummary {
+    /// Starts the summary, and the elapsed time, for a migration run
+    fn start(dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            migrated: 0,
+            skipped: 0,
+            failed: 0,
+            not_attempted: 0,
+            leaves_copied: 0,
+            no_scabbard_services: false,
+            elapsed_ms: 0,
+            start: Instant::now(),
+        }
+    }
+
+    fn finish(&mut self) {
+        self.elapsed_ms = duration_ms(self.start.elapsed());
+    }
+}
+
+impl fmt::Display for MigrationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let elapsed = self.elapsed_ms as f64 / 1000.0;
+        // Nothing was attempted, so the counts of migrated and failed services would mislead
+        if self.no_scabbard_services
+            && self.migrated == 0
+            && self.failed == 0
+            && self.not_attempted == 0
+        {
+            return write!(
+                f,
+                "{}: no local scabbard services to migrate, {} skipped in {:.2}s",
+                if self.dry_run {
+                    "Dry run summary"
+                } else {
+                    "Migration summary"
+                },
+                self.skipped,
+                elapsed
+            );
+        }
+
+        if self.dry_run {
+            write!(
+                f,
+                "Dry run summary: {} services would be migrated, {} skipped, {} failed in {:.2}s",
+                self.migrated, self.skipped, self.failed, elapsed
+            )
+        } else {
+            write!(
+                f,
+                "Migration summary: {} services migrated ({} leaves copied), {} skipped, {} failed",
+                self.migrated, self.leaves_copied, self.skipped, self.failed
+            )?;
+            if self.not_attempted > 0 {
+                write!(f, ", {} not attempted", self.not_attempted)?;
+            }
+            write!(f, " in {:.2}s", elapsed)
+        }
+    }
+}
+
+/// The results of a migration run, from which every report format is rendered
+#[derive(Debug, Serialize)]
+pub struct MigrationReport {
+    summary: MigrationSummary,
+    /// The result of each service whose state was copied, or failed to copy
+    services: Vec<ServiceMigrationResult>,
+    skipped: Vec<SkippedService>,
+    /// The readiness of each service, for a dry run
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    readiness: Vec<ServiceReadiness>,
+    /// The verification of each service, when copies were verified with `--jobs`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    verification: Vec<VerificationResult>,
+    /// The result of each job, when the migration was run from a manifest
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    jobs: Vec<JobOutcome>,
+}
+
+impl MigrationReport {
+    /// Starts the report, and the elapsed time, for a migration run
+    pub fn start(dry_run: bool) -> Self {
+        Self {
+            summary: MigrationSummary::start(dry_run),
+            services: vec![],
+            skipped: vec![],
+            readiness: vec![],
+            verification: vec![],
+            jobs: vec![],
+        }
+    }
+
+    pub fn record_service(&mut self, result: ServiceMigrationResult) {
+        match result.outcome {
+            ServiceOutcome::Migrated => {
+                self.summary.migrated += 1;
+                self.summary.leaves_copied += result.leaves_copied;
+            }
+            ServiceOutcome::Failed => self.summary.failed += 1,
+            ServiceOutcome::NotAttempted => self.summary.not_attempted += 1,
+        }
+        self.services.push(result);
+    }
+
+    pub fn record_skipped(&mut self, skipped: SkippedService) {
+        self.summary.skipped += 1;
+        self.skipped.push(skipped);
+    }
+
+    /// Records the readiness of a service assessed by a dry run, counting it as a service that
+    /// would be migrated if `ready` is true, and as a failure otherwise
+    pub fn record_readiness(&mut self, readiness: ServiceReadiness, ready: bool) {
+        if ready {
+            self.summary.migrated += 1;
+        } else {
+            self.summary.failed += 1;
+        }
+        self.readiness.push(readiness);
+    }
+
+    /// Records that a migration job found no local scabbard services to migrate
+    pub fn record_no_scabbard_services(&mut self) {
+        self.summary.no_scabbard_services = true;
+    }
+
+    pub fn record_verification(&mut self, result: VerificationResult) {
+        self.verification.push(result);
+    }
+
+    pub fn record_job(&mut self, outcome: JobOutcome) {
+        self.jobs.push(outcome);
+    }
+
+    pub fn jobs(&self) -> &[JobOutcome] {
+        &self.jobs
+    }
+
+    /// Stops the elapsed time of the run and returns its summary
+    pub fn finish(&mut self) -> &MigrationSummary {
+        self.summary.finish();
+        &self.summary
+    }
+
+    /// Returns the number of services that were not attempted because the deadline passed
+    pub fn not_attempted(&self) -> usize {
+        self.summary.not_attempted
+    }
+
+    /// Returns the lines of the csv report, starting with the header: one row for the result of
+    /// each service that was copied, failed or not attempted, followed by one row for each
+    /// skipped service
+    fn csv_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            "CIRCUIT_ID,SERVICE_ID,OUTCOME,LEAVES_COPIED,DURATION_MS,REASON,ERROR".to_string(),
+        ];
+        lines.extend(self.services.iter().map(|result| {
+            [
+                csv_field(&result.circuit_id),
+                csv_field(&result.service_id),
+                result.outcome.as_str().to_string(),
+                result.leaves_copied.to_string(),
+                result.duration_ms.to_string(),
+                String::new(),
+                csv_field(result.error.as_deref().unwrap_or_default()),
+            ]
+            .join(",")
+        }));
+        lines.extend(self.skipped.iter().map(|skip| {
+            [
+                csv_field(&skip.circuit_id),
+                csv_field(&skip.service_id),
+                "skipped".to_string(),
+                String::new(),
+                String::new(),
+                skip.reason.as_str().to_string(),
+                String::new(),
+            ]
+            .join(",")
+        }));
+        lines
+    }
+
+    /// Returns the json report, which includes the whole report
+    fn to_json(&self) -> Result<String, CliError> {
+        serde_json::to_string_pretty(self).map_err(|err| {
+            CliError::ActionError(format!("Cannot format report into json: {}", err))
+        })
+    }
+
+    /// Returns the csv report, one line per row
+    fn to_csv(&self) -> String {
+        self.csv_lines().join("\n")
+    }
+}
+
+/// Logs the summary of the migration, then prints the report in the given format. Nothing further
+/// is printed about skips for the human format, as they have already been logged.
+///
+/// The readiness of each service assessed by a dry run, and the result of verifying each
+/// service's copy with `--jobs`, are printed as tables for the human format. The json format
+/// includes the whole report, while the csv format has a row for each service that was copied,
+/// failed, not attempted or skipped.
+pub fn print_report(format: ReportFormat, mut report: MigrationReport) -> Result<(), CliError> {
+    report.summary.finish();
+    info!("{}", report.summary);
+    if !report.verification.is_empty() {
+        let failed = report
+            .verification
+            .iter()
+            .filter(|result| !result.passed())
+            .count();
+        info!(
+            "Verification: {} passed, {} failed",
+            report.verification.len() - failed,
+            failed
+        );
+    }
+    if !report.jobs.is_empty() {
+        let failed = report.jobs.iter().filter(|job| !job.is_success()).count();
+        info!(
+            "Migration jobs: {} succeeded, {} failed",
+            report.jobs.len() - failed,
+            failed
+        );
+    }
+
+    match format {
+        ReportFormat::Human => {
+            if !report.readiness.is_empty() {
+                print_readiness_table(&report.readiness);
+            }
+            if !report.verification.is_empty() {
+                print_verification_table(&report.verification);
+            }
+        }
+        ReportFormat::Json => println!("{}", report.to_json()?),
+        ReportFormat::Csv => println!("{}", report.to_csv()),
+    }
+
+    Ok(())
+}
+
+fn print_readiness_table(readiness: &[ServiceReadiness]) {
+    let mut rows = vec![vec![
+        "CIRCUIT".to_string(),
+        "SERVICE".to_string(),
+        "SOURCE LEAVES".to_string(),
+        "TARGET EXISTS".to_string(),
+        "ESTIMATED SIZE".to_string(),
+    ]];
+    for service in readiness {
+        let (source_leaves, estimated_size) = match service.error {
+            Some(_) => ("unreadable".to_string(), "-".to_string()),
+            None => (
+                service.source_leaves.to_string(),
+                format_size(service.estimated_bytes),
+            ),
+        };
+        rows.push(vec![
+            service.circuit_id.clone(),
+            service.service_id.clone(),
+            source_leaves,
+            if service.target_exists { "yes" } else { "no" }.to_string(),
+            estimated_size,
+        ]);
+    }
+
+    print_table(rows);
+}
+
+fn print_verification_table(verification: &[VerificationResult]) {
+    let mut rows = vec![vec![
+        "CIRCUIT".to_string(),
+        "SERVICE".to_string(),
+        "VERIFIED".to_string(),
+        "ERROR".to_string(),
+    ]];
+    for result in verification {
+        rows.push(vec![
+            result.circuit_id.clone(),
+            result.service_id.clone(),
+            if result.passed() { "yes" } else { "no" }.to_string(),
+            result.error.clone().unwrap_or_else(|| "-".to_string()),
+        ]);
+    }
+
+    print_table(rows);
+}
+
+/// Quotes a csv field if it contains a comma, quote or line break, such as an error message
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Formats a size in bytes using binary units, e.g. `1.5 MiB`
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that sizes are formatted with the largest unit that keeps the value above 1.
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(
+            format_size(3 * 1024 * 1024 * 1024 * 1024 * 1024),
+            "3072.0 TiB"
+        );
+    }
+
+    /// Verify that recorded services are counted in the summary and included in the json report.
+    #[test]
+    fn test_report_records_services() {
+        let mut report = MigrationReport::start(false);
+        report.record_service(ServiceMigrationResult::migrated(
+            ServiceId::new("abcde-01234".to_string(), "a000".to_string()),
+            "commit".to_string(),
+            10,
+            Duration::from_millis(5),
+        ));
+        report.record_service(ServiceMigrationResult::failed(
+            ServiceId::new("abcde-01234".to_string(), "b000".to_string()),
+            "commit".to_string(),
+            Duration::from_millis(5),
+            "copy failed".to_string(),
+        ));
+        report.record_skipped(SkippedService::new(
+            ServiceId::new("fghij-56789".to_string(), "a000".to_string()),
+            SkipReason::NoCommitHash,
+        ));
+
+        assert_eq!(report.summary.migrated, 1);
+        assert_eq!(report.summary.failed, 1);
+        assert_eq!(report.summary.skipped, 1);
+        assert_eq!(report.summary.leaves_copied, 10);
+
+        let json = serde_json::to_value(&report).expect("Unable to serialize report");
+        assert_eq!(json["services"][0]["outcome"], "migrated");
+        assert_eq!(json["services"][1]["outcome"], "failed");
+        assert_eq!(json["services"][1]["error"], "copy failed");
+        assert_eq!(json["skipped"][0]["reason"], "no_commit_hash");
+        assert!(json.get("readiness").is_none());
+        assert!(json["summary"].get("not_attempted").is_none());
+    }
+
+    /// Verify that the csv report has a row for each migrated, failed and skipped service, and
+    /// that errors containing commas or quotes are quoted.
+    #[test]
+    fn test_report_csv_lines() {
+        let mut report = MigrationReport::start(false);
+        report.record_service(ServiceMigrationResult::migrated(
+            ServiceId::new("abcde-01234".to_string(), "a000".to_string()),
+            "commit".to_string(),
+            10,
+            Duration::from_millis(5),
+        ));
+        report.record_service(ServiceMigrationResult::failed(
+            ServiceId::new("abcde-01234".to_string(), "b000".to_string()),
+            "commit".to_string(),
+            Duration::from_millis(7),
+            "copy failed: missing node \"01\", aborted".to_string(),
+        ));
+        report.record_skipped(SkippedService::new(
+            ServiceId::new("fghij-56789".to_string(), "a000".to_string()),
+            SkipReason::NoCommitHash,
+        ));
+
+        assert_eq!(
+            report.csv_lines(),
+            vec![
+                "CIRCUIT_ID,SERVICE_ID,OUTCOME,LEAVES_COPIED,DURATION_MS,REASON,ERROR".to_string(),
+                "abcde-01234,a000,migrated,10,5,,".to_string(),
+                "abcde-01234,b000,failed,0,7,,\"copy failed: missing node \"\"01\"\", aborted\""
+                    .to_string(),
+                "fghij-56789,a000,skipped,,,no_commit_hash,".to_string(),
+            ]
+        );
+    }
+
+    /// Verify that services left alone because the deadline passed are counted separately from
+    /// failures.
+    #[test]
+    fn test_report_records_not_attempted() {
+        let mut report = MigrationReport::start(false);
+        report.record_service(ServiceMigrationResult::not_attempted(
+            ServiceId::new("abcde-01234".to_string(), "a000".to_string()),
+            "commit".to_string(),
+        ));
+
+        assert_eq!(report.not_attempted(), 1);
+        assert_eq!(report.summary.failed, 0);
+        assert!(report.summary.to_string().contains("1 not attempted"));
+
+        let json = serde_json::to_value(&report).expect("Unable to serialize report");
+        assert_eq!(json["services"][0]["outcome"], "not_attempted");
+        assert_eq!(json["summary"]["not_attempted"], 1);
+    }
+
+    /// Verify that a migration without local scabbard services is summarized as having nothing to
+    /// migrate, rather than as a successful migration.
+    #[test]
+    fn test_report_records_no_scabbard_services() {
+        let mut report = MigrationReport::start(false);
+        report.record_skipped(SkippedService::new(
+            ServiceId::new("abcde-01234".to_string(), "a000".to_string()),
+            SkipReason::ServiceTypeMismatch,
+        ));
+        report.record_no_scabbard_services();
+
+        let summary = report.summary.to_string();
+        assert!(summary.contains("no local scabbard services to migrate, 1 skipped"));
+        assert!(!summary.contains("services migrated"));
+
+        let json = serde_json::to_value(&report).expect("Unable to serialize report");
+        assert_eq!(json["summary"]["no_scabbard_services"], true);
+        assert_eq!(json["summary"]["migrated"], 0);
+        assert_eq!(json["skipped"][0]["reason"], "service_type_mismatch");
+    }
+
+    /// Verify that a dry run counts a service whose state could not be read as a failure, and
+    /// includes the error in the json report.
+    #[test]
+    fn test_report_records_unreadable_readiness() {
+        let mut report = MigrationReport::start(true);
+        report.record_readiness(
+            ServiceReadiness::new(
+                ServiceId::new("abcde-01234".to_string(), "a000".to_string()),
+                10,
+                false,
+                640,
+            ),
+            true,
+        );
+        report.record_readiness(
+            ServiceReadiness::unreadable(
+                ServiceId::new("abcde-01234".to_string(), "b000".to_string()),
+                false,
+                "missing node".to_string(),
+            ),
+            false,
+        );
+
+        assert_eq!(report.summary.migrated, 1);
+        assert_eq!(report.summary.failed, 1);
+
+        let json = serde_json::to_value(&report).expect("Unable to serialize report");
+        assert_eq!(json["readiness"][0]["source_leaves"], 10);
+        assert!(json["readiness"][0].get("error").is_none());
+        assert_eq!(json["readiness"][1]["error"], "missing node");
+    }
+
+    /// Verify that each skip reason is written the same way in the json and csv reports, along
+    /// with the skipped service's IDs.
+    #[test]
+    fn test_report_skip_reasons() {
+        let reasons = [
+            (SkipReason::NoCommitHash, "no_commit_hash"),
+            (SkipReason::TreeExists, "tree_exists"),
+            (SkipReason::ServiceTypeMismatch, "service_type_mismatch"),
+            (SkipReason::Checkpointed, "checkpointed"),
+        ];
+
+        for (reason, expected) in &reasons {
+            assert_eq!(reason.as_str(), *expected);
+            assert_eq!(
+                serde_json::to_value(reason).expect("Unable to serialize skip reason"),
+                *expected
+            );
+
+            let skipped = SkippedService::new(
+                ServiceId::new("abcde-01234".to_string(), "a000".to_string()),
+                *reason,
+            );
+            assert_eq!(
+                serde_json::to_value(&skipped).expect("Unable to serialize skipped service"),
+                serde_json::json!({
+                    "circuit_id": "abcde-01234",
+                    "service_id": "a000",
+                    "reason": expected,
+                })
+            );
+
+            let mut report = MigrationReport::start(false);
+            report.record_skipped(skipped);
+
+            let json: serde_json::Value =
+                serde_json::from_str(&report.to_json().expect("Unable to format json report"))
+                    .expect("Unable to parse json report");
+            assert_eq!(json["summary"]["skipped"], 1);
+            assert_eq!(json["skipped"][0]["circuit_id"], "abcde-01234");
+            assert_eq!(json["skipped"][0]["service_id"], "a000");
+            assert_eq!(json["skipped"][0]["reason"], *expected);
+            assert_eq!(json["services"], serde_json::json!([]));
+
+            assert_eq!(
+                report.to_csv(),
+                format!(
+                    "CIRCUIT_ID,SERVICE_ID,OUTCOME,LEAVES_COPIED,DURATION_MS,REASON,ERROR\n\
+                    abcde-01234,a000,skipped,,,{},",
+                    expected
+                )
+            );
+        }
+    }
+}
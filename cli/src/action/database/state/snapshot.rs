@@ -0,0 +1,178 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the record of the state roots purged by migrations, used to restore purged state
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use splinter::admin::store::ServiceId;
+
+use crate::error::CliError;
+
+const SNAPSHOT_FILE_NAME: &str = "state-migrate.snapshots.json";
+
+/// How long a snapshot is kept, in seconds
+const SNAPSHOT_RETENTION_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// The state root of a service, recorded before its state was purged from the in database
+#[derive(Debug, Deserialize, Serialize)]
+struct SourceSnapshot {
+    circuit_id: String,
+    service_id: String,
+    root: String,
+    /// Seconds since the Unix epoch
+    recorded_at: u64,
+}
+
+/// The snapshots recorded in a state directory.
+///
+/// State trees are content-addressed, so the root of a purged tree is enough to copy the same
+/// state back from the out database, as long as the copy still contains that root. Snapshots
+/// older than the retention window are discarded the next time the log is written.
+pub struct SnapshotLog {
+    path: PathBuf,
+    snapshots: Vec<SourceSnapshot>,
+}
+
+impl SnapshotLog {
+    /// Opens the snapshot log for the given state directory, which is empty if no snapshots have
+    /// been recorded.
+    pub fn open(state_dir: &Path) -> Result<Self, CliError> {
+        let path = state_dir.join(SNAPSHOT_FILE_NAME);
+
+        let snapshots = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|err| {
+                CliError::EnvironmentError(format!(
+                    "Snapshot file {} is invalid: {}",
+                    path.display(),
+                    err
+                ))
+            })?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+            Err(err) => {
+                return Err(CliError::EnvironmentError(format!(
+                    "Unable to read snapshot file {}: {}",
+                    path.display(),
+                    err
+                )))
+            }
+        };
+
+        Ok(Self { path, snapshots })
+    }
+
+    /// Records the root of a service's state before it is purged.
+    pub fn record(&mut self, service_id: &ServiceId, root: &str) -> Result<(), CliError> {
+        self.snapshots.push(SourceSnapshot {
+            circuit_id: service_id.circuit().to_string(),
+            service_id: service_id.service_id().to_string(),
+            root: root.to_string(),
+            recorded_at: now(),
+        });
+        self.write()
+    }
+
+    /// Returns the services whose state had the given root when it was purged.
+    pub fn services_with_root(&self, root: &str) -> Vec<ServiceId> {
+        self.snapshots
+            .iter()
+            .filter(|snapshot| snapshot.root == root)
+            .map(|snapshot| {
+                ServiceId::new(snapshot.circuit_id.clone(), snapshot.service_id.clone())
+            })
+            .collect()
+    }
+
+    /// Removes the snapshots with the given root, once their state has been restored.
+    pub fn remove(&mut self, root: &str) -> Result<(), CliError> {
+        self.snapshots.retain(|snapshot| snapshot.root != root);
+        self.write()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn write(&mut self) -> Result<(), CliError> {
+        let expired_before = now().saturating_sub(SNAPSHOT_RETENTION_SECS);
+        self.snapshots
+            .retain(|snapshot| snapshot.recorded_at >= expired_before);
+
+        let contents = serde_json::to_string_pretty(&self.snapshots).map_err(|err| {
+            CliError::ActionError(format!("Cannot format snapshots into json: {}", err))
+        })?;
+
+        // Write to a temporary file first, so that an interrupted write does not lose the
+        // snapshots already recorded
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, contents)
+            .and_then(|_| fs::rename(&temp_path, &self.path))
+            .map_err(|err| {
+                CliError::EnvironmentError(format!(
+                    "Unable to write snapshot file {}: {}",
+                    self.path.display(),
+                    err
+                ))
+            })
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::Builder;
+
+    /// Verify that recorded snapshots are persisted, found by root, removed once restored, and
+    /// discarded once they are older than the retention window.
+    #[test]
+    fn test_snapshot_log() {
+        let temp_dir = Builder::new()
+            .prefix("test_snapshot_log")
+            .tempdir()
+            .expect("Unable to create temp dir");
+
+        let service_id = ServiceId::new("abcde-01234".to_string(), "a000".to_string());
+
+        let mut log = SnapshotLog::open(temp_dir.path()).expect("Unable to open log");
+        log.record(&service_id, "root-a").expect("Unable to record");
+        log.record(&service_id, "root-b").expect("Unable to record");
+
+        let mut log = SnapshotLog::open(temp_dir.path()).expect("Unable to reopen log");
+        assert_eq!(log.services_with_root("root-a"), vec![service_id.clone()]);
+        assert!(log.services_with_root("root-c").is_empty());
+
+        log.remove("root-a").expect("Unable to remove");
+        let mut log = SnapshotLog::open(temp_dir.path()).expect("Unable to reopen log");
+        assert!(log.services_with_root("root-a").is_empty());
+        assert_eq!(log.services_with_root("root-b"), vec![service_id.clone()]);
+
+        log.snapshots[0].recorded_at = 0;
+        log.record(&service_id, "root-c").expect("Unable to record");
+        let log = SnapshotLog::open(temp_dir.path()).expect("Unable to reopen log");
+        assert!(log.services_with_root("root-b").is_empty());
+        assert_eq!(log.services_with_root("root-c"), vec![service_id]);
+    }
+}
@@ -15,13 +15,14 @@
 use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, Pool};
 use sawtooth::receipt::store::{diesel::DieselReceiptStore, ReceiptStore};
+use scabbard::state_migration::{self, MigrationState, StateMigrationStores};
 use scabbard::store::transact::factory::LmdbDatabaseFactory;
 use scabbard::store::{
     diesel::{DieselCommitHashStore, DieselInTransactionCommitHashStore},
     CommitHashStore,
 };
 use splinter::{
-    admin::store::{diesel::DieselAdminServiceStore, AdminServiceStore},
+    admin::store::{diesel::DieselAdminServiceStore, AdminServiceStore, ServiceId},
     error::InternalError,
     node_id::store::{diesel::DieselNodeIdStore, NodeIdStore},
 };
@@ -37,11 +38,8 @@ pub trait UpgradeStores {
 
     fn new_node_id_store<'a>(&'a self) -> Box<dyn NodeIdStore + 'a>;
 
-    fn new_commit_hash_store<'a>(
-        &'a self,
-        circuit_id: &str,
-        service_id: &str,
-    ) -> Box<dyn CommitHashStore + 'a>;
+    fn new_commit_hash_store<'a>(&'a self, service_id: &ServiceId)
+        -> Box<dyn CommitHashStore + 'a>;
 
     fn new_receipt_store<'a>(
         &'a self,
@@ -51,12 +49,84 @@ pub trait UpgradeStores {
 
     fn get_merkle_state(
         &self,
-        circuit_id: &str,
-        service_id: &str,
+        service_id: &ServiceId,
         create_tree: bool,
     ) -> Result<MerkleState, InternalError>;
 
     fn new_state_tree_store<'a>(&'a self) -> Box<dyn StateTreeStore + 'a>;
+
+    /// Lists the services run by the given node on active circuits.
+    ///
+    /// The current commit hash is included for each scabbard service that has one; services of
+    /// other types never have a commit hash.
+    fn list_local_services(&self, node_id: &str) -> Result<Vec<LocalService>, InternalError> {
+        let circuits = self
+            .new_admin_service_store()
+            .list_circuits(&[])
+            .map_err(|err| InternalError::from_source(Box::new(err)))?;
+
+        let mut local_services = vec![];
+        for circuit in circuits {
+            for service in circuit
+                .roster()
+                .iter()
+                .filter(|service| service.node_id() == node_id)
+            {
+                let service_id = ServiceId::new(
+                    circuit.circuit_id().to_string(),
+                    service.service_id().to_string(),
+                );
+                let commit_hash = if service.service_type() == SCABBARD_SERVICE_TYPE {
+                    self.new_commit_hash_store(&service_id)
+                        .get_current_commit_hash()
+                        .map_err(|err| InternalError::from_source(Box::new(err)))?
+                } else {
+                    None
+                };
+
+                local_services.push(LocalService {
+                    service_id,
+                    service_type: service.service_type().to_string(),
+                    commit_hash,
+                });
+            }
+        }
+
+        Ok(local_services)
+    }
+}
+
+const SCABBARD_SERVICE_TYPE: &str = "scabbard";
+
+/// A service run by the local node, as returned by `UpgradeStores::list_local_services`.
+pub struct LocalService {
+    service_id: ServiceId,
+    service_type: String,
+    commit_hash: Option<String>,
+}
+
+impl LocalService {
+    pub fn service_id(&self) -> &ServiceId {
+        &self.service_id
+    }
+
+    pub fn service_type(&self) -> &str {
+        &self.service_type
+    }
+
+    /// Returns whether this is a scabbard service.
+    pub fn is_scabbard(&self) -> bool {
+        self.service_type == SCABBARD_SERVICE_TYPE
+    }
+
+    /// Returns the current commit hash of a scabbard service, if it has one.
+    pub fn commit_hash(&self) -> Option<&str> {
+        self.commit_hash.as_deref()
+    }
+
+    pub fn into_service_id(self) -> ServiceId {
+        self.service_id
+    }
 }
 
 type InTransactionHandle<'a> =
@@ -68,6 +138,76 @@ pub trait TransactionalUpgradeStores: UpgradeStores {
     fn as_upgrade_stores(&self) -> &dyn UpgradeStores;
 }
 
+/// Provides upgrade stores to `scabbard::state_migration`, which copies scabbard state between
+/// them
+pub struct MigrationStores<'a> {
+    stores: &'a dyn UpgradeStores,
+    /// The stores a transaction is started on; `None` if `stores` are already in a transaction
+    transactional: Option<&'a dyn TransactionalUpgradeStores>,
+}
+
+impl<'a> MigrationStores<'a> {
+    pub fn new(stores: &'a dyn TransactionalUpgradeStores) -> Self {
+        Self {
+            stores: stores.as_upgrade_stores(),
+            transactional: Some(stores),
+        }
+    }
+}
+
+impl<'a> StateMigrationStores for MigrationStores<'a> {
+    fn node_id(&self) -> Result<Option<String>, InternalError> {
+        self.stores
+            .new_node_id_store()
+            .get_node_id()
+            .map_err(|err| InternalError::from_source(Box::new(err)))
+    }
+
+    fn list_scabbard_services(
+        &self,
+        node_id: &str,
+    ) -> Result<Vec<state_migration::LocalService>, InternalError> {
+        Ok(self
+            .stores
+            .list_local_services(node_id)?
+            .into_iter()
+            .filter(LocalService::is_scabbard)
+            .map(|service| {
+                state_migration::LocalService::new(service.service_id, service.commit_hash)
+            })
+            .collect())
+    }
+
+    fn has_tree(&self, service_id: &ServiceId) -> Result<bool, InternalError> {
+        self.stores.new_state_tree_store().has_tree(service_id)
+    }
+
+    fn get_merkle_state<'b>(
+        &'b self,
+        service_id: &ServiceId,
+        create_tree: bool,
+    ) -> Result<Box<dyn MigrationState + 'b>, InternalError> {
+        Ok(Box::new(
+            self.stores.get_merkle_state(service_id, create_tree)?,
+        ))
+    }
+
+    fn in_transaction(
+        &self,
+        f: state_migration::InTransactionHandle<'_>,
+    ) -> Result<(), InternalError> {
+        match self.transactional {
+            Some(transactional) => transactional.in_transaction(Box::new(move |stores| {
+                f(&MigrationStores {
+                    stores,
+                    transactional: None,
+                })
+            })),
+            None => f(self),
+        }
+    }
+}
+
 pub fn new_upgrade_stores(
     database_uri: &ConnectionUri,
 ) -> Result<Box<dyn TransactionalUpgradeStores>, InternalError> {
@@ -132,15 +272,11 @@ impl UpgradeStores for PostgresUpgradeStores {
         Box::new(DieselNodeIdStore::new(self.0.clone()))
     }
 
-    fn new_commit_hash_store(
-        &self,
-        circuit_id: &str,
-        service_id: &str,
-    ) -> Box<dyn CommitHashStore> {
+    fn new_commit_hash_store(&self, service_id: &ServiceId) -> Box<dyn CommitHashStore> {
         Box::new(DieselCommitHashStore::new(
             self.0.clone(),
-            circuit_id,
-            service_id,
+            service_id.circuit(),
+            service_id.service_id(),
         ))
     }
 
@@ -153,14 +289,13 @@ impl UpgradeStores for PostgresUpgradeStores {
 
     fn get_merkle_state(
         &self,
-        circuit_id: &str,
-        service_id: &str,
+        service_id: &ServiceId,
         create_tree: bool,
     ) -> Result<MerkleState, InternalError> {
         let backend = backend::PostgresBackend::from(self.0.clone());
         let mut builder = SqlMerkleStateBuilder::new()
             .with_backend(backend)
-            .with_tree(format!("{}::{}", circuit_id, service_id));
+            .with_tree(service_id.to_string());
 
         if create_tree {
             builder = builder.create_tree_if_necessary();
@@ -211,11 +346,12 @@ impl<'a> UpgradeStores for InTransactionPostgresUpgradeStores<'a> {
 
     fn new_commit_hash_store<'b>(
         &'b self,
-        circuit_id: &str,
-        service_id: &str,
+        service_id: &ServiceId,
     ) -> Box<dyn CommitHashStore + 'a> {
         Box::new(DieselInTransactionCommitHashStore::new(
-            self.0, circuit_id, service_id,
+            self.0,
+            service_id.circuit(),
+            service_id.service_id(),
         ))
     }
 
@@ -225,14 +361,13 @@ impl<'a> UpgradeStores for InTransactionPostgresUpgradeStores<'a> {
 
     fn get_merkle_state(
         &self,
-        circuit_id: &str,
-        service_id: &str,
+        service_id: &ServiceId,
         create_tree: bool,
     ) -> Result<MerkleState, InternalError> {
         let backend = backend::InTransactionPostgresBackend::from(self.0);
         let mut builder = SqlMerkleStateBuilder::new()
             .with_backend(backend)
-            .with_tree(format!("{}::{}", circuit_id, service_id));
+            .with_tree(service_id.to_string());
 
         if create_tree {
             builder = builder.create_tree_if_necessary();
@@ -262,15 +397,11 @@ impl UpgradeStores for SqliteUpgradeStores {
         Box::new(DieselNodeIdStore::new(self.0.clone()))
     }
 
-    fn new_commit_hash_store(
-        &self,
-        circuit_id: &str,
-        service_id: &str,
-    ) -> Box<dyn CommitHashStore> {
+    fn new_commit_hash_store(&self, service_id: &ServiceId) -> Box<dyn CommitHashStore> {
         Box::new(DieselCommitHashStore::new(
             self.0.clone(),
-            circuit_id,
-            service_id,
+            service_id.circuit(),
+            service_id.service_id(),
         ))
     }
 
@@ -283,14 +414,13 @@ impl UpgradeStores for SqliteUpgradeStores {
 
     fn get_merkle_state(
         &self,
-        circuit_id: &str,
-        service_id: &str,
+        service_id: &ServiceId,
         create_tree: bool,
     ) -> Result<MerkleState, InternalError> {
         let backend = backend::SqliteBackend::from(self.0.clone());
         let mut builder = SqlMerkleStateBuilder::new()
             .with_backend(backend)
-            .with_tree(format!("{}::{}", circuit_id, service_id));
+            .with_tree(service_id.to_string());
 
         if create_tree {
             builder = builder.create_tree_if_necessary();
@@ -340,11 +470,12 @@ impl<'a> UpgradeStores for InTransactionSqliteUpgradeStores<'a> {
 
     fn new_commit_hash_store<'b>(
         &'b self,
-        circuit_id: &str,
-        service_id: &str,
+        service_id: &ServiceId,
     ) -> Box<dyn CommitHashStore + 'a> {
         Box::new(DieselInTransactionCommitHashStore::new(
-            self.0, circuit_id, service_id,
+            self.0,
+            service_id.circuit(),
+            service_id.service_id(),
         ))
     }
 
@@ -354,14 +485,13 @@ impl<'a> UpgradeStores for InTransactionSqliteUpgradeStores<'a> {
 
     fn get_merkle_state(
         &self,
-        circuit_id: &str,
-        service_id: &str,
+        service_id: &ServiceId,
         create_tree: bool,
     ) -> Result<MerkleState, InternalError> {
         let backend = backend::InTransactionSqliteBackend::from(self.0);
         let mut builder = SqlMerkleStateBuilder::new()
             .with_backend(backend)
-            .with_tree(format!("{}::{}", circuit_id, service_id));
+            .with_tree(service_id.to_string());
 
         if create_tree {
             builder = builder.create_tree_if_necessary();
@@ -406,11 +536,9 @@ impl UpgradeStores for UpgradeStoresWithLmdb {
 
     fn new_commit_hash_store<'a>(
         &'a self,
-        circuit_id: &str,
-        service_id: &str,
+        service_id: &ServiceId,
     ) -> Box<dyn CommitHashStore + 'a> {
-        self.upgrade_stores
-            .new_commit_hash_store(circuit_id, service_id)
+        self.upgrade_stores.new_commit_hash_store(service_id)
     }
 
     fn new_receipt_store<'a>(
@@ -424,11 +552,10 @@ impl UpgradeStores for UpgradeStoresWithLmdb {
 
     fn get_merkle_state<'a>(
         &'a self,
-        circuit_id: &str,
-        service_id: &str,
+        service_id: &ServiceId,
         create_tree: bool,
     ) -> Result<MerkleState<'a>, InternalError> {
-        create_lmdb_merkle_state(&self.lmdb_db_factory, circuit_id, service_id, create_tree)
+        create_lmdb_merkle_state(&self.lmdb_db_factory, service_id, create_tree)
     }
 
     fn new_state_tree_store<'a>(&'a self) -> Box<dyn StateTreeStore + 'a> {
@@ -472,11 +599,9 @@ impl<'u> UpgradeStores for InTransactionUpgradeStoresWithLmdb<'u> {
 
     fn new_commit_hash_store<'a>(
         &'a self,
-        circuit_id: &str,
-        service_id: &str,
+        service_id: &ServiceId,
     ) -> Box<dyn CommitHashStore + 'a> {
-        self.upgrade_stores
-            .new_commit_hash_store(circuit_id, service_id)
+        self.upgrade_stores.new_commit_hash_store(service_id)
     }
 
     fn new_receipt_store<'a>(
@@ -490,11 +615,10 @@ impl<'u> UpgradeStores for InTransactionUpgradeStoresWithLmdb<'u> {
 
     fn get_merkle_state(
         &self,
-        circuit_id: &str,
-        service_id: &str,
+        service_id: &ServiceId,
         create_tree: bool,
     ) -> Result<MerkleState, InternalError> {
-        create_lmdb_merkle_state(&self.lmdb_db_factory, circuit_id, service_id, create_tree)
+        create_lmdb_merkle_state(&self.lmdb_db_factory, service_id, create_tree)
     }
 
     fn new_state_tree_store(&self) -> Box<dyn StateTreeStore> {
@@ -504,15 +628,14 @@ impl<'u> UpgradeStores for InTransactionUpgradeStoresWithLmdb<'u> {
 
 fn create_lmdb_merkle_state<'a>(
     lmdb_db_factory: &LmdbDatabaseFactory,
-    circuit_id: &str,
-    service_id: &str,
+    service_id: &ServiceId,
     create_tree: bool,
 ) -> Result<MerkleState<'a>, InternalError> {
     Ok(MerkleState::Lmdb {
         state: LazyLmdbMerkleState::new(
             lmdb_db_factory.clone(),
-            circuit_id,
-            service_id,
+            service_id.circuit(),
+            service_id.service_id(),
             create_tree,
         )?,
     })
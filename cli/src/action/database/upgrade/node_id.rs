@@ -19,6 +19,7 @@ use std::path::PathBuf;
 use log::info;
 use splinter::node_id::store::file::FileNodeIdStore;
 use splinter::node_id::store::NodeIdStore;
+use splinter::node_id::NodeId;
 
 use crate::error::CliError;
 
@@ -33,10 +34,13 @@ fn import_store(
     from: &'_ dyn NodeIdStore,
 ) -> Result<WarningEmitted, CliError> {
     match (from.get_node_id(), to.get_node_id()) {
-        (Ok(Some(id)), Ok(None)) => to
-            .set_node_id(id)
-            .map_err(|e| CliError::ActionError(format!("{}", e)))
-            .map(|_| WarningEmitted::No),
+        (Ok(Some(id)), Ok(None)) => {
+            let id = NodeId::new(id)
+                .map_err(|e| CliError::ActionError(format!("Unable to import node_id: {}", e)))?;
+            to.set_node_id(id)
+                .map_err(|e| CliError::ActionError(format!("{}", e)))
+                .map(|_| WarningEmitted::No)
+        }
         (Ok(Some(_)), Ok(Some(_))) => Err(CliError::ActionError(
             "Skipping node_id import: destination store already has node_id set".to_string(),
         )),
@@ -111,8 +115,8 @@ mod tests {
         fn get_node_id(&self) -> Result<Option<String>, NodeIdStoreError> {
             Ok(self.value.borrow().to_owned())
         }
-        fn set_node_id(&self, node_id: String) -> Result<(), NodeIdStoreError> {
-            self.value.replace(Some(node_id));
+        fn set_node_id(&self, node_id: NodeId) -> Result<(), NodeIdStoreError> {
+            self.value.replace(Some(node_id.to_string()));
             Ok(())
         }
     }
@@ -135,7 +139,7 @@ mod tests {
                 )),
             ))
         }
-        fn set_node_id(&self, _node_id: String) -> Result<(), NodeIdStoreError> {
+        fn set_node_id(&self, _node_id: NodeId) -> Result<(), NodeIdStoreError> {
             Err(NodeIdStoreError::InternalError(
                 InternalError::with_message(String::from(
                     "This is an intentional error, please disregard",
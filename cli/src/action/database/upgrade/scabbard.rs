@@ -21,7 +21,10 @@ use scabbard::store::{
     CommitHashStore,
 };
 
-use splinter::error::{InternalError, InvalidStateError};
+use splinter::{
+    admin::store::ServiceId,
+    error::{InternalError, InvalidStateError},
+};
 
 use super::error::UpgradeError;
 
@@ -63,7 +66,7 @@ pub(super) fn upgrade_scabbard_commit_hash_state(
             .iter()
             .filter_map(|svc| {
                 if svc.node_id() == node_id && svc.service_type() == "scabbard" {
-                    Some((
+                    Some(ServiceId::new(
                         circuit.circuit_id().to_string(),
                         svc.service_id().to_string(),
                     ))
@@ -74,10 +77,11 @@ pub(super) fn upgrade_scabbard_commit_hash_state(
             .collect::<Vec<_>>()
     });
 
-    for (circuit_id, service_id) in local_services {
-        let lmdb_commit_hash_store =
-            TransactCommitHashStore::new(lmdb_db_factory.get_database(&circuit_id, &service_id)?);
-        let db_commit_hash_store = upgrade_stores.new_commit_hash_store(&circuit_id, &service_id);
+    for service_id in local_services {
+        let lmdb_commit_hash_store = TransactCommitHashStore::new(
+            lmdb_db_factory.get_database(service_id.circuit(), service_id.service_id())?,
+        );
+        let db_commit_hash_store = upgrade_stores.new_commit_hash_store(&service_id);
 
         if let Some(current_commit_hash) = lmdb_commit_hash_store
             .get_current_commit_hash()
@@ -86,12 +90,9 @@ pub(super) fn upgrade_scabbard_commit_hash_state(
             db_commit_hash_store
                 .set_current_commit_hash(&current_commit_hash)
                 .map_err(|e| InternalError::from_source(Box::new(e)))?;
-            info!("Upgraded scabbard service {}::{}", circuit_id, service_id);
+            info!("Upgraded scabbard service {}", service_id);
         } else {
-            debug!(
-                "No commit hash found for service {}::{}",
-                circuit_id, service_id
-            );
+            debug!("No commit hash found for service {}", service_id);
         }
     }
 
@@ -44,11 +44,15 @@ use std::path::Path;
 
 use clap::ArgMatches;
 
+use super::config::CliConfig;
 use super::error::CliError;
 
 const DEFAULT_SPLINTER_REST_API_URL: &str = "http://127.0.0.1:8080";
 const SPLINTER_REST_API_URL_ENV: &str = "SPLINTER_REST_API_URL";
 
+const DEFAULT_OUTPUT_FORMAT: &str = "human";
+const SPLINTER_OUTPUT_FORMAT_ENV: &str = "SPLINTER_OUTPUT_FORMAT";
+
 #[cfg(any(feature = "workload", feature = "playlist-smallbank"))]
 const DEFAULT_LOG_TIME_SECS: u32 = 30; // time in seconds
 
@@ -112,6 +116,53 @@ fn chown(path: &Path, uid: u32, gid: u32) -> Result<(), CliError> {
     }
 }
 
+/// Resolves the output format of a list command.
+///
+/// The format is taken from the first of the following that is set: the `--format` argument (or
+/// its hidden `-f` alias), the `SPLINTER_OUTPUT_FORMAT` environment variable, the `output.format`
+/// setting in the CLI config file, and finally `human`. The resolved format must be one of
+/// `formats`, the formats supported by the command.
+fn resolve_output_format(
+    arg_matches: Option<&ArgMatches>,
+    formats: &[&str],
+) -> Result<String, CliError> {
+    // The `format` argument has a default value, so only use it if it was given explicitly
+    let arg_format = arg_matches.and_then(|args| {
+        args.value_of("hidden_format").or_else(|| {
+            if args.occurrences_of("format") > 0 {
+                args.value_of("format")
+            } else {
+                None
+            }
+        })
+    });
+
+    let (format, source) = if let Some(format) = arg_format {
+        (format.to_string(), "the --format argument")
+    } else if let Ok(format) = std::env::var(SPLINTER_OUTPUT_FORMAT_ENV) {
+        (format, SPLINTER_OUTPUT_FORMAT_ENV)
+    } else if let Some(format) = CliConfig::load()?.output().format() {
+        (format.to_string(), "the config file")
+    } else {
+        (DEFAULT_OUTPUT_FORMAT.to_string(), "the default")
+    };
+
+    check_output_format(format, source, formats)
+}
+
+fn check_output_format(format: String, source: &str, formats: &[&str]) -> Result<String, CliError> {
+    if formats.contains(&format.as_str()) {
+        Ok(format)
+    } else {
+        Err(CliError::ActionError(format!(
+            "Invalid output format '{}' from {}; this command supports: {}",
+            format,
+            source,
+            formats.join(", ")
+        )))
+    }
+}
+
 fn msg_from_io_error(err: IoError) -> String {
     match err.kind() {
         ErrorKind::NotFound => "File not found".into(),
@@ -154,3 +205,27 @@ fn print_table(table: Vec<Vec<String>>) {
         println!("{}", col_string);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that a supported output format is accepted and an unsupported one is rejected with
+    /// an error naming its source.
+    #[test]
+    fn test_check_output_format() {
+        assert_eq!(
+            check_output_format("csv".into(), "the config file", &["human", "csv"])
+                .expect("csv was rejected"),
+            "csv"
+        );
+
+        match check_output_format("yaml".into(), SPLINTER_OUTPUT_FORMAT_ENV, &["human", "csv"]) {
+            Err(CliError::ActionError(msg)) => {
+                assert!(msg.contains("'yaml'"));
+                assert!(msg.contains(SPLINTER_OUTPUT_FORMAT_ENV));
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+}
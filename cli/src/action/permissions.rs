@@ -16,13 +16,14 @@
 //! Actions for handling permissions subcommands.
 
 use clap::ArgMatches;
+use reqwest::blocking::Client;
 
 use crate::error::CliError;
 use crate::signing::{create_cylinder_jwt_auth, load_signer};
 
 use super::{
-    api::SplinterRestClientBuilder, print_table, Action, DEFAULT_SPLINTER_REST_API_URL,
-    SPLINTER_REST_API_URL_ENV,
+    api::SplinterRestClientBuilder, print_table, resolve_output_format, Action,
+    DEFAULT_SPLINTER_REST_API_URL, SPLINTER_REST_API_URL_ENV,
 };
 
 /// The action responsible for listing permissions.
@@ -32,13 +33,27 @@ use super::{
 /// * url: specifies the URL of the splinter node to be queried; falls back to the environment
 ///   variable SPLINTER_REST_API_URL
 /// * format: specifies the output format; one of "human", "json" or "csv"
-pub struct ListAction;
+/// * search: only list the permissions whose ID, name or description contains this text
+/// * fail_on_empty: return an error after printing the result if there are no permissions
+///
+/// By default, a new HTTP client is created each time the action is run. Code that runs the
+/// action repeatedly can provide its own with `with_http_client`, so that connections are reused.
+#[derive(Default)]
+pub struct ListAction {
+    http_client: Option<Client>,
+}
+
+impl ListAction {
+    /// Sets the HTTP client used to list permissions; it is shared by every run of the action.
+    pub fn with_http_client(mut self, http_client: Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+}
 
 impl Action for ListAction {
     fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
-        let format = arg_matches
-            .and_then(|args| args.value_of("format"))
-            .unwrap_or("human");
+        let format = resolve_output_format(arg_matches, &["human", "csv", "json"])?;
         let url = arg_matches
             .and_then(|args| args.value_of("url"))
             .map(ToOwned::to_owned)
@@ -47,11 +62,16 @@ impl Action for ListAction {
 
         let signer = load_signer(arg_matches.and_then(|args| args.value_of("private_key_file")))?;
 
-        let permissions = SplinterRestClientBuilder::new()
+        let mut builder = SplinterRestClientBuilder::new()
             .with_url(url)
-            .with_auth(create_cylinder_jwt_auth(signer)?)
+            .with_auth(create_cylinder_jwt_auth(signer)?);
+        if let Some(http_client) = &self.http_client {
+            builder = builder.with_http_client(http_client.clone());
+        }
+        let permissions = builder
             .build()?
-            .list_permissions()?;
+            .list_permissions(arg_matches.and_then(|args| args.value_of("search")))?;
+        let is_empty = permissions.is_empty();
 
         let data = std::iter::once(vec![
             "ID".to_string(),
@@ -66,7 +86,7 @@ impl Action for ListAction {
             ]
         }));
 
-        match format {
+        match format.as_str() {
             "csv" => {
                 for row in data {
                     println!("{}", row.join(","))
@@ -81,6 +101,10 @@ impl Action for ListAction {
             _ => print_table(data.collect()),
         }
 
+        if is_empty && arg_matches.map_or(false, |args| args.is_present("fail_on_empty")) {
+            return Err(CliError::EmptyResult("No permissions found".into()));
+        }
+
         Ok(())
     }
 }
@@ -20,7 +20,7 @@ use clap::ArgMatches;
 
 use crate::action::{
     api::{Assignment, AssignmentBuilder, AssignmentUpdateBuilder, Identity, SplinterRestClient},
-    print_table, Action,
+    print_table, resolve_output_format, Action,
 };
 use crate::error::CliError;
 
@@ -35,9 +35,7 @@ pub struct ListAssignmentsAction;
 
 impl Action for ListAssignmentsAction {
     fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
-        let format = arg_matches
-            .and_then(|args| args.value_of("format"))
-            .unwrap_or("human");
+        let format = resolve_output_format(arg_matches, &["human", "csv"])?;
 
         let client = new_client(&arg_matches)?;
 
@@ -17,9 +17,11 @@
 mod assignments;
 mod roles;
 
+use std::path::PathBuf;
+
 use clap::ArgMatches;
 
-use crate::action::api::{SplinterRestClient, SplinterRestClientBuilder};
+use crate::action::api::{AuditLog, SplinterRestClient, SplinterRestClientBuilder};
 use crate::action::{DEFAULT_SPLINTER_REST_API_URL, SPLINTER_REST_API_URL_ENV};
 use crate::error::CliError;
 use crate::signing::{create_cylinder_jwt_auth, load_signer};
@@ -29,9 +31,13 @@ pub use assignments::{
     UpdateAssignmentAction,
 };
 pub use roles::{
-    CreateRoleAction, DeleteRoleAction, ListRolesAction, ShowRoleAction, UpdateRoleAction,
+    AuditRolesAction, CloneRoleAction, CreateRoleAction, DeleteRoleAction, ImportRolesAction,
+    ListRolesAction, ListRolesByPermissionAction, ShowRoleAction, UpdateRoleAction,
 };
 
+/// The environment variable naming the audit log file, used if `--audit-log` is not provided
+const SPLINTER_AUDIT_LOG_ENV: &str = "SPLINTER_AUDIT_LOG";
+
 /// Constructs a new Splinter REST client from the CLI arguments.
 fn new_client(arg_matches: &Option<&ArgMatches<'_>>) -> Result<SplinterRestClient, CliError> {
     let url = arg_matches
@@ -42,8 +48,27 @@ fn new_client(arg_matches: &Option<&ArgMatches<'_>>) -> Result<SplinterRestClien
 
     let signer = load_signer(arg_matches.and_then(|args| args.value_of("private_key_file")))?;
 
-    SplinterRestClientBuilder::new()
-        .with_url(url)
-        .with_auth(create_cylinder_jwt_auth(signer)?)
-        .build()
+    let audit_log_path = arg_matches
+        .and_then(|args| args.value_of("audit_log"))
+        .map(ToOwned::to_owned)
+        .or_else(|| std::env::var(SPLINTER_AUDIT_LOG_ENV).ok());
+
+    let mut builder = SplinterRestClientBuilder::new().with_url(url).with_dry_run(
+        arg_matches
+            .map(|args| args.is_present("dry_run"))
+            .unwrap_or(false),
+    );
+
+    // Changes are recorded as made by the key that authorizes them
+    if let Some(path) = audit_log_path {
+        let identity = signer
+            .public_key()
+            .map_err(|err| {
+                CliError::ActionError(format!("Unable to get signer public key: {}", err))
+            })?
+            .as_hex();
+        builder = builder.with_audit_log(AuditLog::new(PathBuf::from(path), identity));
+    }
+
+    builder.with_auth(create_cylinder_jwt_auth(signer)?).build()
 }
@@ -14,13 +14,18 @@
 
 //! Actions to support the RBAC subcommands related to roles.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use clap::ArgMatches;
+use serde::Deserialize;
 
 use crate::action::{
-    api::{RoleBuilder, RoleUpdateBuilder, SplinterRestClient},
-    print_table, Action,
+    api::{Role, RoleBuilder, RoleDiff, RoleUpdateBuilder, SplinterRestClient},
+    print_table, resolve_output_format, Action,
 };
 use crate::error::CliError;
 
@@ -31,38 +36,167 @@ use super::new_client;
 /// The specific args for this action:
 ///
 /// * format: specifies the output format; one of "human" or "csv"
+/// * fail_on_empty: return an error after printing the result if there are no roles
 pub struct ListRolesAction;
 
 impl Action for ListRolesAction {
     fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
-        let format = arg_matches
-            .and_then(|args| args.value_of("format"))
-            .unwrap_or("human");
+        let format = resolve_output_format(arg_matches, &["human", "csv"])?;
 
         let client = new_client(&arg_matches)?;
         let roles = client.list_roles()?;
 
         let header = vec!["ID".to_string(), "NAME".to_string()];
 
+        let mut count = 0;
         if format == "csv" {
             println!("{}", header.join(","));
             for role_res in roles {
                 let role = role_res?;
                 println!("{},{}", role.role_id, role.display_name);
+                count += 1;
             }
         } else {
             let mut rows = vec![header];
             for role_res in roles {
                 let role = role_res?;
                 rows.push(vec![role.role_id, role.display_name]);
+                count += 1;
             }
             print_table(rows);
         }
 
+        if count == 0 && arg_matches.map_or(false, |args| args.is_present("fail_on_empty")) {
+            return Err(CliError::EmptyResult("No roles found".into()));
+        }
+
+        Ok(())
+    }
+}
+
+/// The action responsible for listing, for each permission, the roles which grant it.
+///
+/// The specific args for this action:
+///
+/// * format: specifies the output format; one of "human", "json", or "csv"
+pub struct ListRolesByPermissionAction;
+
+impl Action for ListRolesByPermissionAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let format = resolve_output_format(arg_matches, &["human", "json", "csv"])?;
+
+        // Sort by permission ID for stable output
+        let index = new_client(&arg_matches)?
+            .list_roles_by_permission()?
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+
+        match format.as_str() {
+            "json" => println!(
+                "{}",
+                serde_json::to_string_pretty(&index).map_err(|err| CliError::ActionError(
+                    format!("Cannot format permission index into json: {}", err)
+                ))?
+            ),
+            "csv" => {
+                println!("PERMISSION,ROLE");
+                for (permission_id, role_ids) in index {
+                    for role_id in role_ids {
+                        println!("{},{}", permission_id, role_id);
+                    }
+                }
+            }
+            _ => {
+                let mut rows = vec![vec!["PERMISSION".to_string(), "ROLES".to_string()]];
+                for (permission_id, role_ids) in index {
+                    rows.push(vec![permission_id, role_ids.join(", ")]);
+                }
+                print_table(rows);
+            }
+        }
+
         Ok(())
     }
 }
 
+/// The action responsible for auditing the roles for permissions that are no longer known to the
+/// node.
+///
+/// The specific args for this action:
+///
+/// * format: specifies the output format; one of "human" or "json"
+/// * strict: return an error after printing the result if any role grants an unknown permission
+pub struct AuditRolesAction;
+
+impl Action for AuditRolesAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let format = resolve_output_format(arg_matches, &["human", "json"])?;
+
+        let client = new_client(&arg_matches)?;
+        let known_permissions = client.permission_ids()?;
+        let roles = client.list_roles()?.collect::<Result<Vec<_>, _>>()?;
+        let dangling = find_dangling_permissions(roles, &known_permissions);
+
+        match format.as_str() {
+            "json" => println!(
+                "{}",
+                serde_json::to_string_pretty(&dangling).map_err(|err| CliError::ActionError(
+                    format!("Cannot format role audit into json: {}", err)
+                ))?
+            ),
+            _ => {
+                if dangling.is_empty() {
+                    println!("Every role grants only known permissions");
+                } else {
+                    let mut rows =
+                        vec![vec!["ROLE".to_string(), "UNKNOWN PERMISSIONS".to_string()]];
+                    for (role_id, permissions) in &dangling {
+                        rows.push(vec![role_id.clone(), permissions.join(", ")]);
+                    }
+                    print_table(rows);
+                }
+            }
+        }
+
+        if !dangling.is_empty() && arg_matches.map_or(false, |args| args.is_present("strict")) {
+            return Err(CliError::ActionError(format!(
+                "{} role(s) grant permissions that are not known to the node",
+                dangling.len()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the permissions of each role that are not in `known_permissions`, keyed by role ID.
+///
+/// Roles that only grant known permissions are not included.
+fn find_dangling_permissions<I>(
+    roles: I,
+    known_permissions: &BTreeSet<String>,
+) -> BTreeMap<String, Vec<String>>
+where
+    I: IntoIterator<Item = Role>,
+{
+    roles
+        .into_iter()
+        .filter_map(|role| {
+            let mut dangling = role
+                .permissions
+                .into_iter()
+                .filter(|permission| !known_permissions.contains(permission))
+                .collect::<Vec<_>>();
+            if dangling.is_empty() {
+                return None;
+            }
+            dangling.sort();
+            dangling.dedup();
+            Some((role.role_id, dangling))
+        })
+        .collect()
+}
+
 /// The action responsible for showing a specific role.
 ///
 /// The specific args for this action:
@@ -114,7 +248,9 @@ impl Action for ShowRoleAction {
 /// * role_id: the specified role ID
 /// * display_name: the role's display name
 /// * permission: a permission granted by the resulting role; repeated
-/// * dry_run: validate the inputs but do not submit the role
+/// * dry_run: validate the inputs and print the request instead of submitting the role
+/// * enforce_unique_name: reject the role if another role has the same display name
+/// * strict: reject the role if any of its permissions is not known to the node
 pub struct CreateRoleAction;
 
 impl Action for CreateRoleAction {
@@ -135,23 +271,66 @@ impl Action for CreateRoleAction {
             .map(|s| s.to_owned())
             .collect();
 
-        let role = RoleBuilder::default()
+        let client = new_client(&arg_matches)?;
+
+        let mut builder = RoleBuilder::default()
             .with_role_id(role_id.into())
             .with_display_name(display_name.into())
-            .with_permissions(permissions)
-            .build()?;
+            .with_permissions(permissions);
+        // Roles may be created ahead of the permissions they grant, so this is only checked on
+        // request
+        if arg_matches.map_or(false, |args| args.is_present("strict")) {
+            builder = builder.with_known_permissions(client.permission_ids()?);
+        }
+        let role = builder.build()?;
 
-        let client = new_client(&arg_matches)?;
-        if !is_dry_run(&arg_matches) {
-            client.create_role(role)
-        } else if client.get_role(role_id)?.is_some() {
-            Err(CliError::ActionError(format!(
+        if is_dry_run(&arg_matches) && client.get_role(role_id)?.is_some() {
+            return Err(CliError::ActionError(format!(
                 "A Role with ID {} already exists",
                 role_id
-            )))
-        } else {
-            Ok(())
+            )));
         }
+
+        // The node allows duplicate display names, so this is only checked on request
+        if arg_matches.map_or(false, |args| args.is_present("enforce_unique_name")) {
+            for existing in client.list_roles()? {
+                let existing = existing?;
+                if existing.display_name == role.display_name {
+                    return Err(CliError::ActionError(format!(
+                        "Role {} already has the display name \"{}\"",
+                        existing.role_id, role.display_name
+                    )));
+                }
+            }
+        }
+
+        client.create_role(role)
+    }
+}
+
+/// The action responsible for creating a role with the permissions of an existing role.
+///
+/// The specific args for this action:
+///
+/// * source_role_id: the ID of the role to be copied
+/// * role_id: the ID of the new role
+/// * display_name: the new role's display name; defaults to the source role's display name
+/// * dry_run: validate the inputs and print the request instead of submitting the role
+pub struct CloneRoleAction;
+
+impl Action for CloneRoleAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let source_role_id = arg_matches
+            .and_then(|args| args.value_of("source_role_id"))
+            .ok_or_else(|| CliError::ActionError("A source role ID must be specified".into()))?;
+
+        let role_id = arg_matches
+            .and_then(|args| args.value_of("role_id"))
+            .ok_or_else(|| CliError::ActionError("A role must have an ID".into()))?;
+
+        let display_name = arg_matches.and_then(|args| args.value_of("display_name"));
+
+        new_client(&arg_matches)?.clone_role(source_role_id, role_id, display_name)
     }
 }
 
@@ -165,7 +344,7 @@ impl Action for CreateRoleAction {
 /// * rm_permission: a permission to remove from the role; repeated
 /// * rm_all: remove all the currently granted permissions from the role
 /// * force: applies the changes, even if a permission is added and removed
-/// * dry_run: validate the inputs but do not submit the changes
+/// * dry_run: validate the inputs and print the request instead of submitting the changes
 pub struct UpdateRoleAction;
 
 impl Action for UpdateRoleAction {
@@ -208,7 +387,6 @@ impl Action for UpdateRoleAction {
             permissions_to_add,
             permission_removal,
             force,
-            is_dry_run(&arg_matches),
         )
     }
 }
@@ -225,68 +403,48 @@ fn update_role(
     permissions_to_add: Vec<String>,
     permission_removal: PermissionRemoval,
     force: bool,
-    is_dry_run: bool,
 ) -> Result<(), CliError> {
     let role = client
         .get_role(role_id)?
         .ok_or_else(|| CliError::ActionError(format!("Role {} does not exist", role_id)))?;
 
-    let permissions = match permission_removal {
+    let builder = RoleUpdateBuilder::default()
+        .with_role_id(role_id.into())
+        .with_display_name(display_name);
+
+    let updated_role = match permission_removal {
         PermissionRemoval::RemoveAll => {
             println!("Removing permissions {}", role.permissions.join(", "));
-            permissions_to_add
+            builder.with_permissions(Some(permissions_to_add)).build()?
         }
+        PermissionRemoval::Remove(permissions_to_rm) if !force => builder
+            .with_current_role(role.clone())
+            .with_add_permissions(permissions_to_add)
+            .with_remove_permissions(permissions_to_rm)
+            .build()?,
+        // Forced changes ignore permissions that are both added and removed, or that do not
+        // belong to the role
         PermissionRemoval::Remove(permissions_to_rm) => {
-            let mut permissions_to_add = permissions_to_add.into_iter().collect::<BTreeSet<_>>();
-            let mut permissions_to_rm = permissions_to_rm.into_iter().collect::<BTreeSet<_>>();
-
-            if !force && permissions_to_add.intersection(&permissions_to_rm).count() > 0 {
-                return Err(CliError::ActionError(format!(
-                    "Cannot add and remove the same permissions: {}",
-                    permissions_to_add
-                        .intersection(&permissions_to_rm)
-                        .map(|s| s.as_str())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                )));
-            }
-
-            let mut current_permissions = role
+            let permissions_to_rm = permissions_to_rm.into_iter().collect::<BTreeSet<_>>();
+            let mut permissions = role
                 .permissions
-                .into_iter()
-                .filter(|perm| !permissions_to_rm.remove(perm))
+                .iter()
+                .filter(|perm| !permissions_to_rm.contains(*perm))
+                .cloned()
                 .collect::<BTreeSet<_>>();
+            permissions.extend(permissions_to_add);
 
-            current_permissions.append(&mut permissions_to_add);
-
-            let permissions = current_permissions.into_iter().collect::<Vec<_>>();
-
-            if !force && !permissions_to_rm.is_empty() {
-                return Err(CliError::ActionError(format!(
-                    "Cannot remove permissions that do not belong to the role: {}",
-                    permissions_to_rm
-                        .iter()
-                        .map(|s| s.as_str())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                )));
-            }
-
-            permissions
+            builder
+                .with_permissions(Some(permissions.into_iter().collect()))
+                .build()?
         }
     };
 
-    let updated_role = RoleUpdateBuilder::default()
-        .with_role_id(role_id.into())
-        .with_display_name(display_name)
-        .with_permissions(Some(permissions))
-        .build()?;
+    let diff = RoleDiff::new(&role, &updated_role);
+    client.update_role(updated_role)?;
+    println!("{}", diff);
 
-    if !is_dry_run {
-        client.update_role(updated_role)
-    } else {
-        Ok(())
-    }
+    Ok(())
 }
 
 /// The action responsible for deleting a specific role.
@@ -294,6 +452,7 @@ fn update_role(
 /// The specific args for this action:
 ///
 /// * role_id: the specified role ID
+/// * dry_run: print the request instead of deleting the role
 pub struct DeleteRoleAction;
 
 impl Action for DeleteRoleAction {
@@ -302,11 +461,282 @@ impl Action for DeleteRoleAction {
             .and_then(|args| args.value_of("role_id"))
             .ok_or_else(|| CliError::ActionError("A role ID must be specified".into()))?;
 
-        if !is_dry_run(&arg_matches) {
-            new_client(&arg_matches)?.delete_role(role_id)
-        } else {
-            Ok(())
+        new_client(&arg_matches)?.delete_role(role_id)
+    }
+}
+
+/// The action responsible for creating the roles defined in a file.
+///
+/// The roles that have been created are recorded in a progress file next to the role file, so
+/// that an interrupted import can be continued with `resume`. The progress file is removed once
+/// every role has been created.
+///
+/// By default, every role is validated before any is created and the import stops at the first
+/// role that cannot be created. With `continue_on_error`, the import goes on past invalid roles
+/// and roles that could not be created, and reports every failure at the end.
+///
+/// The specific args for this action:
+///
+/// * file: a YAML or JSON file containing a list of roles
+/// * resume: skip the roles recorded by an interrupted import
+/// * continue_on_error: keep going after a role could not be created
+pub struct ImportRolesAction;
+
+impl Action for ImportRolesAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let file = arg_matches
+            .and_then(|args| args.value_of("file"))
+            .ok_or_else(|| CliError::ActionError("A role file must be specified".into()))?;
+        let resume = arg_matches
+            .map(|args| args.is_present("resume"))
+            .unwrap_or(false);
+        let continue_on_error = arg_matches
+            .map(|args| args.is_present("continue_on_error"))
+            .unwrap_or(false);
+
+        let roles = read_role_file(Path::new(file))?;
+        if !continue_on_error {
+            if let Some((role_id, Err(err))) = roles.iter().find(|(_, role)| role.is_err()) {
+                return Err(CliError::ActionError(format!(
+                    "Invalid role {}: {}",
+                    role_id, err
+                )));
+            }
+        }
+
+        let mut progress =
+            ImportProgress::open(PathBuf::from(format!("{}.progress", file)), resume)?;
+
+        let client = new_client(&arg_matches)?;
+        let summary = import_roles(roles, &mut progress, continue_on_error, |role| {
+            client.create_role(role)
+        })?;
+        println!("{}", summary);
+
+        if !summary.failed.is_empty() {
+            return Err(CliError::ActionError(format!(
+                "{} of {} roles could not be imported; run the import again with --resume to \
+                retry them",
+                summary.failed.len(),
+                summary.total()
+            )));
+        }
+
+        progress.finish()
+    }
+}
+
+/// Creates each of the given roles with `create_role`, skipping those already recorded in
+/// `progress`, and records each role that is created.
+///
+/// If `continue_on_error` is false, the first invalid role or role that cannot be created is
+/// returned as an error. Otherwise the failure is recorded in the summary and the import continues
+/// with the next role.
+fn import_roles<F>(
+    roles: Vec<(String, Result<Role, CliError>)>,
+    progress: &mut ImportProgress,
+    continue_on_error: bool,
+    mut create_role: F,
+) -> Result<ImportSummary, CliError>
+where
+    F: FnMut(Role) -> Result<(), CliError>,
+{
+    let mut summary = ImportSummary::default();
+    for (role_id, role) in roles {
+        if progress.is_applied(&role_id) {
+            info!(
+                "Skipping role {}, it was created by a previous import",
+                role_id
+            );
+            summary.skipped.push(role_id);
+            continue;
+        }
+
+        match role.and_then(&mut create_role) {
+            Ok(()) => {
+                progress.record(&role_id)?;
+                info!("Created role {}", role_id);
+                summary.created.push(role_id);
+            }
+            Err(err) if continue_on_error => {
+                error!("Unable to create role {}: {}", role_id, err);
+                summary.failed.push((role_id, err));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(summary)
+}
+
+/// The outcome of importing each role in a role file
+#[derive(Debug, Default)]
+struct ImportSummary {
+    created: Vec<String>,
+    /// The roles created by a previous import
+    skipped: Vec<String>,
+    failed: Vec<(String, CliError)>,
+}
+
+impl ImportSummary {
+    fn total(&self) -> usize {
+        self.created.len() + self.skipped.len() + self.failed.len()
+    }
+}
+
+impl fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Imported {} of {} roles ({} created by a previous import, {} failed)",
+            self.created.len(),
+            self.total(),
+            self.skipped.len(),
+            self.failed.len()
+        )?;
+        for (role_id, err) in &self.failed {
+            write!(f, "\n    {}: {}", role_id, err)?;
         }
+
+        Ok(())
+    }
+}
+
+/// A role as defined in a role file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoleDefinition {
+    role_id: String,
+    display_name: String,
+    permissions: Vec<String>,
+}
+
+/// Reads a list of roles from a YAML or JSON file, validating each role.
+///
+/// Each role is returned with the role ID given in the file, so that an invalid role can still be
+/// reported by ID.
+fn read_role_file(path: &Path) -> Result<Vec<(String, Result<Role, CliError>)>, CliError> {
+    let file = File::open(path).map_err(|err| {
+        CliError::EnvironmentError(format!(
+            "Unable to open role file {}: {}",
+            path.display(),
+            err
+        ))
+    })?;
+
+    let definitions: Vec<RoleDefinition> = serde_yaml::from_reader(file).map_err(|err| {
+        CliError::ActionError(format!(
+            "Unable to parse role file {}: {}",
+            path.display(),
+            err
+        ))
+    })?;
+
+    Ok(definitions
+        .into_iter()
+        .map(|definition| {
+            let role_id = definition.role_id.trim().to_string();
+            let role = RoleBuilder::default()
+                .with_role_id(definition.role_id)
+                .with_display_name(definition.display_name)
+                .with_permissions(definition.permissions)
+                .build();
+            (role_id, role)
+        })
+        .collect())
+}
+
+/// Records the IDs of the roles created by an import, one per line.
+struct ImportProgress {
+    path: PathBuf,
+    applied: HashSet<String>,
+    file: File,
+}
+
+impl ImportProgress {
+    /// Opens the progress file at the given path.
+    ///
+    /// An existing progress file is only loaded when resuming; otherwise it is reported as an
+    /// error, so that a new import is not mistaken for the continuation of an interrupted one.
+    fn open(path: PathBuf, resume: bool) -> Result<Self, CliError> {
+        let applied = if path.exists() {
+            if !resume {
+                return Err(CliError::ActionError(format!(
+                    "Found progress file {} from an interrupted import; use --resume to \
+                    continue that import, or remove the file to start over",
+                    path.display()
+                )));
+            }
+
+            fs::read_to_string(&path)
+                .map_err(|err| {
+                    CliError::EnvironmentError(format!(
+                        "Unable to read progress file {}: {}",
+                        path.display(),
+                        err
+                    ))
+                })?
+                .lines()
+                .map(str::trim)
+                .filter(|role_id| !role_id.is_empty())
+                .map(String::from)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|err| {
+                CliError::EnvironmentError(format!(
+                    "Unable to open progress file {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+
+        Ok(Self {
+            path,
+            applied,
+            file,
+        })
+    }
+
+    fn is_applied(&self, role_id: &str) -> bool {
+        self.applied.contains(role_id)
+    }
+
+    /// Records that the role was created, syncing the progress file so the record survives an
+    /// interruption.
+    fn record(&mut self, role_id: &str) -> Result<(), CliError> {
+        writeln!(self.file, "{}", role_id)
+            .and_then(|_| self.file.sync_data())
+            .map_err(|err| {
+                CliError::EnvironmentError(format!(
+                    "Unable to write progress file {}: {}",
+                    self.path.display(),
+                    err
+                ))
+            })?;
+        self.applied.insert(role_id.to_string());
+
+        Ok(())
+    }
+
+    /// Removes the progress file once the import has completed.
+    fn finish(self) -> Result<(), CliError> {
+        let ImportProgress { path, file, .. } = self;
+        drop(file);
+
+        fs::remove_file(&path).map_err(|err| {
+            CliError::EnvironmentError(format!(
+                "Unable to remove progress file {}: {}",
+                path.display(),
+                err
+            ))
+        })
     }
 }
 
@@ -315,3 +745,152 @@ fn is_dry_run<'a>(arg_matches: &Option<&ArgMatches<'a>>) -> bool {
         .map(|args| args.is_present("dry_run"))
         .unwrap_or(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::Builder;
+
+    /// Verify that only the unknown permissions of each role are reported, and that roles which
+    /// grant only known permissions are omitted.
+    #[test]
+    fn test_find_dangling_permissions() {
+        let known = ["circuit.read", "circuit.write"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<BTreeSet<_>>();
+        let role = |role_id: &str, permissions: &[&str]| Role {
+            role_id: role_id.into(),
+            display_name: role_id.into(),
+            permissions: permissions.iter().map(|s| s.to_string()).collect(),
+        };
+
+        let dangling = find_dangling_permissions(
+            vec![
+                role("circuit_admin", &["circuit.read", "circuit.write"]),
+                role("stale", &["registry.write", "circuit.read", "biome.read"]),
+                role("empty", &[]),
+            ],
+            &known,
+        );
+
+        let mut expected = BTreeMap::new();
+        expected.insert(
+            "stale".to_string(),
+            vec!["biome.read".to_string(), "registry.write".to_string()],
+        );
+        assert_eq!(dangling, expected);
+    }
+
+    /// Verify that the progress of an import is recorded, loaded when resuming, and removed when
+    /// the import finishes.
+    ///
+    /// 1. Record a role in a new progress file
+    /// 2. Verify that opening the file again without resuming is an error
+    /// 3. Verify that resuming loads the recorded role
+    /// 4. Verify that finishing the import removes the file
+    #[test]
+    fn test_import_progress() {
+        let directory = Builder::new()
+            .prefix("test")
+            .tempdir()
+            .expect("could not create temp directory");
+        let path = directory.path().join("roles.yaml.progress");
+
+        let mut progress =
+            ImportProgress::open(path.clone(), false).expect("could not open progress file");
+        assert!(!progress.is_applied("circuit_admin"));
+        progress
+            .record("circuit_admin")
+            .expect("could not record role");
+        drop(progress);
+
+        assert!(ImportProgress::open(path.clone(), false).is_err());
+
+        let progress =
+            ImportProgress::open(path.clone(), true).expect("could not resume progress file");
+        assert!(progress.is_applied("circuit_admin"));
+        assert!(!progress.is_applied("circuit_reader"));
+
+        progress.finish().expect("could not finish import");
+        assert!(!path.exists());
+    }
+
+    /// Verify that an import stops at the first role that cannot be created by default, and
+    /// continues past it with `continue_on_error`.
+    ///
+    /// 1. Import three roles, where the second is invalid, without continuing on error
+    /// 2. Verify the import fails after creating only the first role
+    /// 3. Import the roles again with a new progress file, continuing on error
+    /// 4. Verify the first and last roles are created and the invalid role is reported as failed
+    /// 5. Import the roles again, resuming, and verify the created roles are skipped
+    #[test]
+    fn test_import_roles_continue_on_error() {
+        let directory = Builder::new()
+            .prefix("test")
+            .tempdir()
+            .expect("could not create temp directory");
+
+        let roles = || {
+            vec![
+                ("circuit_admin", vec!["circuit.write"]),
+                ("no_permissions", vec![]),
+                ("circuit_reader", vec!["circuit.read"]),
+            ]
+            .into_iter()
+            .map(|(role_id, permissions)| {
+                let role = RoleBuilder::default()
+                    .with_role_id(role_id.into())
+                    .with_display_name(role_id.into())
+                    .with_permissions(permissions.into_iter().map(String::from).collect())
+                    .build();
+                (role_id.to_string(), role)
+            })
+            .collect::<Vec<_>>()
+        };
+
+        let mut progress = ImportProgress::open(directory.path().join("fail_fast"), false)
+            .expect("could not open progress file");
+        let mut created = vec![];
+        assert!(import_roles(roles(), &mut progress, false, |role| {
+            created.push(role.role_id);
+            Ok(())
+        })
+        .is_err());
+        assert_eq!(created, vec!["circuit_admin".to_string()]);
+
+        let path = directory.path().join("continue");
+        let mut progress =
+            ImportProgress::open(path.clone(), false).expect("could not open progress file");
+        let mut created = vec![];
+        let summary = import_roles(roles(), &mut progress, true, |role| {
+            created.push(role.role_id);
+            Ok(())
+        })
+        .expect("could not import roles");
+        assert_eq!(
+            created,
+            vec!["circuit_admin".to_string(), "circuit_reader".to_string()]
+        );
+        assert_eq!(summary.created, created);
+        assert_eq!(summary.total(), 3);
+        assert_eq!(
+            summary
+                .failed
+                .iter()
+                .map(|(role_id, _)| role_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["no_permissions"]
+        );
+        drop(progress);
+
+        let mut progress =
+            ImportProgress::open(path, true).expect("could not resume progress file");
+        let summary =
+            import_roles(roles(), &mut progress, true, |_| Ok(())).expect("could not import roles");
+        assert!(summary.created.is_empty());
+        assert_eq!(summary.skipped, created);
+        assert_eq!(summary.failed.len(), 1);
+    }
+}
@@ -21,7 +21,10 @@ use crate::error::CliError;
 use crate::signing::{create_cylinder_jwt_auth, load_signer};
 
 use super::api::SplinterRestClientBuilder;
-use super::{print_table, Action, DEFAULT_SPLINTER_REST_API_URL, SPLINTER_REST_API_URL_ENV};
+use super::{
+    print_table, resolve_output_format, Action, DEFAULT_SPLINTER_REST_API_URL,
+    SPLINTER_REST_API_URL_ENV,
+};
 use api::{ClientBiomeUser, ClientOAuthUser};
 
 pub struct ListSplinterUsersAction;
@@ -30,7 +33,7 @@ impl Action for ListSplinterUsersAction {
     fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
         let args = arg_matches.ok_or(CliError::RequiresArgs)?;
 
-        let format = args.value_of("format").unwrap_or("human");
+        let format = resolve_output_format(arg_matches, &["human", "csv"])?;
         let signer = load_signer(args.value_of("private_key_file"))?;
         let url = args
             .value_of("url")
@@ -38,7 +41,7 @@ impl Action for ListSplinterUsersAction {
             .or_else(|| std::env::var(SPLINTER_REST_API_URL_ENV).ok())
             .unwrap_or_else(|| DEFAULT_SPLINTER_REST_API_URL.to_string());
 
-        display_splinter_users(&url, format, signer)
+        display_splinter_users(&url, &format, signer)
     }
 }
 
@@ -0,0 +1,174 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the optional TOML configuration file for the splinter CLI.
+//!
+//! The file is read from the path in `SPLINTER_CLI_CONFIG`, if set, otherwise from
+//! `$HOME/.splinter/config.toml`. A missing file is treated as an empty configuration.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::CliError;
+
+const SPLINTER_CLI_CONFIG_ENV: &str = "SPLINTER_CLI_CONFIG";
+
+/// Settings read from the CLI configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CliConfig {
+    #[serde(default)]
+    output: OutputConfig,
+    #[serde(default)]
+    state_migrate: StateMigrateConfig,
+}
+
+/// Settings for command output, under the `[output]` table.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputConfig {
+    format: Option<String>,
+}
+
+impl OutputConfig {
+    /// Returns the default output format for list commands, if one is configured.
+    pub fn format(&self) -> Option<&str> {
+        self.format.as_deref()
+    }
+}
+
+/// Settings for the `splinter state migrate` command, under the `[state_migrate]` table.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateMigrateConfig {
+    assume_yes: Option<bool>,
+}
+
+impl StateMigrateConfig {
+    /// Returns whether the migration should proceed without prompting for confirmation.
+    pub fn assume_yes(&self) -> bool {
+        self.assume_yes.unwrap_or(false)
+    }
+}
+
+impl CliConfig {
+    /// Loads the configuration file from the default location.
+    pub fn load() -> Result<Self, CliError> {
+        match config_path() {
+            Some(path) => Self::from_path(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Loads the configuration file from the default location, logging a warning and returning
+    /// the default configuration if the file cannot be read or parsed.
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_else(|err| {
+            warn!("{}; using the default configuration", err);
+            Self::default()
+        })
+    }
+
+    /// Loads the configuration file at the given path, returning the default configuration if
+    /// the file does not exist.
+    pub fn from_path(path: &Path) -> Result<Self, CliError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path).map_err(|err| {
+            CliError::EnvironmentError(format!(
+                "Unable to read config file {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+
+        toml::from_str(&contents).map_err(|err| {
+            CliError::EnvironmentError(format!("Invalid config file {}: {}", path.display(), err))
+        })
+    }
+
+    pub fn output(&self) -> &OutputConfig {
+        &self.output
+    }
+
+    pub fn state_migrate(&self) -> &StateMigrateConfig {
+        &self.state_migrate
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    match env::var(SPLINTER_CLI_CONFIG_ENV) {
+        Ok(path) => Some(PathBuf::from(path)),
+        Err(_) => dirs::home_dir().map(|mut path| {
+            path.push(".splinter");
+            path.push("config.toml");
+            path
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    /// Verify that a missing config file results in the default configuration.
+    #[test]
+    fn test_missing_config_file() {
+        let config = CliConfig::from_path(Path::new("/nonexistent/splinter/config.toml"))
+            .expect("Unable to load default config");
+
+        assert!(!config.state_migrate().assume_yes());
+        assert_eq!(config.output().format(), None);
+    }
+
+    /// Verify that the `output.format` setting is read from the config file.
+    #[test]
+    fn test_output_format() {
+        let mut file = NamedTempFile::new().expect("Unable to create temp file");
+        writeln!(file, "[output]\nformat = \"csv\"").expect("Unable to write config");
+
+        let config = CliConfig::from_path(file.path()).expect("Unable to load config");
+
+        assert_eq!(config.output().format(), Some("csv"));
+    }
+
+    /// Verify that the `state_migrate.assume_yes` setting is read from the config file.
+    #[test]
+    fn test_state_migrate_assume_yes() {
+        let mut file = NamedTempFile::new().expect("Unable to create temp file");
+        writeln!(file, "[state_migrate]\nassume_yes = true").expect("Unable to write config");
+
+        let config = CliConfig::from_path(file.path()).expect("Unable to load config");
+
+        assert!(config.state_migrate().assume_yes());
+    }
+
+    /// Verify that an unknown setting in the config file is reported as an error.
+    #[test]
+    fn test_invalid_config_file() {
+        let mut file = NamedTempFile::new().expect("Unable to create temp file");
+        writeln!(file, "[state_migrate]\nassume_no = true").expect("Unable to write config");
+
+        assert!(CliConfig::from_path(file.path()).is_err());
+    }
+}
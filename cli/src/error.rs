@@ -29,6 +29,21 @@ pub enum CliError {
     ActionError(String),
     /// The environment is not in the correct state to execute the subcommand as requested.
     EnvironmentError(String),
+    /// A list subcommand run with `--fail-on-empty` found no results.
+    EmptyResult(String),
+}
+
+/// The exit code used when a list subcommand run with `--fail-on-empty` finds no results.
+pub const EMPTY_RESULT_EXIT_CODE: i32 = 2;
+
+impl CliError {
+    /// Returns the code the process should exit with when this error is returned.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::EmptyResult(_) => EMPTY_RESULT_EXIT_CODE,
+            _ => 1,
+        }
+    }
 }
 
 impl Error for CliError {}
@@ -44,6 +59,7 @@ impl fmt::Display for CliError {
             CliError::ClapError(err) => f.write_str(&err.message),
             CliError::ActionError(msg) => write!(f, "Subcommand encountered an error: {}", msg),
             CliError::EnvironmentError(msg) => f.write_str(msg),
+            CliError::EmptyResult(msg) => f.write_str(msg),
         }
     }
 }
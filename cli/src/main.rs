@@ -18,6 +18,7 @@ extern crate log;
 extern crate diesel;
 
 mod action;
+mod config;
 mod error;
 mod signing;
 #[cfg(test)]
@@ -439,6 +440,31 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                         .help("Name or path of private key"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Export a circuit's full topology as JSON")
+                .arg(
+                    Arg::with_name("url")
+                        .short("U")
+                        .long("url")
+                        .help("URL of the Splinter daemon REST API")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("circuit")
+                        .help("ID of the circuit to be exported")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("private_key_file")
+                        .value_name("private-key-file")
+                        .short("k")
+                        .long("key")
+                        .takes_value(true)
+                        .help("Name or path of private key"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("proposals")
                 .about("List the circuit proposals")
@@ -922,6 +948,47 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                                 .takes_value(true)
                                 .help("Database connection URI"),
                         ),
+                )
+                .subcommand(
+                    SubCommand::with_name("check")
+                        .about(
+                            "Checks the admin service store for orphaned or inconsistent \
+                            records",
+                        )
+                        .arg(
+                            Arg::with_name("connect")
+                                .short("C")
+                                .takes_value(true)
+                                .help("Database connection URI"),
+                        )
+                        .arg(
+                            Arg::with_name("format")
+                                .short("F")
+                                .long("format")
+                                .help("Output format")
+                                .possible_values(&["human", "json"])
+                                .default_value("human")
+                                .takes_value(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("doctor")
+                        .about("Checks the health of the database and reports pending migrations")
+                        .arg(
+                            Arg::with_name("connect")
+                                .short("C")
+                                .takes_value(true)
+                                .help("Database connection URI"),
+                        )
+                        .arg(
+                            Arg::with_name("format")
+                                .short("F")
+                                .long("format")
+                                .help("Output format")
+                                .possible_values(&["human", "json"])
+                                .default_value("human")
+                                .takes_value(true),
+                        ),
                 ),
         );
 
@@ -954,6 +1021,25 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                                 )
                                 .takes_value(true),
                         )
+                        .arg(
+                            Arg::with_name("manifest")
+                                .long("manifest")
+                                .takes_value(true)
+                                .conflicts_with_all(&["in", "out"])
+                                .help(
+                                    "YAML or JSON file listing migration jobs, each with its own \
+                                    in and out databases, state directory and circuits",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("continue_on_error")
+                                .long("continue-on-error")
+                                .requires("manifest")
+                                .help(
+                                    "Keep running the remaining jobs in the manifest after a job \
+                                    fails",
+                                ),
+                        )
                         .arg(
                             Arg::with_name("state_dir")
                                 .long("state-dir")
@@ -965,20 +1051,297 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                                 )
                                 .takes_value(true),
                         )
-                        .arg(Arg::with_name("force").short("f").long("force").help(
-                            "Always attempt to move state, regardless of if there is \
+                        .arg(
+                            Arg::with_name("in_state_dir")
+                                .long("in-state-dir")
+                                .takes_value(true)
+                                .value_name("IN_STATE_DIR")
+                                .help(
+                                    "The state directory of the LMDB files to read when --in \
+                                    is lmdb, overriding --state-dir",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("out_state_dir")
+                                .long("out-state-dir")
+                                .takes_value(true)
+                                .value_name("OUT_STATE_DIR")
+                                .help(
+                                    "The state directory in which to write the LMDB files when \
+                                    --out is lmdb, overriding --state-dir",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("force")
+                                .short("f")
+                                .long("force")
+                                .conflicts_with("skip_existing")
+                                .help(
+                                    "Always attempt to move state, regardless of if there is \
                                     existing data in the out database",
+                                ),
+                        )
+                        .arg(Arg::with_name("force_unlock").long("force-unlock").help(
+                            "Remove an existing migration lock in the state directory \
+                                    before starting",
                         ))
+                        .arg(
+                            Arg::with_name("detail")
+                                .long("detail")
+                                .help(
+                                    "How much to log while migrating: only the start and \
+                                    finish (summary), each service (service), or each service \
+                                    and each batch of leaves written (batch)",
+                                )
+                                .possible_values(&["summary", "service", "batch"])
+                                .default_value("service")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("deadline")
+                                .long("deadline")
+                                .takes_value(true)
+                                .value_name("DURATION")
+                                .help(
+                                    "Stop starting new work once this much time has passed, \
+                                    e.g. 90s, 45m or 2h; services not yet migrated are reported",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("flush_idle_ms")
+                                .long("flush-idle-ms")
+                                .takes_value(true)
+                                .value_name("MS")
+                                .help(
+                                    "Write the pending batch of leaves as soon as reading a leaf \
+                                    takes longer than this many milliseconds",
+                                ),
+                        )
+                        .arg(Arg::with_name("progress").long("progress").help(
+                            "Log the percentage of each service's state that has been \
+                            migrated. This counts each tree's leaves before it is moved",
+                        ))
+                        .arg(
+                            Arg::with_name("progress_interval")
+                                .long("progress-interval")
+                                .takes_value(true)
+                                .value_name("SECONDS")
+                                .help(
+                                    "Log the number of leaves read while copying each service's \
+                                    state every SECONDS seconds, or never if 0 (default: 30)",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("progress_fd")
+                                .long("progress-fd")
+                                .takes_value(true)
+                                .value_name("FD")
+                                .conflicts_with("progress_socket")
+                                .help(
+                                    "Write progress events as newline-delimited JSON to the \
+                                    open file descriptor FD",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("progress_socket")
+                                .long("progress-socket")
+                                .takes_value(true)
+                                .value_name("PATH")
+                                .help(
+                                    "Write progress events as newline-delimited JSON to the Unix \
+                                    socket listening at PATH",
+                                ),
+                        )
+                        .arg(Arg::with_name("skip_existing").long("skip-existing").help(
+                            "Skip services whose state already exists in the out database, \
+                            instead of failing",
+                        ))
+                        .arg(Arg::with_name("paranoid").long("paranoid").help(
+                            "Read back each batch of leaves from the out database as soon as \
+                            it is written, and fail if any leaf does not match",
+                        ))
+                        .arg(
+                            Arg::with_name("prefix")
+                                .long("prefix")
+                                .takes_value(true)
+                                .help(
+                                    "Only copy leaves whose address starts with this prefix. \
+                                    The state in the in database is not deleted",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("prefix_file")
+                                .long("prefix-file")
+                                .takes_value(true)
+                                .value_name("FILE")
+                                .conflicts_with("prefix")
+                                .help(
+                                    "Only copy leaves whose address starts with one of the \
+                                    prefixes listed in FILE, one per line. The state in the in \
+                                    database is not deleted",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("verify")
+                                .long("verify")
+                                .conflicts_with_all(&["prefix", "prefix_file"])
+                                .help(
+                                    "Compare the leaves of each copied tree with the original \
+                                    before the original is deleted",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("jobs")
+                                .long("jobs")
+                                .takes_value(true)
+                                .value_name("N")
+                                .requires("verify")
+                                .conflicts_with("dry_run")
+                                .help(
+                                    "Verify the copied trees using N threads once all services \
+                                    have been copied, deleting each original once its copy is \
+                                    verified",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("batch_size")
+                                .long("batch-size")
+                                .takes_value(true)
+                                .value_name("LEAVES")
+                                .help(
+                                    "The number of leaves written to the out database at once, \
+                                    from 1 to 1000000 (default: 1000)",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("checkpoint_file")
+                                .long("checkpoint-file")
+                                .takes_value(true)
+                                .value_name("FILE")
+                                .conflicts_with("restore_root")
+                                .help(
+                                    "Record each migrated service in this file, and skip the \
+                                    services it already records when the migration is run again",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("restore_root")
+                                .long("restore-root")
+                                .takes_value(true)
+                                .value_name("ROOT")
+                                .conflicts_with_all(&[
+                                    "manifest",
+                                    "dry_run",
+                                    "prefix",
+                                    "prefix_file",
+                                    "verify",
+                                    "skip_existing",
+                                ])
+                                .help(
+                                    "Restore the state with this root, purged from the in \
+                                    database by an earlier migration, by copying it back from \
+                                    the out database",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("skip_root_check")
+                                .long("skip-root-check")
+                                .help(
+                                    "Log the resulting state root instead of failing when it \
+                                    does not match the commit hash. Requires --prefix or \
+                                    --prefix-file",
+                                ),
+                        )
                         .arg(
                             Arg::with_name("yes")
                                 .short("y")
                                 .long("yes")
                                 .help("Do not prompt for confirmation"),
                         )
+                        .arg(
+                            Arg::with_name("confirm_count")
+                                .long("confirm-count")
+                                .conflicts_with("yes")
+                                .help(
+                                    "Confirm by typing the number of services that will be \
+                                    migrated, instead of y",
+                                ),
+                        )
                         .arg(Arg::with_name("dry_run").long("dry-run").long_help(
                             "Check that the in and out databases are available and that \
                             the in database has a commit hash. The command will not \
-                            attempt to move the state",
+                            attempt to move the state, and prints a table of each service's \
+                            leaf count, whether its tree exists in the out database, and its \
+                            estimated size",
+                        ))
+                        .arg(
+                            Arg::with_name("report_format")
+                                .long("report-format")
+                                .help(
+                                    "Output format for the migration report, which lists the \
+                                    services that were skipped and why",
+                                )
+                                .possible_values(&["human", "json", "csv"])
+                                .default_value("human")
+                                .takes_value(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("leaves")
+                        .about(
+                            "List the leaves of a scabbard service's current state, for debugging",
+                        )
+                        .arg(
+                            Arg::with_name("connect")
+                                .short("C")
+                                .long("connect")
+                                .takes_value(true)
+                                .required(true)
+                                .help(
+                                    "Database URI containing the service's commit hash and, \
+                                    unless --lmdb is provided, its state",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("circuit_id")
+                                .long("circuit")
+                                .takes_value(true)
+                                .required(true)
+                                .help("ID of the circuit the service belongs to"),
+                        )
+                        .arg(
+                            Arg::with_name("service_id")
+                                .long("service")
+                                .takes_value(true)
+                                .required(true)
+                                .help("ID of the scabbard service"),
+                        )
+                        .arg(
+                            Arg::with_name("lmdb")
+                                .long("lmdb")
+                                .help("Read the state from the service's LMDB file"),
+                        )
+                        .arg(
+                            Arg::with_name("state_dir")
+                                .long("state-dir")
+                                .requires("lmdb")
+                                .long_help(
+                                    "The location of the state directory for the LMDB files. \
+                                    Defaults to /var/lib/splinter. This location can also be \
+                                    changed with the SPLINTER_STATE_DIR or SPLINTER_HOME \
+                                    environment variables",
+                                )
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("prefix")
+                                .long("prefix")
+                                .takes_value(true)
+                                .help("Only list leaves whose address starts with this prefix"),
+                        )
+                        .arg(Arg::with_name("reverse").long("reverse").help(
+                            "List the leaves in reverse address order. The same leaves are \
+                            listed as without this flag",
                         )),
                 ),
         );
@@ -1105,6 +1468,81 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                                 .possible_values(&["human", "csv"])
                                 .default_value("human")
                                 .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("fail_on_empty")
+                                .long("fail-on-empty")
+                                .help("Exit with a non-zero status if there are no roles"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("audit")
+                        .about(
+                            "Lists the roles of a Splinter node that grant permissions which are \
+                            not known to the node",
+                        )
+                        .arg(
+                            Arg::with_name("url")
+                                .short("U")
+                                .long("url")
+                                .help("URL of the Splinter daemon REST API")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("private_key_file")
+                                .value_name("private-key-file")
+                                .short("k")
+                                .long("key")
+                                .takes_value(true)
+                                .help("Name or path of private key"),
+                        )
+                        .arg(
+                            Arg::with_name("format")
+                                .short("F")
+                                .long("format")
+                                .help("Output format")
+                                .possible_values(&["human", "json"])
+                                .default_value("human")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("strict")
+                                .long("strict")
+                                .help(
+                                    "Exit with a non-zero status if any role grants an unknown \
+                                    permission",
+                                ),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("by-permission")
+                        .about(
+                            "Lists each permission granted by the roles of a Splinter node, \
+                            with the roles that grant it",
+                        )
+                        .arg(
+                            Arg::with_name("url")
+                                .short("U")
+                                .long("url")
+                                .help("URL of the Splinter daemon REST API")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("private_key_file")
+                                .value_name("private-key-file")
+                                .short("k")
+                                .long("key")
+                                .takes_value(true)
+                                .help("Name or path of private key"),
+                        )
+                        .arg(
+                            Arg::with_name("format")
+                                .short("F")
+                                .long("format")
+                                .help("Output format")
+                                .possible_values(&["human", "json", "csv"])
+                                .default_value("human")
+                                .takes_value(true),
                         ),
                 )
                 .subcommand(
@@ -1187,11 +1625,141 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                                 .value_name("ROLE ID")
                                 .help("ID of role to be created"),
                         )
+                        .arg(
+                            Arg::with_name("audit_log")
+                                .long("audit-log")
+                                .value_name("FILE")
+                                .takes_value(true)
+                                .help(
+                                    "File to which a JSON line is appended for each successful \
+                                    role change",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("dry_run")
+                                .long("dry-run")
+                                .short("n")
+                                .help(
+                                    "Validate the command and print the request without \
+                                    performing the role creation",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("enforce_unique_name")
+                                .long("enforce-unique-name")
+                                .help(
+                                    "Fail if another role already has the same display name",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("strict")
+                                .long("strict")
+                                .help(
+                                    "Fail if a permission is not one of the permissions known \
+                                    to the Splinter node",
+                                ),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("clone")
+                        .about("Create a new role with the permissions of an existing role")
+                        .arg(
+                            Arg::with_name("url")
+                                .short("U")
+                                .long("url")
+                                .help("URL of the Splinter daemon REST API")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("private_key_file")
+                                .value_name("private-key-file")
+                                .short("k")
+                                .long("key")
+                                .takes_value(true)
+                                .help("Name or path of private key"),
+                        )
+                        .arg(
+                            Arg::with_name("display_name")
+                                .value_name("display-name")
+                                .short("D")
+                                .long("display")
+                                .takes_value(true)
+                                .help(
+                                    "Display name of the new role; defaults to the display name \
+                                    of the source role",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("source_role_id")
+                                .required(true)
+                                .takes_value(true)
+                                .value_name("SOURCE ROLE ID")
+                                .help("ID of the role to be copied"),
+                        )
+                        .arg(
+                            Arg::with_name("role_id")
+                                .required(true)
+                                .takes_value(true)
+                                .value_name("ROLE ID")
+                                .help("ID of role to be created"),
+                        )
+                        .arg(
+                            Arg::with_name("audit_log")
+                                .long("audit-log")
+                                .value_name("FILE")
+                                .takes_value(true)
+                                .help(
+                                    "File to which a JSON line is appended for each successful \
+                                    role change",
+                                ),
+                        )
                         .arg(
                             Arg::with_name("dry_run")
                                 .long("dry-run")
                                 .short("n")
-                                .help("Validate the command without performing the role creation"),
+                                .help(
+                                    "Validate the command and print the request without \
+                                    performing the role creation",
+                                ),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("import")
+                        .about("Create the roles defined in a file on a Splinter node")
+                        .arg(
+                            Arg::with_name("url")
+                                .short("U")
+                                .long("url")
+                                .help("URL of the Splinter daemon REST API")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("private_key_file")
+                                .value_name("private-key-file")
+                                .short("k")
+                                .long("key")
+                                .takes_value(true)
+                                .help("Name or path of private key"),
+                        )
+                        .arg(
+                            Arg::with_name("resume")
+                                .long("resume")
+                                .help("Skip the roles created by an interrupted import"),
+                        )
+                        .arg(
+                            Arg::with_name("continue_on_error")
+                                .long("continue-on-error")
+                                .help(
+                                    "Keep importing after a role could not be created, and report \
+                                    every failure at the end",
+                                ),
+                        )
+                        .arg(
+                            Arg::with_name("file")
+                                .required(true)
+                                .takes_value(true)
+                                .value_name("FILE")
+                                .help("YAML or JSON file containing a list of roles"),
                         ),
                 )
                 .subcommand(
@@ -1261,11 +1829,24 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                                 .value_name("ROLE ID")
                                 .help("ID of role to be updated"),
                         )
+                        .arg(
+                            Arg::with_name("audit_log")
+                                .long("audit-log")
+                                .value_name("FILE")
+                                .takes_value(true)
+                                .help(
+                                    "File to which a JSON line is appended for each successful \
+                                    role change",
+                                ),
+                        )
                         .arg(
                             Arg::with_name("dry_run")
                                 .long("dry-run")
                                 .short("n")
-                                .help("Validate the command without performing the role update"),
+                                .help(
+                                    "Validate the command and print the request without \
+                                    performing the role update",
+                                ),
                         ),
                 )
                 .subcommand(
@@ -1293,11 +1874,24 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                                 .value_name("ROLE ID")
                                 .help("ID of role to be deleted"),
                         )
+                        .arg(
+                            Arg::with_name("audit_log")
+                                .long("audit-log")
+                                .value_name("FILE")
+                                .takes_value(true)
+                                .help(
+                                    "File to which a JSON line is appended for each successful \
+                                    role change",
+                                ),
+                        )
                         .arg(
                             Arg::with_name("dry_run")
                                 .long("dry-run")
                                 .short("n")
-                                .help("Validate the command without performing the role deletion"),
+                                .help(
+                                    "Validate the command and print the request without \
+                                    performing the role deletion",
+                                ),
                         ),
                 ),
         ).subcommand(
@@ -1570,6 +2164,21 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                     .default_value("human")
                     .takes_value(true),
             )
+            .arg(
+                Arg::with_name("fail_on_empty")
+                    .long("fail-on-empty")
+                    .help("Exit with a non-zero status if there are no permissions"),
+            )
+            .arg(
+                Arg::with_name("search")
+                    .long("search")
+                    .value_name("TEXT")
+                    .takes_value(true)
+                    .help(
+                        "Only list permissions whose ID, name or description contains this text, \
+                        ignoring case",
+                    ),
+            )
             .arg(
                 Arg::with_name("url")
                     .short("U")
@@ -2038,6 +2647,7 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
         .with_command("vote", circuit::CircuitVoteAction)
         .with_command("list", circuit::CircuitListAction)
         .with_command("show", circuit::CircuitShowAction)
+        .with_command("export", circuit::CircuitExportAction)
         .with_command("proposals", circuit::CircuitProposalsAction)
         .with_command("disband", circuit::CircuitDisbandAction)
         .with_command("abandon", circuit::CircuitAbandonAction)
@@ -2070,12 +2680,17 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
         use action::database;
         subcommands = subcommands.with_command(
             "database",
-            SubcommandActions::new().with_command("migrate", database::MigrateAction),
+            SubcommandActions::new()
+                .with_command("migrate", database::MigrateAction)
+                .with_command("doctor", database::DoctorAction)
+                .with_command("check", database::StoreIntegrityCheckAction),
         );
 
         subcommands = subcommands.with_command(
             "state",
-            SubcommandActions::new().with_command("migrate", database::StateMigrateAction),
+            SubcommandActions::new()
+                .with_command("migrate", database::StateMigrateAction)
+                .with_command("leaves", database::StateLeavesAction),
         );
     }
 
@@ -2104,9 +2719,13 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                 "role",
                 SubcommandActions::new()
                     .with_command("create", rbac::CreateRoleAction)
+                    .with_command("clone", rbac::CloneRoleAction)
+                    .with_command("import", rbac::ImportRolesAction)
                     .with_command("update", rbac::UpdateRoleAction)
                     .with_command("delete", rbac::DeleteRoleAction)
                     .with_command("list", rbac::ListRolesAction)
+                    .with_command("by-permission", rbac::ListRolesByPermissionAction)
+                    .with_command("audit", rbac::AuditRolesAction)
                     .with_command("show", rbac::ShowRoleAction),
             )
             .with_command(
@@ -2120,7 +2739,7 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
             )
     }
 
-    subcommands = subcommands.with_command("permissions", permissions::ListAction);
+    subcommands = subcommands.with_command("permissions", permissions::ListAction::default());
 
     #[cfg(feature = "user")]
     {
@@ -2168,7 +2787,7 @@ fn main() {
         Err(CliError::ClapError(err)) => err.exit(),
         Err(e) => {
             error!("ERROR: {}", e);
-            std::process::exit(1);
+            std::process::exit(e.exit_code());
         }
     }
 }
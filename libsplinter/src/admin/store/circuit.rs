@@ -22,7 +22,8 @@ use crate::error::InvalidStateError;
 use crate::protos::admin;
 
 use super::{
-    CircuitNode, ProposedCircuit, ProposedNode, Service, ServiceBuilder, UNSET_CIRCUIT_VERSION,
+    CircuitNode, CircuitNodeBuilder, ProposedCircuit, ProposedNode, Service, ServiceBuilder,
+    UNSET_CIRCUIT_VERSION,
 };
 
 /// Native representation of a circuit in state
@@ -96,6 +97,37 @@ impl Circuit {
     pub fn circuit_status(&self) -> &CircuitStatus {
         &self.circuit_status
     }
+
+    /// Returns a copy of the circuit in which the member with the given node ID has the given
+    /// endpoints
+    pub(super) fn with_member_endpoints(
+        &self,
+        node_id: &str,
+        endpoints: &[String],
+    ) -> Result<Circuit, InvalidStateError> {
+        let members = self
+            .members
+            .iter()
+            .map(|member| {
+                if member.node_id() != node_id {
+                    return Ok(member.clone());
+                }
+
+                let mut builder = CircuitNodeBuilder::new()
+                    .with_node_id(node_id)
+                    .with_endpoints(endpoints);
+                if let Some(public_key) = member.public_key() {
+                    builder = builder.with_public_key(public_key);
+                }
+                builder.build()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Circuit {
+            members,
+            ..self.clone()
+        })
+    }
 }
 
 impl TryFrom<&admin::Circuit> for Circuit {
@@ -63,6 +63,39 @@ impl From<ProposedNode> for CircuitNode {
     }
 }
 
+/// Checks that a node's endpoints can replace its existing endpoints
+///
+/// There must be at least one endpoint, each endpoint must be of the form `<scheme>://<address>`
+/// and no endpoint may be listed more than once.
+pub(super) fn validate_node_endpoints(endpoints: &[String]) -> Result<(), InvalidStateError> {
+    if endpoints.is_empty() {
+        return Err(InvalidStateError::with_message(
+            "A node must have at least one endpoint".to_string(),
+        ));
+    }
+
+    for (idx, endpoint) in endpoints.iter().enumerate() {
+        match endpoint.split_once("://") {
+            Some((scheme, address)) if !scheme.is_empty() && !address.is_empty() => (),
+            _ => {
+                return Err(InvalidStateError::with_message(format!(
+                    "Endpoint {} is not of the form <scheme>://<address>",
+                    endpoint
+                )))
+            }
+        }
+
+        if endpoints[..idx].contains(endpoint) {
+            return Err(InvalidStateError::with_message(format!(
+                "Endpoint {} is listed more than once",
+                endpoint
+            )));
+        }
+    }
+
+    Ok(())
+}
+
 /// Builder for creating a `CircuitNode`
 #[derive(Default, Clone)]
 pub struct CircuitNodeBuilder {
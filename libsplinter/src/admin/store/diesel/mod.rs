@@ -37,7 +37,7 @@ use crate::admin::store::{
     CircuitProposal, Service, ServiceId,
 };
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
-use crate::admin::store::{AdminServiceEvent, EventIter};
+use crate::admin::store::{AdminServiceEvent, EventIter, ServiceEndpointsIter};
 use crate::store::pool::ConnectionPool;
 
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
@@ -51,6 +51,14 @@ use operations::count_circuits::AdminServiceStoreCountCircuitsOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::count_proposals::AdminServiceStoreCountProposalsOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
+use operations::count_services::AdminServiceStoreCountServicesOperation as _;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use operations::fetch_circuit_version::AdminServiceStoreFetchCircuitVersionOperation as _;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use operations::find_duplicate_endpoints::AdminServiceStoreFindDuplicateEndpointsOperation as _;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use operations::find_orphaned_service_arguments::AdminServiceStoreFindOrphanedServiceArgumentsOperation as _;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::get_circuit::AdminServiceStoreFetchCircuitOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::get_node::AdminServiceStoreFetchNodeOperation as _;
@@ -61,6 +69,8 @@ use operations::get_service::AdminServiceStoreFetchServiceOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::list_circuits::AdminServiceStoreListCircuitsOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
+use operations::list_circuits_modified_since::AdminServiceStoreListCircuitsModifiedSinceOperation as _;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::list_events_by_management_type_since::AdminServiceStoreListEventsByManagementTypeSinceOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::list_events_since::AdminServiceStoreListEventsSinceOperation as _;
@@ -69,14 +79,22 @@ use operations::list_nodes::AdminServiceStoreListNodesOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::list_proposals::AdminServiceStoreListProposalsOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
+use operations::list_service_types::AdminServiceStoreListServiceTypesOperation as _;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::list_services::AdminServiceStoreListServicesOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
+use operations::list_services_for_node::AdminServiceStoreListServicesForNodeOperation as _;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use operations::list_services_with_endpoints::AdminServiceStoreListServicesWithEndpointsOperation as _;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::remove_circuit::AdminServiceStoreRemoveCircuitOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::remove_proposal::AdminServiceStoreRemoveProposalOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::update_circuit::AdminServiceStoreUpdateCircuitOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
+use operations::update_node_endpoints::AdminServiceStoreUpdateNodeEndpointsOperation as _;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::update_proposal::AdminServiceStoreUpdateProposalOperation as _;
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
 use operations::upgrade::AdminServiceStoreUpgradeProposalToCircuitOperation as _;
@@ -202,6 +220,15 @@ impl AdminServiceStore for DieselAdminServiceStore<diesel::pg::PgConnection> {
             .execute_read(|conn| AdminServiceStoreOperations::new(conn).get_circuit(circuit_id))
     }
 
+    fn fetch_circuit_version(
+        &self,
+        circuit_id: &str,
+    ) -> Result<Option<i32>, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).fetch_circuit_version(circuit_id)
+        })
+    }
+
     fn list_circuits(
         &self,
         predicates: &[CircuitPredicate],
@@ -210,6 +237,26 @@ impl AdminServiceStore for DieselAdminServiceStore<diesel::pg::PgConnection> {
             .execute_read(|conn| AdminServiceStoreOperations::new(conn).list_circuits(predicates))
     }
 
+    fn list_circuits_paged(
+        &self,
+        predicates: &[CircuitPredicate],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Box<dyn ExactSizeIterator<Item = Circuit>>, usize), AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).list_circuits_paged(predicates, offset, limit)
+        })
+    }
+
+    fn list_circuits_modified_since(
+        &self,
+        timestamp: i64,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = Circuit>>, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).list_circuits_modified_since(timestamp)
+        })
+    }
+
     fn count_circuits(
         &self,
         predicates: &[CircuitPredicate],
@@ -236,6 +283,40 @@ impl AdminServiceStore for DieselAdminServiceStore<diesel::pg::PgConnection> {
             .execute_read(|conn| AdminServiceStoreOperations::new(conn).list_nodes())
     }
 
+    fn list_nodes_filtered(
+        &self,
+        node_ids: &[String],
+    ) -> Result<Box<dyn ExactSizeIterator<Item = CircuitNode>>, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).list_nodes_filtered(node_ids)
+        })
+    }
+
+    fn update_node_endpoints(
+        &self,
+        node_id: &str,
+        endpoints: &[String],
+    ) -> Result<(), AdminServiceStoreError> {
+        self.connection_pool.execute_write(|conn| {
+            AdminServiceStoreOperations::new(conn).update_node_endpoints(node_id, endpoints)
+        })
+    }
+
+    fn find_duplicate_endpoints(
+        &self,
+    ) -> Result<Vec<(String, Vec<String>)>, AdminServiceStoreError> {
+        self.connection_pool
+            .execute_read(|conn| AdminServiceStoreOperations::new(conn).find_duplicate_endpoints())
+    }
+
+    fn find_orphaned_service_arguments(
+        &self,
+    ) -> Result<Vec<(String, String)>, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).find_orphaned_service_arguments()
+        })
+    }
+
     fn get_service(
         &self,
         service_id: &ServiceId,
@@ -252,6 +333,37 @@ impl AdminServiceStore for DieselAdminServiceStore<diesel::pg::PgConnection> {
             .execute_read(|conn| AdminServiceStoreOperations::new(conn).list_services(circuit_id))
     }
 
+    fn list_services_with_endpoints(
+        &self,
+        circuit_id: &str,
+    ) -> Result<ServiceEndpointsIter, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).list_services_with_endpoints(circuit_id)
+        })
+    }
+
+    fn count_services(&self, circuit_id: &str) -> Result<usize, AdminServiceStoreError> {
+        self.connection_pool
+            .execute_read(|conn| AdminServiceStoreOperations::new(conn).count_services(circuit_id))
+    }
+
+    fn list_services_for_node(
+        &self,
+        node_id: &str,
+    ) -> Result<Vec<(String, Service)>, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).list_services_for_node(node_id)
+        })
+    }
+
+    fn list_service_types(
+        &self,
+        node_id: Option<&str>,
+    ) -> Result<Vec<String>, AdminServiceStoreError> {
+        self.connection_pool
+            .execute_read(|conn| AdminServiceStoreOperations::new(conn).list_service_types(node_id))
+    }
+
     fn add_event(
         &self,
         event: messages::AdminServiceEvent,
@@ -348,6 +460,15 @@ impl AdminServiceStore for DieselAdminServiceStore<diesel::sqlite::SqliteConnect
             .execute_read(|conn| AdminServiceStoreOperations::new(conn).get_circuit(circuit_id))
     }
 
+    fn fetch_circuit_version(
+        &self,
+        circuit_id: &str,
+    ) -> Result<Option<i32>, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).fetch_circuit_version(circuit_id)
+        })
+    }
+
     fn list_circuits(
         &self,
         predicates: &[CircuitPredicate],
@@ -356,6 +477,26 @@ impl AdminServiceStore for DieselAdminServiceStore<diesel::sqlite::SqliteConnect
             .execute_read(|conn| AdminServiceStoreOperations::new(conn).list_circuits(predicates))
     }
 
+    fn list_circuits_paged(
+        &self,
+        predicates: &[CircuitPredicate],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Box<dyn ExactSizeIterator<Item = Circuit>>, usize), AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).list_circuits_paged(predicates, offset, limit)
+        })
+    }
+
+    fn list_circuits_modified_since(
+        &self,
+        timestamp: i64,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = Circuit>>, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).list_circuits_modified_since(timestamp)
+        })
+    }
+
     fn count_circuits(
         &self,
         predicates: &[CircuitPredicate],
@@ -382,6 +523,40 @@ impl AdminServiceStore for DieselAdminServiceStore<diesel::sqlite::SqliteConnect
             .execute_read(|conn| AdminServiceStoreOperations::new(conn).list_nodes())
     }
 
+    fn list_nodes_filtered(
+        &self,
+        node_ids: &[String],
+    ) -> Result<Box<dyn ExactSizeIterator<Item = CircuitNode>>, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).list_nodes_filtered(node_ids)
+        })
+    }
+
+    fn update_node_endpoints(
+        &self,
+        node_id: &str,
+        endpoints: &[String],
+    ) -> Result<(), AdminServiceStoreError> {
+        self.connection_pool.execute_write(|conn| {
+            AdminServiceStoreOperations::new(conn).update_node_endpoints(node_id, endpoints)
+        })
+    }
+
+    fn find_duplicate_endpoints(
+        &self,
+    ) -> Result<Vec<(String, Vec<String>)>, AdminServiceStoreError> {
+        self.connection_pool
+            .execute_read(|conn| AdminServiceStoreOperations::new(conn).find_duplicate_endpoints())
+    }
+
+    fn find_orphaned_service_arguments(
+        &self,
+    ) -> Result<Vec<(String, String)>, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).find_orphaned_service_arguments()
+        })
+    }
+
     fn get_service(
         &self,
         service_id: &ServiceId,
@@ -398,6 +573,37 @@ impl AdminServiceStore for DieselAdminServiceStore<diesel::sqlite::SqliteConnect
             .execute_read(|conn| AdminServiceStoreOperations::new(conn).list_services(circuit_id))
     }
 
+    fn list_services_with_endpoints(
+        &self,
+        circuit_id: &str,
+    ) -> Result<ServiceEndpointsIter, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).list_services_with_endpoints(circuit_id)
+        })
+    }
+
+    fn count_services(&self, circuit_id: &str) -> Result<usize, AdminServiceStoreError> {
+        self.connection_pool
+            .execute_read(|conn| AdminServiceStoreOperations::new(conn).count_services(circuit_id))
+    }
+
+    fn list_services_for_node(
+        &self,
+        node_id: &str,
+    ) -> Result<Vec<(String, Service)>, AdminServiceStoreError> {
+        self.connection_pool.execute_read(|conn| {
+            AdminServiceStoreOperations::new(conn).list_services_for_node(node_id)
+        })
+    }
+
+    fn list_service_types(
+        &self,
+        node_id: Option<&str>,
+    ) -> Result<Vec<String>, AdminServiceStoreError> {
+        self.connection_pool
+            .execute_read(|conn| AdminServiceStoreOperations::new(conn).list_service_types(node_id))
+    }
+
     fn add_event(
         &self,
         event: messages::AdminServiceEvent,
@@ -445,6 +651,7 @@ pub mod tests {
     use diesel::{
         r2d2::{ConnectionManager, Pool},
         sqlite::SqliteConnection,
+        ExpressionMethods, QueryDsl, RunQueryDsl,
     };
 
     #[test]
@@ -816,6 +1023,117 @@ pub mod tests {
         )
     }
 
+    /// Verify that a circuit's version can be fetched without fetching the circuit
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Create a circuit and nodes
+    /// 4. Add circuit to store
+    /// 5. Fetch the circuit's version, validate it matches the added circuit's version
+    /// 6. Fetch the version of a circuit that does not exist, validate `None` is returned
+    #[test]
+    fn test_fetch_circuit_version() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        let circuit = create_circuit("WBKLF-BBBBB", CircuitStatus::Active);
+        let nodes = create_nodes();
+
+        store
+            .add_circuit(circuit.clone(), nodes)
+            .expect("Unable to add circuit");
+
+        assert_eq!(
+            Some(circuit.circuit_version()),
+            store
+                .fetch_circuit_version("WBKLF-BBBBB")
+                .expect("Unable to fetch circuit version")
+        );
+
+        assert_eq!(
+            None,
+            store
+                .fetch_circuit_version("WBKLF-AAAAA")
+                .expect("Unable to fetch circuit version")
+        );
+    }
+
+    /// Verify that list_circuits_modified_since only returns circuits updated at or after the
+    /// provided timestamp, ordered by update time
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add two circuits and set their update times to 100 and 200
+    /// 4. List circuits modified since 150, validate only the second circuit is returned
+    /// 5. List circuits modified since 50, validate both circuits are returned in update order
+    /// 6. List circuits modified since 200, validate the circuit updated at 200 is returned
+    /// 7. Update the first circuit
+    /// 8. List circuits modified since 150, validate both circuits are returned with the updated
+    ///    circuit last
+    #[test]
+    fn test_list_circuits_modified_since() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool.clone());
+
+        let circuit = create_circuit("WBKLF-BBBBB", CircuitStatus::Active);
+        let extra_circuit = create_extra_circuit("WBKLF-CCCCC");
+
+        store
+            .add_circuit(circuit.clone(), create_nodes())
+            .expect("Unable to add circuit");
+        store
+            .add_circuit(extra_circuit.clone(), create_extra_nodes())
+            .expect("Unable to add circuit");
+
+        {
+            let conn = pool.get().expect("Unable to get connection");
+            for (circuit_id, last_updated) in &[("WBKLF-BBBBB", 100), ("WBKLF-CCCCC", 200)] {
+                diesel::update(schema::circuit::table.find(circuit_id))
+                    .set(schema::circuit::last_updated.eq(last_updated))
+                    .execute(&*conn)
+                    .expect("Unable to set circuit update time");
+            }
+        }
+
+        assert_eq!(
+            store
+                .list_circuits_modified_since(150)
+                .expect("Unable to list circuits")
+                .collect::<Vec<_>>(),
+            vec![extra_circuit.clone()]
+        );
+
+        assert_eq!(
+            store
+                .list_circuits_modified_since(50)
+                .expect("Unable to list circuits")
+                .collect::<Vec<_>>(),
+            vec![circuit.clone(), extra_circuit.clone()]
+        );
+
+        assert_eq!(
+            store
+                .list_circuits_modified_since(200)
+                .expect("Unable to list circuits")
+                .collect::<Vec<_>>(),
+            vec![extra_circuit.clone()]
+        );
+
+        store
+            .update_circuit(circuit.clone())
+            .expect("Unable to update circuit");
+
+        assert_eq!(
+            store
+                .list_circuits_modified_since(150)
+                .expect("Unable to list circuits")
+                .collect::<Vec<_>>(),
+            vec![extra_circuit, circuit]
+        );
+    }
+
     /// Verify that list_circuits works correctly
     ///
     /// 1. Run sqlite migrations
@@ -939,86 +1257,409 @@ pub mod tests {
         assert_eq!(circuits.len(), 2);
     }
 
-    /// Verify that count_circuits works correctly
+    /// Verify that list_all_circuits returns circuits of every status
     ///
     /// 1. Run sqlite migrations
     /// 2. Create DieselAdminServiceStore
-    /// 3. Create a circuit and nodes
-    /// 4. Add circuit to store
-    /// 5. Count circuits from store with no predicates, validated correct number is returned
-    /// 6. Count circuits from store with management type predicate, validated correct number is
-    ///    returned
-    /// 7. Count circuits from store with member predicate, validated correct number is returned
-    /// 8. Count circuits from store with mismatching management type predicate, validated 0 is
-    ///    returned
-    /// 9. Add a `Disbanded` circuit to the store
-    /// 10. Count circuits from store with no circuit status predicate, validate that the correct
-    ///     number of `Active` circuits are returned
-    /// 11. Count circuits with the `CircuitStatus::Disbanded` circuit status predicate, validate
-    ///     that the correct number of `Disbanded` circuits are returned
-    /// 12. Count circuits with the `CircuitStatus::Abandoned` circuit status predicate, validate
-    ///     that the correct number of `Abandoned` circuits are returned
+    /// 3. Add an `Active` and a `Disbanded` circuit to the store
+    /// 4. List circuits with no predicates, validate only the `Active` circuit is returned
+    /// 5. List all circuits, validate both circuits are returned
     #[test]
-    fn test_count_circuits() {
+    fn test_list_all_circuits() {
         let pool = create_connection_pool_and_migrate();
 
         let store = DieselAdminServiceStore::new(pool);
 
         let circuit = create_circuit("WBKLF-BBBBB", CircuitStatus::Active);
+        let disbanded_circuit = create_circuit("WBKLF-DDDDD", CircuitStatus::Disbanded);
         let nodes = create_nodes();
 
-        let extra_circuit = create_extra_circuit("WBKLF-CCCCC");
-        let extra_nodes = create_extra_nodes();
-
         store
             .add_circuit(circuit.clone(), nodes.clone())
             .expect("Unable to add circuit");
+        store
+            .add_circuit(disbanded_circuit.clone(), nodes)
+            .expect("Unable to add disbanded circuit");
 
-        // test no predicates
-        assert_eq!(
-            store
-                .count_circuits(&vec![])
-                .expect("Unable to list circuits"),
-            1
-        );
+        let circuits = store
+            .list_circuits(&[])
+            .expect("Unable to list circuits")
+            .collect::<Vec<_>>();
 
-        // test management type predicate
-        assert_eq!(
-            store
-                .count_circuits(&vec![CircuitPredicate::ManagementTypeEq(
-                    "gameroom".to_string(),
-                )])
-                .expect("Unable to list circuits"),
-            1
-        );
+        assert_eq!(circuits, vec![circuit.clone()]);
 
-        // test bad management type predicate
-        assert_eq!(
-            store
-                .count_circuits(&vec![CircuitPredicate::ManagementTypeEq(
-                    "arcade".to_string(),
-                )])
-                .expect("Unable to list circuits"),
-            0
-        );
+        let circuits = store
+            .list_all_circuits()
+            .expect("Unable to list all circuits")
+            .collect::<Vec<_>>();
 
-        store
-            .add_circuit(extra_circuit.clone(), extra_nodes)
-            .expect("Unable to add circuit");
+        assert_eq!(circuits, vec![disbanded_circuit, circuit]);
+    }
 
-        // test members type predicate
-        assert_eq!(
-            store
-                .count_circuits(&vec![CircuitPredicate::MembersInclude(vec![
-                    "gumbo-node-000".to_string(),
-                ])])
-                .expect("Unable to list circuits"),
-            1
-        );
+    /// Verify that list_circuits returns the arguments of every service in order, for a circuit
+    /// with many services
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add a circuit with 500 services, each with 20 arguments whose keys are not in sorted
+    ///    order
+    /// 4. List the circuits, validate the listed circuit is the circuit that was added, with each
+    ///    service's arguments in their original order
+    #[test]
+    fn test_list_circuits_many_service_arguments() {
+        let pool = create_connection_pool_and_migrate();
 
-        // test circuit status predicate
+        let store = DieselAdminServiceStore::new(pool);
 
-        // Add a `Disbanded` circuit
+        let nodes = create_nodes();
+        let roster = (0..500)
+            .map(|i| {
+                ServiceBuilder::default()
+                    .with_service_id(&format!("{:04}", i))
+                    .with_service_type("scabbard")
+                    .with_node_id(if i % 2 == 0 {
+                        "acme-node-000"
+                    } else {
+                        "bubba-node-000"
+                    })
+                    .with_arguments(
+                        &(0..20)
+                            .map(|j| (format!("key-{:02}", (j * 7) % 20), format!("{}-{}", i, j)))
+                            .collect::<Vec<_>>(),
+                    )
+                    .build()
+                    .expect("Unable to build service")
+            })
+            .collect::<Vec<_>>();
+        let circuit = CircuitBuilder::default()
+            .with_circuit_id("WBKLF-BBBBB")
+            .with_roster(&roster)
+            .with_members(&nodes)
+            .with_circuit_management_type("gameroom")
+            .build()
+            .expect("Unable to build circuit");
+
+        store
+            .add_circuit(circuit.clone(), nodes)
+            .expect("Unable to add circuit");
+
+        let circuits = store
+            .list_circuits(&[])
+            .expect("Unable to list circuits")
+            .collect::<Vec<_>>();
+
+        assert_eq!(circuits, vec![circuit]);
+    }
+
+    /// Verify that list_circuits_paged returns a page of the circuits and the total count
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add five `Active` circuits and a `Disbanded` circuit to the store
+    /// 4. List the second page of two circuits, validate the circuits are the third and fourth
+    ///    circuits in descending order of circuit ID, with all of their members and services, and
+    ///    that the total is the number of active circuits
+    /// 5. List a page past the last circuit, validate no circuits are returned with the same total
+    /// 6. List a page of every status, validate the total includes the `Disbanded` circuit
+    #[test]
+    fn test_list_circuits_paged() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        let nodes = create_nodes();
+        let mut circuits = vec![];
+        for circuit_id in &[
+            "WBKLF-AAAAA",
+            "WBKLF-BBBBB",
+            "WBKLF-CCCCC",
+            "WBKLF-DDDDD",
+            "WBKLF-EEEEE",
+        ] {
+            let circuit = create_circuit(circuit_id, CircuitStatus::Active);
+            store
+                .add_circuit(circuit.clone(), nodes.clone())
+                .expect("Unable to add circuit");
+            circuits.push(circuit);
+        }
+        store
+            .add_circuit(
+                create_circuit("WBKLF-FFFFF", CircuitStatus::Disbanded),
+                nodes,
+            )
+            .expect("Unable to add disbanded circuit");
+
+        let (page, total) = store
+            .list_circuits_paged(&[], 2, 2)
+            .expect("Unable to list page of circuits");
+
+        assert_eq!(
+            page.collect::<Vec<_>>(),
+            vec![circuits[2].clone(), circuits[1].clone()]
+        );
+        assert_eq!(total, 5);
+
+        let (page, total) = store
+            .list_circuits_paged(&[], 10, 2)
+            .expect("Unable to list page of circuits");
+
+        assert_eq!(page.len(), 0);
+        assert_eq!(total, 5);
+
+        let (page, total) = store
+            .list_circuits_paged(&[CircuitPredicate::AnyStatus], 0, usize::MAX)
+            .expect("Unable to list page of circuits");
+
+        assert_eq!(page.len(), 6);
+        assert_eq!(total, 6);
+    }
+
+    /// Verify that the `AnyStatus` predicate lists and counts circuits of every status
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add an `Active`, a `Disbanded` and an `Abandoned` circuit to the store
+    /// 4. List circuits with the `AnyStatus` predicate, validate every circuit is returned
+    /// 5. List circuits with the `AnyStatus` and a `CircuitStatus` predicate, validate every
+    ///    circuit is returned
+    /// 6. Count circuits with the `AnyStatus` predicate, validate every circuit is counted
+    #[test]
+    fn test_list_circuits_any_status() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        let circuit = create_circuit("WBKLF-BBBBB", CircuitStatus::Active);
+        let disbanded_circuit = create_circuit("WBKLF-DDDDD", CircuitStatus::Disbanded);
+        let abandoned_circuit = create_circuit("WBKLF-AAAAA", CircuitStatus::Abandoned);
+        let nodes = create_nodes();
+
+        store
+            .add_circuit(circuit.clone(), nodes.clone())
+            .expect("Unable to add circuit");
+        store
+            .add_circuit(disbanded_circuit.clone(), nodes.clone())
+            .expect("Unable to add disbanded circuit");
+        store
+            .add_circuit(abandoned_circuit.clone(), nodes)
+            .expect("Unable to add abandoned circuit");
+
+        let circuits = store
+            .list_circuits(&[CircuitPredicate::AnyStatus])
+            .expect("Unable to list circuits with `AnyStatus` predicate")
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            circuits,
+            vec![
+                disbanded_circuit.clone(),
+                circuit.clone(),
+                abandoned_circuit.clone()
+            ]
+        );
+
+        let circuits = store
+            .list_circuits(&[
+                CircuitPredicate::AnyStatus,
+                CircuitPredicate::CircuitStatus(CircuitStatus::Active),
+            ])
+            .expect("Unable to list circuits with `AnyStatus` predicate")
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            circuits,
+            vec![disbanded_circuit, circuit, abandoned_circuit]
+        );
+
+        assert_eq!(
+            store
+                .count_circuits(&[CircuitPredicate::AnyStatus])
+                .expect("Unable to count circuits with `AnyStatus` predicate"),
+            3
+        );
+    }
+
+    /// Verify that the `ServiceIdEq` predicate lists and counts the circuits with the service
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add two circuits with services `a000` and `a001`, and a circuit with service `b000`
+    /// 4. List circuits with a `ServiceIdEq` predicate for `b000`, validate only the circuit with
+    ///    that service is returned
+    /// 5. List and count circuits with a `ServiceIdEq` predicate for `a001`, validate both
+    ///    circuits with that service are returned and counted
+    /// 6. List circuits with a `ServiceIdEq` predicate for an unknown service, validate no
+    ///    circuits are returned
+    /// 7. Add a proposal with services `a000` and `a001`, validate it is listed and counted with
+    ///    a `ServiceIdEq` predicate for `a001` but not for `b000`
+    #[test]
+    fn test_list_circuits_service_id() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        let nodes = create_nodes();
+        let circuit_a = create_circuit("WBKLF-AAAAA", CircuitStatus::Active);
+        let circuit_b = create_circuit("WBKLF-BBBBB", CircuitStatus::Active);
+        let circuit_c = CircuitBuilder::default()
+            .with_circuit_id("WBKLF-CCCCC")
+            .with_roster(&[ServiceBuilder::default()
+                .with_service_id("b000")
+                .with_service_type("scabbard")
+                .with_node_id("acme-node-000")
+                .build()
+                .expect("Unable to build service")])
+            .with_members(&nodes)
+            .with_circuit_management_type("gameroom")
+            .build()
+            .expect("Unable to build circuit");
+
+        for circuit in &[&circuit_a, &circuit_b, &circuit_c] {
+            store
+                .add_circuit((*circuit).clone(), nodes.clone())
+                .expect("Unable to add circuit");
+        }
+
+        let circuits = store
+            .list_circuits(&[CircuitPredicate::ServiceIdEq("b000".into())])
+            .expect("Unable to list circuits with `ServiceIdEq` predicate")
+            .collect::<Vec<_>>();
+
+        assert_eq!(circuits, vec![circuit_c]);
+
+        let circuits = store
+            .list_circuits(&[CircuitPredicate::ServiceIdEq("a001".into())])
+            .expect("Unable to list circuits with `ServiceIdEq` predicate")
+            .collect::<Vec<_>>();
+
+        assert_eq!(circuits, vec![circuit_b, circuit_a]);
+        assert_eq!(
+            store
+                .count_circuits(&[CircuitPredicate::ServiceIdEq("a001".into())])
+                .expect("Unable to count circuits with `ServiceIdEq` predicate"),
+            2
+        );
+
+        assert_eq!(
+            store
+                .list_circuits(&[CircuitPredicate::ServiceIdEq("c000".into())])
+                .expect("Unable to list circuits with `ServiceIdEq` predicate")
+                .len(),
+            0
+        );
+
+        let proposal = create_proposal();
+        store
+            .add_proposal(proposal.clone())
+            .expect("Unable to add circuit proposal");
+
+        let proposals = store
+            .list_proposals(&[CircuitPredicate::ServiceIdEq("a001".into())])
+            .expect("Unable to list proposals with `ServiceIdEq` predicate")
+            .collect::<Vec<_>>();
+
+        assert_eq!(proposals, vec![proposal]);
+        assert_eq!(
+            store
+                .count_proposals(&[CircuitPredicate::ServiceIdEq("a001".into())])
+                .expect("Unable to count proposals with `ServiceIdEq` predicate"),
+            1
+        );
+        assert_eq!(
+            store
+                .list_proposals(&[CircuitPredicate::ServiceIdEq("b000".into())])
+                .expect("Unable to list proposals with `ServiceIdEq` predicate")
+                .len(),
+            0
+        );
+        assert_eq!(
+            store
+                .count_proposals(&[CircuitPredicate::ServiceIdEq("b000".into())])
+                .expect("Unable to count proposals with `ServiceIdEq` predicate"),
+            0
+        );
+    }
+
+    /// Verify that count_circuits works correctly
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Create a circuit and nodes
+    /// 4. Add circuit to store
+    /// 5. Count circuits from store with no predicates, validated correct number is returned
+    /// 6. Count circuits from store with management type predicate, validated correct number is
+    ///    returned
+    /// 7. Count circuits from store with member predicate, validated correct number is returned
+    /// 8. Count circuits from store with mismatching management type predicate, validated 0 is
+    ///    returned
+    /// 9. Add a `Disbanded` circuit to the store
+    /// 10. Count circuits from store with no circuit status predicate, validate that the correct
+    ///     number of `Active` circuits are returned
+    /// 11. Count circuits with the `CircuitStatus::Disbanded` circuit status predicate, validate
+    ///     that the correct number of `Disbanded` circuits are returned
+    /// 12. Count circuits with the `CircuitStatus::Abandoned` circuit status predicate, validate
+    ///     that the correct number of `Abandoned` circuits are returned
+    #[test]
+    fn test_count_circuits() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        let circuit = create_circuit("WBKLF-BBBBB", CircuitStatus::Active);
+        let nodes = create_nodes();
+
+        let extra_circuit = create_extra_circuit("WBKLF-CCCCC");
+        let extra_nodes = create_extra_nodes();
+
+        store
+            .add_circuit(circuit.clone(), nodes.clone())
+            .expect("Unable to add circuit");
+
+        // test no predicates
+        assert_eq!(
+            store
+                .count_circuits(&vec![])
+                .expect("Unable to list circuits"),
+            1
+        );
+
+        // test management type predicate
+        assert_eq!(
+            store
+                .count_circuits(&vec![CircuitPredicate::ManagementTypeEq(
+                    "gameroom".to_string(),
+                )])
+                .expect("Unable to list circuits"),
+            1
+        );
+
+        // test bad management type predicate
+        assert_eq!(
+            store
+                .count_circuits(&vec![CircuitPredicate::ManagementTypeEq(
+                    "arcade".to_string(),
+                )])
+                .expect("Unable to list circuits"),
+            0
+        );
+
+        store
+            .add_circuit(extra_circuit.clone(), extra_nodes)
+            .expect("Unable to add circuit");
+
+        // test members type predicate
+        assert_eq!(
+            store
+                .count_circuits(&vec![CircuitPredicate::MembersInclude(vec![
+                    "gumbo-node-000".to_string(),
+                ])])
+                .expect("Unable to list circuits"),
+            1
+        );
+
+        // test circuit status predicate
+
+        // Add a `Disbanded` circuit
         let disbanded_circuit = create_circuit("WBKLF-DDDDD", CircuitStatus::Disbanded);
         store
             .add_circuit(disbanded_circuit.clone(), nodes.clone())
@@ -1132,6 +1773,91 @@ pub mod tests {
         assert_eq!(fetched_circuit.roster()[0], fetched_service);
     }
 
+    /// Verify that the services from a circuit can be listed with the endpoints of their nodes
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add a circuit with two services on `acme-node-000`, which has two endpoints, and a
+    ///    service on `bubba-node-000`
+    /// 4. List the services with endpoints, validate each service is returned in roster order
+    ///    with its node's endpoints, and that no endpoint is repeated
+    /// 5. List the services with endpoints for an unknown circuit, validate nothing is returned
+    #[test]
+    fn test_list_services_with_endpoints() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        let nodes = vec![
+            CircuitNodeBuilder::default()
+                .with_node_id("bubba-node-000".into())
+                .with_endpoints(&["tcps://splinterd-node-bubba:8044".into()])
+                .build()
+                .expect("Unable to build node"),
+            CircuitNodeBuilder::default()
+                .with_node_id("acme-node-000".into())
+                .with_endpoints(&[
+                    "tcps://splinterd-node-acme:8044".into(),
+                    "tcps://splinterd-node-acme:8045".into(),
+                ])
+                .build()
+                .expect("Unable to build node"),
+        ];
+        let roster = [
+            ("a000", "acme-node-000"),
+            ("a001", "acme-node-000"),
+            ("b000", "bubba-node-000"),
+        ]
+        .iter()
+        .map(|(service_id, node_id)| {
+            ServiceBuilder::default()
+                .with_service_id(service_id)
+                .with_service_type("scabbard")
+                .with_node_id(node_id)
+                .build()
+                .expect("Unable to build service")
+        })
+        .collect::<Vec<_>>();
+        let circuit = CircuitBuilder::default()
+            .with_circuit_id("WBKLF-BBBBB")
+            .with_roster(&roster)
+            .with_members(&nodes)
+            .with_circuit_management_type("gameroom")
+            .build()
+            .expect("Unable to build circuit");
+
+        store
+            .add_circuit(circuit, nodes)
+            .expect("Unable to add circuit");
+
+        let acme_endpoints = vec![
+            "tcps://splinterd-node-acme:8044".to_string(),
+            "tcps://splinterd-node-acme:8045".to_string(),
+        ];
+        assert_eq!(
+            store
+                .list_services_with_endpoints("WBKLF-BBBBB")
+                .expect("Unable to list services with endpoints")
+                .collect::<Vec<_>>(),
+            vec![
+                (roster[0].clone(), acme_endpoints.clone()),
+                (roster[1].clone(), acme_endpoints),
+                (
+                    roster[2].clone(),
+                    vec!["tcps://splinterd-node-bubba:8044".to_string()]
+                ),
+            ]
+        );
+
+        assert_eq!(
+            store
+                .list_services_with_endpoints("WBKLF-AAAAA")
+                .expect("Unable to list services with endpoints")
+                .len(),
+            0
+        );
+    }
+
     /// Verify that all service from a circuit can be listed from the store
     ///
     /// 1. Run sqlite migrations
@@ -1175,6 +1901,156 @@ pub mod tests {
         assert_eq!(None, services.next());
     }
 
+    /// Verify that the services in a circuit can be counted
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Create a circuit
+    /// 4. Add circuit to store
+    /// 5. Count the services in the circuit, validate that the roster size is returned
+    /// 6. Count the services in a circuit that does not exist, validate that 0 is returned
+    #[test]
+    fn test_count_services() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        let circuit = create_circuit("WBKLF-BBBBB", CircuitStatus::Active);
+        let nodes = create_nodes();
+
+        store
+            .add_circuit(circuit.clone(), nodes)
+            .expect("Unable to add circuit");
+
+        assert_eq!(
+            circuit.roster().len(),
+            store
+                .count_services("WBKLF-BBBBB")
+                .expect("Unable to count services")
+        );
+
+        assert_eq!(
+            0,
+            store
+                .count_services("WBKLF-AAAAA")
+                .expect("Unable to count services")
+        );
+    }
+
+    /// Verify that the services run by a node can be listed across circuits
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Create two circuits which both have a service on `acme-node-000`
+    /// 4. Add circuits to store
+    /// 5. List the services for `acme-node-000`, validate that the service from each circuit is
+    ///    returned with its circuit ID, in circuit ID order
+    /// 6. List the services for `gumbo-node-000`, validate only the extra circuit's service is
+    ///    returned
+    /// 7. List the services for an unknown node, validate that nothing is returned
+    #[test]
+    fn test_list_services_for_node() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        let circuit = create_circuit("WBKLF-BBBBB", CircuitStatus::Active);
+        let extra_circuit = create_extra_circuit("WBKLF-AAAAA");
+
+        store
+            .add_circuit(circuit.clone(), create_nodes())
+            .expect("Unable to add circuit");
+        store
+            .add_circuit(extra_circuit.clone(), create_extra_nodes())
+            .expect("Unable to add circuit");
+
+        assert_eq!(
+            vec![
+                ("WBKLF-AAAAA".to_string(), extra_circuit.roster()[0].clone()),
+                ("WBKLF-BBBBB".to_string(), circuit.roster()[0].clone()),
+            ],
+            store
+                .list_services_for_node("acme-node-000")
+                .expect("Unable to list services")
+        );
+
+        assert_eq!(
+            vec![("WBKLF-AAAAA".to_string(), extra_circuit.roster()[1].clone())],
+            store
+                .list_services_for_node("gumbo-node-000")
+                .expect("Unable to list services")
+        );
+
+        assert!(store
+            .list_services_for_node("unknown-node-000")
+            .expect("Unable to list services")
+            .is_empty());
+    }
+
+    /// Verify that the distinct service types can be listed from the store
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add a circuit with two `scabbard` services, and a circuit with an `echo` service on
+    ///    `acme-node-000` and a `scabbard` service on `gumbo-node-000`
+    /// 4. List the service types, validate each type is returned once, in order
+    /// 5. List the service types for `bubba-node-000`, validate only `scabbard` is returned
+    /// 6. List the service types for an unknown node, validate that nothing is returned
+    #[test]
+    fn test_list_service_types() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        let circuit = create_circuit("WBKLF-BBBBB", CircuitStatus::Active);
+        let echo_circuit = CircuitBuilder::default()
+            .with_circuit_id("WBKLF-AAAAA")
+            .with_roster(&[
+                ServiceBuilder::default()
+                    .with_service_id("e000")
+                    .with_service_type("echo")
+                    .with_node_id("acme-node-000")
+                    .build()
+                    .expect("Unable to build service"),
+                ServiceBuilder::default()
+                    .with_service_id("e001")
+                    .with_service_type("scabbard")
+                    .with_node_id("gumbo-node-000")
+                    .build()
+                    .expect("Unable to build service"),
+            ])
+            .with_members(&create_extra_nodes())
+            .with_circuit_management_type("test")
+            .build()
+            .expect("Unable to build circuit");
+
+        store
+            .add_circuit(circuit, create_nodes())
+            .expect("Unable to add circuit");
+        store
+            .add_circuit(echo_circuit, create_extra_nodes())
+            .expect("Unable to add circuit");
+
+        assert_eq!(
+            vec!["echo".to_string(), "scabbard".to_string()],
+            store
+                .list_service_types(None)
+                .expect("Unable to list service types")
+        );
+
+        assert_eq!(
+            vec!["scabbard".to_string()],
+            store
+                .list_service_types(Some("bubba-node-000"))
+                .expect("Unable to list service types")
+        );
+
+        assert!(store
+            .list_service_types(Some("unknown-node-000"))
+            .expect("Unable to list service types")
+            .is_empty());
+    }
+
     /// Verify that all nodes can be listed from the store
     ///
     /// 1. Run sqlite migrations
@@ -1216,6 +2092,312 @@ pub mod tests {
         assert!(nodes.next().is_none());
     }
 
+    /// Verify that list_nodes returns every node's endpoints sorted, however many endpoints the
+    /// node has and in whatever order they were added
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add two circuits, with the bubba, acme and gumbo nodes, to the store
+    /// 4. Replace the endpoints of bubba and acme with three and two endpoints, out of order
+    /// 5. Validate that list_nodes returns the nodes in a stable order, with sorted endpoints
+    #[test]
+    fn test_list_nodes_sorted_endpoints() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        store
+            .add_circuit(
+                create_circuit("WBKLF-BBBBB", CircuitStatus::Active),
+                create_nodes(),
+            )
+            .expect("Unable to add circuit");
+        store
+            .add_circuit(create_extra_circuit("WBKLF-CCCCC"), create_extra_nodes())
+            .expect("Unable to add circuit");
+
+        store
+            .update_node_endpoints(
+                "bubba-node-000",
+                &[
+                    "tcps://splinterd-node-bubba-c:8044".to_string(),
+                    "tcps://splinterd-node-bubba-a:8044".to_string(),
+                    "tcps://splinterd-node-bubba-b:8044".to_string(),
+                ],
+            )
+            .expect("Unable to update node endpoints");
+        store
+            .update_node_endpoints(
+                "acme-node-000",
+                &[
+                    "tcps://splinterd-node-acme-b:8044".to_string(),
+                    "tcps://splinterd-node-acme-a:8044".to_string(),
+                ],
+            )
+            .expect("Unable to update node endpoints");
+
+        let listed = store
+            .list_nodes()
+            .expect("Unable to list nodes")
+            .map(|node| (node.node_id().to_string(), node.endpoints().to_vec()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            listed,
+            vec![
+                (
+                    "bubba-node-000".to_string(),
+                    vec![
+                        "tcps://splinterd-node-bubba-a:8044".to_string(),
+                        "tcps://splinterd-node-bubba-b:8044".to_string(),
+                        "tcps://splinterd-node-bubba-c:8044".to_string(),
+                    ]
+                ),
+                (
+                    "gumbo-node-000".to_string(),
+                    vec!["tcps://splinterd-node-gumbo:8044".to_string()]
+                ),
+                (
+                    "acme-node-000".to_string(),
+                    vec![
+                        "tcps://splinterd-node-acme-a:8044".to_string(),
+                        "tcps://splinterd-node-acme-b:8044".to_string(),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    /// Verify that only the requested nodes are listed by list_nodes_filtered
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add two circuits, sharing the acme node, to the store
+    /// 4. Validate that listing bubba and gumbo returns only those nodes
+    /// 5. Validate that the acme node is listed once, with its endpoints, and that unknown IDs
+    ///    are ignored
+    #[test]
+    fn test_list_nodes_filtered() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        store
+            .add_circuit(
+                create_circuit("WBKLF-BBBBB", CircuitStatus::Active),
+                create_nodes(),
+            )
+            .expect("Unable to add circuit");
+        store
+            .add_circuit(create_extra_circuit("WBKLF-CCCCC"), create_extra_nodes())
+            .expect("Unable to add circuit");
+
+        let nodes = store
+            .list_nodes_filtered(&["gumbo-node-000".to_string(), "bubba-node-000".to_string()])
+            .expect("Unable to list nodes")
+            .collect::<Vec<_>>();
+        let mut node_ids = nodes
+            .iter()
+            .map(|node| node.node_id().to_string())
+            .collect::<Vec<_>>();
+        node_ids.sort();
+        assert_eq!(
+            node_ids,
+            vec!["bubba-node-000".to_string(), "gumbo-node-000".to_string()]
+        );
+
+        let acme = store
+            .get_node("acme-node-000")
+            .expect("Unable to get node")
+            .expect("Got None when expecting node");
+        let nodes = store
+            .list_nodes_filtered(&["acme-node-000".to_string(), "unknown-node-000".to_string()])
+            .expect("Unable to list nodes")
+            .collect::<Vec<_>>();
+        assert_eq!(nodes, vec![acme]);
+
+        assert_eq!(
+            store
+                .list_nodes_filtered(&[])
+                .expect("Unable to list nodes")
+                .len(),
+            0
+        );
+    }
+
+    /// Verify that a node's endpoints can be replaced and that invalid updates are rejected
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add a circuit and nodes to the store
+    /// 4. Replace the endpoints of one node
+    /// 5. Validate the new endpoints are returned by get_node and list_nodes
+    /// 6. Validate that updates with malformed, duplicate or taken endpoints, or for an unknown
+    ///    node, fail and leave the endpoints unchanged
+    #[test]
+    fn test_update_node_endpoints() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        store
+            .add_circuit(
+                create_circuit("WBKLF-BBBBB", CircuitStatus::Active),
+                create_nodes(),
+            )
+            .expect("Unable to add circuit");
+
+        let new_endpoints = vec![
+            "tcps://splinterd-node-bubba-2:8044".to_string(),
+            "tcp://splinterd-node-bubba-2:8043".to_string(),
+        ];
+        store
+            .update_node_endpoints("bubba-node-000", &new_endpoints)
+            .expect("Unable to update node endpoints");
+
+        let expected_node = CircuitNodeBuilder::default()
+            .with_node_id("bubba-node-000")
+            .with_endpoints(&new_endpoints)
+            .build()
+            .expect("Unable to build node");
+
+        assert_eq!(
+            store
+                .get_node("bubba-node-000")
+                .expect("Unable to get node")
+                .expect("Got None when expecting node"),
+            expected_node
+        );
+        assert!(store
+            .list_nodes()
+            .expect("Unable to list nodes")
+            .any(|node| node == expected_node));
+
+        for invalid_endpoints in [
+            vec![],
+            vec!["splinterd-node-bubba:8044".to_string()],
+            vec![
+                "tcps://splinterd-node-bubba:8044".to_string(),
+                "tcps://splinterd-node-bubba:8044".to_string(),
+            ],
+            vec!["tcps://splinterd-node-acme:8044".to_string()],
+        ] {
+            assert!(matches!(
+                store.update_node_endpoints("bubba-node-000", &invalid_endpoints),
+                Err(AdminServiceStoreError::InvalidStateError(_))
+            ));
+        }
+
+        assert!(matches!(
+            store.update_node_endpoints(
+                "unknown-node-000",
+                &["tcps://splinterd-node-unknown:8044".to_string()]
+            ),
+            Err(AdminServiceStoreError::InvalidStateError(_))
+        ));
+
+        assert_eq!(
+            store
+                .get_node("bubba-node-000")
+                .expect("Unable to get node")
+                .expect("Got None when expecting node"),
+            expected_node
+        );
+    }
+
+    /// Verify that endpoints registered for more than one node are found
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add a circuit whose nodes have distinct endpoints
+    /// 4. Validate no duplicate endpoints are found
+    /// 5. Add a second circuit with a node that reuses the endpoint of a node in the first circuit
+    /// 6. Validate the shared endpoint is returned with both node IDs
+    #[test]
+    fn test_find_duplicate_endpoints() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool);
+
+        store
+            .add_circuit(
+                create_circuit("WBKLF-BBBBB", CircuitStatus::Active),
+                create_nodes(),
+            )
+            .expect("Unable to add circuit");
+
+        assert!(store
+            .find_duplicate_endpoints()
+            .expect("Unable to find duplicate endpoints")
+            .is_empty());
+
+        let extra_nodes = vec![
+            CircuitNodeBuilder::default()
+                .with_node_id("gumbo-node-000".into())
+                .with_endpoints(&vec!["tcps://splinterd-node-bubba:8044".into()])
+                .build()
+                .expect("Unable to build node"),
+            CircuitNodeBuilder::default()
+                .with_node_id("acme-node-000".into())
+                .with_endpoints(&vec!["tcps://splinterd-node-acme:8044".into()])
+                .build()
+                .expect("Unable to build node"),
+        ];
+        store
+            .add_circuit(create_extra_circuit("WBKLF-CCCCC"), extra_nodes)
+            .expect("Unable to add circuit");
+
+        assert_eq!(
+            store
+                .find_duplicate_endpoints()
+                .expect("Unable to find duplicate endpoints"),
+            vec![(
+                "tcps://splinterd-node-bubba:8044".to_string(),
+                vec!["bubba-node-000".to_string(), "gumbo-node-000".to_string()]
+            )]
+        );
+    }
+
+    /// Verify that service arguments whose service has been removed are found
+    ///
+    /// 1. Run sqlite migrations
+    /// 2. Create DieselAdminServiceStore
+    /// 3. Add a circuit whose services have arguments
+    /// 4. Validate there are no orphaned service arguments
+    /// 5. Delete one service's row, leaving its arguments behind
+    /// 6. Validate the deleted service is returned
+    #[test]
+    fn test_find_orphaned_service_arguments() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselAdminServiceStore::new(pool.clone());
+
+        store
+            .add_circuit(
+                create_circuit("WBKLF-BBBBB", CircuitStatus::Active),
+                create_nodes(),
+            )
+            .expect("Unable to add circuit");
+
+        assert!(store
+            .find_orphaned_service_arguments()
+            .expect("Unable to find orphaned service arguments")
+            .is_empty());
+
+        {
+            let conn = pool.get().expect("Unable to get connection");
+            diesel::delete(schema::service::table.find(("WBKLF-BBBBB", "a001")))
+                .execute(&*conn)
+                .expect("Unable to delete service");
+        }
+
+        assert_eq!(
+            store
+                .find_orphaned_service_arguments()
+                .expect("Unable to find orphaned service arguments"),
+            vec![("WBKLF-BBBBB".to_string(), "a001".to_string())]
+        );
+    }
+
     #[test]
     /// Verify that an event can be added to the store correctly and then returned by the store
     ///
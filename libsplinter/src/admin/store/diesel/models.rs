@@ -18,6 +18,7 @@
 
 use std::convert::TryFrom;
 use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use diesel::{
     backend::Backend,
@@ -438,6 +439,8 @@ pub struct CircuitModel {
     pub display_name: Option<String>,
     pub circuit_version: i32,
     pub circuit_status: CircuitStatusModel,
+    /// Seconds since the Unix epoch at which the circuit was last written to the store
+    pub last_updated: i64,
 }
 
 impl From<&Circuit> for CircuitModel {
@@ -452,10 +455,19 @@ impl From<&Circuit> for CircuitModel {
             display_name: circuit.display_name().clone(),
             circuit_version: circuit.circuit_version(),
             circuit_status: CircuitStatusModel::from(circuit.circuit_status()),
+            last_updated: current_timestamp(),
         }
     }
 }
 
+/// Returns the current time as seconds since the Unix epoch.
+fn current_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0)
+}
+
 /// Database model representation of the `members` of a `Circuit`
 #[derive(
     Debug, PartialEq, Eq, Associations, Identifiable, Insertable, Queryable, QueryableByName,
@@ -24,7 +24,7 @@ use diesel::{
 use crate::admin::store::{
     diesel::{
         models::CircuitStatusModel,
-        schema::{circuit, circuit_member},
+        schema::{circuit, circuit_member, service},
     },
     error::AdminServiceStoreError,
     CircuitPredicate,
@@ -73,6 +73,17 @@ where
                 _ => None,
             })
             .collect();
+        let any_status = predicates
+            .iter()
+            .any(|pred| matches!(pred, CircuitPredicate::AnyStatus));
+        // Collects the service IDs included in the list of `CircuitPredicates`
+        let service_ids: Vec<String> = predicates
+            .iter()
+            .filter_map(|pred| match pred {
+                CircuitPredicate::ServiceIdEq(service_id) => Some(service_id.to_string()),
+                _ => None,
+            })
+            .collect();
         self.conn.transaction::<u32, _, _>(|| {
             // Collects circuits which match the circuit predicates
             let mut query = circuit::table.into_boxed().select(circuit::all_columns);
@@ -93,14 +104,29 @@ where
                 ));
             }
 
-            if statuses.is_empty() {
-                // By default, only display active circuits
-                query = query.filter(circuit::circuit_status.eq(CircuitStatusModel::Active));
-            } else {
-                query = query.filter(
-                    // Select only circuits that have the `CircuitStatus` in the predicates
-                    circuit::circuit_status.eq_any(statuses),
-                );
+            if !service_ids.is_empty() {
+                query = query.filter(exists(
+                    // Selects all `service` entries where the `service_id` is equal to any of
+                    // the service IDs in the circuit predicates
+                    service::table.filter(
+                        service::circuit_id
+                            .eq(circuit::circuit_id)
+                            .and(service::service_id.eq_any(service_ids)),
+                    ),
+                ));
+            }
+
+            // Filter by status, unless circuits of every status are requested
+            if !any_status {
+                if statuses.is_empty() {
+                    // By default, only display active circuits
+                    query = query.filter(circuit::circuit_status.eq(CircuitStatusModel::Active));
+                } else {
+                    query = query.filter(
+                        // Select only circuits that have the `CircuitStatus` in the predicates
+                        circuit::circuit_status.eq_any(statuses),
+                    );
+                }
             }
 
             let count = query.select(count_star()).first::<i64>(self.conn)?;
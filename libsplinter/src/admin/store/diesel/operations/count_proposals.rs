@@ -22,7 +22,7 @@ use diesel::{
 };
 
 use crate::admin::store::{
-    diesel::schema::{proposed_circuit, proposed_node},
+    diesel::schema::{proposed_circuit, proposed_node, proposed_service},
     error::AdminServiceStoreError,
     CircuitPredicate,
 };
@@ -63,6 +63,14 @@ where
             })
             .flatten()
             .collect();
+        // Collects the service IDs included in the list of `CircuitPredicates`
+        let service_ids: Vec<String> = predicates
+            .iter()
+            .filter_map(|pred| match pred {
+                CircuitPredicate::ServiceIdEq(service_id) => Some(service_id.to_string()),
+                _ => None,
+            })
+            .collect();
 
         self.conn.transaction::<u32, _, _>(|| {
             let mut query = proposed_circuit::table
@@ -81,6 +89,18 @@ where
                 ))
             }
 
+            if !service_ids.is_empty() {
+                query = query.filter(exists(
+                    // Selects all `proposed_service` entries where the `service_id` is equal
+                    // to any of the service IDs in the circuit predicates
+                    proposed_service::table.filter(
+                        proposed_service::circuit_id
+                            .eq(proposed_circuit::circuit_id)
+                            .and(proposed_service::service_id.eq_any(service_ids)),
+                    ),
+                ));
+            }
+
             // Selects proposed circuits that match the management types
             if !management_types.is_empty() {
                 query = query
@@ -0,0 +1,50 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the "count services" operation for the `DieselAdminServiceStore`.
+
+use std::convert::TryFrom;
+
+use diesel::{dsl::count_star, prelude::*};
+
+use crate::admin::store::{diesel::schema::service, error::AdminServiceStoreError};
+use crate::error::InternalError;
+
+use super::AdminServiceStoreOperations;
+
+pub(in crate::admin::store::diesel) trait AdminServiceStoreCountServicesOperation {
+    fn count_services(&self, circuit_id: &str) -> Result<usize, AdminServiceStoreError>;
+}
+
+impl<'a, C> AdminServiceStoreCountServicesOperation for AdminServiceStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    i64: diesel::deserialize::FromSql<diesel::sql_types::BigInt, C::Backend>,
+{
+    fn count_services(&self, circuit_id: &str) -> Result<usize, AdminServiceStoreError> {
+        // Only the `service` table is queried, as the service arguments are not needed to
+        // determine the size of the roster. Each service has a single row per circuit, so the
+        // rows can be counted directly
+        let count = service::table
+            .filter(service::circuit_id.eq(circuit_id))
+            .select(count_star())
+            .first::<i64>(self.conn)?;
+
+        usize::try_from(count).map_err(|_| {
+            AdminServiceStoreError::InternalError(InternalError::with_message(
+                "The number of services is larger than the max usize".to_string(),
+            ))
+        })
+    }
+}
@@ -0,0 +1,47 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the "fetch circuit version" operation for the `DieselAdminServiceStore`.
+
+use diesel::prelude::*;
+
+use crate::admin::store::{diesel::schema::circuit, error::AdminServiceStoreError};
+
+use super::AdminServiceStoreOperations;
+
+pub(in crate::admin::store::diesel) trait AdminServiceStoreFetchCircuitVersionOperation {
+    fn fetch_circuit_version(
+        &self,
+        circuit_id: &str,
+    ) -> Result<Option<i32>, AdminServiceStoreError>;
+}
+
+impl<'a, C> AdminServiceStoreFetchCircuitVersionOperation for AdminServiceStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    i32: diesel::deserialize::FromSql<diesel::sql_types::Integer, C::Backend>,
+{
+    fn fetch_circuit_version(
+        &self,
+        circuit_id: &str,
+    ) -> Result<Option<i32>, AdminServiceStoreError> {
+        // Only the version is selected, so the rest of the circuit is not loaded
+        circuit::table
+            .select(circuit::circuit_version)
+            .filter(circuit::circuit_id.eq(circuit_id))
+            .first::<i32>(self.conn)
+            .optional()
+            .map_err(AdminServiceStoreError::from)
+    }
+}
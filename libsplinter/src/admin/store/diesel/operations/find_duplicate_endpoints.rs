@@ -0,0 +1,60 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the "find duplicate endpoints" operation for the `DieselAdminServiceStore`.
+
+use std::collections::BTreeMap;
+
+use diesel::prelude::*;
+
+use crate::admin::store::{diesel::models::NodeEndpointModel, error::AdminServiceStoreError};
+
+use super::AdminServiceStoreOperations;
+
+// Diesel 1.4 does not support `HAVING`, so the query is written out in full. Each
+// (node_id, endpoint) pair is unique, so counting rows per endpoint counts distinct nodes.
+const DUPLICATE_ENDPOINTS_QUERY: &str = "SELECT node_id, endpoint FROM node_endpoint \
+    WHERE endpoint IN ( \
+        SELECT endpoint FROM node_endpoint GROUP BY endpoint HAVING COUNT(*) > 1 \
+    ) \
+    ORDER BY endpoint, node_id";
+
+pub(in crate::admin::store::diesel) trait AdminServiceStoreFindDuplicateEndpointsOperation {
+    fn find_duplicate_endpoints(
+        &self,
+    ) -> Result<Vec<(String, Vec<String>)>, AdminServiceStoreError>;
+}
+
+impl<'a, C> AdminServiceStoreFindDuplicateEndpointsOperation for AdminServiceStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    NodeEndpointModel: diesel::deserialize::QueryableByName<C::Backend>,
+{
+    fn find_duplicate_endpoints(
+        &self,
+    ) -> Result<Vec<(String, Vec<String>)>, AdminServiceStoreError> {
+        let node_endpoints =
+            diesel::sql_query(DUPLICATE_ENDPOINTS_QUERY).load::<NodeEndpointModel>(self.conn)?;
+
+        let mut duplicates: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for node_endpoint in node_endpoints {
+            duplicates
+                .entry(node_endpoint.endpoint)
+                .or_default()
+                .push(node_endpoint.node_id);
+        }
+
+        Ok(duplicates.into_iter().collect())
+    }
+}
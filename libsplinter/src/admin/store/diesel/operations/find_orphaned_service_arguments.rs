@@ -0,0 +1,64 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the "find orphaned service arguments" operation for the `DieselAdminServiceStore`.
+
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+
+use crate::admin::store::error::AdminServiceStoreError;
+
+use super::AdminServiceStoreOperations;
+
+// The `service_argument` table only references `circuit`, so nothing prevents its rows from
+// outliving their service.
+const ORPHANED_SERVICE_ARGUMENTS_QUERY: &str = "SELECT DISTINCT circuit_id, service_id \
+    FROM service_argument \
+    WHERE NOT EXISTS ( \
+        SELECT 1 FROM service \
+        WHERE service.circuit_id = service_argument.circuit_id \
+        AND service.service_id = service_argument.service_id \
+    ) \
+    ORDER BY circuit_id, service_id";
+
+#[derive(QueryableByName)]
+struct OrphanedServiceArgument {
+    #[sql_type = "Text"]
+    circuit_id: String,
+    #[sql_type = "Text"]
+    service_id: String,
+}
+
+pub(in crate::admin::store::diesel) trait AdminServiceStoreFindOrphanedServiceArgumentsOperation {
+    fn find_orphaned_service_arguments(
+        &self,
+    ) -> Result<Vec<(String, String)>, AdminServiceStoreError>;
+}
+
+impl<'a, C> AdminServiceStoreFindOrphanedServiceArgumentsOperation
+    for AdminServiceStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    String: diesel::deserialize::FromSql<Text, C::Backend>,
+{
+    fn find_orphaned_service_arguments(
+        &self,
+    ) -> Result<Vec<(String, String)>, AdminServiceStoreError> {
+        Ok(diesel::sql_query(ORPHANED_SERVICE_ARGUMENTS_QUERY)
+            .load::<OrphanedServiceArgument>(self.conn)?
+            .into_iter()
+            .map(|orphan| (orphan.circuit_id, orphan.service_id))
+            .collect())
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the "list circuits modified since" operation for the `DieselAdminServiceStore`.
+
+use diesel::prelude::*;
+use diesel::sql_types::{Binary, Integer, Nullable, Text};
+
+use crate::admin::store::{
+    diesel::{models::CircuitMemberModel, schema::circuit},
+    error::AdminServiceStoreError,
+    Circuit,
+};
+use crate::error::InvalidStateError;
+
+use super::{get_circuit::AdminServiceStoreFetchCircuitOperation, AdminServiceStoreOperations};
+
+pub(in crate::admin::store::diesel) trait AdminServiceStoreListCircuitsModifiedSinceOperation {
+    fn list_circuits_modified_since(
+        &self,
+        timestamp: i64,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = Circuit>>, AdminServiceStoreError>;
+}
+
+impl<'a, C> AdminServiceStoreListCircuitsModifiedSinceOperation
+    for AdminServiceStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    String: diesel::deserialize::FromSql<Text, C::Backend>,
+    i64: diesel::deserialize::FromSql<diesel::sql_types::BigInt, C::Backend>,
+    i32: diesel::deserialize::FromSql<Integer, C::Backend>,
+    i16: diesel::deserialize::FromSql<diesel::sql_types::SmallInt, C::Backend>,
+    CircuitMemberModel: diesel::Queryable<(Text, Text, Integer, Nullable<Binary>), C::Backend>,
+{
+    fn list_circuits_modified_since(
+        &self,
+        timestamp: i64,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = Circuit>>, AdminServiceStoreError> {
+        self.conn
+            .transaction::<Box<dyn ExactSizeIterator<Item = Circuit>>, _, _>(|| {
+                // Collect the IDs of the modified circuits, oldest update first; the circuit ID
+                // breaks ties so the order is stable between polls
+                let circuit_ids: Vec<String> = circuit::table
+                    .select(circuit::circuit_id)
+                    .filter(circuit::last_updated.ge(timestamp))
+                    .order((circuit::last_updated.asc(), circuit::circuit_id.asc()))
+                    .load::<String>(self.conn)?;
+
+                let circuits = circuit_ids
+                    .iter()
+                    .map(|circuit_id| {
+                        self.get_circuit(circuit_id)?.ok_or_else(|| {
+                            AdminServiceStoreError::InvalidStateError(
+                                InvalidStateError::with_message(format!(
+                                    "Circuit {} was removed while being listed",
+                                    circuit_id
+                                )),
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<Circuit>, AdminServiceStoreError>>()?;
+
+                Ok(Box::new(circuits.into_iter()))
+            })
+    }
+}
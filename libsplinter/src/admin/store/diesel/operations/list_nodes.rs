@@ -38,6 +38,11 @@ pub(in crate::admin::store::diesel) trait AdminServiceStoreListNodesOperation {
     fn list_nodes(
         &self,
     ) -> Result<Box<dyn ExactSizeIterator<Item = CircuitNode>>, AdminServiceStoreError>;
+
+    fn list_nodes_filtered(
+        &self,
+        node_ids: &[String],
+    ) -> Result<Box<dyn ExactSizeIterator<Item = CircuitNode>>, AdminServiceStoreError>;
 }
 
 impl<'a, C> AdminServiceStoreListNodesOperation for AdminServiceStoreOperations<'a, C>
@@ -60,47 +65,76 @@ where
             .order(circuit_member::position)
             .inner_join(node_endpoint::table.on(circuit_member::node_id.eq(node_endpoint::node_id)))
             .load(self.conn)?;
-        let mut node_map: HashMap<String, Vec<String>> = HashMap::new();
-        let mut nodes: HashMap<String, CircuitMemberModel> = HashMap::new();
-        // Iterate over the list of node data retrieved from the database, in order to collect all
-        // endpoints associated with the `node_ids` in a HashMap.
-        nodes_info.into_iter().for_each(|(node, node_endpoint)| {
-            if let Some(endpoint_list) = node_map.get_mut(&node.node_id) {
-                endpoint_list.push(node_endpoint.endpoint);
-                // Ensure only unique endpoints are added to the node's endpoint list
-                endpoint_list.sort();
-                endpoint_list.dedup();
-            } else {
-                node_map.insert(node.node_id.to_string(), vec![node_endpoint.endpoint]);
-            }
-
-            if !nodes.contains_key(&node.node_id) {
-                nodes.insert(node.node_id.to_string(), node);
-            }
-        });
 
-        let mut nodes_vec: Vec<CircuitMemberModel> =
-            nodes.into_iter().map(|(_, node)| node).collect();
-        nodes_vec.sort_by_key(|node| node.position);
+        nodes_from_rows(nodes_info)
+    }
 
-        let nodes: Vec<CircuitNode> = nodes_vec
-            .iter()
-            .map(|node| {
-                let mut builder = CircuitNodeBuilder::new().with_node_id(&node.node_id);
+    fn list_nodes_filtered(
+        &self,
+        node_ids: &[String],
+    ) -> Result<Box<dyn ExactSizeIterator<Item = CircuitNode>>, AdminServiceStoreError> {
+        let nodes_info: Vec<(CircuitMemberModel, NodeEndpointModel)> = circuit_member::table
+            .filter(circuit_member::node_id.eq_any(node_ids))
+            .order(circuit_member::position)
+            .inner_join(node_endpoint::table.on(circuit_member::node_id.eq(node_endpoint::node_id)))
+            .load(self.conn)?;
 
-                if let Some(public_key) = &node.public_key {
-                    builder = builder.with_public_key(&PublicKey::from_bytes(public_key.to_vec()));
-                }
+        nodes_from_rows(nodes_info)
+    }
+}
 
-                if let Some(endpoints) = node_map.get(&node.node_id) {
-                    builder = builder.with_endpoints(endpoints);
-                }
+/// Builds the nodes from the joined `circuit_member` and `node_endpoint` entries, with each node's
+/// sorted, unique endpoints, ordered by the node's position in its circuit and then by node ID.
+fn nodes_from_rows(
+    nodes_info: Vec<(CircuitMemberModel, NodeEndpointModel)>,
+) -> Result<Box<dyn ExactSizeIterator<Item = CircuitNode>>, AdminServiceStoreError> {
+    let mut node_map: HashMap<String, Vec<String>> = HashMap::new();
+    let mut nodes: HashMap<String, CircuitMemberModel> = HashMap::new();
+    // Iterate over the list of node data retrieved from the database, in order to collect all
+    // endpoints associated with the `node_ids` in a HashMap.
+    nodes_info.into_iter().for_each(|(node, node_endpoint)| {
+        node_map
+            .entry(node.node_id.to_string())
+            .or_default()
+            .push(node_endpoint.endpoint);
 
-                builder.build()
-            })
-            .collect::<Result<Vec<CircuitNode>, InvalidStateError>>()
-            .map_err(AdminServiceStoreError::InvalidStateError)?;
+        if !nodes.contains_key(&node.node_id) {
+            nodes.insert(node.node_id.to_string(), node);
+        }
+    });
 
-        Ok(Box::new(nodes.into_iter()))
+    // Every node's endpoints are sorted and unique, however many it has and in whatever order
+    // they were returned, so that the same nodes are always listed identically
+    for endpoint_list in node_map.values_mut() {
+        endpoint_list.sort();
+        endpoint_list.dedup();
     }
+
+    let mut nodes_vec: Vec<CircuitMemberModel> = nodes.into_iter().map(|(_, node)| node).collect();
+    // Nodes at the same position in different circuits are ordered by ID
+    nodes_vec.sort_by(|a, b| {
+        a.position
+            .cmp(&b.position)
+            .then_with(|| a.node_id.cmp(&b.node_id))
+    });
+
+    let nodes: Vec<CircuitNode> = nodes_vec
+        .iter()
+        .map(|node| {
+            let mut builder = CircuitNodeBuilder::new().with_node_id(&node.node_id);
+
+            if let Some(public_key) = &node.public_key {
+                builder = builder.with_public_key(&PublicKey::from_bytes(public_key.to_vec()));
+            }
+
+            if let Some(endpoints) = node_map.get(&node.node_id) {
+                builder = builder.with_endpoints(endpoints);
+            }
+
+            builder.build()
+        })
+        .collect::<Result<Vec<CircuitNode>, InvalidStateError>>()
+        .map_err(AdminServiceStoreError::InvalidStateError)?;
+
+    Ok(Box::new(nodes.into_iter()))
 }
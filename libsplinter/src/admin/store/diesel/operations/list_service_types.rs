@@ -0,0 +1,53 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the "list service types" operation for the `DieselAdminServiceStore`.
+
+use diesel::prelude::*;
+
+use crate::admin::store::{diesel::schema::service, error::AdminServiceStoreError};
+
+use super::AdminServiceStoreOperations;
+
+pub(in crate::admin::store::diesel) trait AdminServiceStoreListServiceTypesOperation {
+    fn list_service_types(
+        &self,
+        node_id: Option<&str>,
+    ) -> Result<Vec<String>, AdminServiceStoreError>;
+}
+
+impl<'a, C> AdminServiceStoreListServiceTypesOperation for AdminServiceStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    fn list_service_types(
+        &self,
+        node_id: Option<&str>,
+    ) -> Result<Vec<String>, AdminServiceStoreError> {
+        // Only the `service` table is queried, as each service's type is stored with the service
+        let mut query = service::table
+            .select(service::service_type)
+            .distinct()
+            .into_boxed();
+
+        if let Some(node_id) = node_id {
+            query = query.filter(service::node_id.eq(node_id));
+        }
+
+        Ok(query
+            .order(service::service_type.asc())
+            .load::<String>(self.conn)?)
+    }
+}
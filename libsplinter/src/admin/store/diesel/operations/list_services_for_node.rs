@@ -0,0 +1,122 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the "list services for node" operation for the `DieselAdminServiceStore`.
+
+use std::collections::BTreeMap;
+
+use diesel::prelude::*;
+
+use crate::admin::store::{
+    diesel::{
+        models::{ServiceArgumentModel, ServiceModel},
+        schema::{service, service_argument},
+    },
+    error::AdminServiceStoreError,
+    Service, ServiceBuilder,
+};
+
+use super::AdminServiceStoreOperations;
+
+pub(in crate::admin::store::diesel) trait AdminServiceStoreListServicesForNodeOperation {
+    fn list_services_for_node(
+        &self,
+        node_id: &str,
+    ) -> Result<Vec<(String, Service)>, AdminServiceStoreError>;
+}
+
+impl<'a, C> AdminServiceStoreListServicesForNodeOperation for AdminServiceStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+    i64: diesel::deserialize::FromSql<diesel::sql_types::BigInt, C::Backend>,
+    i32: diesel::deserialize::FromSql<diesel::sql_types::Integer, C::Backend>,
+{
+    fn list_services_for_node(
+        &self,
+        node_id: &str,
+    ) -> Result<Vec<(String, Service)>, AdminServiceStoreError> {
+        // Create BTreeMap of (`circuit_id`, `service_id`) to a `ServiceModel`, which keeps the
+        // services grouped by circuit
+        let mut services: BTreeMap<(String, String), ServiceModel> = BTreeMap::new();
+        // Create BTreeMap of (`circuit_id`, `service_id`) to the associated argument values
+        let mut arguments_map: BTreeMap<(String, String), Vec<ServiceArgumentModel>> =
+            BTreeMap::new();
+        for (service, opt_arg) in service::table
+            // Filter retrieved 'service' entries by the provided `node_id`
+            .filter(service::node_id.eq(&node_id))
+            // The `service` table has a one-to-many relationship with the `service_argument` table.
+            .left_join(
+                service_argument::table.on(service::circuit_id
+                    .eq(service_argument::circuit_id)
+                    .and(service::service_id.eq(service_argument::service_id))),
+            )
+            .select((
+                service::all_columns,
+                service_argument::all_columns.nullable(),
+            ))
+            .load::<(ServiceModel, Option<ServiceArgumentModel>)>(self.conn)?
+        {
+            let key = (
+                service.circuit_id.to_string(),
+                service.service_id.to_string(),
+            );
+            if let Some(arg_model) = opt_arg {
+                arguments_map
+                    .entry(key.clone())
+                    .or_insert_with(Vec::new)
+                    .push(arg_model);
+            }
+            services.entry(key).or_insert(service);
+        }
+
+        let mut service_vec: Vec<ServiceModel> = services.into_iter().map(|(_, svc)| svc).collect();
+        // Services are ordered by circuit, then by their position in the circuit's roster
+        service_vec.sort_by(|a, b| {
+            a.circuit_id
+                .cmp(&b.circuit_id)
+                .then(a.position.cmp(&b.position))
+        });
+
+        service_vec
+            .into_iter()
+            .map(|service| {
+                let mut builder = ServiceBuilder::new()
+                    .with_service_id(&service.service_id)
+                    .with_service_type(&service.service_type)
+                    .with_node_id(&service.node_id);
+
+                if let Some(args) = arguments_map.get_mut(&(
+                    service.circuit_id.to_string(),
+                    service.service_id.to_string(),
+                )) {
+                    args.sort_by_key(|arg| arg.position);
+                    builder = builder.with_arguments(
+                        &args
+                            .iter()
+                            .map(|args| (args.key.to_string(), args.value.to_string()))
+                            .collect::<Vec<(String, String)>>(),
+                    );
+                }
+
+                Ok((
+                    service.circuit_id,
+                    builder
+                        .build()
+                        .map_err(AdminServiceStoreError::InvalidStateError)?,
+                ))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the "list services with endpoints" operation for the `DieselAdminServiceStore`.
+
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use crate::admin::store::{
+    diesel::{
+        models::NodeEndpointModel,
+        schema::{node_endpoint, service},
+    },
+    error::AdminServiceStoreError,
+    ServiceEndpointsIter,
+};
+
+use super::list_services::AdminServiceStoreListServicesOperation as _;
+use super::AdminServiceStoreOperations;
+
+pub(in crate::admin::store::diesel) trait AdminServiceStoreListServicesWithEndpointsOperation {
+    fn list_services_with_endpoints(
+        &self,
+        circuit_id: &str,
+    ) -> Result<ServiceEndpointsIter, AdminServiceStoreError>;
+}
+
+impl<'a, C> AdminServiceStoreListServicesWithEndpointsOperation
+    for AdminServiceStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+    i64: diesel::deserialize::FromSql<diesel::sql_types::BigInt, C::Backend>,
+    i32: diesel::deserialize::FromSql<diesel::sql_types::Integer, C::Backend>,
+{
+    fn list_services_with_endpoints(
+        &self,
+        circuit_id: &str,
+    ) -> Result<ServiceEndpointsIter, AdminServiceStoreError> {
+        self.conn.transaction::<_, AdminServiceStoreError, _>(|| {
+            let services = self.list_services(circuit_id)?;
+
+            // Collect the endpoints of the nodes running the circuit's services and put them in
+            // a HashMap to associate the list of endpoints to the `node_id`
+            let mut node_map: HashMap<String, Vec<String>> = HashMap::new();
+            for node_endpoint in node_endpoint::table
+                // Joins a `service` entry to a `node_endpoint` entry, based on `node_id`. A node
+                // running several of the circuit's services is returned once for each service
+                .inner_join(service::table.on(service::node_id.eq(node_endpoint::node_id)))
+                .filter(service::circuit_id.eq(&circuit_id))
+                .select(node_endpoint::all_columns)
+                .load::<NodeEndpointModel>(self.conn)?
+            {
+                if let Some(endpoint_list) = node_map.get_mut(&node_endpoint.node_id) {
+                    endpoint_list.push(node_endpoint.endpoint);
+                    // Ensure only unique endpoints are added to the node's endpoint list
+                    endpoint_list.sort();
+                    endpoint_list.dedup();
+                } else {
+                    node_map.insert(node_endpoint.node_id, vec![node_endpoint.endpoint]);
+                }
+            }
+
+            let services_with_endpoints = services
+                .map(|service| {
+                    let endpoints = node_map.get(service.node_id()).cloned().unwrap_or_default();
+                    (service, endpoints)
+                })
+                .collect::<Vec<_>>();
+
+            Ok(Box::new(services_with_endpoints.into_iter()) as ServiceEndpointsIter)
+        })
+    }
+}
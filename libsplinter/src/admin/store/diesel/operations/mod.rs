@@ -25,6 +25,14 @@ pub(super) mod count_circuits;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod count_proposals;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub(super) mod count_services;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub(super) mod fetch_circuit_version;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub(super) mod find_duplicate_endpoints;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub(super) mod find_orphaned_service_arguments;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod get_circuit;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod get_node;
@@ -35,6 +43,8 @@ pub(super) mod get_service;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod list_circuits;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub(super) mod list_circuits_modified_since;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod list_events;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod list_events_by_management_type_since;
@@ -45,14 +55,22 @@ pub(super) mod list_nodes;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod list_proposals;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub(super) mod list_service_types;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod list_services;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub(super) mod list_services_for_node;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub(super) mod list_services_with_endpoints;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod remove_circuit;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod remove_proposal;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod update_circuit;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub(super) mod update_node_endpoints;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod update_proposal;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 pub(super) mod upgrade;
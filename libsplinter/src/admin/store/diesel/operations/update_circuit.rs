@@ -62,6 +62,7 @@ impl<'a> AdminServiceStoreUpdateCircuitOperation
                     circuit::routes.eq(circuit_model.routes),
                     circuit::circuit_management_type.eq(circuit_model.circuit_management_type),
                     circuit::circuit_status.eq(circuit_model.circuit_status),
+                    circuit::last_updated.eq(circuit_model.last_updated),
                 ))
                 .execute(self.conn)?;
             // Delete existing data associated with the `Circuit`
@@ -121,6 +122,7 @@ impl<'a> AdminServiceStoreUpdateCircuitOperation
                     circuit::routes.eq(circuit_model.routes),
                     circuit::circuit_management_type.eq(circuit_model.circuit_management_type),
                     circuit::circuit_status.eq(circuit_model.circuit_status),
+                    circuit::last_updated.eq(circuit_model.last_updated),
                 ))
                 .execute(self.conn)?;
             // Delete existing data associated with the `Circuit`
@@ -0,0 +1,140 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides the "update node endpoints" operation for the `DieselAdminServiceStore`.
+
+use diesel::{
+    dsl::{delete, insert_into},
+    prelude::*,
+    sql_types::Text,
+};
+
+use super::AdminServiceStoreOperations;
+use crate::admin::store::{
+    circuit_node::validate_node_endpoints,
+    diesel::{
+        models::NodeEndpointModel,
+        schema::{circuit_member, node_endpoint},
+    },
+    error::AdminServiceStoreError,
+};
+use crate::error::InvalidStateError;
+
+pub(in crate::admin::store::diesel) trait AdminServiceStoreUpdateNodeEndpointsOperation {
+    fn update_node_endpoints(
+        &self,
+        node_id: &str,
+        endpoints: &[String],
+    ) -> Result<(), AdminServiceStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> AdminServiceStoreUpdateNodeEndpointsOperation
+    for AdminServiceStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn update_node_endpoints(
+        &self,
+        node_id: &str,
+        endpoints: &[String],
+    ) -> Result<(), AdminServiceStoreError> {
+        validate_node_endpoints(endpoints).map_err(AdminServiceStoreError::InvalidStateError)?;
+
+        self.conn.transaction::<(), _, _>(|| {
+            check_node_endpoints(self.conn, node_id, endpoints)?;
+
+            delete(node_endpoint::table.filter(node_endpoint::node_id.eq(node_id)))
+                .execute(self.conn)?;
+            insert_into(node_endpoint::table)
+                .values(node_endpoint_models(node_id, endpoints))
+                .execute(self.conn)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> AdminServiceStoreUpdateNodeEndpointsOperation
+    for AdminServiceStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn update_node_endpoints(
+        &self,
+        node_id: &str,
+        endpoints: &[String],
+    ) -> Result<(), AdminServiceStoreError> {
+        validate_node_endpoints(endpoints).map_err(AdminServiceStoreError::InvalidStateError)?;
+
+        self.conn.transaction::<(), _, _>(|| {
+            check_node_endpoints(self.conn, node_id, endpoints)?;
+
+            delete(node_endpoint::table.filter(node_endpoint::node_id.eq(node_id)))
+                .execute(self.conn)?;
+            insert_into(node_endpoint::table)
+                .values(node_endpoint_models(node_id, endpoints))
+                .execute(self.conn)?;
+
+            Ok(())
+        })
+    }
+}
+
+/// Verifies that the node is a member of a circuit and that none of the endpoints are registered
+/// for another node.
+fn check_node_endpoints<C>(
+    conn: &C,
+    node_id: &str,
+    endpoints: &[String],
+) -> Result<(), AdminServiceStoreError>
+where
+    C: diesel::Connection,
+    i64: diesel::deserialize::FromSql<diesel::sql_types::BigInt, C::Backend>,
+    NodeEndpointModel: diesel::Queryable<(Text, Text), C::Backend>,
+{
+    if let Some(0) = circuit_member::table
+        .filter(circuit_member::node_id.eq(node_id))
+        .count()
+        .first::<i64>(conn)
+        .optional()?
+    {
+        return Err(AdminServiceStoreError::InvalidStateError(
+            InvalidStateError::with_message(format!("Node {} does not exist", node_id)),
+        ));
+    }
+
+    if let Some(taken) = node_endpoint::table
+        .filter(node_endpoint::endpoint.eq_any(endpoints))
+        .filter(node_endpoint::node_id.ne(node_id))
+        .first::<NodeEndpointModel>(conn)
+        .optional()?
+    {
+        return Err(AdminServiceStoreError::InvalidStateError(
+            InvalidStateError::with_message(format!(
+                "Endpoint {} is already registered for node {}",
+                taken.endpoint, taken.node_id
+            )),
+        ));
+    }
+
+    Ok(())
+}
+
+fn node_endpoint_models(node_id: &str, endpoints: &[String]) -> Vec<NodeEndpointModel> {
+    endpoints
+        .iter()
+        .map(|endpoint| NodeEndpointModel {
+            node_id: node_id.into(),
+            endpoint: endpoint.into(),
+        })
+        .collect()
+}
@@ -117,6 +117,7 @@ table! {
         display_name -> Nullable<Text>,
         circuit_version -> Integer,
         circuit_status -> SmallInt,
+        last_updated -> BigInt,
     }
 }
 
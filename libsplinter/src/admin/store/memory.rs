@@ -0,0 +1,1009 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A memory-backed implementation of the [AdminServiceStore]
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::admin::service::messages;
+use crate::error::{
+    ConstraintViolationError, ConstraintViolationType, InternalError, InvalidStateError,
+};
+
+use super::circuit_node::validate_node_endpoints;
+use super::{
+    AdminServiceEvent, AdminServiceStore, AdminServiceStoreError, Circuit, CircuitBuilder,
+    CircuitNode, CircuitNodeBuilder, CircuitPredicate, CircuitProposal, CircuitStatus, EventIter,
+    Service, ServiceId,
+};
+
+/// An `AdminServiceStore` that keeps its circuits, proposals, nodes and events in memory.
+///
+/// Nothing is persisted, so this store is intended for tests and short-lived nodes. Clones share
+/// the same state.
+#[derive(Default, Clone)]
+pub struct MemoryAdminServiceStore {
+    inner: Arc<Mutex<MemoryState>>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    circuits: BTreeMap<String, StoredCircuit>,
+    proposals: BTreeMap<String, CircuitProposal>,
+    /// The sorted endpoints of each node, which are shared by every circuit the node is a member of
+    node_endpoints: BTreeMap<String, Vec<String>>,
+    events: Vec<AdminServiceEvent>,
+}
+
+struct StoredCircuit {
+    circuit: Circuit,
+    /// Seconds since the Unix epoch at which the circuit was added or last updated
+    last_updated: i64,
+}
+
+impl MemoryAdminServiceStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> Result<MutexGuard<'_, MemoryState>, AdminServiceStoreError> {
+        self.inner.lock().map_err(|_| {
+            AdminServiceStoreError::InternalError(InternalError::with_message(
+                "Cannot access admin service store: mutex lock poisoned".to_string(),
+            ))
+        })
+    }
+}
+
+impl MemoryState {
+    fn insert_circuit(&mut self, circuit: Circuit, nodes: Vec<CircuitNode>) {
+        // As with the other stores, the endpoints of a node that is already known are kept
+        for node in nodes {
+            if !self.node_endpoints.contains_key(node.node_id()) {
+                self.node_endpoints.insert(
+                    node.node_id().to_string(),
+                    sorted_endpoints(node.endpoints()),
+                );
+            }
+        }
+
+        self.circuits.insert(
+            circuit.circuit_id().to_string(),
+            StoredCircuit {
+                circuit,
+                last_updated: now(),
+            },
+        );
+    }
+
+    /// Returns the roster of the circuit, which is empty if the circuit does not exist
+    fn roster(&self, circuit_id: &str) -> &[Service] {
+        self.circuits
+            .get(circuit_id)
+            .map(|stored| stored.circuit.roster())
+            .unwrap_or_default()
+    }
+
+    fn is_member(&self, node_id: &str) -> bool {
+        self.circuits.values().any(|stored| {
+            stored
+                .circuit
+                .members()
+                .iter()
+                .any(|member| member.node_id() == node_id)
+        })
+    }
+
+    /// Returns the circuits that match the predicates, ordered by circuit ID in descending order
+    fn matching_circuits(&self, predicates: &[CircuitPredicate]) -> Vec<&Circuit> {
+        let filter = CircuitFilter::new(predicates);
+
+        self.circuits
+            .values()
+            .rev()
+            .map(|stored| &stored.circuit)
+            .filter(|circuit| filter.matches_circuit(circuit))
+            .collect()
+    }
+
+    /// Returns the circuit with the stored endpoints of its members
+    fn load_circuit(&self, circuit: &Circuit) -> Result<Circuit, AdminServiceStoreError> {
+        let members = circuit
+            .members()
+            .iter()
+            .map(|member| self.load_member(member))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut builder = CircuitBuilder::new()
+            .with_circuit_id(circuit.circuit_id())
+            .with_roster(circuit.roster())
+            .with_members(&members)
+            .with_authorization_type(circuit.authorization_type())
+            .with_persistence(circuit.persistence())
+            .with_durability(circuit.durability())
+            .with_routes(circuit.routes())
+            .with_circuit_management_type(circuit.circuit_management_type())
+            .with_circuit_version(circuit.circuit_version())
+            .with_circuit_status(circuit.circuit_status());
+        if let Some(display_name) = circuit.display_name() {
+            builder = builder.with_display_name(display_name);
+        }
+
+        builder
+            .build()
+            .map_err(AdminServiceStoreError::InvalidStateError)
+    }
+
+    /// Returns the circuit member with the stored endpoints of the node
+    fn load_member(&self, member: &CircuitNode) -> Result<CircuitNode, AdminServiceStoreError> {
+        let endpoints = self
+            .node_endpoints
+            .get(member.node_id())
+            .cloned()
+            .unwrap_or_default();
+
+        let mut builder = CircuitNodeBuilder::new()
+            .with_node_id(member.node_id())
+            .with_endpoints(&endpoints);
+        if let Some(public_key) = member.public_key() {
+            builder = builder.with_public_key(public_key);
+        }
+
+        builder
+            .build()
+            .map_err(AdminServiceStoreError::InvalidStateError)
+    }
+
+    /// Returns the circuit members that have endpoints.
+    ///
+    /// The nodes are ordered by their earliest position in the members of a circuit, then by
+    /// node ID.
+    fn member_nodes(&self) -> Result<Vec<CircuitNode>, AdminServiceStoreError> {
+        let mut members: BTreeMap<&str, (usize, &CircuitNode)> = BTreeMap::new();
+        for stored in self.circuits.values() {
+            for (position, member) in stored.circuit.members().iter().enumerate() {
+                let node_id = member.node_id();
+                if !self.node_endpoints.contains_key(node_id) {
+                    continue;
+                }
+
+                match members.get(node_id) {
+                    Some((first, _)) if *first <= position => (),
+                    _ => {
+                        members.insert(node_id, (position, member));
+                    }
+                }
+            }
+        }
+
+        let mut members = members.into_iter().collect::<Vec<_>>();
+        members.sort_by_key(|(node_id, (position, _))| (*position, *node_id));
+
+        members
+            .into_iter()
+            .map(|(_, (_, member))| self.load_member(member))
+            .collect()
+    }
+}
+
+/// The circuit predicates grouped by kind. As in the database stores, a circuit matches a kind
+/// of predicate if it matches any of the predicates of that kind.
+struct CircuitFilter<'a> {
+    management_types: Vec<&'a str>,
+    members: Vec<&'a str>,
+    service_ids: Vec<&'a str>,
+    statuses: Vec<&'a CircuitStatus>,
+    any_status: bool,
+}
+
+impl<'a> CircuitFilter<'a> {
+    fn new(predicates: &'a [CircuitPredicate]) -> Self {
+        let mut filter = CircuitFilter {
+            management_types: vec![],
+            members: vec![],
+            service_ids: vec![],
+            statuses: vec![],
+            any_status: false,
+        };
+
+        for predicate in predicates {
+            match predicate {
+                CircuitPredicate::ManagementTypeEq(management_type) => {
+                    filter.management_types.push(management_type)
+                }
+                CircuitPredicate::MembersInclude(members) => {
+                    filter.members.extend(members.iter().map(String::as_str))
+                }
+                CircuitPredicate::ServiceIdEq(service_id) => filter.service_ids.push(service_id),
+                CircuitPredicate::CircuitStatus(status) => filter.statuses.push(status),
+                CircuitPredicate::AnyStatus => filter.any_status = true,
+            }
+        }
+
+        filter
+    }
+
+    /// Only active circuits match unless a status predicate is provided
+    fn matches_circuit(&self, circuit: &Circuit) -> bool {
+        let status_matches = if self.any_status {
+            true
+        } else if self.statuses.is_empty() {
+            circuit.circuit_status() == &CircuitStatus::Active
+        } else {
+            self.statuses.contains(&circuit.circuit_status())
+        };
+
+        status_matches
+            && (self.management_types.is_empty()
+                || self
+                    .management_types
+                    .contains(&circuit.circuit_management_type()))
+            && (self.members.is_empty()
+                || circuit
+                    .members()
+                    .iter()
+                    .any(|member| self.members.contains(&member.node_id())))
+            && (self.service_ids.is_empty()
+                || circuit
+                    .roster()
+                    .iter()
+                    .any(|service| self.service_ids.contains(&service.service_id())))
+    }
+
+    /// The status predicates do not apply to proposals
+    fn matches_proposal(&self, proposal: &CircuitProposal) -> bool {
+        let circuit = proposal.circuit();
+
+        (self.management_types.is_empty()
+            || self
+                .management_types
+                .contains(&circuit.circuit_management_type()))
+            && (self.members.is_empty()
+                || circuit
+                    .members()
+                    .iter()
+                    .any(|member| self.members.contains(&member.node_id())))
+            && (self.service_ids.is_empty()
+                || circuit
+                    .roster()
+                    .iter()
+                    .any(|service| self.service_ids.contains(&service.service_id())))
+    }
+}
+
+impl AdminServiceStore for MemoryAdminServiceStore {
+    fn add_proposal(&self, proposal: CircuitProposal) -> Result<(), AdminServiceStoreError> {
+        let mut state = self.state()?;
+        if state.proposals.contains_key(proposal.circuit_id()) {
+            return Err(AdminServiceStoreError::ConstraintViolationError(
+                ConstraintViolationError::with_violation_type(ConstraintViolationType::Unique),
+            ));
+        }
+
+        state
+            .proposals
+            .insert(proposal.circuit_id().to_string(), proposal);
+        Ok(())
+    }
+
+    fn update_proposal(&self, proposal: CircuitProposal) -> Result<(), AdminServiceStoreError> {
+        let mut state = self.state()?;
+        match state.proposals.get_mut(proposal.circuit_id()) {
+            Some(existing) => {
+                *existing = proposal;
+                Ok(())
+            }
+            None => Err(AdminServiceStoreError::InvalidStateError(
+                InvalidStateError::with_message(format!(
+                    "A proposal with ID {} does not exist",
+                    proposal.circuit_id()
+                )),
+            )),
+        }
+    }
+
+    fn remove_proposal(&self, proposal_id: &str) -> Result<(), AdminServiceStoreError> {
+        match self.state()?.proposals.remove(proposal_id) {
+            Some(_) => Ok(()),
+            None => Err(AdminServiceStoreError::InvalidStateError(
+                InvalidStateError::with_message(format!(
+                    "A proposal with ID {} does not exist",
+                    proposal_id
+                )),
+            )),
+        }
+    }
+
+    fn get_proposal(
+        &self,
+        proposal_id: &str,
+    ) -> Result<Option<CircuitProposal>, AdminServiceStoreError> {
+        Ok(self.state()?.proposals.get(proposal_id).cloned())
+    }
+
+    fn list_proposals(
+        &self,
+        predicates: &[CircuitPredicate],
+    ) -> Result<Box<dyn ExactSizeIterator<Item = CircuitProposal>>, AdminServiceStoreError> {
+        let filter = CircuitFilter::new(predicates);
+        let proposals = self
+            .state()?
+            .proposals
+            .values()
+            .rev()
+            .filter(|proposal| filter.matches_proposal(proposal))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Ok(Box::new(proposals.into_iter()))
+    }
+
+    fn count_proposals(
+        &self,
+        predicates: &[CircuitPredicate],
+    ) -> Result<u32, AdminServiceStoreError> {
+        let filter = CircuitFilter::new(predicates);
+        let count = self
+            .state()?
+            .proposals
+            .values()
+            .filter(|proposal| filter.matches_proposal(proposal))
+            .count();
+
+        u32::try_from(count).map_err(|_| {
+            AdminServiceStoreError::InternalError(InternalError::with_message(
+                "The number of proposals is larger than the max u32".to_string(),
+            ))
+        })
+    }
+
+    fn add_circuit(
+        &self,
+        circuit: Circuit,
+        nodes: Vec<CircuitNode>,
+    ) -> Result<(), AdminServiceStoreError> {
+        let mut state = self.state()?;
+        if state.circuits.contains_key(circuit.circuit_id()) {
+            return Err(AdminServiceStoreError::ConstraintViolationError(
+                ConstraintViolationError::with_violation_type(ConstraintViolationType::Unique),
+            ));
+        }
+
+        state.insert_circuit(circuit, nodes);
+        Ok(())
+    }
+
+    fn update_circuit(&self, circuit: Circuit) -> Result<(), AdminServiceStoreError> {
+        let mut state = self.state()?;
+        match state.circuits.get_mut(circuit.circuit_id()) {
+            Some(stored) => {
+                stored.circuit = circuit;
+                stored.last_updated = now();
+                Ok(())
+            }
+            None => Err(AdminServiceStoreError::InvalidStateError(
+                InvalidStateError::with_message(format!(
+                    "A circuit with ID {} does not exist",
+                    circuit.circuit_id()
+                )),
+            )),
+        }
+    }
+
+    fn remove_circuit(&self, circuit_id: &str) -> Result<(), AdminServiceStoreError> {
+        let mut state = self.state()?;
+        let stored = state.circuits.remove(circuit_id).ok_or_else(|| {
+            AdminServiceStoreError::InvalidStateError(InvalidStateError::with_message(format!(
+                "A circuit with ID {} does not exist",
+                circuit_id
+            )))
+        })?;
+
+        // The endpoints of a node are only kept while it is a member of a circuit
+        for member in stored.circuit.members() {
+            if !state.is_member(member.node_id()) {
+                state.node_endpoints.remove(member.node_id());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_circuit(&self, circuit_id: &str) -> Result<Option<Circuit>, AdminServiceStoreError> {
+        let state = self.state()?;
+        state
+            .circuits
+            .get(circuit_id)
+            .map(|stored| state.load_circuit(&stored.circuit))
+            .transpose()
+    }
+
+    fn list_circuits(
+        &self,
+        predicates: &[CircuitPredicate],
+    ) -> Result<Box<dyn ExactSizeIterator<Item = Circuit>>, AdminServiceStoreError> {
+        let state = self.state()?;
+        let circuits = state
+            .matching_circuits(predicates)
+            .into_iter()
+            .map(|circuit| state.load_circuit(circuit))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Box::new(circuits.into_iter()))
+    }
+
+    fn list_circuits_modified_since(
+        &self,
+        timestamp: i64,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = Circuit>>, AdminServiceStoreError> {
+        let state = self.state()?;
+        let mut circuits = state
+            .circuits
+            .values()
+            .filter(|stored| stored.last_updated >= timestamp)
+            .collect::<Vec<_>>();
+        // The circuits are already ordered by ID, which breaks ties as the stable sort keeps them
+        circuits.sort_by_key(|stored| stored.last_updated);
+
+        let circuits = circuits
+            .into_iter()
+            .map(|stored| state.load_circuit(&stored.circuit))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Box::new(circuits.into_iter()))
+    }
+
+    fn count_circuits(
+        &self,
+        predicates: &[CircuitPredicate],
+    ) -> Result<u32, AdminServiceStoreError> {
+        let count = self.state()?.matching_circuits(predicates).len();
+
+        u32::try_from(count).map_err(|_| {
+            AdminServiceStoreError::InternalError(InternalError::with_message(
+                "The number of circuits is larger than the max u32".to_string(),
+            ))
+        })
+    }
+
+    fn upgrade_proposal_to_circuit(&self, circuit_id: &str) -> Result<(), AdminServiceStoreError> {
+        let mut state = self.state()?;
+        let proposal = state.proposals.remove(circuit_id).ok_or_else(|| {
+            AdminServiceStoreError::InvalidStateError(InvalidStateError::with_message(format!(
+                "A circuit proposal with ID {} does not exist",
+                circuit_id
+            )))
+        })?;
+
+        let nodes = proposal
+            .circuit()
+            .members()
+            .iter()
+            .cloned()
+            .map(CircuitNode::from)
+            .collect();
+        let circuit = Circuit::from(proposal.circuit().clone());
+        state.insert_circuit(circuit, nodes);
+
+        Ok(())
+    }
+
+    fn get_node(&self, node_id: &str) -> Result<Option<CircuitNode>, AdminServiceStoreError> {
+        let state = self.state()?;
+        if !state.is_member(node_id) {
+            return Ok(None);
+        }
+
+        let endpoints = state
+            .node_endpoints
+            .get(node_id)
+            .cloned()
+            .unwrap_or_default();
+
+        CircuitNodeBuilder::new()
+            .with_node_id(node_id)
+            .with_endpoints(&endpoints)
+            .build()
+            .map(Some)
+            .map_err(AdminServiceStoreError::InvalidStateError)
+    }
+
+    fn list_nodes(
+        &self,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = CircuitNode>>, AdminServiceStoreError> {
+        let nodes = self.state()?.member_nodes()?;
+
+        Ok(Box::new(nodes.into_iter()))
+    }
+
+    fn update_node_endpoints(
+        &self,
+        node_id: &str,
+        endpoints: &[String],
+    ) -> Result<(), AdminServiceStoreError> {
+        validate_node_endpoints(endpoints).map_err(AdminServiceStoreError::InvalidStateError)?;
+
+        let mut state = self.state()?;
+        if !state.is_member(node_id) {
+            return Err(AdminServiceStoreError::InvalidStateError(
+                InvalidStateError::with_message(format!("Node {} does not exist", node_id)),
+            ));
+        }
+
+        if let Some((other, taken)) = state
+            .node_endpoints
+            .iter()
+            .filter(|(other, _)| other.as_str() != node_id)
+            .find_map(|(other, other_endpoints)| {
+                other_endpoints
+                    .iter()
+                    .find(|endpoint| endpoints.contains(endpoint))
+                    .map(|taken| (other, taken))
+            })
+        {
+            return Err(AdminServiceStoreError::InvalidStateError(
+                InvalidStateError::with_message(format!(
+                    "Endpoint {} is already registered for node {}",
+                    taken, other
+                )),
+            ));
+        }
+
+        state
+            .node_endpoints
+            .insert(node_id.to_string(), sorted_endpoints(endpoints));
+
+        Ok(())
+    }
+
+    fn get_service(
+        &self,
+        service_id: &ServiceId,
+    ) -> Result<Option<Service>, AdminServiceStoreError> {
+        Ok(self
+            .state()?
+            .roster(service_id.circuit())
+            .iter()
+            .find(|service| service.service_id() == service_id.service_id())
+            .cloned())
+    }
+
+    fn list_services(
+        &self,
+        circuit_id: &str,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = Service>>, AdminServiceStoreError> {
+        let services = self.state()?.roster(circuit_id).to_vec();
+
+        Ok(Box::new(services.into_iter()))
+    }
+
+    fn add_event(
+        &self,
+        event: messages::AdminServiceEvent,
+    ) -> Result<AdminServiceEvent, AdminServiceStoreError> {
+        let mut state = self.state()?;
+        // Event IDs start at 1, as they do in the database stores
+        let event_id = i64::try_from(state.events.len() + 1).map_err(|_| {
+            AdminServiceStoreError::InternalError(InternalError::with_message(
+                "The number of events is larger than the max i64".to_string(),
+            ))
+        })?;
+        let event = AdminServiceEvent::try_from((event_id, &event))
+            .map_err(AdminServiceStoreError::InvalidStateError)?;
+
+        state.events.push(event.clone());
+        Ok(event)
+    }
+
+    fn list_events_since(&self, start: i64) -> Result<EventIter, AdminServiceStoreError> {
+        let events = self
+            .state()?
+            .events
+            .iter()
+            .filter(|event| *event.event_id() > start)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Ok(Box::new(events.into_iter()))
+    }
+
+    fn list_events_by_management_type_since(
+        &self,
+        management_type: String,
+        start: i64,
+    ) -> Result<EventIter, AdminServiceStoreError> {
+        let events = self
+            .state()?
+            .events
+            .iter()
+            .filter(|event| {
+                *event.event_id() > start
+                    && event.proposal().circuit().circuit_management_type() == management_type
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Ok(Box::new(events.into_iter()))
+    }
+
+    fn clone_boxed(&self) -> Box<dyn AdminServiceStore> {
+        Box::new(self.clone())
+    }
+}
+
+/// Returns the endpoints sorted and without duplicates, as the database stores return them
+fn sorted_endpoints(endpoints: &[String]) -> Vec<String> {
+    let mut endpoints = endpoints.to_vec();
+    endpoints.sort();
+    endpoints.dedup();
+    endpoints
+}
+
+/// Returns the number of seconds since the Unix epoch
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::admin::store::{CircuitBuilder, ServiceBuilder};
+
+    fn create_nodes() -> Vec<CircuitNode> {
+        vec![
+            CircuitNodeBuilder::default()
+                .with_node_id("bubba-node-000")
+                .with_endpoints(&["tcps://splinterd-node-bubba:8044".into()])
+                .build()
+                .expect("Unable to build node"),
+            CircuitNodeBuilder::default()
+                .with_node_id("acme-node-000")
+                .with_endpoints(&["tcps://splinterd-node-acme:8044".into()])
+                .build()
+                .expect("Unable to build node"),
+        ]
+    }
+
+    fn create_circuit(circuit_id: &str, status: CircuitStatus) -> Circuit {
+        CircuitBuilder::default()
+            .with_circuit_id(circuit_id)
+            .with_roster(&[ServiceBuilder::default()
+                .with_service_id("a000")
+                .with_service_type("scabbard")
+                .with_node_id("acme-node-000")
+                .build()
+                .expect("Unable to build service")])
+            .with_members(&create_nodes())
+            .with_circuit_management_type("gameroom")
+            .with_circuit_status(&status)
+            .build()
+            .expect("Unable to build circuit")
+    }
+
+    fn create_gumbo_circuit(circuit_id: &str) -> (Circuit, Vec<CircuitNode>) {
+        let nodes = vec![
+            CircuitNodeBuilder::default()
+                .with_node_id("gumbo-node-000")
+                .with_endpoints(&["tcps://splinterd-node-gumbo:8044".into()])
+                .build()
+                .expect("Unable to build node"),
+            CircuitNodeBuilder::default()
+                .with_node_id("acme-node-000")
+                .with_endpoints(&["tcps://splinterd-node-acme:8044".into()])
+                .build()
+                .expect("Unable to build node"),
+        ];
+        let circuit = CircuitBuilder::default()
+            .with_circuit_id(circuit_id)
+            .with_roster(&[ServiceBuilder::default()
+                .with_service_id("g000")
+                .with_service_type("scabbard")
+                .with_node_id("gumbo-node-000")
+                .build()
+                .expect("Unable to build service")])
+            .with_members(&nodes)
+            .with_circuit_management_type("other")
+            .build()
+            .expect("Unable to build circuit");
+
+        (circuit, nodes)
+    }
+
+    fn circuit_ids(
+        store: &MemoryAdminServiceStore,
+        predicates: &[CircuitPredicate],
+    ) -> Vec<String> {
+        store
+            .list_circuits(predicates)
+            .expect("Unable to list circuits")
+            .map(|circuit| circuit.circuit_id().to_string())
+            .collect()
+    }
+
+    /// Verify that circuits and their nodes can be added, listed and removed, and that clones of
+    /// the store share its state.
+    ///
+    /// 1. Add an active and a disbanded circuit
+    /// 2. Verify only the active circuit is listed by default, and both with `AnyStatus`
+    /// 3. Verify a circuit with the same ID cannot be added again
+    /// 4. Verify the circuit members were added as nodes, in member order, from a clone
+    /// 5. Remove the active circuit and verify it is no longer returned
+    #[test]
+    fn test_memory_admin_service_store_circuits() {
+        let store = MemoryAdminServiceStore::new();
+        let clone = store.clone();
+
+        store
+            .add_circuit(
+                create_circuit("WBKLF-AAAAA", CircuitStatus::Active),
+                create_nodes(),
+            )
+            .expect("Unable to add circuit");
+        store
+            .add_circuit(
+                create_circuit("WBKLF-BBBBB", CircuitStatus::Disbanded),
+                create_nodes(),
+            )
+            .expect("Unable to add circuit");
+
+        assert_eq!(
+            store
+                .list_circuits(&[])
+                .expect("Unable to list circuits")
+                .map(|circuit| circuit.circuit_id().to_string())
+                .collect::<Vec<_>>(),
+            vec!["WBKLF-AAAAA".to_string()]
+        );
+        assert_eq!(
+            store
+                .count_circuits(&[CircuitPredicate::AnyStatus])
+                .expect("Unable to count circuits"),
+            2
+        );
+
+        assert!(matches!(
+            store.add_circuit(create_circuit("WBKLF-AAAAA", CircuitStatus::Active), vec![]),
+            Err(AdminServiceStoreError::ConstraintViolationError(_))
+        ));
+
+        assert_eq!(
+            clone
+                .list_nodes()
+                .expect("Unable to list nodes")
+                .map(|node| node.node_id().to_string())
+                .collect::<Vec<_>>(),
+            vec!["bubba-node-000".to_string(), "acme-node-000".to_string()]
+        );
+
+        store
+            .remove_circuit("WBKLF-AAAAA")
+            .expect("Unable to remove circuit");
+        assert!(clone
+            .get_circuit("WBKLF-AAAAA")
+            .expect("Unable to get circuit")
+            .is_none());
+        assert!(store.remove_circuit("WBKLF-AAAAA").is_err());
+    }
+
+    /// Verify that circuits are listed in descending order of circuit ID, and that a circuit
+    /// matches several predicates of the same kind if it matches any of them.
+    ///
+    /// 1. Add an active, a disbanded and an abandoned circuit, and an active circuit with a
+    ///    different management type and members
+    /// 2. Verify the circuits with either of two statuses are listed, in descending order
+    /// 3. Verify the active circuits with either of two members are listed
+    /// 4. Verify the active circuits with either of two management types are counted
+    /// 5. Verify predicates of different kinds must all match
+    #[test]
+    fn test_memory_admin_service_store_predicates() {
+        let store = MemoryAdminServiceStore::new();
+        for (circuit_id, status) in [
+            ("WBKLF-AAAAA", CircuitStatus::Active),
+            ("WBKLF-BBBBB", CircuitStatus::Disbanded),
+            ("WBKLF-DDDDD", CircuitStatus::Abandoned),
+        ] {
+            store
+                .add_circuit(create_circuit(circuit_id, status), create_nodes())
+                .expect("Unable to add circuit");
+        }
+        let (circuit, nodes) = create_gumbo_circuit("WBKLF-CCCCC");
+        store
+            .add_circuit(circuit, nodes)
+            .expect("Unable to add circuit");
+
+        assert_eq!(
+            circuit_ids(
+                &store,
+                &[
+                    CircuitPredicate::CircuitStatus(CircuitStatus::Active),
+                    CircuitPredicate::CircuitStatus(CircuitStatus::Disbanded),
+                ]
+            ),
+            vec!["WBKLF-CCCCC", "WBKLF-BBBBB", "WBKLF-AAAAA"]
+        );
+
+        assert_eq!(
+            circuit_ids(
+                &store,
+                &[
+                    CircuitPredicate::MembersInclude(vec!["bubba-node-000".to_string()]),
+                    CircuitPredicate::MembersInclude(vec!["gumbo-node-000".to_string()]),
+                ]
+            ),
+            vec!["WBKLF-CCCCC", "WBKLF-AAAAA"]
+        );
+
+        assert_eq!(
+            store
+                .count_circuits(&[
+                    CircuitPredicate::ManagementTypeEq("gameroom".to_string()),
+                    CircuitPredicate::ManagementTypeEq("other".to_string()),
+                ])
+                .expect("Unable to count circuits"),
+            2
+        );
+
+        assert_eq!(
+            circuit_ids(
+                &store,
+                &[
+                    CircuitPredicate::ManagementTypeEq("gameroom".to_string()),
+                    CircuitPredicate::MembersInclude(vec!["gumbo-node-000".to_string()]),
+                ]
+            ),
+            Vec::<String>::new()
+        );
+    }
+
+    /// Verify that removing a circuit removes the nodes that are no longer a member of any
+    /// circuit.
+    ///
+    /// 1. Add two circuits that share `acme-node-000` as a member
+    /// 2. Remove the first circuit
+    /// 3. Verify `bubba-node-000`, which was only a member of the removed circuit, is removed
+    /// 4. Verify the members of the remaining circuit are listed, in member order
+    #[test]
+    fn test_memory_admin_service_store_remove_circuit_nodes() {
+        let store = MemoryAdminServiceStore::new();
+        store
+            .add_circuit(
+                create_circuit("WBKLF-BBBBB", CircuitStatus::Active),
+                create_nodes(),
+            )
+            .expect("Unable to add circuit");
+        let (circuit, nodes) = create_gumbo_circuit("WBKLF-CCCCC");
+        store
+            .add_circuit(circuit, nodes)
+            .expect("Unable to add circuit");
+
+        store
+            .remove_circuit("WBKLF-BBBBB")
+            .expect("Unable to remove circuit");
+
+        assert!(store
+            .get_node("bubba-node-000")
+            .expect("Unable to get node")
+            .is_none());
+        assert_eq!(
+            store
+                .list_nodes()
+                .expect("Unable to list nodes")
+                .map(|node| node.node_id().to_string())
+                .collect::<Vec<_>>(),
+            vec!["gumbo-node-000".to_string(), "acme-node-000".to_string()]
+        );
+    }
+
+    /// Verify that the endpoints of a node are shared by every circuit it is a member of.
+    ///
+    /// 1. Add two circuits that share `acme-node-000` as a member
+    /// 2. Update the endpoints of `acme-node-000`
+    /// 3. Verify both circuits and the services on the node return the sorted new endpoints
+    /// 4. Verify an endpoint registered for another node is rejected
+    /// 5. Verify the endpoints of a node that is not a member of a circuit cannot be updated
+    #[test]
+    fn test_memory_admin_service_store_node_endpoints() {
+        let store = MemoryAdminServiceStore::new();
+        store
+            .add_circuit(
+                create_circuit("WBKLF-BBBBB", CircuitStatus::Active),
+                create_nodes(),
+            )
+            .expect("Unable to add circuit");
+        let (circuit, nodes) = create_gumbo_circuit("WBKLF-CCCCC");
+        store
+            .add_circuit(circuit, nodes)
+            .expect("Unable to add circuit");
+
+        let endpoints = vec![
+            "tcps://splinterd-node-acme:8045".to_string(),
+            "tcps://splinterd-node-acme:8044".to_string(),
+        ];
+        store
+            .update_node_endpoints("acme-node-000", &endpoints)
+            .expect("Unable to update endpoints");
+
+        let sorted = vec![
+            "tcps://splinterd-node-acme:8044".to_string(),
+            "tcps://splinterd-node-acme:8045".to_string(),
+        ];
+        for circuit_id in ["WBKLF-BBBBB", "WBKLF-CCCCC"] {
+            let circuit = store
+                .get_circuit(circuit_id)
+                .expect("Unable to get circuit")
+                .expect("Got None when expecting circuit");
+            let acme = circuit
+                .members()
+                .iter()
+                .find(|member| member.node_id() == "acme-node-000")
+                .expect("Missing member");
+            assert_eq!(acme.endpoints(), sorted.as_slice());
+        }
+        assert_eq!(
+            store
+                .list_services_with_endpoints("WBKLF-BBBBB")
+                .expect("Unable to list services with endpoints")
+                .map(|(_, endpoints)| endpoints)
+                .collect::<Vec<_>>(),
+            vec![sorted]
+        );
+
+        assert!(store
+            .update_node_endpoints(
+                "acme-node-000",
+                &["tcps://splinterd-node-gumbo:8044".to_string()]
+            )
+            .is_err());
+        assert!(store
+            .update_node_endpoints(
+                "unknown-node-000",
+                &["tcps://splinterd-node-unknown:8044".to_string()]
+            )
+            .is_err());
+    }
+
+    /// Verify that the services of a circuit that does not exist are empty.
+    #[test]
+    fn test_memory_admin_service_store_unknown_circuit_services() {
+        let store = MemoryAdminServiceStore::new();
+
+        assert_eq!(
+            store
+                .list_services("WBKLF-AAAAA")
+                .expect("Unable to list services")
+                .len(),
+            0
+        );
+        assert_eq!(
+            store
+                .list_services_with_endpoints("WBKLF-AAAAA")
+                .expect("Unable to list services with endpoints")
+                .len(),
+            0
+        );
+        assert_eq!(
+            store
+                .count_services("WBKLF-AAAAA")
+                .expect("Unable to count services"),
+            0
+        );
+    }
+}
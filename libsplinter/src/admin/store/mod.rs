@@ -34,6 +34,7 @@ mod circuit_proposal;
 pub mod diesel;
 pub mod error;
 mod event;
+pub mod memory;
 mod proposed_circuit;
 mod proposed_node;
 mod proposed_service;
@@ -41,14 +42,17 @@ mod service;
 pub mod yaml;
 
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 use crate::admin::service::messages;
+use crate::error::{InternalError, InvalidStateError};
 
 pub use self::circuit::{
     AuthorizationType, Circuit, CircuitBuilder, CircuitStatus, DurabilityType, PersistenceType,
     RouteType,
 };
+use self::circuit_node::validate_node_endpoints;
 pub use self::circuit_node::{CircuitNode, CircuitNodeBuilder};
 pub use self::circuit_proposal::{
     CircuitProposal, CircuitProposalBuilder, ProposalType, Vote, VoteRecord, VoteRecordBuilder,
@@ -130,6 +134,11 @@ pub enum CircuitPredicate {
     ManagementTypeEq(String),
     MembersInclude(Vec<String>),
     CircuitStatus(CircuitStatus),
+    /// Matches circuits of every status, so that listing circuits does not default to only the
+    /// active circuits. Any `CircuitStatus` predicates are ignored when this predicate is present.
+    AnyStatus,
+    /// Matches circuits whose roster includes a service with the given service ID
+    ServiceIdEq(String),
 }
 
 impl CircuitPredicate {
@@ -152,6 +161,11 @@ impl CircuitPredicate {
                 true
             }
             CircuitPredicate::CircuitStatus(status) => circuit.circuit_status() == status,
+            CircuitPredicate::AnyStatus => true,
+            CircuitPredicate::ServiceIdEq(service_id) => circuit
+                .roster()
+                .iter()
+                .any(|service| service.service_id() == service_id),
         }
     }
 
@@ -177,10 +191,19 @@ impl CircuitPredicate {
             CircuitPredicate::CircuitStatus(status) => {
                 proposal.circuit().circuit_status() == status
             }
+            CircuitPredicate::AnyStatus => true,
+            CircuitPredicate::ServiceIdEq(service_id) => proposal
+                .circuit()
+                .roster()
+                .iter()
+                .any(|service| service.service_id() == service_id),
         }
     }
 }
 
+/// Return type of the admin store's `list_services_with_endpoints` method.
+pub type ServiceEndpointsIter = Box<dyn ExactSizeIterator<Item = (Service, Vec<String>)>>;
+
 /// Return type of the admin store's `list_events_*` methods.
 pub type EventIter = Box<dyn ExactSizeIterator<Item = AdminServiceEvent> + Send>;
 
@@ -282,14 +305,116 @@ pub trait AdminServiceStore: Send + Sync {
     ///  * `circuit_id` - The unique ID of the circuit to be returned
     fn get_circuit(&self, circuit_id: &str) -> Result<Option<Circuit>, AdminServiceStoreError>;
 
+    /// Fetches the version of a circuit from the store
+    ///
+    /// Returns `None` if the circuit does not exist. By default, the circuit is fetched and its
+    /// version returned; stores that can do so should fetch the version without loading the rest
+    /// of the circuit.
+    ///
+    /// # Arguments
+    ///
+    ///  * `circuit_id` - The unique ID of the circuit whose version should be returned
+    fn fetch_circuit_version(
+        &self,
+        circuit_id: &str,
+    ) -> Result<Option<i32>, AdminServiceStoreError> {
+        Ok(self
+            .get_circuit(circuit_id)?
+            .map(|circuit| circuit.circuit_version()))
+    }
+
     /// List all circuits from the store
     ///
-    /// `CircuitPredicate`s may be provided for filtering which circuits are returned.
+    /// `CircuitPredicate`s may be provided for filtering which circuits are returned. Unless a
+    /// `CircuitPredicate::CircuitStatus` predicate is provided, only active circuits are returned,
+    /// so `list_circuits(&[])` lists the active circuits. Provide `CircuitPredicate::AnyStatus`, or
+    /// use `list_all_circuits`, to list the circuits of every status.
     fn list_circuits(
         &self,
         predicates: &[CircuitPredicate],
     ) -> Result<Box<dyn ExactSizeIterator<Item = Circuit>>, AdminServiceStoreError>;
 
+    /// List a page of the circuits from the store, returning the circuits in the page and the
+    /// total number of circuits that match the predicates
+    ///
+    /// The circuits are filtered by the `CircuitPredicate`s as they are by `list_circuits`, and
+    /// ordered by circuit ID in descending order. At most `limit` circuits are returned, after
+    /// skipping the first `offset` circuits.
+    ///
+    /// By default, every matching circuit is listed and the page is taken from them; stores that
+    /// can do so should only load the circuits in the page.
+    fn list_circuits_paged(
+        &self,
+        predicates: &[CircuitPredicate],
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Box<dyn ExactSizeIterator<Item = Circuit>>, usize), AdminServiceStoreError> {
+        let mut circuits = self.list_circuits(predicates)?.collect::<Vec<_>>();
+        circuits.sort_by(|a, b| b.circuit_id().cmp(a.circuit_id()));
+        let total = circuits.len();
+
+        Ok((
+            Box::new(
+                circuits
+                    .into_iter()
+                    .skip(offset)
+                    .take(limit)
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+            total,
+        ))
+    }
+
+    /// List the circuits of every status from the store, including disbanded and abandoned
+    /// circuits, ordered by circuit ID in descending order
+    fn list_all_circuits(
+        &self,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = Circuit>>, AdminServiceStoreError> {
+        let mut circuits = vec![];
+        // Each status is listed separately, as predicates of the same type are not combined the
+        // same way by every store
+        for status in &[
+            CircuitStatus::Active,
+            CircuitStatus::Disbanded,
+            CircuitStatus::Abandoned,
+        ] {
+            circuits
+                .extend(self.list_circuits(&[CircuitPredicate::CircuitStatus(status.clone())])?);
+        }
+        circuits.sort_by(|a, b| b.circuit_id().cmp(a.circuit_id()));
+
+        Ok(Box::new(circuits.into_iter()))
+    }
+
+    /// List the circuits that have been added or updated since the provided timestamp, ordered
+    /// from the least to the most recently updated
+    ///
+    /// Update times are recorded in whole seconds, so circuits updated at exactly `timestamp` are
+    /// included; otherwise a circuit updated later in the same second as the last poll would be
+    /// missed. A caller that passes back the latest update time it has seen will receive the
+    /// circuits updated at that time again, and must ignore those it has already processed.
+    /// Circuits of every status are returned, so that changes such as a circuit being disbanded
+    /// are also reported.
+    ///
+    /// Stores that do not record when circuits are updated return an `InternalError`, which is
+    /// the default.
+    ///
+    /// # Arguments
+    ///
+    ///  * `timestamp` - Seconds since the Unix epoch; only circuits updated at or after this are
+    ///    listed
+    fn list_circuits_modified_since(
+        &self,
+        _timestamp: i64,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = Circuit>>, AdminServiceStoreError> {
+        Err(AdminServiceStoreError::InternalError(
+            InternalError::with_message(
+                "list_circuits_modified_since is not supported by this store".into(),
+            ),
+        ))
+    }
+
     /// Returns the count of circuits in the store
     ///
     /// # Arguments
@@ -322,6 +447,130 @@ pub trait AdminServiceStore: Send + Sync {
         &self,
     ) -> Result<Box<dyn ExactSizeIterator<Item = CircuitNode>>, AdminServiceStoreError>;
 
+    /// List the nodes with the given IDs from the store
+    ///
+    /// IDs that do not belong to a node in the store are ignored. By default, every node is
+    /// listed and those with the given IDs are kept.
+    ///
+    /// # Arguments
+    ///
+    ///  * `node_ids` - The unique IDs of the nodes to be returned
+    fn list_nodes_filtered(
+        &self,
+        node_ids: &[String],
+    ) -> Result<Box<dyn ExactSizeIterator<Item = CircuitNode>>, AdminServiceStoreError> {
+        Ok(Box::new(
+            self.list_nodes()?
+                .filter(|node| node_ids.iter().any(|node_id| node_id == node.node_id()))
+                .collect::<Vec<_>>()
+                .into_iter(),
+        ))
+    }
+
+    /// Replaces all of a node's endpoints with the given endpoints
+    ///
+    /// Returns an `InvalidStateError` if the node does not exist, if the endpoints are empty,
+    /// malformed or contain duplicates, or if an endpoint is already registered for another node.
+    ///
+    /// By default, the node's endpoints are replaced in each circuit it is a member of using
+    /// `update_circuit`, one circuit at a time, so the update is not atomic. Stores that keep
+    /// their nodes apart from their circuits must override this, and should replace the endpoints
+    /// atomically.
+    ///
+    /// # Arguments
+    ///
+    ///  * `node_id` - The unique ID of the node to be updated
+    ///  * `endpoints` - The node's new endpoints, each of the form `<scheme>://<address>`
+    fn update_node_endpoints(
+        &self,
+        node_id: &str,
+        endpoints: &[String],
+    ) -> Result<(), AdminServiceStoreError> {
+        validate_node_endpoints(endpoints).map_err(AdminServiceStoreError::InvalidStateError)?;
+
+        let nodes = self.list_nodes()?.collect::<Vec<_>>();
+        if !nodes.iter().any(|node| node.node_id() == node_id) {
+            return Err(AdminServiceStoreError::InvalidStateError(
+                InvalidStateError::with_message(format!("Node {} does not exist", node_id)),
+            ));
+        }
+
+        if let Some((other, taken)) = nodes
+            .iter()
+            .filter(|other| other.node_id() != node_id)
+            .find_map(|other| {
+                other
+                    .endpoints()
+                    .iter()
+                    .find(|endpoint| endpoints.contains(endpoint))
+                    .map(|taken| (other.node_id(), taken))
+            })
+        {
+            return Err(AdminServiceStoreError::InvalidStateError(
+                InvalidStateError::with_message(format!(
+                    "Endpoint {} is already registered for node {}",
+                    taken, other
+                )),
+            ));
+        }
+
+        for circuit in self.list_all_circuits()? {
+            if circuit
+                .members()
+                .iter()
+                .any(|member| member.node_id() == node_id)
+            {
+                self.update_circuit(
+                    circuit
+                        .with_member_endpoints(node_id, endpoints)
+                        .map_err(AdminServiceStoreError::InvalidStateError)?,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find endpoints that are registered for more than one node
+    ///
+    /// Returns pairs of an endpoint and the IDs of the nodes that share it, ordered by endpoint
+    /// and then node ID. An empty list means every endpoint belongs to a single node. By default,
+    /// the endpoints of every node are listed and compared.
+    fn find_duplicate_endpoints(
+        &self,
+    ) -> Result<Vec<(String, Vec<String>)>, AdminServiceStoreError> {
+        let mut endpoints: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for node in self.list_nodes()? {
+            for endpoint in node.endpoints() {
+                endpoints
+                    .entry(endpoint.to_string())
+                    .or_default()
+                    .insert(node.node_id().to_string());
+            }
+        }
+
+        Ok(endpoints
+            .into_iter()
+            .filter(|(_, node_ids)| node_ids.len() > 1)
+            .map(|(endpoint, node_ids)| (endpoint, node_ids.into_iter().collect()))
+            .collect())
+    }
+
+    /// Find service arguments whose service no longer exists
+    ///
+    /// Returns the circuit ID and service ID of each missing service that still has arguments,
+    /// ordered by circuit ID and then service ID. Such arguments are never returned with a
+    /// service, so an empty list means there is nothing to clean up.
+    ///
+    /// By default, an empty list is returned, which is correct for stores that keep a service's
+    /// arguments with the service, as they cannot be orphaned. Stores that keep arguments apart
+    /// from their services must override this.
+    fn find_orphaned_service_arguments(
+        &self,
+    ) -> Result<Vec<(String, String)>, AdminServiceStoreError> {
+        Ok(vec![])
+    }
+
     /// Fetches a service from the store
     ///
     /// # Arguments
@@ -342,6 +591,107 @@ pub trait AdminServiceStore: Send + Sync {
         circuit_id: &str,
     ) -> Result<Box<dyn ExactSizeIterator<Item = Service>>, AdminServiceStoreError>;
 
+    /// List all services in a specific circuit from the store, each with the endpoints of the
+    /// node that runs it
+    ///
+    /// Returns no services if the circuit does not exist, as `count_services` returns 0. By
+    /// default, the circuit is fetched and the endpoints are taken from its members; stores that
+    /// do not keep endpoints with a circuit's members must override this.
+    ///
+    /// # Arguments
+    ///
+    ///  * `circuit_id` - The unique ID of the circuit the services belong to
+    fn list_services_with_endpoints(
+        &self,
+        circuit_id: &str,
+    ) -> Result<ServiceEndpointsIter, AdminServiceStoreError> {
+        let services = match self.get_circuit(circuit_id)? {
+            Some(circuit) => circuit
+                .roster()
+                .iter()
+                .map(|service| {
+                    let endpoints = circuit
+                        .members()
+                        .iter()
+                        .find(|node| node.node_id() == service.node_id())
+                        .map(|node| node.endpoints().to_vec())
+                        .unwrap_or_default();
+                    (service.clone(), endpoints)
+                })
+                .collect::<Vec<_>>(),
+            None => vec![],
+        };
+
+        Ok(Box::new(services.into_iter()))
+    }
+
+    /// Returns the number of services in a specific circuit
+    ///
+    /// Returns 0 if the circuit does not exist. By default, the circuit is loaded and its roster
+    /// counted; stores that can do so should count the services without loading them.
+    ///
+    /// # Arguments
+    ///
+    ///  * `circuit_id` - The unique ID of the circuit the services belong to
+    fn count_services(&self, circuit_id: &str) -> Result<usize, AdminServiceStoreError> {
+        Ok(self
+            .get_circuit(circuit_id)?
+            .map(|circuit| circuit.roster().len())
+            .unwrap_or(0))
+    }
+
+    /// List the services run by a specific node, across all circuits, as pairs of circuit ID and
+    /// service
+    ///
+    /// Services are ordered by circuit ID, then by their position in the circuit's roster. By
+    /// default, every circuit is listed and the services are taken from their rosters.
+    ///
+    /// # Arguments
+    ///
+    ///  * `node_id` - The ID of the node the services run on
+    fn list_services_for_node(
+        &self,
+        node_id: &str,
+    ) -> Result<Vec<(String, Service)>, AdminServiceStoreError> {
+        let mut circuits = self.list_all_circuits()?.collect::<Vec<_>>();
+        circuits.sort_by(|a, b| a.circuit_id().cmp(b.circuit_id()));
+
+        Ok(circuits
+            .iter()
+            .flat_map(|circuit| {
+                circuit
+                    .roster()
+                    .iter()
+                    .filter(|service| service.node_id() == node_id)
+                    .map(move |service| (circuit.circuit_id().to_string(), service.clone()))
+            })
+            .collect())
+    }
+
+    /// List the distinct types of the services in the store, in alphabetical order
+    ///
+    /// By default, every circuit is listed and the types are taken from their rosters.
+    ///
+    /// # Arguments
+    ///
+    ///  * `node_id` - If provided, only the types of the services run by this node are listed
+    fn list_service_types(
+        &self,
+        node_id: Option<&str>,
+    ) -> Result<Vec<String>, AdminServiceStoreError> {
+        Ok(self
+            .list_all_circuits()?
+            .flat_map(|circuit| circuit.roster().to_vec())
+            .filter(|service| match node_id {
+                Some(node_id) => service.node_id() == node_id,
+                None => true,
+            })
+            .map(|service| service.service_type().to_string())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect())
+    }
+
     /// Add an event to the `AdminServiceEventStore`.  Returns the recorded event index and
     /// a copy of the event.
     ///
@@ -14,6 +14,8 @@
 
 //! Structs for building services
 
+use std::fmt;
+
 use crate::admin::messages::is_valid_service_id;
 use crate::error::InvalidStateError;
 
@@ -48,6 +50,44 @@ impl Service {
     pub fn arguments(&self) -> &[(String, String)] {
         &self.arguments
     }
+
+    /// Returns a single line summary of the service, for use in tabular output
+    ///
+    /// The service ID, service type, node ID and argument keys are separated by tabs. Argument
+    /// values are omitted, as they may be arbitrarily long.
+    pub fn summary_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.service_id,
+            self.service_type,
+            self.node_id,
+            self.argument_keys()
+        )
+    }
+
+    fn argument_keys(&self) -> String {
+        self.arguments
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) on {}",
+            self.service_id, self.service_type, self.node_id
+        )?;
+
+        if !self.arguments.is_empty() {
+            write!(f, " [arguments: {}]", self.argument_keys())?;
+        }
+
+        Ok(())
+    }
 }
 
 /// Builder for creating a `Service`
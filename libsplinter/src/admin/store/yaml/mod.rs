@@ -26,7 +26,8 @@ use std::io::Write;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use super::{AdminServiceEvent, EventIter};
+use super::circuit_node::validate_node_endpoints;
+use super::{AdminServiceEvent, EventIter, ServiceEndpointsIter};
 use super::{
     AdminServiceStore, AdminServiceStoreError, AuthorizationType, Circuit, CircuitBuilder,
     CircuitNode, CircuitNodeBuilder, CircuitPredicate, CircuitProposal, CircuitProposalBuilder,
@@ -1025,6 +1026,67 @@ impl AdminServiceStore for YamlAdminServiceStore {
         Ok(nodes)
     }
 
+    /// Replaces all of a node's endpoints in the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `node_id` - The unique ID of the node to be updated
+    ///  * `endpoints` - The node's new endpoints
+    fn update_node_endpoints(
+        &self,
+        node_id: &str,
+        endpoints: &[String],
+    ) -> Result<(), AdminServiceStoreError> {
+        validate_node_endpoints(endpoints).map_err(AdminServiceStoreError::InvalidStateError)?;
+
+        {
+            let mut state = self.state.lock().map_err(|_| {
+                AdminServiceStoreError::InternalError(InternalError::with_message(
+                    "YAML admin service store's internal lock was poisoned".to_string(),
+                ))
+            })?;
+
+            if let Some(other) = state.circuit_state.nodes.values().find(|node| {
+                node.node_id() != node_id
+                    && node
+                        .endpoints()
+                        .iter()
+                        .any(|endpoint| endpoints.contains(endpoint))
+            }) {
+                return Err(AdminServiceStoreError::InvalidStateError(
+                    InvalidStateError::with_message(format!(
+                        "An endpoint is already registered for node {}",
+                        other.node_id()
+                    )),
+                ));
+            }
+
+            let node = state.circuit_state.nodes.get_mut(node_id).ok_or_else(|| {
+                AdminServiceStoreError::InvalidStateError(InvalidStateError::with_message(format!(
+                    "Node {} does not exist",
+                    node_id
+                )))
+            })?;
+
+            let mut builder = CircuitNodeBuilder::new()
+                .with_node_id(node_id)
+                .with_endpoints(endpoints);
+            if let Some(public_key) = node.public_key() {
+                builder = builder.with_public_key(public_key);
+            }
+            *node = builder
+                .build()
+                .map_err(AdminServiceStoreError::InvalidStateError)?;
+        }
+
+        self.write_circuit_state().map_err(|err| {
+            AdminServiceStoreError::InternalError(InternalError::from_source_with_prefix(
+                Box::new(err),
+                "Unable to write circuit state yaml file".to_string(),
+            ))
+        })
+    }
+
     /// Fetches a service from the underlying storage
     ///
     /// # Arguments
@@ -1078,6 +1140,45 @@ impl AdminServiceStore for YamlAdminServiceStore {
         Ok(Box::new(services.into_iter()))
     }
 
+    /// List all services in a specific circuit from the underlying storage, each with the
+    /// endpoints of the node that runs it
+    ///
+    /// The YAML state keeps the endpoints of a circuit's members with its nodes rather than with
+    /// the circuit, so they are taken from the nodes.
+    ///
+    /// # Arguments
+    ///
+    ///  * `circuit_id` - The unique ID of the circuit the services belong to
+    fn list_services_with_endpoints(
+        &self,
+        circuit_id: &str,
+    ) -> Result<ServiceEndpointsIter, AdminServiceStoreError> {
+        let state = self.state.lock().map_err(|_| {
+            AdminServiceStoreError::InternalError(InternalError::with_message(
+                "YAML admin service store's internal lock was poisoned".to_string(),
+            ))
+        })?;
+
+        let services = match state.circuit_state.circuits.get(circuit_id) {
+            Some(circuit) => circuit
+                .roster()
+                .iter()
+                .map(|service| {
+                    let endpoints = state
+                        .circuit_state
+                        .nodes
+                        .get(service.node_id())
+                        .map(|node| node.endpoints().to_vec())
+                        .unwrap_or_default();
+                    (service.clone(), endpoints)
+                })
+                .collect::<Vec<_>>(),
+            None => vec![],
+        };
+
+        Ok(Box::new(services.into_iter()))
+    }
+
     /// Add an event to the `AdminServiceEventStore`.  Returns the recorded event index and
     /// a copy of the event.
     ///
@@ -2325,6 +2426,39 @@ proposals:
         assert_eq!(yaml_state_vec, contents)
     }
 
+    // Test that listing the circuits modified since a timestamp returns an error, as the YAML
+    // store does not record when circuits are updated
+    #[test]
+    fn test_list_circuits_modified_since_unsupported() {
+        let temp_dir = Builder::new()
+            .prefix("test_list_circuits_modified_since_unsupported")
+            .tempdir()
+            .expect("Failed to create temp dir");
+        let circuit_path = temp_dir
+            .path()
+            .join("circuits.yaml")
+            .to_str()
+            .expect("Failed to get path")
+            .to_string();
+        let proposals_path = temp_dir
+            .path()
+            .join("circuit_proposals.yaml")
+            .to_str()
+            .expect("Failed to get path")
+            .to_string();
+
+        write_file(CIRCUIT_STATE, &circuit_path);
+        write_file(PROPOSAL_STATE, &proposals_path);
+
+        let store = YamlAdminServiceStore::new(circuit_path, proposals_path)
+            .expect("Unable to create yaml admin store");
+
+        assert!(matches!(
+            store.list_circuits_modified_since(0),
+            Err(AdminServiceStoreError::InternalError(_))
+        ));
+    }
+
     // Test the node CRUD operations
     //
     // 1. Setup the temp directory with existing state
@@ -2481,6 +2615,45 @@ proposals:
                     .expect("Unable to build service")
             ]
         );
+
+        assert_eq!(
+            store
+                .list_services_with_endpoints("WBKLF-AAAAA")
+                .expect("Unable to list services with endpoints")
+                .map(|(service, endpoints)| (service.service_id().to_string(), endpoints))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "a000".to_string(),
+                    vec!["tcps://splinterd-node-acme:8044".to_string()]
+                ),
+                (
+                    "a001".to_string(),
+                    vec!["tcps://splinterd-node-bubba:8044".to_string()]
+                ),
+            ]
+        );
+        assert_eq!(
+            store
+                .count_services("WBKLF-AAAAA")
+                .expect("Unable to count services"),
+            2
+        );
+
+        // a circuit that does not exist has no services
+        assert_eq!(
+            store
+                .list_services_with_endpoints("WBKLF-CCCCC")
+                .expect("Unable to list services with endpoints")
+                .len(),
+            0
+        );
+        assert_eq!(
+            store
+                .count_services("WBKLF-CCCCC")
+                .expect("Unable to count services"),
+            0
+        );
     }
 
     // Test that a proposals can be upgraded to a circuit and both yaml files are upgraded.
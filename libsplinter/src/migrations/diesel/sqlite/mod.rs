@@ -16,6 +16,8 @@
 
 embed_migrations!("./src/migrations/diesel/sqlite/migrations");
 
+use std::collections::HashSet;
+
 use diesel::sqlite::SqliteConnection;
 use diesel::Connection;
 use diesel_migrations::MigrationConnection;
@@ -59,3 +61,35 @@ pub fn any_pending_migrations(conn: &SqliteConnection) -> Result<bool, InternalE
 
     Ok(current_version == latest_version)
 }
+
+/// Get the versions of the migrations that have not been applied, in the order they would be
+/// applied.
+///
+/// As with `any_pending_migrations`, the migrations are run in a test transaction that is rolled
+/// back, so the database is not changed.
+///
+/// # Arguments
+///
+/// * `conn` - Connection to SQLite database
+///
+pub fn pending_migrations(conn: &SqliteConnection) -> Result<Vec<String>, InternalError> {
+    // The migrations table does not exist until the first migration is run
+    let applied = conn
+        .previously_run_migration_versions()
+        .unwrap_or_else(|_| HashSet::new());
+
+    let all = conn.test_transaction::<Result<HashSet<String>, InternalError>, (), _>(|| {
+        Ok(match embedded_migrations::run(conn) {
+            Ok(_) => conn
+                .previously_run_migration_versions()
+                .map_err(|err| InternalError::from_source(Box::new(err))),
+            Err(err) => Err(InternalError::from_source(Box::new(err))),
+        })
+    })?;
+
+    // Migration versions are timestamps, so they sort in the order the migrations are run
+    let mut pending = all.difference(&applied).cloned().collect::<Vec<_>>();
+    pending.sort();
+
+    Ok(pending)
+}
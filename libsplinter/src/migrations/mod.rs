@@ -29,6 +29,8 @@
 #[cfg(feature = "diesel")]
 mod diesel;
 
+use std::fmt;
+
 #[cfg(feature = "postgres")]
 pub use self::diesel::postgres::any_pending_migrations as any_pending_postgres_migrations;
 #[cfg(feature = "postgres")]
@@ -36,4 +38,37 @@ pub use self::diesel::postgres::run_migrations as run_postgres_migrations;
 #[cfg(feature = "sqlite")]
 pub use self::diesel::sqlite::any_pending_migrations as any_pending_sqlite_migrations;
 #[cfg(feature = "sqlite")]
+pub use self::diesel::sqlite::pending_migrations as pending_sqlite_migrations;
+#[cfg(feature = "sqlite")]
 pub use self::diesel::sqlite::run_migrations as run_sqlite_migrations;
+
+/// Whether a database has had every migration of this version of splinter applied
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MigrationStatus {
+    UpToDate,
+    /// The migrations that have not been applied, in the order they would be applied
+    Pending(Vec<MigrationName>),
+}
+
+/// The name of a migration, which is its version: the timestamp that starts the name of the
+/// migration's directory, without separators (for example, `20220404120000`)
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MigrationName(String);
+
+impl MigrationName {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for MigrationName {
+    fn from(name: String) -> Self {
+        MigrationName(name)
+    }
+}
+
+impl fmt::Display for MigrationName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of the node identifier, NodeId.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::InvalidArgumentError;
+
+/// A node identifier.
+///
+/// A node ID is a non-empty string of ASCII alphanumeric characters, `-`, `_` and `.`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct NodeId {
+    inner: Box<str>,
+}
+
+impl NodeId {
+    /// Create a `NodeId` from a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_id` - A string representing a node identifier.
+    ///
+    /// # Errors
+    ///
+    /// [`InvalidArgumentError`] can occur when the string is empty or contains invalid characters
+    /// (only ASCII alphanumeric characters, `-`, `_` and `.` are allowed).
+    pub fn new<T: Into<String>>(node_id: T) -> Result<Self, InvalidArgumentError> {
+        NodeId::new_box_str(node_id.into().into_boxed_str())
+    }
+
+    // Private as external API for this is to use try_from.
+    fn new_box_str(node_id: Box<str>) -> Result<Self, InvalidArgumentError> {
+        if node_id.is_empty() {
+            return Err(InvalidArgumentError::new(
+                "node_id".to_string(),
+                "empty string".to_string(),
+            ));
+        }
+
+        if !node_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        {
+            return Err(InvalidArgumentError::new(
+                "node_id".to_string(),
+                "invalid characters, expected ASCII alphanumeric, '-', '_' or '.'".to_string(),
+            ));
+        }
+
+        Ok(NodeId { inner: node_id })
+    }
+
+    /// Returns a `&str` representing the value of `NodeId`.
+    pub fn as_str(&self) -> &str {
+        &self.inner
+    }
+
+    /// Returns a `Box<str>`, consuming the `NodeId`.
+    pub fn deconstruct(self) -> Box<str> {
+        self.inner
+    }
+}
+
+impl FromStr for NodeId {
+    type Err = InvalidArgumentError;
+
+    fn from_str(node_id: &str) -> Result<Self, Self::Err> {
+        NodeId::new(node_id)
+    }
+}
+
+impl TryFrom<String> for NodeId {
+    type Error = InvalidArgumentError;
+
+    fn try_from(node_id: String) -> Result<Self, Self::Error> {
+        NodeId::new(node_id)
+    }
+}
+
+impl TryFrom<&str> for NodeId {
+    type Error = InvalidArgumentError;
+
+    fn try_from(node_id: &str) -> Result<Self, Self::Error> {
+        NodeId::new(node_id)
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use crate::error::InvalidArgumentError;
+
+    use super::NodeId;
+
+    /// Tests successfully creating a NodeId from well-formed strings, using NodeId::new(),
+    /// str::parse() and String::try_into().
+    #[test]
+    fn test_node_id_well_formed() {
+        let node_id = NodeId::new("acme-node-000").expect("creating NodeId from \"acme-node-000\"");
+        assert_eq!(node_id.as_str(), "acme-node-000");
+
+        let node_id: NodeId = "n12345".parse().expect("creating NodeId from \"n12345\"");
+        assert_eq!(node_id.as_str(), "n12345");
+
+        let node_id: NodeId = String::from("node_b.example")
+            .try_into()
+            .expect("creating NodeId from \"node_b.example\"");
+        assert_eq!(&format!("{}", node_id), "node_b.example");
+    }
+
+    /// Tests for an error creating a NodeId from an empty string.
+    #[test]
+    fn test_node_id_empty() {
+        assert_eq!(
+            &NodeId::new("").unwrap_err().to_string(),
+            "empty string (node_id)",
+        );
+    }
+
+    /// Tests for an error creating a NodeId with invalid characters.
+    #[test]
+    fn test_node_id_invalid_characters() {
+        for invalid in &["node 1", "node/1", "node:1", "node\n"] {
+            let result: Result<NodeId, InvalidArgumentError> = invalid.parse();
+            assert_eq!(
+                &result.unwrap_err().to_string(),
+                "invalid characters, expected ASCII alphanumeric, '-', '_' or '.' (node_id)",
+            );
+        }
+    }
+}
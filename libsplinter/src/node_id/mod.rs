@@ -14,5 +14,8 @@
 
 //! Traits, types, and implementations for interacting with node_id's.
 
+mod id;
 /// Store logic for accessing and modifying an instances node_id.
 pub mod store;
+
+pub use id::NodeId;
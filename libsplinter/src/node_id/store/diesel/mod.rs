@@ -24,6 +24,8 @@ use diesel::r2d2::{ConnectionManager, Pool};
 
 use crate::store::pool::ConnectionPool;
 
+use crate::node_id::NodeId;
+
 use super::error::NodeIdStoreError;
 use super::NodeIdStore;
 
@@ -70,9 +72,10 @@ impl NodeIdStore for DieselNodeIdStore<diesel::pg::PgConnection> {
         self.pool
             .execute_read(|conn| NodeIdOperations::new(conn).get_node_id())
     }
-    fn set_node_id(&self, new_id: String) -> Result<(), NodeIdStoreError> {
-        self.pool
-            .execute_write(|conn| NodeIdOperations::new(conn).set_node_id(new_id))
+    fn set_node_id(&self, new_id: NodeId) -> Result<(), NodeIdStoreError> {
+        self.pool.execute_write(|conn| {
+            NodeIdOperations::new(conn).set_node_id(String::from(new_id.deconstruct()))
+        })
     }
 }
 #[cfg(feature = "sqlite")]
@@ -81,8 +84,9 @@ impl NodeIdStore for DieselNodeIdStore<diesel::sqlite::SqliteConnection> {
         self.pool
             .execute_read(|conn| NodeIdOperations::new(conn).get_node_id())
     }
-    fn set_node_id(&self, new_id: String) -> Result<(), NodeIdStoreError> {
-        self.pool
-            .execute_write(|conn| NodeIdOperations::new(conn).set_node_id(new_id))
+    fn set_node_id(&self, new_id: NodeId) -> Result<(), NodeIdStoreError> {
+        self.pool.execute_write(|conn| {
+            NodeIdOperations::new(conn).set_node_id(String::from(new_id.deconstruct()))
+        })
     }
 }
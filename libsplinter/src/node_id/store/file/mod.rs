@@ -17,6 +17,8 @@
 use std::fs;
 use std::path::PathBuf;
 
+use crate::node_id::NodeId;
+
 use super::NodeIdStore;
 use super::NodeIdStoreError;
 
@@ -46,7 +48,7 @@ impl NodeIdStore for FileNodeIdStore {
             })
     }
 
-    fn set_node_id(&self, node_id: String) -> Result<(), NodeIdStoreError> {
-        fs::write(&self.filename, node_id).map_err(|e| e.into())
+    fn set_node_id(&self, node_id: NodeId) -> Result<(), NodeIdStoreError> {
+        fs::write(&self.filename, node_id.as_str()).map_err(|e| e.into())
     }
 }
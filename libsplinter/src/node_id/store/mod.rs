@@ -19,6 +19,8 @@ pub mod file;
 
 use error::NodeIdStoreError;
 
+use super::NodeId;
+
 /// Trait for interacting with the instances node_id.
 pub trait NodeIdStore {
     /// Gets node_id for the instance
@@ -29,5 +31,5 @@ pub trait NodeIdStore {
     /// # Arguments
     ///
     /// * `node_id` - the desired node_id
-    fn set_node_id(&self, node_id: String) -> Result<(), NodeIdStoreError>;
+    fn set_node_id(&self, node_id: NodeId) -> Result<(), NodeIdStoreError>;
 }
@@ -0,0 +1,238 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A memory-backed implementation of the registry traits
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::error::{
+    ConstraintViolationError, ConstraintViolationType, InternalError, InvalidStateError,
+};
+
+use super::{
+    MetadataPredicate, Node, NodeIter, RegistryError, RegistryReader, RegistryWriter, RwRegistry,
+};
+
+/// A registry that keeps its nodes in memory, ordered by identity.
+///
+/// Nothing is persisted, so this registry is intended for tests and short-lived nodes. Clones
+/// share the same nodes.
+#[derive(Default, Clone)]
+pub struct MemoryRegistry {
+    nodes: Arc<Mutex<BTreeMap<String, Node>>>,
+}
+
+impl MemoryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn nodes(&self) -> Result<MutexGuard<'_, BTreeMap<String, Node>>, RegistryError> {
+        self.nodes.lock().map_err(|_| {
+            RegistryError::InternalError(InternalError::with_message(
+                "Cannot access registry: mutex lock poisoned".to_string(),
+            ))
+        })
+    }
+}
+
+/// Returns an error if an endpoint of `node` belongs to another node
+fn check_endpoints_are_unique(
+    nodes: &BTreeMap<String, Node>,
+    node: &Node,
+) -> Result<(), RegistryError> {
+    match nodes
+        .values()
+        .filter(|existing| existing.identity != node.identity)
+        .flat_map(|existing| existing.endpoints.iter())
+        .find(|endpoint| node.endpoints.contains(endpoint))
+    {
+        Some(endpoint) => Err(RegistryError::InvalidStateError(
+            InvalidStateError::with_message(format!(
+                "another node with endpoint {} exists",
+                endpoint
+            )),
+        )),
+        None => Ok(()),
+    }
+}
+
+impl RegistryReader for MemoryRegistry {
+    fn list_nodes<'a, 'b: 'a>(
+        &'b self,
+        predicates: &'a [MetadataPredicate],
+    ) -> Result<NodeIter<'a>, RegistryError> {
+        let nodes = self
+            .nodes()?
+            .values()
+            .filter(|node| predicates.iter().all(|predicate| predicate.apply(node)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Ok(Box::new(nodes.into_iter()))
+    }
+
+    fn count_nodes(&self, predicates: &[MetadataPredicate]) -> Result<u32, RegistryError> {
+        let count = self
+            .nodes()?
+            .values()
+            .filter(|node| predicates.iter().all(|predicate| predicate.apply(node)))
+            .count();
+
+        u32::try_from(count).map_err(|_| {
+            RegistryError::InternalError(InternalError::with_message(
+                "The number of nodes is larger than the max u32".to_string(),
+            ))
+        })
+    }
+
+    fn get_node(&self, identity: &str) -> Result<Option<Node>, RegistryError> {
+        Ok(self.nodes()?.get(identity).cloned())
+    }
+}
+
+impl RegistryWriter for MemoryRegistry {
+    fn add_node(&self, node: Node) -> Result<(), RegistryError> {
+        let mut nodes = self.nodes()?;
+        check_endpoints_are_unique(&nodes, &node)?;
+        if nodes.contains_key(&node.identity) {
+            return Err(RegistryError::ConstraintViolationError(
+                ConstraintViolationError::with_violation_type(ConstraintViolationType::Unique),
+            ));
+        }
+
+        nodes.insert(node.identity.clone(), node);
+        Ok(())
+    }
+
+    fn update_node(&self, node: Node) -> Result<(), RegistryError> {
+        let mut nodes = self.nodes()?;
+        check_endpoints_are_unique(&nodes, &node)?;
+        match nodes.get_mut(&node.identity) {
+            Some(existing) => {
+                *existing = node;
+                Ok(())
+            }
+            None => Err(RegistryError::InvalidStateError(
+                InvalidStateError::with_message(format!("Node does not exist: {}", node.identity)),
+            )),
+        }
+    }
+
+    fn delete_node(&self, identity: &str) -> Result<Option<Node>, RegistryError> {
+        Ok(self.nodes()?.remove(identity))
+    }
+}
+
+impl RwRegistry for MemoryRegistry {
+    fn clone_box(&self) -> Box<dyn RwRegistry> {
+        Box::new(self.clone())
+    }
+
+    fn clone_box_as_reader(&self) -> Box<dyn RegistryReader> {
+        Box::new(Clone::clone(self))
+    }
+
+    fn clone_box_as_writer(&self) -> Box<dyn RegistryWriter> {
+        Box::new(Clone::clone(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(identity: &str, endpoint: &str) -> Node {
+        Node::builder(identity)
+            .with_endpoint(endpoint)
+            .with_display_name(identity)
+            .with_key("0123")
+            .with_metadata("company", identity)
+            .build()
+            .expect("Unable to build node")
+    }
+
+    /// Verify that nodes can be added, updated, listed and deleted, and that clones of the
+    /// registry share its nodes.
+    ///
+    /// 1. Add two nodes and verify they are listed in identity order from a clone
+    /// 2. Verify that a node with a duplicate identity or endpoint cannot be added
+    /// 3. Update a node and verify that updating an unknown node fails
+    /// 4. Verify metadata predicates are applied when listing and counting
+    /// 5. Delete a node and verify it is no longer returned
+    #[test]
+    fn test_memory_registry() {
+        let registry = MemoryRegistry::new();
+        let clone = registry.clone();
+
+        registry
+            .add_node(node("node-b", "tcps://node-b:8044"))
+            .expect("Unable to add node");
+        registry
+            .add_node(node("node-a", "tcps://node-a:8044"))
+            .expect("Unable to add node");
+        assert_eq!(
+            clone
+                .list_nodes(&[])
+                .expect("Unable to list nodes")
+                .map(|node| node.identity)
+                .collect::<Vec<_>>(),
+            vec!["node-a".to_string(), "node-b".to_string()]
+        );
+
+        assert!(matches!(
+            registry.add_node(node("node-a", "tcps://node-c:8044")),
+            Err(RegistryError::ConstraintViolationError(_))
+        ));
+        assert!(matches!(
+            registry.add_node(node("node-c", "tcps://node-a:8044")),
+            Err(RegistryError::InvalidStateError(_))
+        ));
+
+        registry
+            .update_node(node("node-a", "tcps://node-a-2:8044"))
+            .expect("Unable to update node");
+        assert_eq!(
+            registry
+                .get_node("node-a")
+                .expect("Unable to get node")
+                .expect("Node not found")
+                .endpoints(),
+            &["tcps://node-a-2:8044".to_string()]
+        );
+        assert!(registry
+            .update_node(node("node-c", "tcps://node-c:8044"))
+            .is_err());
+
+        let predicates = [MetadataPredicate::eq("company", "node-b")];
+        assert_eq!(
+            registry
+                .list_nodes(&predicates)
+                .expect("Unable to list nodes")
+                .map(|node| node.identity)
+                .collect::<Vec<_>>(),
+            vec!["node-b".to_string()]
+        );
+        assert_eq!(registry.count_nodes(&predicates).unwrap(), 1);
+
+        assert!(registry
+            .delete_node("node-b")
+            .expect("Unable to delete node")
+            .is_some());
+        assert!(!clone.has_node("node-b").expect("Unable to check node"));
+        assert_eq!(registry.count_nodes(&[]).unwrap(), 1);
+    }
+}
@@ -29,6 +29,7 @@ pub mod client;
 #[cfg(feature = "diesel")]
 mod diesel;
 mod error;
+mod memory;
 mod unified;
 mod yaml;
 
@@ -38,6 +39,7 @@ use std::iter::ExactSizeIterator;
 #[cfg(feature = "diesel")]
 pub use self::diesel::DieselRegistry;
 pub use error::{InvalidNodeError, RegistryError};
+pub use memory::MemoryRegistry;
 pub use unified::UnifiedRegistry;
 pub use yaml::{LocalYamlRegistry, YamlNode};
 #[cfg(feature = "registry-remote")]
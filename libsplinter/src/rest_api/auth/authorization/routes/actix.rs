@@ -14,18 +14,22 @@
 
 //! This module provides the following endpoints:
 //!
-//! * `GET /authroization/permissions` for displaying all REST API permissions
+//! * `GET /authroization/permissions` for displaying all REST API permissions, or those matching
+//!   the `search` query parameter
 
-use actix_web::HttpResponse;
+use actix_web::{web, HttpResponse};
 use futures::future::IntoFuture;
 
 use crate::rest_api::{
     actix_web_1::{Method, ProtocolVersionRangeGuard, Resource},
     auth::authorization::Permission,
-    SPLINTER_PROTOCOL_VERSION,
+    ErrorResponse, SPLINTER_PROTOCOL_VERSION,
 };
 
-use super::{resources::PermissionResponse, AUTHORIZATION_PERMISSIONS_READ_PERMISSION};
+use super::{
+    resources::{GetPermissionsQuery, PermissionResponse},
+    AUTHORIZATION_PERMISSIONS_READ_PERMISSION,
+};
 
 const AUTHORIZATION_PERMISSIONS_MIN: u32 = 1;
 
@@ -65,13 +69,29 @@ pub fn make_permissions_resource(permissions: Vec<Permission>) -> Resource {
         .add_method(
             Method::Get,
             AUTHORIZATION_PERMISSIONS_READ_PERMISSION,
-            move |_, _| {
+            move |r, _| {
                 Box::new(
-                    HttpResponse::Ok()
-                        .json(json!({
-                            "data": &permissions,
-                        }))
-                        .into_future(),
+                    match web::Query::<GetPermissionsQuery>::from_query(r.query_string()) {
+                        Ok(query) => {
+                            let search = query.search.as_ref().map(|search| search.to_lowercase());
+                            let data = permissions
+                                .iter()
+                                .filter(|perm| match &search {
+                                    Some(search) => perm.matches(search),
+                                    None => true,
+                                })
+                                .collect::<Vec<_>>();
+
+                            HttpResponse::Ok()
+                                .json(json!({
+                                    "data": data,
+                                }))
+                                .into_future()
+                        }
+                        Err(_) => HttpResponse::BadRequest()
+                            .json(ErrorResponse::bad_request("Invalid query"))
+                            .into_future(),
+                    },
                 )
             },
         )
@@ -178,6 +198,56 @@ mod tests {
         join_handle.join().expect("Unable to join rest api thread");
     }
 
+    /// Verifies that the `GET /authorization/permissions` endpoint only returns the permissions
+    /// matching the `search` query parameter, ignoring case
+    ///
+    /// 1. Start the REST API with two permissions
+    /// 2. Search for text in one permission's description and verify that only that permission
+    ///    is returned
+    /// 3. Search for text that no permission contains and verify that no permissions are returned
+    /// 4. Shutdown the REST API
+    #[test]
+    fn get_permissions_search() {
+        let (shutdown_handle, join_handle, bind_url) =
+            run_rest_api_on_open_port(vec![make_permissions_resource(vec![PERM1, PERM2])]);
+
+        let url = Url::parse(&format!("http://{}/authorization/permissions", bind_url))
+            .expect("Failed to parse URL");
+
+        let resp = Client::new()
+            .get(url.clone())
+            .query(&[("search", "DESCRIPTION 2")])
+            .header("SplinterProtocolVersion", SPLINTER_PROTOCOL_VERSION)
+            .send()
+            .expect("Failed to perform request");
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let permissions = resp
+            .json::<Response>()
+            .expect("Failed to parse response body")
+            .data;
+        assert_eq!(permissions.len(), 1);
+        assert_eq!(permissions[0].permission_id, "id2");
+
+        let resp = Client::new()
+            .get(url)
+            .query(&[("search", "unknown")])
+            .header("SplinterProtocolVersion", SPLINTER_PROTOCOL_VERSION)
+            .send()
+            .expect("Failed to perform request");
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp
+            .json::<Response>()
+            .expect("Failed to parse response body")
+            .data
+            .is_empty());
+
+        shutdown_handle
+            .shutdown()
+            .expect("Unable to shutdown rest api");
+        join_handle.join().expect("Unable to join rest api thread");
+    }
+
     #[derive(Deserialize)]
     struct Response {
         data: Vec<PermissionData>,
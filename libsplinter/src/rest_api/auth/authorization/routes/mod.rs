@@ -33,7 +33,8 @@ const AUTHORIZATION_PERMISSIONS_READ_PERMISSION: Permission = Permission::Check
 /// Provides the REST API [Resource] definitions for authorization endpoints. The following
 /// endpoints are provided:
 ///
-/// * `GET /authorization/permissions` - Get the list of all REST API permissions
+/// * `GET /authorization/permissions` - Get the list of all REST API permissions, optionally
+///   only those matching the `search` query parameter
 ///
 /// These endpoints are only available if the following REST API backend feature is enabled:
 ///
@@ -20,3 +20,22 @@ pub struct PermissionResponse {
     pub permission_display_name: &'static str,
     pub permission_description: &'static str,
 }
+
+impl PermissionResponse {
+    /// Returns whether the permission's ID, display name or description contains the given
+    /// lowercase search text, ignoring case.
+    pub fn matches(&self, search: &str) -> bool {
+        [
+            self.permission_id,
+            self.permission_display_name,
+            self.permission_description,
+        ]
+        .iter()
+        .any(|field| field.to_lowercase().contains(search))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct GetPermissionsQuery {
+    pub search: Option<String>,
+}
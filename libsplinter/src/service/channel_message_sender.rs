@@ -0,0 +1,175 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contains `ChannelMessageSender`.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use crate::error::InternalError;
+
+use super::{MessageSender, ServiceId};
+
+/// A `MessageSender` that delivers messages to services running in the same process.
+///
+/// Each local service registers its `ServiceId` and receives messages sent to it on the returned
+/// `Receiver`, without going through the network. Sending to a service that has no registered
+/// receiver, or whose receiver has been dropped, returns an `InternalError`.
+///
+/// Clones share the same set of registered receivers, so a clone may be given to each local
+/// service.
+pub struct ChannelMessageSender<M> {
+    receivers: Arc<Mutex<HashMap<ServiceId, Sender<M>>>>,
+}
+
+impl<M> ChannelMessageSender<M> {
+    /// Construct a new `ChannelMessageSender` with no registered receivers.
+    pub fn new() -> Self {
+        Self {
+            receivers: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Registers a local service, returning the `Receiver` on which it receives messages.
+    ///
+    /// If the service was already registered, its previous receiver no longer receives messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `service_id` - The ID of the local service
+    pub fn register(&self, service_id: ServiceId) -> Result<Receiver<M>, InternalError> {
+        let (sender, receiver) = channel();
+        self.receivers
+            .lock()
+            .map_err(|_| {
+                InternalError::with_message("ChannelMessageSender lock was poisoned".into())
+            })?
+            .insert(service_id, sender);
+        Ok(receiver)
+    }
+
+    /// Removes a local service, so that messages are no longer delivered to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `service_id` - The ID of the local service
+    pub fn unregister(&self, service_id: &ServiceId) -> Result<(), InternalError> {
+        self.receivers
+            .lock()
+            .map_err(|_| {
+                InternalError::with_message("ChannelMessageSender lock was poisoned".into())
+            })?
+            .remove(service_id);
+        Ok(())
+    }
+}
+
+impl<M> Default for ChannelMessageSender<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> Clone for ChannelMessageSender<M> {
+    fn clone(&self) -> Self {
+        Self {
+            receivers: self.receivers.clone(),
+        }
+    }
+}
+
+impl<M> MessageSender<M> for ChannelMessageSender<M> {
+    fn send(&self, to_service: &ServiceId, message: M) -> Result<(), InternalError> {
+        let receivers = self.receivers.lock().map_err(|_| {
+            InternalError::with_message("ChannelMessageSender lock was poisoned".into())
+        })?;
+
+        let sender = receivers.get(to_service).ok_or_else(|| {
+            InternalError::with_message(format!(
+                "Unable to send message to {}: no local receiver is registered",
+                to_service
+            ))
+        })?;
+
+        sender.send(message).map_err(|_| {
+            InternalError::with_message(format!(
+                "Unable to send message to {}: the local receiver has been dropped",
+                to_service
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that a message sent with the `ChannelMessageSender` is received by the registered
+    /// service.
+    ///
+    /// 1. Create a `ChannelMessageSender` and register two services
+    /// 2. Send a message to each service from a clone of the sender
+    /// 3. Verify each service received only its own message
+    #[test]
+    fn test_local_round_trip() {
+        let sender = ChannelMessageSender::new();
+        let service_a = ServiceId::new("a000").expect("Unable to create service ID");
+        let service_b = ServiceId::new("b000").expect("Unable to create service ID");
+
+        let receiver_a = sender
+            .register(service_a.clone())
+            .expect("Unable to register service");
+        let receiver_b = sender
+            .register(service_b.clone())
+            .expect("Unable to register service");
+
+        let clone = sender.clone();
+        clone
+            .send(&service_a, b"to a".to_vec())
+            .expect("Unable to send message");
+        clone
+            .send(&service_b, b"to b".to_vec())
+            .expect("Unable to send message");
+
+        assert_eq!(receiver_a.try_recv(), Ok(b"to a".to_vec()));
+        assert!(receiver_a.try_recv().is_err());
+        assert_eq!(receiver_b.try_recv(), Ok(b"to b".to_vec()));
+        assert!(receiver_b.try_recv().is_err());
+    }
+
+    /// Verify that sending to a service with no registered receiver, or whose receiver was
+    /// dropped or unregistered, returns an error.
+    #[test]
+    fn test_no_local_receiver() {
+        let sender = ChannelMessageSender::new();
+        let service_id = ServiceId::new("a000").expect("Unable to create service ID");
+
+        assert!(sender.send(&service_id, ()).is_err());
+
+        let receiver = sender
+            .register(service_id.clone())
+            .expect("Unable to register service");
+        drop(receiver);
+        assert!(sender.send(&service_id, ()).is_err());
+
+        let _receiver = sender
+            .register(service_id.clone())
+            .expect("Unable to register service");
+        sender
+            .unregister(&service_id)
+            .expect("Unable to unregister service");
+        assert!(sender.send(&service_id, ()).is_err());
+    }
+}
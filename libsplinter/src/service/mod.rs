@@ -37,6 +37,8 @@
 
 #[cfg(feature = "service-arguments-converter")]
 mod arguments_converter;
+#[cfg(feature = "service-message-sender-channel")]
+mod channel_message_sender;
 mod id;
 pub mod instance;
 #[cfg(feature = "service-lifecycle")]
@@ -49,10 +51,18 @@ mod message_handler_factory;
 mod message_sender;
 #[cfg(feature = "service-message-sender-factory")]
 mod message_sender_factory;
+#[cfg(feature = "service-message-sender-observing")]
+mod observing_message_sender;
+#[cfg(feature = "service-message-sender-rate-limited")]
+mod rate_limited_message_sender;
 #[cfg(feature = "rest-api-actix-web-1")]
 pub mod rest_api;
+#[cfg(feature = "service-message-sender-round-robin")]
+mod round_robin_message_sender;
 mod routable;
 mod service_type;
+#[cfg(feature = "service-message-sender-size-limited")]
+mod size_limited_message_sender;
 #[cfg(feature = "service-timer-alarm")]
 mod timer_alarm;
 #[cfg(feature = "service-timer-alarm-factory")]
@@ -66,6 +76,8 @@ mod timer_handler_factory;
 
 #[cfg(feature = "service-arguments-converter")]
 pub use arguments_converter::ArgumentsConverter;
+#[cfg(feature = "service-message-sender-channel")]
+pub use channel_message_sender::ChannelMessageSender;
 pub use id::{CircuitId, FullyQualifiedServiceId, ServiceId};
 #[cfg(feature = "service-lifecycle")]
 pub use lifecycle::Lifecycle;
@@ -79,8 +91,16 @@ use message_sender::IntoMessageSender;
 pub use message_sender::MessageSender;
 #[cfg(feature = "service-message-sender-factory")]
 pub use message_sender_factory::MessageSenderFactory;
+#[cfg(feature = "service-message-sender-observing")]
+pub use observing_message_sender::ObservingMessageSender;
+#[cfg(feature = "service-message-sender-rate-limited")]
+pub use rate_limited_message_sender::RateLimitedMessageSender;
+#[cfg(feature = "service-message-sender-round-robin")]
+pub use round_robin_message_sender::RoundRobinMessageSender;
 pub use routable::Routable;
 pub use service_type::ServiceType;
+#[cfg(feature = "service-message-sender-size-limited")]
+pub use size_limited_message_sender::SizeLimitedMessageSender;
 #[cfg(feature = "service-timer-alarm")]
 pub use timer_alarm::TimerAlarm;
 #[cfg(feature = "service-timer-alarm-factory")]
@@ -0,0 +1,132 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contains `ObservingMessageSender`.
+
+use std::sync::Mutex;
+
+use crate::error::InternalError;
+
+use super::{MessageSender, ServiceId};
+
+type Observer<M> = Box<dyn FnMut(&ServiceId, &M, Result<(), &InternalError>) + Send>;
+
+/// A `MessageSender` that reports each send attempt to an observer.
+///
+/// After every call to the inner `MessageSender`, whether it succeeded or failed, the observer is
+/// called with the recipient, the message and the result. The inner sender's result is then
+/// returned unchanged. Because the inner sender takes ownership of the message, a copy is kept to
+/// pass to the observer.
+pub struct ObservingMessageSender<M> {
+    inner: Box<dyn MessageSender<M>>,
+    observer: Mutex<Observer<M>>,
+}
+
+impl<M> ObservingMessageSender<M> {
+    /// Construct a new `ObservingMessageSender`.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The `MessageSender` that will send the messages
+    /// * `observer` - Called after each send attempt with the recipient, message and result
+    pub fn new<F>(inner: Box<dyn MessageSender<M>>, observer: F) -> Self
+    where
+        F: FnMut(&ServiceId, &M, Result<(), &InternalError>) + Send + 'static,
+    {
+        Self {
+            inner,
+            observer: Mutex::new(Box::new(observer)),
+        }
+    }
+}
+
+impl<M: Clone> MessageSender<M> for ObservingMessageSender<M> {
+    fn send(&self, to_service: &ServiceId, message: M) -> Result<(), InternalError> {
+        let observed = message.clone();
+        let result = self.inner.send(to_service, message);
+
+        let mut observer = self.observer.lock().map_err(|_| {
+            InternalError::with_message("Message sender observer lock was poisoned".into())
+        })?;
+        (*observer)(to_service, &observed, result.as_ref().map(|_| ()));
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    struct TestMessageSender;
+
+    impl MessageSender<String> for TestMessageSender {
+        fn send(&self, _to_service: &ServiceId, message: String) -> Result<(), InternalError> {
+            if message == "fail" {
+                Err(InternalError::with_message("send failed".into()))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// Verify that the `ObservingMessageSender` calls the observer for both successful and failed
+    /// sends and returns the inner sender's result unchanged.
+    ///
+    /// 1. Create an `ObservingMessageSender` whose observer records each call
+    /// 2. Send a message that succeeds and verify `Ok` is returned
+    /// 3. Send a message that fails and verify the inner sender's error is returned
+    /// 4. Verify the observer saw both attempts with the correct recipient, message and result
+    #[test]
+    fn test_observing_message_sender() {
+        let observed = Arc::new(Mutex::new(vec![]));
+        let observer_observed = observed.clone();
+        let sender = ObservingMessageSender::new(
+            Box::new(TestMessageSender),
+            move |to_service: &ServiceId, message: &String, result: Result<(), &InternalError>| {
+                observer_observed
+                    .lock()
+                    .expect("observed lock was poisoned")
+                    .push((
+                        to_service.to_string(),
+                        message.clone(),
+                        result.map_err(|err| err.to_string()),
+                    ));
+            },
+        );
+        let to_service = ServiceId::new("a000").expect("Unable to create service ID");
+
+        sender
+            .send(&to_service, "hello".to_string())
+            .expect("Unable to send message");
+        let err = sender
+            .send(&to_service, "fail".to_string())
+            .expect_err("Send did not fail");
+        assert_eq!(err.to_string(), "send failed");
+
+        assert_eq!(
+            *observed.lock().expect("observed lock was poisoned"),
+            vec![
+                ("a000".to_string(), "hello".to_string(), Ok(())),
+                (
+                    "a000".to_string(),
+                    "fail".to_string(),
+                    Err("send failed".to_string())
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contains `RateLimitedMessageSender`.
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{InternalError, InvalidArgumentError};
+
+use super::{MessageSender, ServiceId};
+
+/// A `MessageSender` that limits the rate at which messages are sent.
+///
+/// The rate is enforced with a token bucket: the bucket holds at most `burst_size` tokens and is
+/// refilled at `messages_per_second` tokens per second. Each message consumes one token. When no
+/// token is available, `send` blocks until one is, as long as that happens within `max_wait`;
+/// otherwise an `InternalError` is returned and the message is not sent.
+pub struct RateLimitedMessageSender<M> {
+    inner: Box<dyn MessageSender<M>>,
+    messages_per_second: f64,
+    burst_size: f64,
+    max_wait: Duration,
+    bucket: Mutex<TokenBucket>,
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl<M> RateLimitedMessageSender<M> {
+    /// Construct a new `RateLimitedMessageSender`. The bucket starts out full.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The `MessageSender` that will send the rate-limited messages
+    /// * `messages_per_second` - The sustained number of messages that may be sent per second
+    /// * `burst_size` - The number of messages that may be sent at once before the limit applies
+    /// * `max_wait` - The maximum amount of time `send` will block waiting for capacity
+    ///
+    /// Returns an `InvalidArgumentError` if `messages_per_second` or `burst_size` is zero.
+    pub fn new(
+        inner: Box<dyn MessageSender<M>>,
+        messages_per_second: u32,
+        burst_size: u32,
+        max_wait: Duration,
+    ) -> Result<Self, InvalidArgumentError> {
+        if messages_per_second == 0 {
+            return Err(InvalidArgumentError::new(
+                "messages_per_second",
+                "must be greater than zero",
+            ));
+        }
+
+        if burst_size == 0 {
+            return Err(InvalidArgumentError::new(
+                "burst_size",
+                "must be greater than zero",
+            ));
+        }
+
+        Ok(Self {
+            inner,
+            messages_per_second: f64::from(messages_per_second),
+            burst_size: f64::from(burst_size),
+            max_wait,
+            bucket: Mutex::new(TokenBucket {
+                tokens: f64::from(burst_size),
+                last_refill: Instant::now(),
+            }),
+        })
+    }
+
+    /// Take a token from the bucket, returning how long to wait for one if none is available.
+    fn try_acquire(&self) -> Result<Option<Duration>, InternalError> {
+        let mut bucket = self.bucket.lock().map_err(|_| {
+            InternalError::with_message("Rate limiter's internal lock was poisoned".into())
+        })?;
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.messages_per_second).min(self.burst_size);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(None)
+        } else {
+            Ok(Some(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.messages_per_second,
+            )))
+        }
+    }
+}
+
+impl<M> MessageSender<M> for RateLimitedMessageSender<M> {
+    fn send(&self, to_service: &ServiceId, message: M) -> Result<(), InternalError> {
+        let deadline = Instant::now() + self.max_wait;
+
+        while let Some(wait) = self.try_acquire()? {
+            if Instant::now() + wait > deadline {
+                return Err(InternalError::with_message(format!(
+                    "Unable to send message to {}: rate limit of {} messages per second \
+                    exceeded and no capacity became available within {:?}",
+                    to_service, self.messages_per_second, self.max_wait
+                )));
+            }
+            thread::sleep(wait);
+        }
+
+        self.inner.send(to_service, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    struct TestMessageSender {
+        sent: Arc<Mutex<usize>>,
+    }
+
+    impl MessageSender<()> for TestMessageSender {
+        fn send(&self, _to_service: &ServiceId, _message: ()) -> Result<(), InternalError> {
+            *self.sent.lock().expect("sent lock was poisoned") += 1;
+            Ok(())
+        }
+    }
+
+    /// Verify that the `RateLimitedMessageSender` rejects messages sent faster than the limit
+    /// once the burst has been used up.
+    ///
+    /// 1. Create a `RateLimitedMessageSender` allowing 1 message per second with a burst of 2
+    ///    and no waiting
+    /// 2. Send 5 messages back to back
+    /// 3. Verify the first 2 are sent and the remaining 3 are rejected
+    #[test]
+    fn test_rate_limit_engages() {
+        let sent = Arc::new(Mutex::new(0));
+        let sender = RateLimitedMessageSender::new(
+            Box::new(TestMessageSender { sent: sent.clone() }),
+            1,
+            2,
+            Duration::from_millis(0),
+        )
+        .expect("Unable to create sender");
+        let to_service = ServiceId::new("a000").expect("Unable to create service ID");
+
+        let results = (0..5)
+            .map(|_| sender.send(&to_service, ()))
+            .collect::<Vec<_>>();
+
+        assert!(results[..2].iter().all(|res| res.is_ok()));
+        assert!(results[2..].iter().all(|res| res.is_err()));
+        assert_eq!(*sent.lock().expect("sent lock was poisoned"), 2);
+    }
+
+    /// Verify that the `RateLimitedMessageSender` blocks until capacity is available when the
+    /// wait fits within the configured maximum.
+    ///
+    /// 1. Create a `RateLimitedMessageSender` allowing 10 messages per second with a burst of 1
+    ///    and a maximum wait of 1 second
+    /// 2. Send 3 messages back to back
+    /// 3. Verify all 3 are sent and that sending took at least the time needed to refill 2 tokens
+    #[test]
+    fn test_rate_limit_waits() {
+        let sent = Arc::new(Mutex::new(0));
+        let sender = RateLimitedMessageSender::new(
+            Box::new(TestMessageSender { sent: sent.clone() }),
+            10,
+            1,
+            Duration::from_secs(1),
+        )
+        .expect("Unable to create sender");
+        let to_service = ServiceId::new("a000").expect("Unable to create service ID");
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            sender
+                .send(&to_service, ())
+                .expect("Unable to send message");
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(150));
+        assert_eq!(*sent.lock().expect("sent lock was poisoned"), 3);
+    }
+
+    /// Verify that a zero rate or burst size is rejected.
+    #[test]
+    fn test_invalid_arguments() {
+        assert!(RateLimitedMessageSender::<()>::new(
+            Box::new(TestMessageSender {
+                sent: Arc::new(Mutex::new(0))
+            }),
+            0,
+            1,
+            Duration::from_secs(1),
+        )
+        .is_err());
+        assert!(RateLimitedMessageSender::<()>::new(
+            Box::new(TestMessageSender {
+                sent: Arc::new(Mutex::new(0))
+            }),
+            1,
+            0,
+            Duration::from_secs(1),
+        )
+        .is_err());
+    }
+}
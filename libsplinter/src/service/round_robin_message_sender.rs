@@ -0,0 +1,187 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contains `RoundRobinMessageSender`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::{InternalError, InvalidArgumentError};
+
+use super::{MessageSender, ServiceId};
+
+/// A `MessageSender` that distributes messages across several inner senders.
+///
+/// Each call to `send` starts with the inner sender after the one that started the previous call,
+/// in the order the senders were provided, wrapping around at the end. If a sender returns an
+/// error, the message is offered to the next sender in order until one succeeds or every sender
+/// has been tried once, in which case an `InternalError` describing each failure is returned.
+///
+/// The starting sender advances on every call regardless of the outcome, so over `n` calls each
+/// of the `n` senders is tried first exactly once. A failing sender is not removed from the
+/// rotation and will be tried again on later calls. When called concurrently, each call still
+/// receives a distinct starting position.
+pub struct RoundRobinMessageSender<M> {
+    senders: Vec<Box<dyn MessageSender<M>>>,
+    next: AtomicUsize,
+}
+
+impl<M> RoundRobinMessageSender<M> {
+    /// Construct a new `RoundRobinMessageSender`.
+    ///
+    /// # Arguments
+    ///
+    /// * `senders` - The inner `MessageSender`s, in the order they will be used
+    ///
+    /// Returns an `InvalidArgumentError` if `senders` is empty.
+    pub fn new(senders: Vec<Box<dyn MessageSender<M>>>) -> Result<Self, InvalidArgumentError> {
+        if senders.is_empty() {
+            return Err(InvalidArgumentError::new(
+                "senders",
+                "at least one sender is required",
+            ));
+        }
+
+        Ok(Self {
+            senders,
+            next: AtomicUsize::new(0),
+        })
+    }
+}
+
+impl<M: Clone> MessageSender<M> for RoundRobinMessageSender<M> {
+    fn send(&self, to_service: &ServiceId, message: M) -> Result<(), InternalError> {
+        let count = self.senders.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % count;
+
+        let mut errors = Vec::with_capacity(count);
+        for offset in 0..count {
+            let index = (start + offset) % count;
+            match self.senders[index].send(to_service, message.clone()) {
+                Ok(()) => return Ok(()),
+                Err(err) => errors.push(format!("sender {}: {}", index, err)),
+            }
+        }
+
+        Err(InternalError::with_message(format!(
+            "Unable to send message to {}; all {} senders failed: {}",
+            to_service,
+            count,
+            errors.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    struct TestMessageSender {
+        id: usize,
+        fail: bool,
+        sent: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl MessageSender<()> for TestMessageSender {
+        fn send(&self, _to_service: &ServiceId, _message: ()) -> Result<(), InternalError> {
+            if self.fail {
+                return Err(InternalError::with_message("failed".into()));
+            }
+            self.sent
+                .lock()
+                .expect("sent lock was poisoned")
+                .push(self.id);
+            Ok(())
+        }
+    }
+
+    fn create_sender(
+        failures: &[bool],
+        sent: &Arc<Mutex<Vec<usize>>>,
+    ) -> RoundRobinMessageSender<()> {
+        RoundRobinMessageSender::new(
+            failures
+                .iter()
+                .enumerate()
+                .map(|(id, fail)| {
+                    Box::new(TestMessageSender {
+                        id,
+                        fail: *fail,
+                        sent: sent.clone(),
+                    }) as Box<dyn MessageSender<()>>
+                })
+                .collect(),
+        )
+        .expect("Unable to create sender")
+    }
+
+    /// Verify that the `RoundRobinMessageSender` alternates between inner senders.
+    ///
+    /// 1. Create a `RoundRobinMessageSender` with 3 working inner senders
+    /// 2. Send 4 messages
+    /// 3. Verify the senders were used in order, wrapping around to the first
+    #[test]
+    fn test_round_robin() {
+        let sent = Arc::new(Mutex::new(vec![]));
+        let sender = create_sender(&[false, false, false], &sent);
+        let to_service = ServiceId::new("a000").expect("Unable to create service ID");
+
+        for _ in 0..4 {
+            sender
+                .send(&to_service, ())
+                .expect("Unable to send message");
+        }
+
+        assert_eq!(
+            *sent.lock().expect("sent lock was poisoned"),
+            vec![0, 1, 2, 0]
+        );
+    }
+
+    /// Verify that the `RoundRobinMessageSender` skips a failing sender.
+    ///
+    /// 1. Create a `RoundRobinMessageSender` where the second of 3 inner senders fails
+    /// 2. Send 3 messages
+    /// 3. Verify all were sent, with the message that started at the failing sender handled by
+    ///    the next one
+    #[test]
+    fn test_round_robin_skips_failures() {
+        let sent = Arc::new(Mutex::new(vec![]));
+        let sender = create_sender(&[false, true, false], &sent);
+        let to_service = ServiceId::new("a000").expect("Unable to create service ID");
+
+        for _ in 0..3 {
+            sender
+                .send(&to_service, ())
+                .expect("Unable to send message");
+        }
+
+        assert_eq!(*sent.lock().expect("sent lock was poisoned"), vec![0, 2, 2]);
+    }
+
+    /// Verify that the `RoundRobinMessageSender` returns an error only when every inner sender
+    /// fails, and that creating one without senders fails.
+    #[test]
+    fn test_round_robin_all_fail() {
+        let sent = Arc::new(Mutex::new(vec![]));
+        let sender = create_sender(&[true, true], &sent);
+        let to_service = ServiceId::new("a000").expect("Unable to create service ID");
+
+        assert!(sender.send(&to_service, ()).is_err());
+        assert!(sent.lock().expect("sent lock was poisoned").is_empty());
+
+        assert!(RoundRobinMessageSender::<()>::new(vec![]).is_err());
+    }
+}
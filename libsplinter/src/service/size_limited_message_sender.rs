@@ -0,0 +1,120 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contains `SizeLimitedMessageSender`.
+
+use crate::error::InternalError;
+
+use super::{MessageSender, ServiceId};
+
+/// A `MessageSender` that rejects messages that are too large to send.
+///
+/// Each message is serialized with the provided serializer to determine its size. Messages whose
+/// serialized form exceeds the maximum size are rejected with an `InternalError`; all others are
+/// passed to the inner `MessageSender` unchanged.
+pub struct SizeLimitedMessageSender<M> {
+    inner: Box<dyn MessageSender<M>>,
+    serializer: Box<dyn Fn(&M) -> Result<Vec<u8>, InternalError>>,
+    max_size: usize,
+}
+
+impl<M> SizeLimitedMessageSender<M> {
+    /// Construct a new `SizeLimitedMessageSender`.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The `MessageSender` that will send messages within the size limit
+    /// * `serializer` - Produces the serialized form of a message, used to measure its size
+    /// * `max_size` - The maximum allowed size of a serialized message, in bytes
+    pub fn new<F>(inner: Box<dyn MessageSender<M>>, serializer: F, max_size: usize) -> Self
+    where
+        F: Fn(&M) -> Result<Vec<u8>, InternalError> + 'static,
+    {
+        Self {
+            inner,
+            serializer: Box::new(serializer),
+            max_size,
+        }
+    }
+}
+
+impl<M> MessageSender<M> for SizeLimitedMessageSender<M> {
+    fn send(&self, to_service: &ServiceId, message: M) -> Result<(), InternalError> {
+        let size = (self.serializer)(&message)?.len();
+        if size > self.max_size {
+            return Err(InternalError::with_message(format!(
+                "Unable to send message to {}: message size of {} bytes exceeds the maximum \
+                allowed size of {} bytes",
+                to_service, size, self.max_size
+            )));
+        }
+
+        self.inner.send(to_service, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    struct TestMessageSender {
+        sent: Arc<Mutex<Vec<(String, Vec<u8>)>>>,
+    }
+
+    impl MessageSender<Vec<u8>> for TestMessageSender {
+        fn send(&self, to_service: &ServiceId, message: Vec<u8>) -> Result<(), InternalError> {
+            self.sent
+                .lock()
+                .expect("sent lock was poisoned")
+                .push((to_service.to_string(), message));
+            Ok(())
+        }
+    }
+
+    /// Verify that the `SizeLimitedMessageSender` passes messages within the limit to the inner
+    /// sender and rejects messages that exceed it.
+    ///
+    /// 1. Create a `SizeLimitedMessageSender` with a maximum size of 4 bytes
+    /// 2. Send a 4 byte message and verify it is passed to the inner sender
+    /// 3. Send a 5 byte message and verify an error is returned that includes both sizes
+    /// 4. Verify the oversized message was not passed to the inner sender
+    #[test]
+    fn test_size_limited_message_sender() {
+        let sent = Arc::new(Mutex::new(vec![]));
+        let sender = SizeLimitedMessageSender::new(
+            Box::new(TestMessageSender { sent: sent.clone() }),
+            |message: &Vec<u8>| Ok(message.clone()),
+            4,
+        );
+        let to_service = ServiceId::new("a000").expect("Unable to create service ID");
+
+        sender
+            .send(&to_service, b"1234".to_vec())
+            .expect("Unable to send message within limit");
+
+        let err = sender
+            .send(&to_service, b"12345".to_vec())
+            .expect_err("Oversized message was sent");
+        let message = err.to_string();
+        assert!(message.contains("5 bytes"));
+        assert!(message.contains("4 bytes"));
+
+        assert_eq!(
+            *sent.lock().expect("sent lock was poisoned"),
+            vec![("a000".to_string(), b"1234".to_vec())]
+        );
+    }
+}
@@ -14,6 +14,11 @@
 
 //! Implementation of a `StoreFactory` for in memory
 
+#[cfg(any(
+    feature = "authorization-handler-rbac",
+    feature = "node-id-store",
+    feature = "service-lifecycle-store"
+))]
 use diesel::{
     r2d2::{ConnectionManager, Pool},
     sqlite::SqliteConnection,
@@ -33,10 +38,19 @@ use crate::error::InternalError;
 #[cfg(feature = "oauth")]
 use crate::oauth::store::MemoryInflightOAuthRequestStore;
 
+#[cfg(any(
+    feature = "authorization-handler-rbac",
+    feature = "node-id-store",
+    feature = "service-lifecycle-store"
+))]
 use super::sqlite::ConnectionCustomizer;
 use super::StoreFactory;
 
-/// A `StoryFactory` backed by memory.
+/// A `StoreFactory` backed by memory.
+///
+/// The admin service and registry stores are kept in in-process maps, so constructing the factory
+/// does no I/O when only those stores are enabled. The remaining stores use an in-memory SQLite
+/// database, which is created and migrated by `new`.
 pub struct MemoryStoreFactory {
     #[cfg(feature = "biome-credentials")]
     biome_credentials_store: MemoryCredentialsStore,
@@ -50,7 +64,16 @@ pub struct MemoryStoreFactory {
     inflight_request_store: MemoryInflightOAuthRequestStore,
     #[cfg(feature = "biome-profile")]
     biome_profile_store: MemoryUserProfileStore,
+    #[cfg(feature = "admin-service")]
+    admin_service_store: crate::admin::store::memory::MemoryAdminServiceStore,
+    #[cfg(feature = "registry")]
+    registry: crate::registry::MemoryRegistry,
     // to be used for sqlite in memory implementations
+    #[cfg(any(
+        feature = "authorization-handler-rbac",
+        feature = "node-id-store",
+        feature = "service-lifecycle-store"
+    ))]
     pool: Pool<ConnectionManager<SqliteConnection>>,
 }
 
@@ -73,19 +96,28 @@ impl MemoryStoreFactory {
         #[cfg(feature = "biome-profile")]
         let biome_profile_store = MemoryUserProfileStore::new();
 
-        let connection_manager = ConnectionManager::<SqliteConnection>::new(":memory:");
-        let pool = Pool::builder()
-            .max_size(1)
-            .connection_customizer(Box::new(ConnectionCustomizer::default()))
-            .build(connection_manager)
+        #[cfg(any(
+            feature = "authorization-handler-rbac",
+            feature = "node-id-store",
+            feature = "service-lifecycle-store"
+        ))]
+        let pool = {
+            let connection_manager = ConnectionManager::<SqliteConnection>::new(":memory:");
+            let pool = Pool::builder()
+                .max_size(1)
+                .connection_customizer(Box::new(ConnectionCustomizer::default()))
+                .build(connection_manager)
+                .map_err(|err| InternalError::from_source(Box::new(err)))?;
+
+            crate::migrations::run_sqlite_migrations(
+                &*pool
+                    .get()
+                    .map_err(|err| InternalError::from_source(Box::new(err)))?,
+            )
             .map_err(|err| InternalError::from_source(Box::new(err)))?;
 
-        crate::migrations::run_sqlite_migrations(
-            &*pool
-                .get()
-                .map_err(|err| InternalError::from_source(Box::new(err)))?,
-        )
-        .map_err(|err| InternalError::from_source(Box::new(err)))?;
+            pool
+        };
 
         Ok(Self {
             #[cfg(feature = "biome-credentials")]
@@ -100,6 +132,15 @@ impl MemoryStoreFactory {
             inflight_request_store,
             #[cfg(feature = "biome-profile")]
             biome_profile_store,
+            #[cfg(feature = "admin-service")]
+            admin_service_store: crate::admin::store::memory::MemoryAdminServiceStore::new(),
+            #[cfg(feature = "registry")]
+            registry: crate::registry::MemoryRegistry::new(),
+            #[cfg(any(
+                feature = "authorization-handler-rbac",
+                feature = "node-id-store",
+                feature = "service-lifecycle-store"
+            ))]
             pool,
         })
     }
@@ -128,9 +169,7 @@ impl StoreFactory for MemoryStoreFactory {
 
     #[cfg(feature = "admin-service")]
     fn get_admin_service_store(&self) -> Box<dyn crate::admin::store::AdminServiceStore> {
-        Box::new(crate::admin::store::diesel::DieselAdminServiceStore::new(
-            self.pool.clone(),
-        ))
+        Box::new(self.admin_service_store.clone())
     }
 
     #[cfg(feature = "oauth")]
@@ -142,7 +181,7 @@ impl StoreFactory for MemoryStoreFactory {
 
     #[cfg(feature = "registry")]
     fn get_registry_store(&self) -> Box<dyn crate::registry::RwRegistry> {
-        Box::new(crate::registry::DieselRegistry::new(self.pool.clone()))
+        Box::new(self.registry.clone())
     }
 
     #[cfg(feature = "authorization-handler-rbac")]
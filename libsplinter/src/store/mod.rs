@@ -24,7 +24,22 @@ pub mod postgres;
 #[cfg(all(feature = "store-factory", feature = "sqlite"))]
 pub mod sqlite;
 
+#[cfg(feature = "store-factory")]
+use std::time::Duration;
+
+#[cfg(feature = "store-factory")]
+use crate::error::InternalError;
+
+/// How long the health check of a database-backed `StoreFactory` waits for a connection
+#[cfg(feature = "store-factory")]
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+
 /// An abstract factory for creating Splinter stores backed by the same storage
+///
+/// Stores that belong to services built on top of this crate, such as scabbard's consensus store,
+/// cannot be provided here, as those crates depend on this one. They are created by the service
+/// crate's own factories (for example, scabbard's `PooledScabbardStoreFactory`) from the same
+/// connection pool that backs this factory.
 #[cfg(feature = "store-factory")]
 pub trait StoreFactory {
     /// Get a new `CredentialsStore`
@@ -67,4 +82,14 @@ pub trait StoreFactory {
 
     #[cfg(feature = "service-lifecycle-store")]
     fn get_lifecycle_store(&self) -> Box<dyn crate::runtime::service::LifecycleStore + Send>;
+
+    /// Checks that the storage behind the factory can be reached, for use by readiness probes.
+    ///
+    /// Database-backed factories check out a connection and run `SELECT 1`, failing if this
+    /// cannot be done within the [HEALTH_CHECK_TIMEOUT]. A pool whose connections are all in use
+    /// fails the check once the timeout passes, rather than blocking; this includes the single
+    /// connection of an in-memory SQLite pool. By default, the check always succeeds.
+    fn health_check(&self) -> Result<(), InternalError> {
+        Ok(())
+    }
 }
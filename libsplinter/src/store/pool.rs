@@ -12,7 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(any(
+    any(feature = "postgres", feature = "sqlite"),
+    all(feature = "diesel", feature = "registry")
+))]
+use std::sync::TryLockError;
 use std::sync::{Arc, RwLock};
+#[cfg(any(
+    any(feature = "postgres", feature = "sqlite"),
+    all(feature = "diesel", feature = "registry")
+))]
+use std::thread;
+#[cfg(any(
+    any(feature = "postgres", feature = "sqlite"),
+    all(feature = "diesel", feature = "registry")
+))]
+use std::time::{Duration, Instant};
 
 use diesel::r2d2::{ConnectionManager, Pool};
 
@@ -75,8 +90,55 @@ impl<C: diesel::Connection> ConnectionPool<C> {
                 .and_then(|pool| f(&*conn!(pool)?)),
         }
     }
+
+    /// Checks out a connection and runs `SELECT 1` on it, failing if this cannot be done within
+    /// `timeout`.
+    ///
+    /// Neither the write lock of a write-exclusive pool nor a connection is waited for past the
+    /// timeout, so the check fails, rather than deadlocking, when every connection is held; for
+    /// example, while the single connection of an in-memory SQLite pool is in use.
+    pub fn health_check(&self, timeout: Duration) -> Result<(), InternalError> {
+        let deadline = Instant::now() + timeout;
+        let pool = match self {
+            Self::Normal(pool) => pool.clone(),
+            // The pool is cloned out of the lock, as a `SELECT 1` does not need to exclude
+            // writers
+            Self::WriteExclusive(locked_pool) => loop {
+                match locked_pool.try_read() {
+                    Ok(pool) => break pool.clone(),
+                    Err(TryLockError::Poisoned(_)) => {
+                        return Err(InternalError::with_message(
+                            "Connection pool rwlock is poisoned".into(),
+                        ))
+                    }
+                    Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                        thread::sleep(HEALTH_CHECK_LOCK_RETRY)
+                    }
+                    Err(TryLockError::WouldBlock) => {
+                        return Err(InternalError::with_message(format!(
+                            "Timed out after {:?} waiting for the connection pool's lock",
+                            timeout
+                        )))
+                    }
+                }
+            },
+        };
+
+        let conn = pool
+            .get_timeout(deadline.saturating_duration_since(Instant::now()))
+            .map_err(|e| InternalError::from_source(Box::new(e)))?;
+        diesel::connection::SimpleConnection::batch_execute(&*conn, "SELECT 1")
+            .map_err(|e| InternalError::from_source(Box::new(e)))
+    }
 }
 
+/// How often a health check retries taking the read lock of a write-exclusive pool
+#[cfg(any(
+    any(feature = "postgres", feature = "sqlite"),
+    all(feature = "diesel", feature = "registry")
+))]
+const HEALTH_CHECK_LOCK_RETRY: Duration = Duration::from_millis(10);
+
 impl<C: diesel::Connection> Clone for ConnectionPool<C> {
     fn clone(&self) -> Self {
         match self {
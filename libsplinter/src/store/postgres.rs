@@ -14,6 +14,8 @@
 
 //! Implementation of a `StoreFactory` for PostgreSQL
 
+use std::sync::{Arc, RwLock};
+
 use diesel::{
     pg::PgConnection,
     r2d2::{ConnectionManager, Pool},
@@ -22,7 +24,8 @@ use diesel::{
 use crate::error::InternalError;
 use crate::migrations::any_pending_postgres_migrations;
 
-use super::StoreFactory;
+use super::pool::ConnectionPool;
+use super::{StoreFactory, HEALTH_CHECK_TIMEOUT};
 
 /// Create a Postgres connection pool.
 ///
@@ -58,88 +61,112 @@ pub fn create_postgres_connection_pool(
     Ok(pool)
 }
 
-/// A `StoryFactory` backed by a PostgreSQL database.
+/// A `StoreFactory` backed by a PostgreSQL database.
+///
+/// The stores share the factory's connection pool. A factory created with
+/// `new_with_write_exclusivity` creates every store with write exclusivity, as the
+/// `SqliteStoreFactory` does.
 pub struct PgStoreFactory {
-    pool: Pool<ConnectionManager<PgConnection>>,
+    pool: ConnectionPool<PgConnection>,
 }
 
 impl PgStoreFactory {
+    /// Create a new `PgStoreFactory`.
     pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
-        Self { pool }
+        Self { pool: pool.into() }
+    }
+
+    /// Create a new `PgStoreFactory` with shared write-exclusivity.
+    pub fn new_with_write_exclusivity(
+        pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+    ) -> Self {
+        Self { pool: pool.into() }
     }
 }
 
+/// Creates a store of the given type on the factory's pool, with write exclusivity if the pool
+/// is shared with it
+macro_rules! new_store {
+    ($pool:expr, $($store:ident)::+) => {
+        match $pool {
+            ConnectionPool::Normal(pool) => Box::new($($store)::+::new(pool.clone())),
+            ConnectionPool::WriteExclusive(pool) => {
+                Box::new($($store)::+::new_with_write_exclusivity(pool.clone()))
+            }
+        }
+    };
+}
+
 impl StoreFactory for PgStoreFactory {
     #[cfg(feature = "biome-credentials")]
     fn get_biome_credentials_store(&self) -> Box<dyn crate::biome::CredentialsStore> {
-        Box::new(crate::biome::DieselCredentialsStore::new(self.pool.clone()))
+        new_store!(&self.pool, crate::biome::DieselCredentialsStore)
     }
 
     #[cfg(feature = "biome-key-management")]
     fn get_biome_key_store(&self) -> Box<dyn crate::biome::KeyStore> {
-        Box::new(crate::biome::DieselKeyStore::new(self.pool.clone()))
+        new_store!(&self.pool, crate::biome::DieselKeyStore)
     }
 
     #[cfg(feature = "biome-credentials")]
     fn get_biome_refresh_token_store(&self) -> Box<dyn crate::biome::RefreshTokenStore> {
-        Box::new(crate::biome::DieselRefreshTokenStore::new(
-            self.pool.clone(),
-        ))
+        new_store!(&self.pool, crate::biome::DieselRefreshTokenStore)
     }
 
     #[cfg(feature = "oauth")]
     fn get_biome_oauth_user_session_store(&self) -> Box<dyn crate::biome::OAuthUserSessionStore> {
-        Box::new(crate::biome::DieselOAuthUserSessionStore::new(
-            self.pool.clone(),
-        ))
+        new_store!(&self.pool, crate::biome::DieselOAuthUserSessionStore)
     }
 
     #[cfg(feature = "admin-service")]
     fn get_admin_service_store(&self) -> Box<dyn crate::admin::store::AdminServiceStore> {
-        Box::new(crate::admin::store::diesel::DieselAdminServiceStore::new(
-            self.pool.clone(),
-        ))
+        new_store!(
+            &self.pool,
+            crate::admin::store::diesel::DieselAdminServiceStore
+        )
     }
 
     #[cfg(feature = "oauth")]
     fn get_oauth_inflight_request_store(
         &self,
     ) -> Box<dyn crate::oauth::store::InflightOAuthRequestStore> {
-        Box::new(crate::oauth::store::DieselInflightOAuthRequestStore::new(
-            self.pool.clone(),
-        ))
+        new_store!(
+            &self.pool,
+            crate::oauth::store::DieselInflightOAuthRequestStore
+        )
     }
 
     #[cfg(feature = "registry")]
     fn get_registry_store(&self) -> Box<dyn crate::registry::RwRegistry> {
-        Box::new(crate::registry::DieselRegistry::new(self.pool.clone()))
+        new_store!(&self.pool, crate::registry::DieselRegistry)
     }
 
     #[cfg(feature = "authorization-handler-rbac")]
     fn get_role_based_authorization_store(
         &self,
     ) -> Box<dyn crate::rbac::store::RoleBasedAuthorizationStore> {
-        Box::new(crate::rbac::store::DieselRoleBasedAuthorizationStore::new(
-            self.pool.clone(),
-        ))
+        new_store!(
+            &self.pool,
+            crate::rbac::store::DieselRoleBasedAuthorizationStore
+        )
     }
 
     #[cfg(feature = "biome-profile")]
     fn get_biome_user_profile_store(&self) -> Box<dyn crate::biome::UserProfileStore> {
-        Box::new(crate::biome::DieselUserProfileStore::new(self.pool.clone()))
+        new_store!(&self.pool, crate::biome::DieselUserProfileStore)
     }
 
     #[cfg(feature = "node-id-store")]
     fn get_node_id_store(&self) -> Box<dyn crate::node_id::store::NodeIdStore> {
-        Box::new(crate::node_id::store::diesel::DieselNodeIdStore::new(
-            self.pool.clone(),
-        ))
+        new_store!(&self.pool, crate::node_id::store::diesel::DieselNodeIdStore)
     }
 
     #[cfg(feature = "service-lifecycle-store")]
     fn get_lifecycle_store(&self) -> Box<dyn crate::runtime::service::LifecycleStore + Send> {
-        Box::new(crate::runtime::service::DieselLifecycleStore::new(
-            self.pool.clone(),
-        ))
+        new_store!(&self.pool, crate::runtime::service::DieselLifecycleStore)
+    }
+
+    fn health_check(&self) -> Result<(), InternalError> {
+        self.pool.health_check(HEALTH_CHECK_TIMEOUT)
     }
 }
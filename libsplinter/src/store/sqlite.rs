@@ -13,20 +13,50 @@
 // limitations under the License.
 
 //! Implementation of a `StoreFactory` for SQLite
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use diesel::{
     connection::SimpleConnection,
-    r2d2::{ConnectionManager, CustomizeConnection, Pool},
+    r2d2::{ConnectionManager, CustomizeConnection, Pool, PoolError, PooledConnection},
     sqlite::SqliteConnection,
+    Connection,
 };
 
-use crate::error::InternalError;
-use crate::migrations::{any_pending_sqlite_migrations, run_sqlite_migrations};
+use crate::error::{InternalError, ResourceTemporarilyUnavailableError};
+use crate::migrations::{
+    any_pending_sqlite_migrations, pending_sqlite_migrations, run_sqlite_migrations, MigrationName,
+    MigrationStatus,
+};
 #[cfg(feature = "authorization-handler-rbac")]
 use crate::rbac::store::{DieselRoleBasedAuthorizationStore, RoleBasedAuthorizationStore};
 
-use super::StoreFactory;
+use super::pool::ConnectionPool;
+use super::{StoreFactory, HEALTH_CHECK_TIMEOUT};
+
+/// How long a connection waits for a lock held by another connection before failing with
+/// "database is locked", unless another timeout is given
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// How long checking out a connection waits for one to be returned to the pool, unless another
+/// timeout is given; this is r2d2's default
+pub const DEFAULT_CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The number of "database is locked" errors handled by the pools created by
+/// `create_sqlite_connection_pool`
+static DATABASE_LOCKED_ERRORS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of "database is locked" errors that have occurred while connecting to SQLite,
+/// across every pool created by [create_sqlite_connection_pool] in this process.
+///
+/// These errors are not logged, as the connection manager retries the connection. The count can
+/// be used to tell whether the `busy_timeout` or journal mode needs tuning.
+pub fn database_locked_errors() -> u64 {
+    DATABASE_LOCKED_ERRORS.load(Ordering::Relaxed)
+}
 
 /// Create a SQLite connection pool.
 ///
@@ -36,23 +66,62 @@ use super::StoreFactory;
 ///
 /// # Errors
 ///
-/// An [InternalError] is returned if
+/// A [SqlitePoolError::Exhausted] is returned if no connection could be checked out before the
+/// pool's connection timeout because every connection was in use. Otherwise, a
+/// [SqlitePoolError::InternalError] is returned if
 /// * The file does not exist
 /// * The pool cannot be created
 /// * The database requires any pending migrations
 pub fn create_sqlite_connection_pool(
     conn_str: &str,
-) -> Result<Pool<ConnectionManager<SqliteConnection>>, InternalError> {
+) -> Result<Pool<ConnectionManager<SqliteConnection>>, SqlitePoolError> {
+    create_sqlite_connection_pool_with_busy_timeout(conn_str, DEFAULT_BUSY_TIMEOUT)
+}
+
+/// Create a SQLite connection pool whose connections wait up to `busy_timeout` for a lock held by
+/// another connection, instead of the [DEFAULT_BUSY_TIMEOUT].
+///
+/// # Errors
+///
+/// A [SqlitePoolError] is returned for the same reasons as [create_sqlite_connection_pool].
+pub fn create_sqlite_connection_pool_with_busy_timeout(
+    conn_str: &str,
+    busy_timeout: Duration,
+) -> Result<Pool<ConnectionManager<SqliteConnection>>, SqlitePoolError> {
+    create_sqlite_connection_pool_with_config(
+        conn_str,
+        SqlitePoolConfig::default().with_busy_timeout(busy_timeout),
+    )
+}
+
+/// Create a SQLite connection pool whose connections are configured by the given
+/// [SqlitePoolConfig].
+///
+/// The journal mode is not applied to an in-memory database, which always uses the `MEMORY`
+/// journal mode.
+///
+/// # Errors
+///
+/// A [SqlitePoolError] is returned for the same reasons as [create_sqlite_connection_pool].
+pub fn create_sqlite_connection_pool_with_config(
+    conn_str: &str,
+    mut config: SqlitePoolConfig,
+) -> Result<Pool<ConnectionManager<SqliteConnection>>, SqlitePoolError> {
+    if conn_str == ":memory:" {
+        config.journal_mode = None;
+    }
     if (conn_str != ":memory:") && !std::path::Path::new(&conn_str).exists() {
-        return Err(InternalError::with_message(format!(
-            "Database file '{}' does not exist",
-            conn_str
+        return Err(SqlitePoolError::InternalError(InternalError::with_message(
+            format!("Database file '{}' does not exist", conn_str),
         )));
     }
     let connection_manager = ConnectionManager::<SqliteConnection>::new(conn_str);
     let mut pool_builder = Pool::builder()
-        .connection_customizer(Box::new(ConnectionCustomizer::default()))
-        .error_handler(Box::new(HandlePoolError));
+        .connection_timeout(config.connection_timeout)
+        .connection_customizer(Box::new(ConnectionCustomizer::with_config(config)))
+        .error_handler(Box::new(HandlePoolError {
+            database_locked_errors: &DATABASE_LOCKED_ERRORS,
+        }));
     // A new database is created for each connection to the in-memory SQLite
     // implementation; to ensure that the resulting stores will operate on the same
     // database, only one connection is allowed.
@@ -60,30 +129,138 @@ pub fn create_sqlite_connection_pool(
         pool_builder = pool_builder.max_size(1);
     }
     let pool = pool_builder.build(connection_manager).map_err(|err| {
-        InternalError::from_source_with_prefix(
+        SqlitePoolError::InternalError(InternalError::from_source_with_prefix(
             Box::new(err),
             "Failed to build connection pool".to_string(),
-        )
+        ))
     })?;
-    let conn = pool
-        .get()
-        .map_err(|err| InternalError::from_source(Box::new(err)))?;
+    let conn = get_sqlite_connection(&pool)?;
     if conn_str == ":memory:" {
         run_sqlite_migrations(&conn)?;
     } else if !any_pending_sqlite_migrations(&conn)? {
-        return Err(InternalError::with_message(String::from(
-            "This version of splinter requires migrations that are not yet applied \
-            to the database. Run `splinter database migrate` to apply migrations \
-            before running splinterd",
+        return Err(SqlitePoolError::InternalError(InternalError::with_message(
+            String::from(
+                "This version of splinter requires migrations that are not yet applied \
+                to the database. Run `splinter database migrate` to apply migrations \
+                before running splinterd",
+            ),
         )));
     }
 
     Ok(pool)
 }
 
+/// Reports whether the SQLite database has had every migration applied, and which migrations are
+/// pending if not.
+///
+/// Unlike [create_sqlite_connection_pool], no pool is built and pending migrations are not an
+/// error, so a caller can decide whether to run `splinter database migrate` first. A single
+/// connection is opened, and the database is not changed.
+///
+/// # Arguments
+///
+/// * conn_str - a filename or ":memory:"; an in-memory database has every migration pending
+///
+/// # Errors
+///
+/// An [InternalError] is returned if the file does not exist, or cannot be opened or read.
+pub fn sqlite_migration_status(conn_str: &str) -> Result<MigrationStatus, InternalError> {
+    // Opening a file that does not exist would create it
+    if (conn_str != ":memory:") && !std::path::Path::new(&conn_str).exists() {
+        return Err(InternalError::with_message(format!(
+            "Database file '{}' does not exist",
+            conn_str
+        )));
+    }
+    let conn = SqliteConnection::establish(conn_str)
+        .map_err(|err| InternalError::from_source(Box::new(err)))?;
+
+    let pending = pending_sqlite_migrations(&conn)?;
+    if pending.is_empty() {
+        Ok(MigrationStatus::UpToDate)
+    } else {
+        Ok(MigrationStatus::Pending(
+            pending.into_iter().map(MigrationName::from).collect(),
+        ))
+    }
+}
+
+/// Checks out a connection from a SQLite connection pool.
+///
+/// # Errors
+///
+/// A [SqlitePoolError::Exhausted] is returned if every connection stayed in use until the pool's
+/// connection timeout, so that the caller can back off and retry. A
+/// [SqlitePoolError::InternalError] is returned if a connection could not be opened.
+pub fn get_sqlite_connection(
+    pool: &Pool<ConnectionManager<SqliteConnection>>,
+) -> Result<PooledConnection<ConnectionManager<SqliteConnection>>, SqlitePoolError> {
+    pool.get().map_err(SqlitePoolError::from)
+}
+
+/// An error returned when a SQLite connection pool cannot be created, or a connection cannot be
+/// checked out of one
+#[derive(Debug)]
+pub enum SqlitePoolError {
+    /// Every connection of the pool stayed in use until the pool's connection timeout
+    Exhausted(ResourceTemporarilyUnavailableError),
+    InternalError(InternalError),
+}
+
+impl Error for SqlitePoolError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SqlitePoolError::Exhausted(err) => Some(err),
+            SqlitePoolError::InternalError(err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for SqlitePoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SqlitePoolError::Exhausted(err) => write!(f, "Connection pool exhausted: {}", err),
+            SqlitePoolError::InternalError(err) => f.write_str(&err.to_string()),
+        }
+    }
+}
+
+impl From<PoolError> for SqlitePoolError {
+    fn from(err: PoolError) -> Self {
+        // r2d2 only returns an error once the connection timeout has passed. The error includes
+        // the last failure to open a connection, if there was one, so an error without one means
+        // that every connection was in use.
+        if err.to_string() == POOL_TIMEOUT_MESSAGE {
+            SqlitePoolError::Exhausted(ResourceTemporarilyUnavailableError::from_source(Box::new(
+                err,
+            )))
+        } else {
+            SqlitePoolError::InternalError(InternalError::from_source(Box::new(err)))
+        }
+    }
+}
+
+impl From<InternalError> for SqlitePoolError {
+    fn from(err: InternalError) -> Self {
+        SqlitePoolError::InternalError(err)
+    }
+}
+
+impl From<SqlitePoolError> for InternalError {
+    fn from(err: SqlitePoolError) -> Self {
+        match err {
+            SqlitePoolError::InternalError(err) => err,
+            err => InternalError::from_source(Box::new(err)),
+        }
+    }
+}
+
+/// The message of an r2d2 timeout that did not fail to open a connection
+const POOL_TIMEOUT_MESSAGE: &str = "timed out waiting for connection";
+
 pub fn create_sqlite_connection_pool_with_write_exclusivity(
     conn_str: &str,
-) -> Result<Arc<RwLock<Pool<ConnectionManager<SqliteConnection>>>>, InternalError> {
+) -> Result<Arc<RwLock<Pool<ConnectionManager<SqliteConnection>>>>, SqlitePoolError> {
     Ok(Arc::new(RwLock::new(create_sqlite_connection_pool(
         conn_str,
     )?)))
@@ -197,27 +374,158 @@ impl StoreFactory for SqliteStoreFactory {
             ),
         )
     }
+
+    fn health_check(&self) -> Result<(), InternalError> {
+        ConnectionPool::from(self.pool.clone()).health_check(HEALTH_CHECK_TIMEOUT)
+    }
+}
+
+/// The SQLite journal mode set on each connection of a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SqliteJournalMode {
+    Delete,
+    Truncate,
+    Persist,
+    /// Write-ahead logging, which lets readers continue while a single writer commits
+    Wal,
+}
+
+impl SqliteJournalMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SqliteJournalMode::Delete => "DELETE",
+            SqliteJournalMode::Truncate => "TRUNCATE",
+            SqliteJournalMode::Persist => "PERSIST",
+            SqliteJournalMode::Wal => "WAL",
+        }
+    }
 }
 
-#[derive(Default, Debug)]
+/// How often SQLite syncs to disk, set on each connection of a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SqliteSynchronous {
+    Off,
+    /// Safe from corruption in WAL mode, though a commit may be rolled back by a power loss
+    Normal,
+    Full,
+    Extra,
+}
+
+impl SqliteSynchronous {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SqliteSynchronous::Off => "OFF",
+            SqliteSynchronous::Normal => "NORMAL",
+            SqliteSynchronous::Full => "FULL",
+            SqliteSynchronous::Extra => "EXTRA",
+        }
+    }
+}
+
+/// The settings applied to each connection of a pool created by
+/// [create_sqlite_connection_pool_with_config].
+///
+/// By default, the journal mode and synchronous level are left as SQLite's defaults, the busy
+/// timeout is the [DEFAULT_BUSY_TIMEOUT] and the connection timeout is the
+/// [DEFAULT_CONNECTION_TIMEOUT].
+#[derive(Clone, Debug)]
+pub struct SqlitePoolConfig {
+    journal_mode: Option<SqliteJournalMode>,
+    synchronous: Option<SqliteSynchronous>,
+    busy_timeout: Duration,
+    connection_timeout: Duration,
+}
+
+impl SqlitePoolConfig {
+    pub fn with_journal_mode(mut self, journal_mode: SqliteJournalMode) -> Self {
+        self.journal_mode = Some(journal_mode);
+        self
+    }
+
+    pub fn with_synchronous(mut self, synchronous: SqliteSynchronous) -> Self {
+        self.synchronous = Some(synchronous);
+        self
+    }
+
+    pub fn with_busy_timeout(mut self, busy_timeout: Duration) -> Self {
+        self.busy_timeout = busy_timeout;
+        self
+    }
+
+    /// Sets how long checking out a connection waits for one to be returned to the pool before
+    /// failing with [SqlitePoolError::Exhausted]
+    pub fn with_connection_timeout(mut self, connection_timeout: Duration) -> Self {
+        self.connection_timeout = connection_timeout;
+        self
+    }
+}
+
+impl Default for SqlitePoolConfig {
+    fn default() -> Self {
+        Self {
+            journal_mode: None,
+            synchronous: None,
+            busy_timeout: DEFAULT_BUSY_TIMEOUT,
+            connection_timeout: DEFAULT_CONNECTION_TIMEOUT,
+        }
+    }
+}
+
+#[derive(Debug)]
 /// Foreign keys must be enabled on a per connection basis. This customizer will be added to the
 /// SQLite pool builder and then ran against every connection returned from the pool.
-pub struct ConnectionCustomizer;
+///
+/// The customizer also applies the settings of a [SqlitePoolConfig], which by default only sets
+/// the busy timeout to [DEFAULT_BUSY_TIMEOUT].
+pub struct ConnectionCustomizer {
+    config: SqlitePoolConfig,
+}
+
+impl ConnectionCustomizer {
+    pub fn with_busy_timeout(busy_timeout: Duration) -> Self {
+        Self::with_config(SqlitePoolConfig::default().with_busy_timeout(busy_timeout))
+    }
+
+    pub fn with_config(config: SqlitePoolConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Default for ConnectionCustomizer {
+    fn default() -> Self {
+        Self::with_config(SqlitePoolConfig::default())
+    }
+}
 
 impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for ConnectionCustomizer {
     fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
-        conn.batch_execute(
-            r#"
-            PRAGMA busy_timeout = 2000;
-            PRAGMA foreign_keys = ON;
-            "#,
-        )
-        .map_err(diesel::r2d2::Error::QueryError)
+        // The busy timeout is set first, so that changing the journal mode waits for other
+        // connections rather than failing
+        let mut pragmas = format!(
+            "PRAGMA busy_timeout = {};\n",
+            self.config.busy_timeout.as_millis()
+        );
+        if let Some(journal_mode) = self.config.journal_mode {
+            pragmas.push_str(&format!(
+                "PRAGMA journal_mode = {};\n",
+                journal_mode.as_str()
+            ));
+        }
+        if let Some(synchronous) = self.config.synchronous {
+            pragmas.push_str(&format!("PRAGMA synchronous = {};\n", synchronous.as_str()));
+        }
+        pragmas.push_str("PRAGMA foreign_keys = ON;\n");
+
+        conn.batch_execute(&pragmas)
+            .map_err(diesel::r2d2::Error::QueryError)
     }
 }
 
 #[derive(Debug)]
-struct HandlePoolError;
+struct HandlePoolError {
+    /// Counts the "database is locked" errors, which are not logged
+    database_locked_errors: &'static AtomicU64,
+}
 
 impl diesel::r2d2::HandleError<diesel::r2d2::Error> for HandlePoolError {
     fn handle_error(&self, error: diesel::r2d2::Error) {
@@ -225,6 +533,214 @@ impl diesel::r2d2::HandleError<diesel::r2d2::Error> for HandlePoolError {
         // connection. The connection will be retried by the connection manager.
         if &error.to_string() != "database is locked" {
             error!("{}", error);
+        } else {
+            self.database_locked_errors.fetch_add(1, Ordering::Relaxed);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use diesel::r2d2::HandleError;
+    use diesel::result::{DatabaseErrorKind, Error as DieselError};
+    use diesel::sql_types::{Integer, Text};
+    use diesel::RunQueryDsl;
+
+    #[derive(QueryableByName)]
+    struct BusyTimeout {
+        #[sql_type = "Integer"]
+        timeout: i32,
+    }
+
+    fn busy_timeout(pool: &Pool<ConnectionManager<SqliteConnection>>) -> i32 {
+        let conn = pool.get().expect("Unable to get connection");
+        diesel::sql_query("PRAGMA busy_timeout")
+            .get_result::<BusyTimeout>(&*conn)
+            .expect("Unable to read busy_timeout")
+            .timeout
+    }
+
+    /// Verify that the busy timeout is applied to the connections checked out of a pool, using the
+    /// default unless another timeout is given.
+    #[test]
+    fn test_busy_timeout_applied_to_connections() {
+        let pool = create_sqlite_connection_pool(":memory:").expect("Unable to create pool");
+        assert_eq!(busy_timeout(&pool), 2000);
+
+        let pool =
+            create_sqlite_connection_pool_with_busy_timeout(":memory:", Duration::from_secs(15))
+                .expect("Unable to create pool");
+        assert_eq!(busy_timeout(&pool), 15000);
+    }
+
+    /// Verify that a connection that cannot be checked out because every connection of the pool
+    /// is in use is reported as an exhausted pool.
+    #[test]
+    fn test_exhausted_pool() {
+        let pool = create_sqlite_connection_pool_with_config(
+            ":memory:",
+            SqlitePoolConfig::default().with_connection_timeout(Duration::from_millis(100)),
+        )
+        .expect("Unable to create pool");
+
+        let _conn = get_sqlite_connection(&pool).expect("Unable to get connection");
+        match get_sqlite_connection(&pool) {
+            Err(SqlitePoolError::Exhausted(_)) => (),
+            Err(err) => panic!("Expected an exhausted pool, got {:?}", err),
+            Ok(_) => panic!("Expected an exhausted pool, got a connection"),
+        }
+    }
+
+    /// Verify that the health check of a factory backed by an in-memory database succeeds while its
+    /// single connection is free, and fails once the timeout passes, rather than blocking, while
+    /// the connection or the pool's write lock is held.
+    #[test]
+    fn test_health_check() {
+        let pool = create_sqlite_connection_pool_with_write_exclusivity(":memory:")
+            .expect("Unable to create pool");
+        let factory = SqliteStoreFactory::new_with_write_exclusivity(pool.clone());
+
+        factory.health_check().expect("Health check failed");
+
+        {
+            let _conn = pool
+                .read()
+                .expect("Pool lock poisoned")
+                .get()
+                .expect("Unable to get connection");
+            let started = std::time::Instant::now();
+            assert!(factory.health_check().is_err());
+            assert!(started.elapsed() >= HEALTH_CHECK_TIMEOUT);
+        }
+
+        {
+            let _write = pool.write().expect("Pool lock poisoned");
+            assert!(factory.health_check().is_err());
+        }
+
+        factory.health_check().expect("Health check failed");
+    }
+
+    /// Verify that the migration status of a database is reported without changing it.
+    ///
+    /// 1. Create an empty database file and verify every migration is pending, in order
+    /// 2. Verify the status is unchanged, as the check did not apply the migrations
+    /// 3. Run the migrations and verify the database is up to date
+    /// 4. Verify that a file that does not exist is an error, and is not created
+    #[test]
+    fn test_sqlite_migration_status() {
+        let dir = tempfile::tempdir().expect("Unable to create temp dir");
+        let path = dir.path().join("splinter.db");
+        let conn_str = path.to_str().expect("Path is not UTF-8");
+        std::fs::File::create(&path).expect("Unable to create database file");
+
+        let pending = match sqlite_migration_status(conn_str).expect("Unable to get status") {
+            MigrationStatus::Pending(pending) => pending,
+            MigrationStatus::UpToDate => panic!("Expected pending migrations"),
+        };
+        assert!(!pending.is_empty());
+        let mut sorted = pending.clone();
+        sorted.sort();
+        assert_eq!(pending, sorted);
+
+        assert_eq!(
+            sqlite_migration_status(conn_str).expect("Unable to get status"),
+            MigrationStatus::Pending(pending)
+        );
+
+        run_sqlite_migrations(
+            &SqliteConnection::establish(conn_str).expect("Unable to open database"),
+        )
+        .expect("Unable to run migrations");
+        assert_eq!(
+            sqlite_migration_status(conn_str).expect("Unable to get status"),
+            MigrationStatus::UpToDate
+        );
+
+        let missing = dir.path().join("missing.db");
+        assert!(sqlite_migration_status(missing.to_str().expect("Path is not UTF-8")).is_err());
+        assert!(!missing.exists());
+    }
+
+    #[derive(QueryableByName)]
+    struct JournalMode {
+        #[sql_type = "Text"]
+        journal_mode: String,
+    }
+
+    #[derive(QueryableByName)]
+    struct Synchronous {
+        #[sql_type = "Integer"]
+        synchronous: i32,
+    }
+
+    fn journal_mode(pool: &Pool<ConnectionManager<SqliteConnection>>) -> String {
+        let conn = pool.get().expect("Unable to get connection");
+        diesel::sql_query("PRAGMA journal_mode")
+            .get_result::<JournalMode>(&*conn)
+            .expect("Unable to read journal_mode")
+            .journal_mode
+    }
+
+    /// Verify that the journal mode and synchronous level of a pool's config are applied to its
+    /// connections, except for the journal mode of an in-memory database.
+    ///
+    /// 1. Create and migrate a database file
+    /// 2. Create a pool for the file with WAL and NORMAL, and verify both are applied
+    /// 3. Create an in-memory pool with WAL, and verify its journal mode is still MEMORY
+    #[test]
+    fn test_pool_config_applied_to_connections() {
+        let dir = tempfile::tempdir().expect("Unable to create temp dir");
+        let path = dir.path().join("splinter.db");
+        let conn_str = path.to_str().expect("Path is not UTF-8");
+        {
+            let conn =
+                SqliteConnection::establish(conn_str).expect("Unable to create database file");
+            run_sqlite_migrations(&conn).expect("Unable to run migrations");
+        }
+
+        let config = SqlitePoolConfig::default()
+            .with_journal_mode(SqliteJournalMode::Wal)
+            .with_synchronous(SqliteSynchronous::Normal);
+
+        let pool = create_sqlite_connection_pool_with_config(conn_str, config.clone())
+            .expect("Unable to create pool");
+        assert_eq!(journal_mode(&pool), "wal");
+        let conn = pool.get().expect("Unable to get connection");
+        assert_eq!(
+            diesel::sql_query("PRAGMA synchronous")
+                .get_result::<Synchronous>(&*conn)
+                .expect("Unable to read synchronous")
+                .synchronous,
+            1
+        );
+
+        let pool = create_sqlite_connection_pool_with_config(":memory:", config)
+            .expect("Unable to create pool");
+        assert_eq!(journal_mode(&pool), "memory");
+    }
+
+    /// Verify that the pool error handler counts "database is locked" errors, and only those.
+    #[test]
+    fn test_handle_pool_error_counts_database_locked() {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let handler = HandlePoolError {
+            database_locked_errors: &COUNTER,
+        };
+
+        let query_error = |message: &str| {
+            diesel::r2d2::Error::QueryError(DieselError::DatabaseError(
+                DatabaseErrorKind::__Unknown,
+                Box::new(message.to_string()),
+            ))
+        };
+
+        handler.handle_error(query_error("database is locked"));
+        handler.handle_error(query_error("disk I/O error"));
+        handler.handle_error(query_error("database is locked"));
+
+        assert_eq!(COUNTER.load(Ordering::Relaxed), 2);
+    }
+}
@@ -16,7 +16,7 @@ mod frame;
 mod tcp;
 mod tls;
 
-pub use tcp::TcpTransport;
+pub use tcp::{TcpOptions, TcpTransport};
 pub use tls::{TlsConnection, TlsInitError, TlsTransport};
 
 #[cfg(test)]
@@ -14,7 +14,9 @@
 
 use mio::{net::TcpStream as MioTcpStream, Evented};
 
+use std::io;
 use std::net::{Shutdown, TcpListener as StdTcpListener, TcpStream};
+use std::time::Duration;
 
 use crate::transport::{
     AcceptError, ConnectError, Connection, DisconnectError, ListenError, Listener, RecvError,
@@ -25,8 +27,63 @@ use super::frame::{Frame, FrameError, FrameNegotiation, FrameRef, FrameVersion};
 
 const PROTOCOL_PREFIX: &str = "tcp://";
 
+/// Socket options applied to each connection made or accepted by a [`TcpTransport`].
+///
+/// By default, neither option is set, so connections use the operating system's defaults.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TcpOptions {
+    nodelay: bool,
+    keepalive: Option<Duration>,
+}
+
+impl TcpOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets TCP_NODELAY, which disables Nagle's algorithm so that small messages are sent
+    /// immediately.
+    pub fn with_nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = nodelay;
+        self
+    }
+
+    /// Sets SO_KEEPALIVE, with the given idle time before keepalive probes are sent.
+    pub fn with_keepalive(mut self, keepalive: Duration) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    pub fn nodelay(&self) -> bool {
+        self.nodelay
+    }
+
+    pub fn keepalive(&self) -> Option<Duration> {
+        self.keepalive
+    }
+
+    fn apply(&self, stream: &MioTcpStream) -> io::Result<()> {
+        if self.nodelay {
+            stream.set_nodelay(true)?;
+        }
+        if self.keepalive.is_some() {
+            stream.set_keepalive(self.keepalive)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Default)]
-pub struct TcpTransport {}
+pub struct TcpTransport {
+    options: TcpOptions,
+}
+
+impl TcpTransport {
+    /// Creates a transport that applies the given options to each of its connections.
+    pub fn new(options: TcpOptions) -> Self {
+        Self { options }
+    }
+}
 
 impl Transport for TcpTransport {
     fn accepts(&self, address: &str) -> bool {
@@ -60,6 +117,7 @@ impl Transport for TcpTransport {
             })?;
 
         let mio_stream = MioTcpStream::from_stream(stream)?;
+        self.options.apply(&mio_stream)?;
         Ok(Box::new(TcpConnection {
             frame_version,
             stream: mio_stream,
@@ -84,16 +142,18 @@ impl Transport for TcpTransport {
             listener: StdTcpListener::bind(address).map_err(|err| {
                 ListenError::IoError(format!("Failed to bind to {}", address), err)
             })?,
+            options: self.options.clone(),
         }))
     }
 }
 
 struct TcpListener {
     listener: StdTcpListener,
+    options: TcpOptions,
 }
 
-impl Listener for TcpListener {
-    fn accept(&mut self) -> Result<Box<dyn Connection>, AcceptError> {
+impl TcpListener {
+    fn accept_tcp(&mut self) -> Result<TcpConnection, AcceptError> {
         let (mut stream, _) = self.listener.accept()?;
 
         let frame_version = FrameNegotiation::inbound(FrameVersion::V1)
@@ -108,11 +168,18 @@ impl Listener for TcpListener {
                 err => AcceptError::ProtocolError(format!("Unexpected protocol error: {}", err)),
             })?;
 
-        let connection = TcpConnection {
+        let stream = MioTcpStream::from_stream(stream)?;
+        self.options.apply(&stream)?;
+        Ok(TcpConnection {
             frame_version,
-            stream: MioTcpStream::from_stream(stream)?,
-        };
-        Ok(Box::new(connection))
+            stream,
+        })
+    }
+}
+
+impl Listener for TcpListener {
+    fn accept(&mut self) -> Result<Box<dyn Connection>, AcceptError> {
+        Ok(Box::new(self.accept_tcp()?))
     }
 
     fn endpoint(&self) -> String {
@@ -164,6 +231,9 @@ impl Connection for TcpConnection {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    use std::thread;
+
     use crate::transport::tests;
 
     #[test]
@@ -191,6 +261,65 @@ mod tests {
         tests::test_transport(transport, "tcp://127.0.0.1:0");
     }
 
+    /// Verify that a transport configured with nodelay and keepalive can connect, listen, send
+    /// and receive.
+    #[test]
+    fn test_transport_with_options() {
+        let transport = TcpTransport::new(
+            TcpOptions::new()
+                .with_nodelay(true)
+                .with_keepalive(Duration::from_secs(60)),
+        );
+
+        tests::test_transport(transport, "127.0.0.1:0");
+    }
+
+    /// Verify that nodelay and keepalive are set on accepted connections when configured, and
+    /// left at the operating system's defaults otherwise.
+    #[test]
+    fn test_accepted_connection_options() {
+        let cases = vec![
+            (
+                TcpOptions::new()
+                    .with_nodelay(true)
+                    .with_keepalive(Duration::from_secs(60)),
+                true,
+                Some(Duration::from_secs(60)),
+            ),
+            (TcpOptions::new(), false, None),
+        ];
+
+        for (options, nodelay, keepalive) in cases {
+            let mut listener = TcpListener {
+                listener: StdTcpListener::bind("127.0.0.1:0").expect("Unable to bind listener"),
+                options,
+            };
+            let endpoint = listener.endpoint();
+
+            let handle = thread::spawn(move || {
+                TcpTransport::default()
+                    .connect(&endpoint)
+                    .map(|_| ())
+                    .expect("Unable to connect")
+            });
+
+            let connection = listener.accept_tcp().expect("Unable to accept connection");
+            assert_eq!(
+                connection.stream.nodelay().expect("Unable to get nodelay"),
+                nodelay
+            );
+            assert_eq!(
+                connection
+                    .stream
+                    .keepalive()
+                    .expect("Unable to get keepalive"),
+                keepalive
+            );
+
+            handle.join().expect("Unable to join connecting thread");
+        }
+    }
+
     #[test]
     fn test_poll() {
         let transport = TcpTransport::default();
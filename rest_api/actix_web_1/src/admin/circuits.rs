@@ -18,13 +18,13 @@
 use std::fmt::Write as _;
 
 use actix_web::{error::BlockingError, web, Error, HttpRequest, HttpResponse};
-use futures::{future::IntoFuture, Future};
+use futures::{future::IntoFuture, stream, Future};
 use std::collections::HashMap;
 
-use splinter::admin::store::{AdminServiceStore, CircuitPredicate, CircuitStatus};
+use splinter::admin::store::{AdminServiceStore, Circuit, CircuitPredicate, CircuitStatus};
 use splinter::rest_api::{
     actix_web_1::{Method, ProtocolVersionRangeGuard, Resource},
-    paging::{PagingBuilder, DEFAULT_LIMIT, DEFAULT_OFFSET},
+    paging::{Paging, PagingBuilder, DEFAULT_LIMIT, DEFAULT_OFFSET},
     ErrorResponse,
 };
 use splinter_rest_api_common::SPLINTER_PROTOCOL_VERSION;
@@ -36,6 +36,9 @@ use super::CIRCUIT_READ_PERMISSION;
 
 const ADMIN_LIST_CIRCUITS_MIN: u32 = 1;
 
+/// The number of circuits serialized into each chunk of a `GET /admin/circuits` response body
+const CIRCUIT_CHUNK_SIZE: usize = 100;
+
 pub fn make_list_circuits_resource(store: Box<dyn AdminServiceStore>) -> Resource {
     let resource = Resource::build("/admin/circuits").add_request_guard(
         ProtocolVersionRangeGuard::new(ADMIN_LIST_CIRCUITS_MIN, SPLINTER_PROTOCOL_VERSION),
@@ -181,27 +184,13 @@ fn query_list_circuits(
             ));
         }
 
-        let circuits = store
-            .list_circuits(&filters)
+        let (circuits, total) = store
+            .list_circuits_paged(&filters, offset.unwrap_or(0), limit.unwrap_or(usize::MAX))
             .map_err(|err| CircuitListError::CircuitStoreError(err.to_string()))?;
 
-        let offset_value = offset.unwrap_or(0);
-        let total = circuits.len();
-        let limit_value = limit.unwrap_or(total as usize);
-
-        let circuits = circuits
-            .skip(offset_value)
-            .take(limit_value)
-            .collect::<Vec<_>>();
+        let circuits = circuits.collect::<Vec<_>>();
 
-        Ok((
-            circuits,
-            link,
-            limit,
-            offset,
-            total as usize,
-            protocol_version,
-        ))
+        Ok((circuits, link, limit, offset, total, protocol_version))
     })
     .then(|res| match res {
         Ok((circuits, link, limit, offset, total_count, protocol_version)) => {
@@ -218,15 +207,7 @@ fn query_list_circuits(
                     } else {
                         paging
                     };
-                    Ok(
-                        HttpResponse::Ok().json(resources::v1::circuits::ListCircuitsResponse {
-                            data: circuits
-                                .iter()
-                                .map(resources::v1::circuits::CircuitResponse::from)
-                                .collect(),
-                            paging: paging.build(),
-                        }),
-                    )
+                    Ok(stream_circuits(circuits, paging.build(), write_v1_circuit))
                 }
 
                 // Handles 2
@@ -242,15 +223,7 @@ fn query_list_circuits(
                     } else {
                         paging
                     };
-                    Ok(
-                        HttpResponse::Ok().json(resources::v2::circuits::ListCircuitsResponse {
-                            data: circuits
-                                .iter()
-                                .map(resources::v2::circuits::CircuitResponse::from)
-                                .collect(),
-                            paging: paging.build(),
-                        }),
-                    )
+                    Ok(stream_circuits(circuits, paging.build(), write_v2_circuit))
                 }
                 _ => Ok(
                     HttpResponse::BadRequest().json(ErrorResponse::bad_request(&format!(
@@ -279,6 +252,92 @@ fn query_list_circuits(
     })
 }
 
+/// Builds a response whose JSON body is written in chunks as it is sent.
+///
+/// The body has the same shape as a serialized `{ "data": [...], "paging": {...} }` response, but
+/// only `CIRCUIT_CHUNK_SIZE` circuits are serialized at a time, so the whole JSON document is never
+/// held in memory. The first chunk opens the `data` array and the last chunk closes it and adds
+/// the paging information.
+fn stream_circuits(
+    circuits: Vec<Circuit>,
+    paging: Paging,
+    write_circuit: WriteCircuit,
+) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .streaming(stream::iter_result(CircuitChunks {
+            circuits: circuits.into_iter(),
+            paging: Some(paging),
+            write_circuit,
+            started: false,
+        }))
+}
+
+/// Serializes a single circuit as a JSON object into the given buffer
+type WriteCircuit = fn(&Circuit, &mut Vec<u8>) -> Result<(), serde_json::Error>;
+
+fn write_v1_circuit(circuit: &Circuit, buf: &mut Vec<u8>) -> Result<(), serde_json::Error> {
+    serde_json::to_writer(
+        buf,
+        &resources::v1::circuits::CircuitResponse::from(circuit),
+    )
+}
+
+fn write_v2_circuit(circuit: &Circuit, buf: &mut Vec<u8>) -> Result<(), serde_json::Error> {
+    serde_json::to_writer(
+        buf,
+        &resources::v2::circuits::CircuitResponse::from(circuit),
+    )
+}
+
+/// Iterates over the chunks of a streamed list circuits response body
+struct CircuitChunks {
+    circuits: std::vec::IntoIter<Circuit>,
+    /// The paging information, taken once the last chunk has been written
+    paging: Option<Paging>,
+    write_circuit: WriteCircuit,
+    /// Whether the first chunk, which opens the `data` array, has been written
+    started: bool,
+}
+
+impl CircuitChunks {
+    fn write_chunk(&mut self, buf: &mut Vec<u8>) -> Result<(), serde_json::Error> {
+        if !self.started {
+            buf.extend_from_slice(b"{\"data\":[");
+        }
+
+        for (i, circuit) in self.circuits.by_ref().take(CIRCUIT_CHUNK_SIZE).enumerate() {
+            if self.started || i > 0 {
+                buf.push(b',');
+            }
+            (self.write_circuit)(&circuit, buf)?;
+        }
+        self.started = true;
+
+        if self.circuits.len() == 0 {
+            if let Some(paging) = self.paging.take() {
+                buf.extend_from_slice(b"],\"paging\":");
+                serde_json::to_writer(&mut *buf, &paging)?;
+                buf.push(b'}');
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for CircuitChunks {
+    type Item = Result<web::Bytes, serde_json::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The paging information is written with the last chunk
+        self.paging.as_ref()?;
+
+        let mut buf = Vec::new();
+        Some(self.write_chunk(&mut buf).map(|_| web::Bytes::from(buf)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -678,6 +737,105 @@ mod tests {
         join_handle.join().expect("Unable to join rest api thread");
     }
 
+    #[test]
+    /// Tests a GET /admin/circuits request for more circuits than fit in a single chunk returns
+    /// every circuit in a valid JSON body.
+    ///
+    /// 1. Add 250 circuits to the store, which are streamed in 3 chunks
+    /// 2. Verify the chunks of the circuits form a single JSON document
+    /// 3. List the circuits and verify all 250 circuits and the paging information are returned
+    fn test_list_circuits_many() {
+        let admin_store = setup_admin_service_store();
+        for i in 0..250 {
+            let (circuit, nodes) = get_numbered_circuit(i);
+            admin_store
+                .add_circuit(circuit, nodes)
+                .expect("Unable to add circuit");
+        }
+
+        let circuits = admin_store
+            .list_circuits(&[])
+            .expect("Unable to list circuits")
+            .collect::<Vec<_>>();
+        let chunks = CircuitChunks {
+            circuits: circuits.into_iter(),
+            paging: Some(create_test_paging_response(
+                0,
+                250,
+                0,
+                0,
+                0,
+                250,
+                "/admin/circuits?",
+            )),
+            write_circuit: write_v2_circuit,
+            started: false,
+        }
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Unable to write chunks");
+        assert_eq!(chunks.len(), 3);
+        let body = chunks.concat();
+        let body: JsonValue = serde_json::from_slice(&body).expect("Invalid JSON body");
+        assert_eq!(
+            body.get("data")
+                .and_then(|data| data.as_array())
+                .map(|data| data.len()),
+            Some(250)
+        );
+
+        let (shutdown_handle, join_handle, bind_url) =
+            run_rest_api_on_open_port(vec![make_list_circuits_resource(admin_store)]);
+
+        let url = Url::parse(&format!("http://{}/admin/circuits?limit=250", bind_url))
+            .expect("Failed to parse URL");
+        let req = Client::new()
+            .get(url)
+            .header("Authorization", "custom")
+            .header("SplinterProtocolVersion", SPLINTER_PROTOCOL_VERSION);
+        let resp = req.send().expect("Failed to perform request");
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let circuits: JsonValue = resp.json().expect("Failed to deserialize body");
+
+        let data = circuits
+            .get("data")
+            .and_then(|data| data.as_array())
+            .expect("no data field in response");
+        assert_eq!(data.len(), 250);
+        let mut circuit_ids = data
+            .iter()
+            .map(|circuit| {
+                circuit
+                    .get("id")
+                    .and_then(|id| id.as_str())
+                    .expect("no id field in circuit")
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        circuit_ids.sort();
+        circuit_ids.dedup();
+        assert_eq!(circuit_ids.len(), 250);
+
+        assert_eq!(
+            circuits.get("paging").expect("no paging field in response"),
+            &to_value(create_test_paging_response(
+                0,
+                250,
+                0,
+                0,
+                0,
+                250,
+                "/admin/circuits?"
+            ))
+            .expect("failed to convert expected paging")
+        );
+
+        shutdown_handle
+            .shutdown()
+            .expect("unable to shutdown rest api");
+        join_handle.join().expect("Unable to join rest api thread");
+    }
+
     fn create_test_paging_response(
         offset: usize,
         limit: usize,
@@ -744,6 +902,36 @@ mod tests {
         )
     }
 
+    fn get_numbered_circuit(number: usize) -> (Circuit, Vec<CircuitNode>) {
+        let service = ServiceBuilder::new()
+            .with_service_id("aaaa")
+            .with_service_type("type_a")
+            .with_node_id("node_1")
+            .build()
+            .expect("Unable to build service");
+
+        let nodes = vec![CircuitNodeBuilder::new()
+            .with_node_id("node_1")
+            .with_endpoints(&["tcp://localhost:8000".to_string()])
+            .build()
+            .expect("Unable to build node")];
+
+        (
+            CircuitBuilder::new()
+                .with_circuit_id(&format!("{:05}-{:05}", number, number))
+                .with_authorization_type(&AuthorizationType::Trust)
+                .with_members(&nodes)
+                .with_roster(&[service])
+                .with_persistence(&PersistenceType::Any)
+                .with_durability(&DurabilityType::NoDurability)
+                .with_routes(&RouteType::Any)
+                .with_circuit_management_type("numbered_type")
+                .build()
+                .expect("Should have built a correct circuit"),
+            nodes,
+        )
+    }
+
     fn get_circuit_2() -> (Circuit, Vec<CircuitNode>) {
         let service = ServiceBuilder::new()
             .with_service_id("bbbb")
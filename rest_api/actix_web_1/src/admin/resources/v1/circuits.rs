@@ -15,13 +15,6 @@
 use std::collections::BTreeMap;
 
 use splinter::admin::store::{Circuit, Service};
-use splinter::rest_api::paging::Paging;
-
-#[derive(Debug, Serialize, Clone, PartialEq)]
-pub(crate) struct ListCircuitsResponse<'a> {
-    pub data: Vec<CircuitResponse<'a>>,
-    pub paging: Paging,
-}
 
 #[derive(Debug, Serialize, Clone, PartialEq)]
 pub(crate) struct CircuitResponse<'a> {
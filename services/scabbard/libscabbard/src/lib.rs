@@ -45,4 +45,6 @@ pub mod protocol;
 pub mod protos;
 #[cfg(feature = "splinter-service")]
 pub mod service;
+#[cfg(feature = "state-migration")]
+pub mod state_migration;
 pub mod store;
@@ -25,12 +25,17 @@ use std::sync::Arc;
 use splinter::error::InternalError;
 use splinter::service::FullyQualifiedServiceId;
 use splinter::service::MessageSenderFactory;
+use splinter::service::ServiceId;
 use splinter::store::command::StoreCommand;
 
 use crate::store::Action;
 use crate::store::ConsensusAction;
+use crate::store::ConsensusContext;
+use crate::store::Context;
 use crate::store::Identified;
+use crate::store::Message;
 use crate::store::ScabbardStoreFactory;
+use crate::store::State;
 
 pub use self::commands::actions::ExecuteActionCommand;
 pub use self::commands::context::UpdateContextCommand;
@@ -142,6 +147,107 @@ impl<C: 'static> ConsensusActionRunner<C> {
         }
         Ok(commands)
     }
+
+    /// Forces the consensus process of a service to abort its current epoch, for use when the
+    /// process can no longer make progress, such as when a participant will never respond.
+    ///
+    /// An abort is only permitted where it cannot contradict a decision made by another process:
+    ///
+    /// * a coordinator that is waiting for votes has not yet decided, so it aborts and sends an
+    ///   abort message to each participant
+    /// * a participant that has yet to vote responds to the coordinator with a no vote, so the
+    ///   coordinator cannot commit the epoch
+    /// * a participant that has voted no already knows the epoch cannot commit
+    ///
+    /// A participant that has voted yes may not abort, as the coordinator may have already decided
+    /// to commit, and a process that has already decided cannot change its decision. Forcing an
+    /// abort in those states would allow processes to disagree on the outcome of the epoch.
+    ///
+    /// The process's alarm is removed along with the update to its context. The supervisor is not
+    /// notified, as there is no consensus action for the abort.
+    ///
+    /// # Arguments
+    ///
+    /// * `service_id` - The service ID of the service whose process should abort
+    /// * `context` - The current consensus context of the service
+    /// * `reason` - Why the abort was forced, which is logged with the abort
+    pub fn abort_process(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        context: ConsensusContext,
+        reason: &str,
+    ) -> Result<Vec<Box<dyn StoreCommand<Context = C>>>, InternalError> {
+        let ConsensusContext::TwoPhaseCommit(context) = context;
+
+        let messages = abort_messages(&context).map_err(|state| {
+            InternalError::with_message(format!(
+                "Unable to abort epoch {} for {}: abort is not permitted in state {}",
+                context.epoch(),
+                service_id,
+                state
+            ))
+        })?;
+
+        warn!(
+            "Forcing abort of epoch {} for {}: {}",
+            context.epoch(),
+            service_id,
+            reason
+        );
+
+        let aborted = context
+            .into_builder()
+            .with_state(State::Abort)
+            .build()
+            .map_err(|err| InternalError::from_source(Box::new(err)))?;
+
+        let commands = self.context_updater.update(
+            ConsensusContext::TwoPhaseCommit(aborted),
+            service_id,
+            None,
+        )?;
+
+        let message_sender = self.message_sender_factory.new_message_sender(service_id)?;
+        for (to_service, msg) in messages {
+            let msg_bytes: Vec<u8> = Vec::<u8>::try_from(msg)
+                .map_err(|err| InternalError::from_source(Box::new(err)))?;
+            if let Err(err) = message_sender.send(&to_service, msg_bytes) {
+                warn!(
+                    "Unable to send consensus message to {}: {}",
+                    to_service, err
+                );
+            }
+        }
+
+        Ok(commands)
+    }
+}
+
+/// Returns the messages to send when aborting from the context's current state, or the name of
+/// the state if an abort is not permitted from it.
+fn abort_messages(context: &Context) -> Result<Vec<(ServiceId, Message)>, &'static str> {
+    let epoch = *context.epoch();
+    let is_coordinator = context.coordinator() == context.this_process();
+
+    match context.state() {
+        State::WaitingForVote | State::Voting { .. } if is_coordinator => Ok(context
+            .participants()
+            .iter()
+            .map(|participant| (participant.process.clone(), Message::Abort(epoch)))
+            .collect()),
+        State::WaitingForVote => Ok(vec![(
+            context.coordinator().clone(),
+            Message::VoteResponse(epoch, false),
+        )]),
+        State::Voted { vote: false, .. } => Ok(vec![]),
+        State::Voted { vote: true, .. } => Err("voted (yes)"),
+        State::Abort => Err("abort"),
+        State::Commit => Err("commit"),
+        State::Voting { .. } => Err("voting"),
+        State::WaitingForStart => Err("waiting for start"),
+        State::WaitingForVoteRequest => Err("waiting for vote request"),
+        State::WaitingForDecisionAck { .. } => Err("waiting for decision ack"),
+    }
 }
 
 #[cfg(all(test, feature = "sqlite"))]
@@ -477,4 +583,99 @@ mod tests {
                 == 1
         );
     }
+
+    /// Test that a coordinator waiting for votes can be forced to abort, and that a participant
+    /// that has voted yes cannot
+    ///
+    /// 1. Add a service and a coordinator context in the voting state with an alarm
+    /// 2. Call abort_process and execute the returned commands
+    /// 3. Verify the context is now in the abort state and the alarm was removed
+    /// 4. Verify that an abort message was sent to the participant
+    /// 5. Verify that abort_process fails for a participant that voted yes
+    #[test]
+    fn test_abort_process() {
+        let (action_runner, executor, message_sender_factory, scabbard_store, _recv) =
+            create_action_runner();
+
+        let service_fqsi = FullyQualifiedServiceId::new_from_string("abcde-fghij::aa00")
+            .expect("creating FullyQualifiedServiceId from string 'abcde-fghij::aa00'");
+        let peer_service_id = ServiceId::new("bb00").unwrap();
+
+        let service = ScabbardServiceBuilder::default()
+            .with_service_id(&service_fqsi)
+            .with_peers(&[peer_service_id.clone()])
+            .with_consensus(&ConsensusType::TwoPC)
+            .with_status(&ServiceStatus::Finalized)
+            .build()
+            .expect("failed to build service");
+
+        scabbard_store.add_service(service.clone()).unwrap();
+
+        let context = create_context(&service)
+            .unwrap()
+            .into_builder()
+            .with_state(State::Voting {
+                vote_timeout_start: SystemTime::now(),
+            })
+            .build()
+            .expect("failed to build context");
+
+        scabbard_store
+            .add_consensus_context(
+                &service_fqsi,
+                ConsensusContext::TwoPhaseCommit(context.clone()),
+            )
+            .expect("unable to add context to scabbard store");
+        scabbard_store
+            .set_alarm(&service_fqsi, &AlarmType::TwoPhaseCommit, SystemTime::now())
+            .expect("unable to set alarm");
+
+        let commands = action_runner
+            .abort_process(
+                &service_fqsi,
+                ConsensusContext::TwoPhaseCommit(context),
+                "participant bb00 is unreachable",
+            )
+            .expect("unable to abort process");
+        executor.execute(commands).unwrap();
+
+        match scabbard_store
+            .get_current_consensus_context(&service_fqsi)
+            .expect("unable to get context")
+        {
+            Some(ConsensusContext::TwoPhaseCommit(context)) => {
+                assert_eq!(context.state(), &State::Abort)
+            }
+            None => panic!("context was removed"),
+        }
+
+        assert!(scabbard_store
+            .get_alarm(&service_fqsi, &AlarmType::TwoPhaseCommit)
+            .expect("failed to get alarm")
+            .is_none());
+
+        let sent_messages = message_sender_factory.sent_messages.lock().unwrap();
+        assert_eq!(sent_messages.len(), 1);
+        assert_eq!(sent_messages[0].0, peer_service_id);
+
+        let voted = ContextBuilder::default()
+            .with_coordinator(&peer_service_id)
+            .with_epoch(1)
+            .with_participants(vec![])
+            .with_state(State::Voted {
+                vote: true,
+                decision_timeout_start: SystemTime::now(),
+            })
+            .with_this_process(service_fqsi.service_id())
+            .build()
+            .expect("failed to build context");
+
+        assert!(action_runner
+            .abort_process(
+                &service_fqsi,
+                ConsensusContext::TwoPhaseCommit(voted),
+                "coordinator is unreachable",
+            )
+            .is_err());
+    }
 }
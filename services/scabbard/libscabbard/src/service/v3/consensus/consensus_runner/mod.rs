@@ -14,6 +14,7 @@
 
 mod builder;
 mod consensus_store_command_factory;
+mod snapshot;
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -23,6 +24,7 @@ use splinter::error::InternalError;
 use splinter::service::FullyQualifiedServiceId;
 use splinter::store::command::StoreCommandExecutor;
 
+use crate::store::AlarmType;
 use crate::store::ConsensusAction;
 use crate::store::ConsensusContext;
 use crate::store::ConsensusEvent;
@@ -32,6 +34,7 @@ use super::ConsensusActionRunner;
 
 pub use builder::ConsensusRunnerBuilder;
 use consensus_store_command_factory::ConsensusStoreCommandFactory;
+pub use snapshot::{ConsensusSnapshot, ParticipantSnapshot};
 
 pub struct ConsensusRunner<E>
 where
@@ -135,6 +138,39 @@ where
 
         Ok(())
     }
+
+    /// Returns a snapshot of the service's consensus process, or `None` if the service has no
+    /// consensus context.
+    ///
+    /// The snapshot is read from the store without running any events or actions, so it can be
+    /// taken at any time, including while the process is stalled.
+    pub fn inspect(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+    ) -> Result<Option<ConsensusSnapshot>, InternalError> {
+        let store = self.pooled_scabbard_store_factory.new_store();
+
+        let context = match store
+            .get_current_consensus_context(service_id)
+            .map_err(|err| InternalError::from_source(Box::new(err)))?
+        {
+            Some(context) => context,
+            None => return Ok(None),
+        };
+
+        let alarm_type = match context {
+            ConsensusContext::TwoPhaseCommit(_) => AlarmType::TwoPhaseCommit,
+        };
+        let alarm = store
+            .get_alarm(service_id, &alarm_type)
+            .map_err(|err| InternalError::from_source(Box::new(err)))?;
+
+        Ok(Some(ConsensusSnapshot::new(
+            service_id.to_string(),
+            &context,
+            alarm,
+        )))
+    }
 }
 
 #[cfg(test)]
@@ -142,6 +178,7 @@ mod tests {
     pub use super::*;
 
     use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
 
     use diesel::{
         r2d2::{ConnectionManager, Pool},
@@ -262,6 +299,89 @@ mod tests {
         Ok(())
     }
 
+    /// Test that the ConsensusRunner reports the state of a service's consensus process
+    ///
+    /// 1. Verify that a service without a context has no snapshot
+    /// 2. Add a coordinator context waiting for a vote from one of two peers, and an alarm
+    /// 3. Verify that the snapshot reports the phase, the peer that has yet to vote and the
+    ///    alarm, and that the context was not changed
+    #[test]
+    fn test_inspect() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = create_connection_pool_and_migrate();
+        let pooled_scabbard_store_factory =
+            Arc::new(PooledSqliteScabbardStoreFactory::new(pool.clone()));
+
+        let service_id = FullyQualifiedServiceId::new_from_string("AAAAA-bbbbb::test")?;
+        let voted_peer = ServiceId::new("bb00")?;
+        let waiting_peer = ServiceId::new("cc00")?;
+        let service = ScabbardServiceBuilder::default()
+            .with_service_id(&service_id)
+            .with_peers(&[voted_peer.clone(), waiting_peer.clone()])
+            .with_consensus(&ConsensusType::TwoPC)
+            .with_status(&ServiceStatus::Finalized)
+            .build()?;
+
+        let scabbard_store = pooled_scabbard_store_factory.new_store();
+        scabbard_store.add_service(service)?;
+
+        let store_factory = Arc::new(SqliteScabbardStoreFactory);
+        let runner = ConsensusRunnerBuilder::new()
+            .with_pooled_scabbard_store_factory(pooled_scabbard_store_factory)
+            .with_scabbard_store_factory(store_factory.clone())
+            .with_store_command_executor(Arc::new(SqliteCommandExecutor {
+                pool: pool.clone().into(),
+            }))
+            .with_message_sender_factory(Box::new(TestMessageSenderFactory::default()))
+            .with_notify_observer(Box::new(SupervisorNotifyObserver::new(store_factory)))
+            .build()?;
+
+        assert!(runner.inspect(&service_id)?.is_none());
+
+        let context = ConsensusContext::TwoPhaseCommit(
+            ContextBuilder::new()
+                .with_coordinator(service_id.service_id())
+                .with_epoch(3)
+                .with_state(State::WaitingForVote)
+                .with_this_process(service_id.service_id())
+                .with_participants(vec![
+                    Participant {
+                        process: voted_peer.clone(),
+                        vote: Some(true),
+                        decision_ack: false,
+                    },
+                    Participant {
+                        process: waiting_peer.clone(),
+                        vote: None,
+                        decision_ack: false,
+                    },
+                ])
+                .build()?,
+        );
+        scabbard_store.add_consensus_context(&service_id, context.clone())?;
+
+        let alarm = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        scabbard_store.set_alarm(&service_id, &AlarmType::TwoPhaseCommit, alarm)?;
+
+        let snapshot = runner
+            .inspect(&service_id)?
+            .expect("Service should have a snapshot");
+
+        assert_eq!(snapshot.service_id, "AAAAA-bbbbb::test");
+        assert_eq!(snapshot.epoch, 3);
+        assert_eq!(snapshot.phase, "waiting_for_vote");
+        assert_eq!(snapshot.participants.len(), 2);
+        assert_eq!(snapshot.participants[0].vote, Some(true));
+        assert_eq!(snapshot.waiting_on, vec![waiting_peer.to_string()]);
+        assert_eq!(snapshot.deadline, Some(1_000));
+
+        assert_eq!(
+            scabbard_store.get_current_consensus_context(&service_id)?,
+            Some(context)
+        );
+
+        Ok(())
+    }
+
     fn create_connection_pool_and_migrate() -> Pool<ConnectionManager<SqliteConnection>> {
         let connection_manager = ConnectionManager::<SqliteConnection>::new(":memory:");
         let pool = Pool::builder()
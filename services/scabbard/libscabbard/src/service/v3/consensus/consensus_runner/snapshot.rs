@@ -0,0 +1,138 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::store::{ConsensusContext, Context, Participant, State};
+
+/// A read-only view of a service's consensus process, used to debug a stalled consensus round.
+///
+/// All times are in seconds since the Unix epoch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConsensusSnapshot {
+    pub service_id: String,
+    pub algorithm: &'static str,
+    pub epoch: u64,
+    pub last_commit_epoch: Option<u64>,
+    pub coordinator: String,
+    pub this_process: String,
+    /// The current phase of the process, e.g. `waiting_for_vote`
+    pub phase: &'static str,
+    /// When the current phase's timeout started, if the phase has one
+    pub phase_started_at: Option<u64>,
+    pub participants: Vec<ParticipantSnapshot>,
+    /// The participants the process is waiting on to continue the current phase
+    pub waiting_on: Vec<String>,
+    /// When the process will next be woken up if nothing else happens
+    pub deadline: Option<u64>,
+}
+
+/// A participant in a consensus process, and how far it has progressed in the current epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParticipantSnapshot {
+    pub service_id: String,
+    pub vote: Option<bool>,
+    pub decision_ack: bool,
+}
+
+impl ConsensusSnapshot {
+    pub(super) fn new(
+        service_id: String,
+        context: &ConsensusContext,
+        alarm: Option<SystemTime>,
+    ) -> Self {
+        match context {
+            ConsensusContext::TwoPhaseCommit(context) => {
+                Self::from_two_phase_commit(service_id, context, alarm)
+            }
+        }
+    }
+
+    fn from_two_phase_commit(
+        service_id: String,
+        context: &Context,
+        alarm: Option<SystemTime>,
+    ) -> Self {
+        let (phase, phase_started_at) = match context.state() {
+            State::Abort => ("abort", None),
+            State::Commit => ("commit", None),
+            State::Voted {
+                decision_timeout_start,
+                ..
+            } => ("voted", Some(*decision_timeout_start)),
+            State::Voting { vote_timeout_start } => ("voting", Some(*vote_timeout_start)),
+            State::WaitingForStart => ("waiting_for_start", None),
+            State::WaitingForVoteRequest => ("waiting_for_vote_request", None),
+            State::WaitingForVote => ("waiting_for_vote", None),
+            State::WaitingForDecisionAck { ack_timeout_start } => {
+                ("waiting_for_decision_ack", Some(*ack_timeout_start))
+            }
+        };
+
+        let participants_where = |pending: fn(&Participant) -> bool| -> Vec<String> {
+            context
+                .participants()
+                .iter()
+                .filter(|participant| pending(participant))
+                .map(|participant| participant.process.to_string())
+                .collect()
+        };
+
+        let waiting_on = match context.state() {
+            State::WaitingForVote | State::Voting { .. } => {
+                participants_where(|participant| participant.vote.is_none())
+            }
+            State::WaitingForDecisionAck { .. } => {
+                participants_where(|participant| !participant.decision_ack)
+            }
+            // A participant waits on the coordinator for the vote request and the decision
+            State::WaitingForVoteRequest | State::Voted { .. }
+                if context.coordinator() != context.this_process() =>
+            {
+                vec![context.coordinator().to_string()]
+            }
+            _ => vec![],
+        };
+
+        Self {
+            service_id,
+            algorithm: "two_phase_commit",
+            epoch: *context.epoch(),
+            last_commit_epoch: context.last_commit_epoch(),
+            coordinator: context.coordinator().to_string(),
+            this_process: context.this_process().to_string(),
+            phase,
+            phase_started_at: phase_started_at.map(to_secs),
+            participants: context
+                .participants()
+                .iter()
+                .map(|participant| ParticipantSnapshot {
+                    service_id: participant.process.to_string(),
+                    vote: participant.vote,
+                    decision_ack: participant.decision_ack,
+                })
+                .collect(),
+            waiting_on,
+            deadline: alarm.map(to_secs),
+        }
+    }
+}
+
+fn to_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
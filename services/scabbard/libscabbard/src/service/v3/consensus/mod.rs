@@ -24,6 +24,8 @@ mod value;
 #[cfg(feature = "scabbardv3-consensus-action-runner")]
 pub use consensus_action_runner::ConsensusActionRunner;
 #[cfg(feature = "scabbardv3-consensus-runner")]
-pub use consensus_runner::{ConsensusRunner, ConsensusRunnerBuilder};
+pub use consensus_runner::{
+    ConsensusRunner, ConsensusRunnerBuilder, ConsensusSnapshot, ParticipantSnapshot,
+};
 pub use process::ScabbardProcess;
 pub use value::ScabbardValue;
@@ -21,6 +21,14 @@ impl ScabbardValue {
     pub fn new(val: Vec<u8>) -> Self {
         Self(val)
     }
+
+    /// Returns the size of the value's serialized form, in bytes.
+    ///
+    /// The value is held in its serialized form, so the size is known without copying it; this
+    /// allows values to be batched within a message size limit before they are sent.
+    pub fn serialized_len(&self) -> usize {
+        self.0.len()
+    }
 }
 
 impl Value for ScabbardValue {}
@@ -35,7 +35,9 @@ pub use consensus::consensus_action_runner::{
     ConsensusActionRunner, ContextUpdater, NotifyObserver, ScabbardStoreContextUpdater,
 };
 #[cfg(feature = "scabbardv3-consensus-runner")]
-pub use consensus::{ConsensusRunner, ConsensusRunnerBuilder};
+pub use consensus::{
+    ConsensusRunner, ConsensusRunnerBuilder, ConsensusSnapshot, ParticipantSnapshot,
+};
 #[cfg(feature = "scabbardv3-consensus")]
 pub use consensus::{ScabbardProcess, ScabbardValue};
 pub use lifecycle::ScabbardLifecycle;
@@ -0,0 +1,376 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Copying and comparing the leaves of merkle trees
+
+use std::time::Instant;
+
+use splinter::{admin::store::ServiceId, error::InternalError};
+use transact::state::{StateChange, ValueIter};
+
+use super::{CopyObserver, MigrationState, ServiceCopy};
+
+/// Copies the state of a service at `current_commit_hash` from `state_reader` to `state_writer`.
+///
+/// The leaves are written in batches, as configured by `copy.batching`, and the previous root is
+/// pruned after each batch is written. If `copy.prefixes` is not empty, only the leaves whose
+/// address starts with one of the prefixes are copied.
+///
+/// Returns the number of leaves copied. If `copy.verify_root` is set, an error is returned if the
+/// resulting state root does not match `current_commit_hash`; otherwise the mismatch is logged.
+/// The output tree is left as it is if an error is returned.
+pub fn copy_state(
+    state_reader: &dyn MigrationState,
+    current_commit_hash: &str,
+    state_writer: &dyn MigrationState,
+    service_id: &ServiceId,
+    copy: &ServiceCopy,
+    observer: &dyn CopyObserver,
+) -> Result<usize, InternalError> {
+    let leaves_err =
+        |e| InternalError::with_message(format!("Unable to get leaves for commit hash: {}", e));
+    let mut state_changes_iter: ValueIter<(String, Vec<u8>)> = if copy.prefixes.is_empty() {
+        state_reader
+            .leaves(current_commit_hash, None)
+            .map_err(leaves_err)?
+    } else {
+        // The prefixes do not overlap, so each leaf is read once
+        let iters = disjoint_prefixes(copy.prefixes)
+            .into_iter()
+            .map(|prefix| state_reader.leaves(current_commit_hash, Some(prefix)))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(leaves_err)?;
+        Box::new(iters.into_iter().flatten())
+    };
+
+    let batching = copy.batching;
+    let mut count = 0;
+    let mut copied = 0;
+    let mut last_state_id = state_writer.state_root()?;
+    let mut state_changes = vec![];
+    let started = Instant::now();
+    loop {
+        let waiting = Instant::now();
+        let state_change = match state_changes_iter.next() {
+            Some(state_change) => state_change,
+            None => break,
+        };
+        let idle =
+            matches!(batching.flush_idle, Some(flush_idle) if waiting.elapsed() >= flush_idle);
+
+        match state_change {
+            Ok((key, value)) => {
+                state_changes.push(StateChange::Set { key, value });
+                count += 1;
+                observer.leaves_read(copied + count, started.elapsed());
+
+                if count >= batching.size || idle {
+                    last_state_id = write_and_prune_with_cleanup(
+                        state_writer,
+                        &last_state_id,
+                        &state_changes,
+                        batching.paranoid,
+                    )?;
+
+                    copied += count;
+                    observer.batch_written(count, copied);
+
+                    count = 0;
+                    state_changes.clear();
+
+                    observer.check_continue(copied)?;
+                }
+            }
+            Err(err) => {
+                return Err(InternalError::with_message(format!(
+                    "Cannot get state change: {}",
+                    err
+                )))
+            }
+        }
+    }
+
+    last_state_id = write_and_prune_with_cleanup(
+        state_writer,
+        &last_state_id,
+        &state_changes,
+        batching.paranoid,
+    )?;
+
+    observer.batch_written(count, copied + count);
+
+    if last_state_id != current_commit_hash {
+        if !copy.verify_root {
+            warn!(
+                "Skipping root check for {}: ending commit hash {} does not match expected {}",
+                service_id, last_state_id, current_commit_hash
+            );
+            return Ok(copied + count);
+        }
+
+        return Err(InternalError::with_message(format!(
+            "Ending commit hash did not match expected {} != {}",
+            last_state_id, current_commit_hash
+        )));
+    }
+
+    Ok(copied + count)
+}
+
+/// Returns the given prefixes without duplicates or prefixes covered by a shorter prefix, so that
+/// no address starts with more than one of them
+pub fn disjoint_prefixes<'a>(prefixes: &[&'a str]) -> Vec<&'a str> {
+    let mut sorted = prefixes.to_vec();
+    sorted.sort_unstable();
+
+    let mut disjoint: Vec<&str> = Vec::with_capacity(sorted.len());
+    for prefix in sorted {
+        // After sorting, a prefix directly follows any shorter prefix that covers it
+        match disjoint.last() {
+            Some(last) if prefix.starts_with(last) => (),
+            _ => disjoint.push(prefix),
+        }
+    }
+
+    disjoint
+}
+
+/// Checks that the copied state contains exactly the same leaves as the original state
+pub fn verify_copy(
+    state_reader: &dyn MigrationState,
+    state_writer: &dyn MigrationState,
+    commit_hash: &str,
+    service_id: &ServiceId,
+) -> Result<(), InternalError> {
+    match merkle_states_equal(state_reader, state_writer, commit_hash)? {
+        MerkleComparison::Equal => {
+            debug!("Verified copied state for {}", service_id);
+            Ok(())
+        }
+        MerkleComparison::Differs { key } => Err(InternalError::with_message(format!(
+            "Copied state for {} does not match the original state at address {}",
+            service_id, key
+        ))),
+    }
+}
+
+/// The result of comparing the leaves of two merkle states
+#[derive(Debug, PartialEq)]
+pub enum MerkleComparison {
+    /// Both states contain the same leaves
+    Equal,
+    /// The states differ; `key` is the first address, in address order, whose leaf is missing
+    /// from one of the states or has a different value
+    Differs { key: String },
+}
+
+/// Compares the leaves of two merkle states at the given commit hash.
+///
+/// Both states are read in address order, so the comparison stops at the first difference.
+pub fn merkle_states_equal(
+    a: &dyn MigrationState,
+    b: &dyn MigrationState,
+    commit_hash: &str,
+) -> Result<MerkleComparison, InternalError> {
+    let get_leaves = |state: &dyn MigrationState| {
+        state.leaves(commit_hash, None).map_err(|e| {
+            InternalError::with_message(format!("Unable to get leaves for commit hash: {}", e))
+        })
+    };
+
+    compare_leaves(get_leaves(a)?, get_leaves(b)?)
+}
+
+/// Compares two sequences of leaves, each sorted by address.
+fn compare_leaves<A, B, E>(mut a: A, mut b: B) -> Result<MerkleComparison, InternalError>
+where
+    A: Iterator<Item = Result<(String, Vec<u8>), E>>,
+    B: Iterator<Item = Result<(String, Vec<u8>), E>>,
+    E: std::fmt::Display,
+{
+    let next_leaf = |leaf: Option<Result<(String, Vec<u8>), E>>| {
+        leaf.transpose()
+            .map_err(|e| InternalError::with_message(format!("Cannot get leaf: {}", e)))
+    };
+
+    loop {
+        match (next_leaf(a.next())?, next_leaf(b.next())?) {
+            (None, None) => return Ok(MerkleComparison::Equal),
+            (Some((key, _)), None) | (None, Some((key, _))) => {
+                return Ok(MerkleComparison::Differs { key })
+            }
+            (Some((key_a, value_a)), Some((key_b, value_b))) => {
+                if key_a != key_b {
+                    // The smaller address is missing from the other state
+                    return Ok(MerkleComparison::Differs {
+                        key: std::cmp::min(key_a, key_b),
+                    });
+                }
+                if value_a != value_b {
+                    return Ok(MerkleComparison::Differs { key: key_a });
+                }
+            }
+        }
+    }
+}
+
+/// Commits the state changes on top of `state_id` and prunes `state_id`, returning the new state
+/// ID.
+///
+/// If `paranoid` is set, the leaves are read back from the new state and compared with the
+/// values that were written before `state_id` is pruned.
+fn write_and_prune_with_cleanup(
+    merkle_state: &dyn MigrationState,
+    state_id: &str,
+    state_changes: &[StateChange],
+    paranoid: bool,
+) -> Result<String, InternalError> {
+    let next_state_id = merkle_state
+        .commit_changes(state_id, state_changes)
+        .map_err(|e| {
+            InternalError::with_message(format!("Unable to commit state changes {}", e))
+        })?;
+
+    if paranoid {
+        check_written_leaves(merkle_state, &next_state_id, state_changes)?;
+    }
+
+    merkle_state.prune_state(state_id)?;
+
+    Ok(next_state_id)
+}
+
+/// Reads the leaves set by `state_changes` from the state at `state_id`, returning an error if
+/// any leaf is missing or its value differs from the value that was written
+fn check_written_leaves(
+    merkle_state: &dyn MigrationState,
+    state_id: &str,
+    state_changes: &[StateChange],
+) -> Result<(), InternalError> {
+    let keys = state_changes
+        .iter()
+        .filter_map(|change| match change {
+            StateChange::Set { key, .. } => Some(key.clone()),
+            StateChange::Delete { .. } => None,
+        })
+        .collect::<Vec<_>>();
+
+    if keys.is_empty() {
+        return Ok(());
+    }
+
+    let written = merkle_state
+        .get_leaves(state_id, &keys)
+        .map_err(|e| InternalError::with_message(format!("Unable to read written leaves {}", e)))?;
+
+    for change in state_changes {
+        if let StateChange::Set { key, value } = change {
+            if written.get(key) != Some(value) {
+                return Err(InternalError::with_message(format!(
+                    "Leaf at address {} does not match the value that was written",
+                    key
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves<'a>(
+        leaves: &'a [(&str, &[u8])],
+    ) -> impl Iterator<Item = Result<(String, Vec<u8>), String>> + 'a {
+        leaves
+            .iter()
+            .map(|(key, value)| Ok((key.to_string(), value.to_vec())))
+    }
+
+    /// Verify that identical leaf sets compare as equal, including empty ones.
+    #[test]
+    fn test_compare_leaves_equal() {
+        let a = [("00aa", &b"one"[..]), ("00bb", &b"two"[..])];
+
+        assert_eq!(
+            compare_leaves(leaves(&a), leaves(&a)).expect("Unable to compare"),
+            MerkleComparison::Equal
+        );
+        assert_eq!(
+            compare_leaves(leaves(&[]), leaves(&[])).expect("Unable to compare"),
+            MerkleComparison::Equal
+        );
+    }
+
+    /// Verify that the first differing address is returned when a value differs, a leaf is
+    /// missing from either side, or one side has extra leaves at the end.
+    #[test]
+    fn test_compare_leaves_differs() {
+        let a = [("00aa", &b"one"[..]), ("00bb", &b"two"[..])];
+
+        let changed = [("00aa", &b"one"[..]), ("00bb", &b"2"[..])];
+        assert_eq!(
+            compare_leaves(leaves(&a), leaves(&changed)).expect("Unable to compare"),
+            MerkleComparison::Differs {
+                key: "00bb".to_string()
+            }
+        );
+
+        let missing = [("00bb", &b"two"[..])];
+        assert_eq!(
+            compare_leaves(leaves(&a), leaves(&missing)).expect("Unable to compare"),
+            MerkleComparison::Differs {
+                key: "00aa".to_string()
+            }
+        );
+        assert_eq!(
+            compare_leaves(leaves(&missing), leaves(&a)).expect("Unable to compare"),
+            MerkleComparison::Differs {
+                key: "00aa".to_string()
+            }
+        );
+
+        let extra = [
+            ("00aa", &b"one"[..]),
+            ("00bb", &b"two"[..]),
+            ("00cc", &b"three"[..]),
+        ];
+        assert_eq!(
+            compare_leaves(leaves(&a), leaves(&extra)).expect("Unable to compare"),
+            MerkleComparison::Differs {
+                key: "00cc".to_string()
+            }
+        );
+    }
+
+    /// Verify that an error reading a leaf is returned.
+    #[test]
+    fn test_compare_leaves_error() {
+        let a = vec![Err("unreadable".to_string())].into_iter();
+
+        assert!(compare_leaves(a, leaves(&[])).is_err());
+    }
+
+    /// Verify that overlapping prefixes are reduced to the shortest, without duplicates.
+    #[test]
+    fn test_disjoint_prefixes() {
+        assert_eq!(
+            disjoint_prefixes(&["00ec01", "00ec00", "00ec0100", "00ec01"]),
+            vec!["00ec00", "00ec01"]
+        );
+        assert!(disjoint_prefixes(&[]).is_empty());
+    }
+}
@@ -0,0 +1,689 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Migration of scabbard state between stores, such as from LMDB to a SQL database.
+//!
+//! The stores on each side of a migration are provided by implementing
+//! [`StateMigrationStores`], whose merkle trees implement [`MigrationState`]. The state of every
+//! local scabbard service can then be moved with [`migrate_scabbard_state`], or the state of a
+//! single service with [`migrate_service`].
+//!
+//! [`StateMigrationStores`]: trait.StateMigrationStores.html
+//! [`MigrationState`]: trait.MigrationState.html
+//! [`migrate_scabbard_state`]: fn.migrate_scabbard_state.html
+//! [`migrate_service`]: fn.migrate_service.html
+
+mod copy;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use splinter::{admin::store::ServiceId, error::InternalError};
+use transact::state::{StateChange, StateError, ValueIter, ValueIterResult};
+
+pub use copy::{copy_state, disjoint_prefixes, merkle_states_equal, verify_copy, MerkleComparison};
+
+/// The number of leaves written to the output tree at once, unless another batch size is given
+pub const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// The merkle tree holding the state of a single scabbard service
+pub trait MigrationState {
+    /// Returns the root of the tree's current state
+    fn state_root(&self) -> Result<String, InternalError>;
+
+    /// Returns the leaves of the state at `state_id`, in address order. If `prefix` is provided,
+    /// only the leaves whose address starts with it are returned
+    fn leaves(
+        &self,
+        state_id: &str,
+        prefix: Option<&str>,
+    ) -> ValueIterResult<ValueIter<(String, Vec<u8>)>>;
+
+    /// Returns the values of the leaves at the given addresses in the state at `state_id`
+    fn get_leaves(
+        &self,
+        state_id: &str,
+        addresses: &[String],
+    ) -> Result<HashMap<String, Vec<u8>>, StateError>;
+
+    /// Commits the state changes on top of the state at `state_id`, returning the new state root
+    fn commit_changes(
+        &self,
+        state_id: &str,
+        state_changes: &[StateChange],
+    ) -> Result<String, StateError>;
+
+    /// Prunes the state at `state_id`, removing the entries that no other state refers to
+    fn prune_state(&self, state_id: &str) -> Result<(), InternalError>;
+
+    /// Deletes the tree
+    fn delete_tree(self: Box<Self>) -> Result<(), InternalError>;
+}
+
+/// Runs with the stores of a transaction, as given to `StateMigrationStores::in_transaction`
+pub type InTransactionHandle<'a> =
+    Box<dyn FnOnce(&dyn StateMigrationStores) -> Result<(), InternalError> + 'a>;
+
+/// The stores that scabbard state is migrated from or to
+pub trait StateMigrationStores {
+    /// Returns the ID of the local node, if it has been set
+    fn node_id(&self) -> Result<Option<String>, InternalError>;
+
+    /// Lists the scabbard services run by the given node on active circuits
+    fn list_scabbard_services(&self, node_id: &str) -> Result<Vec<LocalService>, InternalError>;
+
+    /// Returns whether the stores hold a merkle tree for the service
+    fn has_tree(&self, service_id: &ServiceId) -> Result<bool, InternalError>;
+
+    /// Returns the merkle tree of the service, creating it if `create_tree` is true
+    fn get_merkle_state<'a>(
+        &'a self,
+        service_id: &ServiceId,
+        create_tree: bool,
+    ) -> Result<Box<dyn MigrationState + 'a>, InternalError>;
+
+    /// Runs `f` with stores whose changes are committed together once it returns successfully,
+    /// and discarded if it returns an error
+    fn in_transaction(&self, f: InTransactionHandle<'_>) -> Result<(), InternalError>;
+}
+
+/// A scabbard service run by the local node
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalService {
+    service_id: ServiceId,
+    commit_hash: Option<String>,
+}
+
+impl LocalService {
+    pub fn new(service_id: ServiceId, commit_hash: Option<String>) -> Self {
+        Self {
+            service_id,
+            commit_hash,
+        }
+    }
+
+    pub fn service_id(&self) -> &ServiceId {
+        &self.service_id
+    }
+
+    /// Returns the current commit hash of the service, if it has one
+    pub fn commit_hash(&self) -> Option<&str> {
+        self.commit_hash.as_deref()
+    }
+
+    pub fn into_parts(self) -> (ServiceId, Option<String>) {
+        (self.service_id, self.commit_hash)
+    }
+}
+
+/// Observes the copy of a single service's state as it is made
+///
+/// Every method does nothing by default.
+pub trait CopyObserver {
+    /// Called as each leaf is read from the input tree, with the number of leaves read so far and
+    /// the time since the copy started
+    fn leaves_read(&self, _read: usize, _elapsed: Duration) {}
+
+    /// Called once each batch of leaves has been written to the output tree, with the number of
+    /// leaves in the batch and the number copied so far
+    fn batch_written(&self, _batch_leaves: usize, _copied: usize) {}
+
+    /// Called after each full batch of leaves has been written; an error stops the copy, and the
+    /// output tree is removed
+    fn check_continue(&self, _copied: usize) -> Result<(), InternalError> {
+        Ok(())
+    }
+
+    /// Called before the input state of a copied service is deleted; an error stops the deletion
+    fn before_purge(
+        &self,
+        _service_id: &ServiceId,
+        _commit_hash: &str,
+    ) -> Result<(), InternalError> {
+        Ok(())
+    }
+}
+
+/// A `CopyObserver` that ignores the copy
+struct Unobserved;
+
+impl CopyObserver for Unobserved {}
+
+/// When the leaves read from the input tree are written to the output tree
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Batching {
+    /// The number of leaves written at once. Each write prunes the previous root, so larger
+    /// batches prune less often
+    pub size: usize,
+    /// If provided, the pending batch is written as soon as a leaf takes longer than this to be
+    /// read, rather than once the batch is full. This bounds how long leaves from a slow source
+    /// wait to be written
+    pub flush_idle: Option<Duration>,
+    /// Whether each batch is read back from the output tree and compared with the leaves that
+    /// were written, before the next batch is read
+    pub paranoid: bool,
+}
+
+impl Default for Batching {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_BATCH_SIZE,
+            flush_idle: None,
+            paranoid: false,
+        }
+    }
+}
+
+/// How `migrate_service` copies a service's state
+pub struct ServiceCopy<'a> {
+    /// If not empty, only the leaves whose address starts with one of these prefixes are copied
+    pub prefixes: &'a [&'a str],
+    /// Whether the root of the copy must match the service's commit hash. If false, a mismatch is
+    /// logged instead of returned as an error
+    pub verify_root: bool,
+    /// Whether the copied leaves are compared with the input leaves
+    pub verify: bool,
+    /// Whether the input state is deleted once it has been copied
+    pub purge: bool,
+    pub batching: &'a Batching,
+}
+
+/// The options for `migrate_scabbard_state`
+pub struct StateMigrationOptions<'a> {
+    /// If not empty, only the leaves whose address starts with one of these prefixes are copied.
+    /// The root of a partial copy cannot match the service's commit hash, so it is not checked,
+    /// and the input state is never deleted
+    pub prefixes: Vec<&'a str>,
+    /// Whether a tree already in the output stores is replaced; if false, an existing tree is an
+    /// error
+    pub force: bool,
+    /// Whether the copied leaves are compared with the input leaves before the input state is
+    /// deleted
+    pub verify: bool,
+    /// The number of leaves written to the output tree at once
+    pub batch_size: usize,
+    /// Whether each batch of leaves is read back from the output tree and compared with the input
+    /// leaves as soon as it is written
+    pub paranoid: bool,
+}
+
+impl<'a> Default for StateMigrationOptions<'a> {
+    fn default() -> Self {
+        Self {
+            prefixes: vec![],
+            force: false,
+            verify: false,
+            batch_size: DEFAULT_BATCH_SIZE,
+            paranoid: false,
+        }
+    }
+}
+
+/// The services whose state was migrated by `migrate_scabbard_state`
+#[derive(Debug, Default)]
+pub struct StateMigrationReport {
+    services: Vec<MigratedService>,
+}
+
+impl StateMigrationReport {
+    /// Returns the migrated services, in the order they were migrated
+    pub fn services(&self) -> &[MigratedService] {
+        &self.services
+    }
+}
+
+/// A service whose state was copied to the output stores
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigratedService {
+    service_id: ServiceId,
+    leaves_copied: usize,
+    state_root: String,
+}
+
+impl MigratedService {
+    pub fn circuit_id(&self) -> &str {
+        self.service_id.circuit()
+    }
+
+    pub fn service_id(&self) -> &str {
+        self.service_id.service_id()
+    }
+
+    /// Returns the number of leaves copied to the output tree
+    pub fn leaves_copied(&self) -> usize {
+        self.leaves_copied
+    }
+
+    /// Returns the root hash of the output tree once the copy finished
+    pub fn state_root(&self) -> &str {
+        &self.state_root
+    }
+}
+
+/// Migrates the state of every local scabbard service from `in_stores` to `out_stores`.
+///
+/// The circuit information is read from `in_stores`. Each service's state is copied at its
+/// current commit hash and, unless `options.prefixes` is not empty, the input state is deleted
+/// once copied. Services without a commit hash have no state, and are skipped.
+///
+/// Migration stops at the first service that cannot be copied; the partial copy of that service
+/// is removed and an error is returned.
+pub fn migrate_scabbard_state(
+    in_stores: &dyn StateMigrationStores,
+    out_stores: &dyn StateMigrationStores,
+    options: &StateMigrationOptions,
+) -> Result<StateMigrationReport, InternalError> {
+    let mut report = StateMigrationReport::default();
+
+    let node_id = match in_stores.node_id()? {
+        Some(node_id) => node_id,
+        // This node has not even set a node id, so it cannot have any circuits.
+        None => return Ok(report),
+    };
+
+    let batching = Batching {
+        size: options.batch_size,
+        flush_idle: None,
+        paranoid: options.paranoid,
+    };
+    let copy = ServiceCopy {
+        prefixes: &options.prefixes,
+        // Only a full copy ends at the service's commit hash
+        verify_root: options.prefixes.is_empty(),
+        verify: options.verify,
+        purge: options.prefixes.is_empty(),
+        batching: &batching,
+    };
+
+    for service in in_stores.list_scabbard_services(&node_id)? {
+        let (service_id, commit_hash) = match service.into_parts() {
+            (service_id, Some(commit_hash)) => (service_id, commit_hash),
+            (_, None) => continue,
+        };
+
+        if !options.force && out_stores.has_tree(&service_id)? {
+            return Err(InternalError::with_message(format!(
+                "Merkle Tree for {} already exists",
+                service_id
+            )));
+        }
+
+        let state_reader = in_stores.get_merkle_state(&service_id, false)?;
+        let migrated = migrate_service(
+            state_reader,
+            out_stores,
+            &service_id,
+            &commit_hash,
+            &copy,
+            &Unobserved,
+        )?;
+
+        report.services.push(migrated);
+    }
+
+    Ok(report)
+}
+
+/// Copies the state of a single service from `state_reader` into `out_stores` in one
+/// transaction.
+///
+/// If the copy fails, the output tree is deleted. Otherwise the input state is deleted if
+/// `copy.purge` is set, once `observer.before_purge` has returned successfully.
+pub fn migrate_service(
+    state_reader: Box<dyn MigrationState + '_>,
+    out_stores: &dyn StateMigrationStores,
+    service_id: &ServiceId,
+    commit_hash: &str,
+    copy: &ServiceCopy,
+    observer: &dyn CopyObserver,
+) -> Result<MigratedService, InternalError> {
+    let mut leaves_copied = 0;
+    let mut state_root = String::new();
+    out_stores.in_transaction(Box::new(|out_stores| {
+        let state_writer = out_stores.get_merkle_state(service_id, true)?;
+
+        let copied = copy_state(
+            &*state_reader,
+            commit_hash,
+            &*state_writer,
+            service_id,
+            copy,
+            observer,
+        )
+        .and_then(|copied| {
+            // Compare the leaves before the input state is deleted
+            if copy.verify {
+                verify_copy(&*state_reader, &*state_writer, commit_hash, service_id)?;
+            }
+            Ok(copied)
+        });
+
+        match copied {
+            Ok(copied) => {
+                leaves_copied = copied;
+                state_root = state_writer.state_root()?;
+                if copy.purge {
+                    observer.before_purge(service_id, commit_hash)?;
+                    state_reader.delete_tree()?;
+                }
+            }
+            Err(err) => {
+                // delete the target scabbard state, so that it doesn't exist.
+                state_writer.delete_tree()?;
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }))?;
+
+    Ok(MigratedService {
+        service_id: service_id.clone(),
+        leaves_copied,
+        state_root,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::BTreeMap;
+    use std::hash::{Hash, Hasher};
+
+    type Leaves = BTreeMap<String, Vec<u8>>;
+
+    /// Returns a root that identifies the leaves, as a merkle root would
+    fn root_of(leaves: &Leaves) -> String {
+        let mut hasher = DefaultHasher::new();
+        leaves.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// The states of a tree by root, and its current root
+    struct MemoryTree {
+        states: HashMap<String, Leaves>,
+        root: String,
+    }
+
+    impl MemoryTree {
+        fn new(leaves: Leaves) -> Self {
+            let root = root_of(&leaves);
+            let mut states = HashMap::new();
+            states.insert(root.clone(), leaves);
+            Self { states, root }
+        }
+    }
+
+    /// Stores that keep each service's tree in memory, for a single local node
+    struct MemoryStores {
+        services: Vec<LocalService>,
+        trees: RefCell<HashMap<String, MemoryTree>>,
+    }
+
+    impl MemoryStores {
+        fn new(services: Vec<LocalService>) -> Self {
+            Self {
+                services,
+                trees: RefCell::new(HashMap::new()),
+            }
+        }
+
+        fn with_tree(self, service_id: &ServiceId, leaves: Leaves) -> Self {
+            self.trees
+                .borrow_mut()
+                .insert(service_id.to_string(), MemoryTree::new(leaves));
+            self
+        }
+
+        /// Returns the current leaves of the service's tree, if it has one
+        fn current_leaves(&self, service_id: &ServiceId) -> Option<Leaves> {
+            self.trees
+                .borrow()
+                .get(&service_id.to_string())
+                .map(|tree| tree.states[&tree.root].clone())
+        }
+    }
+
+    impl StateMigrationStores for MemoryStores {
+        fn node_id(&self) -> Result<Option<String>, InternalError> {
+            Ok(Some("node-000".to_string()))
+        }
+
+        fn list_scabbard_services(
+            &self,
+            _node_id: &str,
+        ) -> Result<Vec<LocalService>, InternalError> {
+            Ok(self.services.clone())
+        }
+
+        fn has_tree(&self, service_id: &ServiceId) -> Result<bool, InternalError> {
+            Ok(self.trees.borrow().contains_key(&service_id.to_string()))
+        }
+
+        fn get_merkle_state<'a>(
+            &'a self,
+            service_id: &ServiceId,
+            create_tree: bool,
+        ) -> Result<Box<dyn MigrationState + 'a>, InternalError> {
+            let tree = service_id.to_string();
+            let mut trees = self.trees.borrow_mut();
+            if !trees.contains_key(&tree) {
+                if !create_tree {
+                    return Err(InternalError::with_message(format!(
+                        "No tree for {}",
+                        service_id
+                    )));
+                }
+                trees.insert(tree.clone(), MemoryTree::new(Leaves::new()));
+            }
+
+            Ok(Box::new(MemoryState { stores: self, tree }))
+        }
+
+        fn in_transaction(&self, f: InTransactionHandle<'_>) -> Result<(), InternalError> {
+            f(self)
+        }
+    }
+
+    struct MemoryState<'a> {
+        stores: &'a MemoryStores,
+        tree: String,
+    }
+
+    impl<'a> MemoryState<'a> {
+        fn state(&self, state_id: &str) -> Leaves {
+            self.stores.trees.borrow()[&self.tree]
+                .states
+                .get(state_id)
+                .cloned()
+                .expect("Unknown state")
+        }
+    }
+
+    impl<'a> MigrationState for MemoryState<'a> {
+        fn state_root(&self) -> Result<String, InternalError> {
+            Ok(self.stores.trees.borrow()[&self.tree].root.clone())
+        }
+
+        fn leaves(
+            &self,
+            state_id: &str,
+            prefix: Option<&str>,
+        ) -> ValueIterResult<ValueIter<(String, Vec<u8>)>> {
+            let leaves = self
+                .state(state_id)
+                .into_iter()
+                .filter(|(address, _)| address.starts_with(prefix.unwrap_or("")))
+                .map(Ok)
+                .collect::<Vec<_>>();
+            Ok(Box::new(leaves.into_iter()))
+        }
+
+        fn get_leaves(
+            &self,
+            state_id: &str,
+            addresses: &[String],
+        ) -> Result<HashMap<String, Vec<u8>>, StateError> {
+            let state = self.state(state_id);
+            Ok(addresses
+                .iter()
+                .filter_map(|address| {
+                    state
+                        .get(address)
+                        .map(|value| (address.clone(), value.clone()))
+                })
+                .collect())
+        }
+
+        fn commit_changes(
+            &self,
+            state_id: &str,
+            state_changes: &[StateChange],
+        ) -> Result<String, StateError> {
+            let mut state = self.state(state_id);
+            for change in state_changes {
+                match change {
+                    StateChange::Set { key, value } => {
+                        state.insert(key.clone(), value.clone());
+                    }
+                    StateChange::Delete { key } => {
+                        state.remove(key);
+                    }
+                }
+            }
+
+            let root = root_of(&state);
+            let mut trees = self.stores.trees.borrow_mut();
+            let tree = trees.get_mut(&self.tree).expect("Tree was deleted");
+            tree.states.insert(root.clone(), state);
+            tree.root = root.clone();
+            Ok(root)
+        }
+
+        fn prune_state(&self, state_id: &str) -> Result<(), InternalError> {
+            let mut trees = self.stores.trees.borrow_mut();
+            let tree = trees.get_mut(&self.tree).expect("Tree was deleted");
+            if tree.root != state_id {
+                tree.states.remove(state_id);
+            }
+            Ok(())
+        }
+
+        fn delete_tree(self: Box<Self>) -> Result<(), InternalError> {
+            self.stores.trees.borrow_mut().remove(&self.tree);
+            Ok(())
+        }
+    }
+
+    fn leaves(addresses: &[&str]) -> Leaves {
+        addresses
+            .iter()
+            .map(|address| (address.to_string(), address.as_bytes().to_vec()))
+            .collect()
+    }
+
+    /// Verify that a migration with prefixes copies only the matching leaves and keeps the input
+    /// state, and that a full migration copies every leaf to the same root and deletes the input
+    /// state.
+    ///
+    /// 1. Migrate with two overlapping prefixes and verify that only the leaves under them are
+    ///    copied, without checking the root against the commit hash
+    /// 2. Verify that the input tree still exists
+    /// 3. Migrate every leaf to another store and verify the root matches the commit hash
+    /// 4. Verify that the input tree was deleted
+    #[test]
+    fn test_migrate_scabbard_state_with_prefixes() {
+        let service_id = ServiceId::new("abcde-01234".to_string(), "a000".to_string());
+        let input = leaves(&["00ec0000", "00ec0101", "00ec0102", "1cf12600"]);
+        let commit_hash = root_of(&input);
+
+        let in_stores = MemoryStores::new(vec![LocalService::new(
+            service_id.clone(),
+            Some(commit_hash.clone()),
+        )])
+        .with_tree(&service_id, input.clone());
+        let out_stores = MemoryStores::new(vec![]);
+
+        let report = migrate_scabbard_state(
+            &in_stores,
+            &out_stores,
+            &StateMigrationOptions {
+                prefixes: vec!["00ec01", "00ec0101"],
+                batch_size: 1,
+                ..Default::default()
+            },
+        )
+        .expect("Unable to migrate with prefixes");
+
+        assert_eq!(report.services().len(), 1);
+        assert_eq!(report.services()[0].service_id(), "a000");
+        assert_eq!(report.services()[0].leaves_copied(), 2);
+        assert_eq!(
+            out_stores.current_leaves(&service_id),
+            Some(leaves(&["00ec0101", "00ec0102"]))
+        );
+        assert_ne!(report.services()[0].state_root(), commit_hash);
+        assert_eq!(in_stores.current_leaves(&service_id), Some(input.clone()));
+
+        let out_stores = MemoryStores::new(vec![]);
+        let report = migrate_scabbard_state(
+            &in_stores,
+            &out_stores,
+            &StateMigrationOptions {
+                verify: true,
+                ..Default::default()
+            },
+        )
+        .expect("Unable to migrate");
+
+        assert_eq!(report.services()[0].leaves_copied(), 4);
+        assert_eq!(report.services()[0].state_root(), commit_hash);
+        assert_eq!(out_stores.current_leaves(&service_id), Some(input));
+        assert_eq!(in_stores.current_leaves(&service_id), None);
+    }
+
+    /// Verify that the migration fails when the output stores already hold a tree for a service,
+    /// unless it is forced.
+    #[test]
+    fn test_migrate_scabbard_state_existing_tree() {
+        let service_id = ServiceId::new("abcde-01234".to_string(), "a000".to_string());
+        let input = leaves(&["00ec0000"]);
+        let services = vec![LocalService::new(service_id.clone(), Some(root_of(&input)))];
+
+        let in_stores = MemoryStores::new(services.clone()).with_tree(&service_id, input.clone());
+        // An empty tree, as left behind by an interrupted migration
+        let out_stores = MemoryStores::new(vec![]).with_tree(&service_id, Leaves::new());
+        assert!(
+            migrate_scabbard_state(&in_stores, &out_stores, &StateMigrationOptions::default())
+                .is_err()
+        );
+        assert_eq!(in_stores.current_leaves(&service_id), Some(input.clone()));
+
+        let report = migrate_scabbard_state(
+            &in_stores,
+            &out_stores,
+            &StateMigrationOptions {
+                force: true,
+                ..Default::default()
+            },
+        )
+        .expect("Unable to migrate");
+        assert_eq!(report.services()[0].leaves_copied(), 1);
+        assert_eq!(out_stores.current_leaves(&service_id), Some(input));
+        assert_eq!(in_stores.current_leaves(&service_id), None);
+    }
+}
@@ -16,6 +16,7 @@ mod models;
 mod operations;
 mod schema;
 
+use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
 use diesel::r2d2::{ConnectionManager, Pool};
@@ -25,6 +26,7 @@ use crate::store::pool::ConnectionPool;
 use super::{CommitHashStore, CommitHashStoreError};
 
 use operations::get_current_commit_hash::CommitHashStoreGetCurrentCommitHashOperation as _;
+use operations::get_current_commit_hashes::CommitHashStoreGetCurrentCommitHashesOperation as _;
 use operations::set_current_commit_hash::CommitHashStoreSetCurrentCommitHashOperation as _;
 use operations::CommitHashStoreOperations;
 
@@ -75,6 +77,23 @@ impl<C: diesel::Connection> DieselCommitHashStore<C> {
     }
 }
 
+#[cfg(feature = "postgres")]
+impl DieselCommitHashStore<diesel::pg::PgConnection> {
+    /// Returns the current commit hash of each of the given services, using a single query.
+    ///
+    /// The services are given as `(circuit_id, service_id)` pairs and are not limited to the
+    /// service associated with this store. Every requested service is included in the result,
+    /// with `None` if it has no commit hash.
+    pub fn get_current_commit_hashes(
+        &self,
+        services: &[(String, String)],
+    ) -> Result<HashMap<(String, String), Option<String>>, CommitHashStoreError> {
+        self.pool.execute_read(|conn| {
+            CommitHashStoreOperations::new(conn).get_current_commit_hashes(services)
+        })
+    }
+}
+
 #[cfg(feature = "postgres")]
 impl CommitHashStore for DieselCommitHashStore<diesel::pg::PgConnection> {
     fn get_current_commit_hash(&self) -> Result<Option<String>, CommitHashStoreError> {
@@ -95,6 +114,23 @@ impl CommitHashStore for DieselCommitHashStore<diesel::pg::PgConnection> {
     }
 }
 
+#[cfg(feature = "sqlite")]
+impl DieselCommitHashStore<diesel::sqlite::SqliteConnection> {
+    /// Returns the current commit hash of each of the given services, using a single query.
+    ///
+    /// The services are given as `(circuit_id, service_id)` pairs and are not limited to the
+    /// service associated with this store. Every requested service is included in the result,
+    /// with `None` if it has no commit hash.
+    pub fn get_current_commit_hashes(
+        &self,
+        services: &[(String, String)],
+    ) -> Result<HashMap<(String, String), Option<String>>, CommitHashStoreError> {
+        self.pool.execute_read(|conn| {
+            CommitHashStoreOperations::new(conn).get_current_commit_hashes(services)
+        })
+    }
+}
+
 #[cfg(feature = "sqlite")]
 impl CommitHashStore for DieselCommitHashStore<diesel::sqlite::SqliteConnection> {
     fn get_current_commit_hash(&self) -> Result<Option<String>, CommitHashStoreError> {
@@ -225,6 +261,46 @@ mod tests {
         Ok(())
     }
 
+    /// Test that a DieselCommitHashStore using a SQLite connection pool can fetch the commit
+    /// hashes of several services at once
+    /// 1. Set hashes for two services on different circuits
+    /// 2. Fetch the hashes for both services, a service with no hash, and a service whose circuit
+    ///    and service IDs each match a stored service but not together
+    /// 3. Verify that each requested service has the expected hash, or None
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_get_current_commit_hashes() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = create_connection_pool_and_migrate()?;
+        let store_1 = DieselCommitHashStore::new(pool.clone(), "circuit_1", "service_a");
+        store_1.set_current_commit_hash("abcdef0123456789")?;
+        let store_2 = DieselCommitHashStore::new(pool, "circuit_2", "service_b");
+        store_2.set_current_commit_hash("9876543210fedcba")?;
+
+        let services = vec![
+            ("circuit_1".to_string(), "service_a".to_string()),
+            ("circuit_2".to_string(), "service_b".to_string()),
+            ("circuit_3".to_string(), "service_c".to_string()),
+            ("circuit_1".to_string(), "service_b".to_string()),
+        ];
+        let commit_hashes = store_1.get_current_commit_hashes(&services)?;
+
+        assert_eq!(commit_hashes.len(), 4);
+        assert_eq!(
+            commit_hashes[&services[0]],
+            Some("abcdef0123456789".to_string())
+        );
+        assert_eq!(
+            commit_hashes[&services[1]],
+            Some("9876543210fedcba".to_string())
+        );
+        assert_eq!(commit_hashes[&services[2]], None);
+        assert_eq!(commit_hashes[&services[3]], None);
+
+        assert!(store_1.get_current_commit_hashes(&[])?.is_empty());
+
+        Ok(())
+    }
+
     /// Creates a connection pool for an in-memory SQLite database with only a single connection
     /// available. Each connection is backed by a different in-memory SQLite database, so limiting
     /// the pool to a single connection ensures that the same DB is used for all operations.
@@ -0,0 +1,80 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use splinter::error::InternalError;
+
+use crate::store::{diesel::schema::scabbard_commit_hash, CommitHashStoreError};
+
+use super::CommitHashStoreOperations;
+
+pub(in crate::store::commit_hash::diesel) trait CommitHashStoreGetCurrentCommitHashesOperation {
+    fn get_current_commit_hashes(
+        &self,
+        services: &[(String, String)],
+    ) -> Result<HashMap<(String, String), Option<String>>, CommitHashStoreError>;
+}
+
+impl<'a, C> CommitHashStoreGetCurrentCommitHashesOperation for CommitHashStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    String: diesel::deserialize::FromSql<Text, C::Backend>,
+{
+    fn get_current_commit_hashes(
+        &self,
+        services: &[(String, String)],
+    ) -> Result<HashMap<(String, String), Option<String>>, CommitHashStoreError> {
+        let mut commit_hashes: HashMap<(String, String), Option<String>> = services
+            .iter()
+            .map(|service| (service.clone(), None))
+            .collect();
+
+        if services.is_empty() {
+            return Ok(commit_hashes);
+        }
+
+        let circuit_ids = services
+            .iter()
+            .map(|(circuit_id, _)| circuit_id.as_str())
+            .collect::<Vec<_>>();
+        let service_ids = services
+            .iter()
+            .map(|(_, service_id)| service_id.as_str())
+            .collect::<Vec<_>>();
+
+        // Filtering on each column separately may match services that were not requested, so
+        // only the rows for requested services are kept
+        let rows = scabbard_commit_hash::table
+            .filter(scabbard_commit_hash::circuit_id.eq_any(circuit_ids))
+            .filter(scabbard_commit_hash::service_id.eq_any(service_ids))
+            .select((
+                scabbard_commit_hash::circuit_id,
+                scabbard_commit_hash::service_id,
+                scabbard_commit_hash::commit_hash,
+            ))
+            .load::<(String, String, String)>(self.conn)
+            .map_err(|err| InternalError::from_source(Box::new(err)))?;
+
+        for (circuit_id, service_id, commit_hash) in rows {
+            if let Some(entry) = commit_hashes.get_mut(&(circuit_id, service_id)) {
+                *entry = Some(commit_hash);
+            }
+        }
+
+        Ok(commit_hashes)
+    }
+}
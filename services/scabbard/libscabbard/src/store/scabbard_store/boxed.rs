@@ -183,6 +183,24 @@ impl ScabbardStore for Box<dyn ScabbardStore> {
         (&**self).add_consensus_event(service_id, event)
     }
 
+    /// Add a new consensus event, unless an event with the same idempotency key has already been
+    /// added for the service
+    ///
+    /// # Arguments
+    ///
+    /// * `service_id` - The combined `CircuitId` and `ServiceId` of the service the event
+    ///    belongs to
+    /// * `event` - The `ConsensusEvent` to be added
+    /// * `idempotency_key` - An optional key identifying the event
+    fn add_consensus_event_with_key(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        event: ConsensusEvent,
+        idempotency_key: Option<&str>,
+    ) -> Result<i64, ScabbardStoreError> {
+        (&**self).add_consensus_event_with_key(service_id, event, idempotency_key)
+    }
+
     /// Update an existing consensus event
     ///
     /// # Arguments
@@ -214,6 +232,15 @@ impl ScabbardStore for Box<dyn ScabbardStore> {
         (&**self).list_consensus_events(service_id)
     }
 
+    /// Remove handled consensus events for a given service with an ID below the given ID
+    fn prune_consensus_events(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        before_id: i64,
+    ) -> Result<usize, ScabbardStoreError> {
+        (&**self).prune_consensus_events(service_id, before_id)
+    }
+
     /// Get the current context for a given service
     ///
     /// # Arguments
@@ -52,6 +52,7 @@ use operations::list_consensus_actions::ListActionsOperation as _;
 use operations::list_consensus_events::ListEventsOperation as _;
 use operations::list_ready_services::ListReadyServicesOperation as _;
 use operations::list_supervisor_notifications::ListSupervisorNotificationOperation as _;
+use operations::prune_consensus_events::PruneEventsOperation as _;
 use operations::remove_service::RemoveServiceOperation as _;
 use operations::set_alarm::SetAlarmOperation as _;
 use operations::unset_alarm::UnsetAlarmOperation as _;
@@ -195,6 +196,21 @@ impl ScabbardStore for DieselScabbardStore<SqliteConnection> {
             ScabbardStoreOperations::new(conn).add_consensus_event(service_id, event)
         })
     }
+    /// Add a new consensus event, unless an event with the same idempotency key exists
+    fn add_consensus_event_with_key(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        event: ConsensusEvent,
+        idempotency_key: Option<&str>,
+    ) -> Result<i64, ScabbardStoreError> {
+        self.pool.execute_write(|conn| {
+            ScabbardStoreOperations::new(conn).add_consensus_event_with_key(
+                service_id,
+                event,
+                idempotency_key,
+            )
+        })
+    }
     /// Update an existing consensus event
     fn update_consensus_event(
         &self,
@@ -221,6 +237,16 @@ impl ScabbardStore for DieselScabbardStore<SqliteConnection> {
             ScabbardStoreOperations::new(conn).list_consensus_events(service_id)
         })
     }
+    /// Remove handled consensus events for a given service with an ID below the given ID
+    fn prune_consensus_events(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        before_id: i64,
+    ) -> Result<usize, ScabbardStoreError> {
+        self.pool.execute_write(|conn| {
+            ScabbardStoreOperations::new(conn).prune_consensus_events(service_id, before_id)
+        })
+    }
     /// Get the current context for a given service
     fn get_current_consensus_context(
         &self,
@@ -421,6 +447,21 @@ impl ScabbardStore for DieselScabbardStore<PgConnection> {
             ScabbardStoreOperations::new(conn).add_consensus_event(service_id, event)
         })
     }
+    /// Add a new consensus event, unless an event with the same idempotency key exists
+    fn add_consensus_event_with_key(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        event: ConsensusEvent,
+        idempotency_key: Option<&str>,
+    ) -> Result<i64, ScabbardStoreError> {
+        self.pool.execute_write(|conn| {
+            ScabbardStoreOperations::new(conn).add_consensus_event_with_key(
+                service_id,
+                event,
+                idempotency_key,
+            )
+        })
+    }
     /// Update an existing consensus event
     fn update_consensus_event(
         &self,
@@ -447,6 +488,16 @@ impl ScabbardStore for DieselScabbardStore<PgConnection> {
             ScabbardStoreOperations::new(conn).list_consensus_events(service_id)
         })
     }
+    /// Remove handled consensus events for a given service with an ID below the given ID
+    fn prune_consensus_events(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        before_id: i64,
+    ) -> Result<usize, ScabbardStoreError> {
+        self.pool.execute_write(|conn| {
+            ScabbardStoreOperations::new(conn).prune_consensus_events(service_id, before_id)
+        })
+    }
     /// Get the current context for a given service
     fn get_current_consensus_context(
         &self,
@@ -645,6 +696,19 @@ impl<'a> ScabbardStore for DieselConnectionScabbardStore<'a, SqliteConnection> {
     ) -> Result<i64, ScabbardStoreError> {
         ScabbardStoreOperations::new(self.connection).add_consensus_event(service_id, event)
     }
+    /// Add a new consensus event, unless an event with the same idempotency key exists
+    fn add_consensus_event_with_key(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        event: ConsensusEvent,
+        idempotency_key: Option<&str>,
+    ) -> Result<i64, ScabbardStoreError> {
+        ScabbardStoreOperations::new(self.connection).add_consensus_event_with_key(
+            service_id,
+            event,
+            idempotency_key,
+        )
+    }
     /// Update an existing consensus event
     fn update_consensus_event(
         &self,
@@ -667,6 +731,14 @@ impl<'a> ScabbardStore for DieselConnectionScabbardStore<'a, SqliteConnection> {
     ) -> Result<Vec<Identified<ConsensusEvent>>, ScabbardStoreError> {
         ScabbardStoreOperations::new(self.connection).list_consensus_events(service_id)
     }
+    /// Remove handled consensus events for a given service with an ID below the given ID
+    fn prune_consensus_events(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        before_id: i64,
+    ) -> Result<usize, ScabbardStoreError> {
+        ScabbardStoreOperations::new(self.connection).prune_consensus_events(service_id, before_id)
+    }
     /// Get the current context for a given service
     fn get_current_consensus_context(
         &self,
@@ -830,6 +902,19 @@ impl<'a> ScabbardStore for DieselConnectionScabbardStore<'a, PgConnection> {
     ) -> Result<i64, ScabbardStoreError> {
         ScabbardStoreOperations::new(self.connection).add_consensus_event(service_id, event)
     }
+    /// Add a new consensus event, unless an event with the same idempotency key exists
+    fn add_consensus_event_with_key(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        event: ConsensusEvent,
+        idempotency_key: Option<&str>,
+    ) -> Result<i64, ScabbardStoreError> {
+        ScabbardStoreOperations::new(self.connection).add_consensus_event_with_key(
+            service_id,
+            event,
+            idempotency_key,
+        )
+    }
     /// Update an existing consensus event
     fn update_consensus_event(
         &self,
@@ -852,6 +937,14 @@ impl<'a> ScabbardStore for DieselConnectionScabbardStore<'a, PgConnection> {
     ) -> Result<Vec<Identified<ConsensusEvent>>, ScabbardStoreError> {
         ScabbardStoreOperations::new(self.connection).list_consensus_events(service_id)
     }
+    /// Remove handled consensus events for a given service with an ID below the given ID
+    fn prune_consensus_events(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        before_id: i64,
+    ) -> Result<usize, ScabbardStoreError> {
+        ScabbardStoreOperations::new(self.connection).prune_consensus_events(service_id, before_id)
+    }
     /// Get the current context for a given service
     fn get_current_consensus_context(
         &self,
@@ -2128,6 +2221,113 @@ pub mod tests {
         })
     }
 
+    /// Test that the scabbard store `add_consensus_event_with_key` operation only adds an event
+    /// once for a given idempotency key.
+    ///
+    /// 1. Add a valid participant context to the store
+    /// 2. Add the same event twice with the key derived from the event, and check that the ID of
+    ///    the first event is returned both times
+    /// 3. Check that only one event was stored
+    /// 4. Add the event again without a key and check that a second event is stored
+    fn scabbard_store_add_event_with_key(store: &dyn ScabbardStore) {
+        let coordinator_fqsi = FullyQualifiedServiceId::new_random();
+
+        let participant_fqsi = FullyQualifiedServiceId::new_random();
+        let participant2_fqsi = FullyQualifiedServiceId::new_random();
+
+        let service = ScabbardServiceBuilder::default()
+            .with_service_id(&participant_fqsi)
+            .with_peers(&[
+                coordinator_fqsi.service_id().clone(),
+                participant2_fqsi.service_id().clone(),
+            ])
+            .with_consensus(&ConsensusType::TwoPC)
+            .with_status(&ServiceStatus::Finalized)
+            .build()
+            .expect("failed to build service");
+
+        store.add_service(service).expect("failed to add service");
+
+        let participant_context = ContextBuilder::default()
+            .with_coordinator(coordinator_fqsi.clone().service_id())
+            .with_epoch(1)
+            .with_participants(vec![
+                Participant {
+                    process: participant_fqsi.service_id().clone(),
+                    vote: None,
+                    decision_ack: false,
+                },
+                Participant {
+                    process: participant2_fqsi.service_id().clone(),
+                    vote: None,
+                    decision_ack: false,
+                },
+            ])
+            .with_state(State::WaitingForVoteRequest)
+            .with_this_process(participant_fqsi.clone().service_id())
+            .build()
+            .expect("failed to build context");
+        let context = ConsensusContext::TwoPhaseCommit(participant_context);
+
+        store
+            .add_consensus_context(&participant_fqsi, context)
+            .expect("failed to add context");
+
+        let event = ConsensusEvent::TwoPhaseCommit(Event::Deliver(
+            coordinator_fqsi.service_id().clone(),
+            Message::VoteRequest(1, b"test".to_vec()),
+        ));
+        let key = event.idempotency_key();
+
+        let event_id = store
+            .add_consensus_event_with_key(&participant_fqsi, event.clone(), Some(&key))
+            .expect("failed to add event");
+        let duplicate_event_id = store
+            .add_consensus_event_with_key(&participant_fqsi, event.clone(), Some(&key))
+            .expect("failed to add duplicate event");
+        assert_eq!(event_id, duplicate_event_id);
+
+        let events = store
+            .list_consensus_events(&participant_fqsi)
+            .expect("failed to list events");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, event_id);
+
+        let unkeyed_event_id = store
+            .add_consensus_event_with_key(&participant_fqsi, event, None)
+            .expect("failed to add event without a key");
+        assert_ne!(event_id, unkeyed_event_id);
+        assert_eq!(
+            store
+                .list_consensus_events(&participant_fqsi)
+                .expect("failed to list events")
+                .len(),
+            2
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_scabbard_store_add_event_with_key() {
+        let pool = create_sqlite_memory_pool();
+
+        let store = DieselScabbardStore::new(pool);
+
+        scabbard_store_add_event_with_key(&store);
+    }
+
+    #[cfg(feature = "diesel-postgres-tests")]
+    #[test]
+    fn postgres_scabbard_store_add_event_with_key() -> Result<(), Box<dyn std::error::Error>> {
+        run_postgres_test(|url| {
+            let pool = create_postgres_pool(url)?;
+            let store = DieselScabbardStore::new(pool);
+            scabbard_store_add_event_with_key(&store);
+
+            Ok(())
+        })
+    }
+
     /// Test that the scabbard store `update_consensus_event` operation is successful.
     ///
     /// 1. Add a valid participant context to the store
@@ -2425,6 +2625,166 @@ pub mod tests {
         })
     }
 
+    /// Test that the scabbard store `prune_consensus_events` operation only removes handled
+    /// events below the given ID.
+    ///
+    /// 1. Add a valid participant context to the store
+    /// 2. Add four valid events to the store
+    /// 3. Handle the first event with an executed action
+    /// 4. Handle the second event, leaving its action unexecuted
+    /// 5. Leave the third event unhandled
+    /// 6. Handle the fourth event with an executed action
+    /// 7. Prune events below the fourth event and check that only the first event is removed
+    /// 8. Check that the third event is still pending and the second event's action is still
+    ///    pending
+    /// 9. Prune events below the fourth event again and check that nothing is removed
+    /// 10. Prune events including the fourth event and check that only it is removed
+    fn scabbard_store_prune_events(store: &dyn ScabbardStore) {
+        let coordinator_fqsi = FullyQualifiedServiceId::new_random();
+
+        let participant_fqsi = FullyQualifiedServiceId::new_random();
+        let participant2_fqsi = FullyQualifiedServiceId::new_random();
+
+        let service = ScabbardServiceBuilder::default()
+            .with_service_id(&participant_fqsi)
+            .with_peers(&[
+                coordinator_fqsi.service_id().clone(),
+                participant2_fqsi.service_id().clone(),
+            ])
+            .with_consensus(&ConsensusType::TwoPC)
+            .with_status(&ServiceStatus::Finalized)
+            .build()
+            .expect("failed to build service");
+
+        store.add_service(service).expect("failed to add service");
+
+        let participant_context = ContextBuilder::default()
+            .with_coordinator(coordinator_fqsi.clone().service_id())
+            .with_epoch(1)
+            .with_participants(vec![
+                Participant {
+                    process: participant_fqsi.service_id().clone(),
+                    vote: None,
+                    decision_ack: false,
+                },
+                Participant {
+                    process: participant2_fqsi.service_id().clone(),
+                    vote: None,
+                    decision_ack: false,
+                },
+            ])
+            .with_state(State::WaitingForVoteRequest)
+            .with_this_process(participant_fqsi.clone().service_id())
+            .build()
+            .expect("failed to build context");
+
+        store
+            .add_consensus_context(
+                &participant_fqsi,
+                ConsensusContext::TwoPhaseCommit(participant_context.clone()),
+            )
+            .expect("failed to add context");
+
+        let event_ids = (0..4)
+            .map(|_| {
+                store
+                    .add_consensus_event(
+                        &participant_fqsi,
+                        ConsensusEvent::TwoPhaseCommit(Event::Alarm()),
+                    )
+                    .expect("failed to add event")
+            })
+            .collect::<Vec<_>>();
+
+        let update_action = || {
+            ConsensusAction::TwoPhaseCommit(Action::Update(
+                ConsensusContext::TwoPhaseCommit(participant_context.clone()),
+                None,
+            ))
+        };
+
+        let action_id = store
+            .add_consensus_action(update_action(), &participant_fqsi, event_ids[0])
+            .expect("failed to add action");
+        store
+            .update_consensus_action(&participant_fqsi, action_id, SystemTime::now())
+            .expect("failed to update action");
+        store
+            .update_consensus_event(&participant_fqsi, event_ids[0], SystemTime::now(), 1)
+            .expect("failed to update event");
+
+        let pending_action_id = store
+            .add_consensus_action(update_action(), &participant_fqsi, event_ids[1])
+            .expect("failed to add action");
+        store
+            .update_consensus_event(&participant_fqsi, event_ids[1], SystemTime::now(), 1)
+            .expect("failed to update event");
+
+        let action_id = store
+            .add_consensus_action(update_action(), &participant_fqsi, event_ids[3])
+            .expect("failed to add action");
+        store
+            .update_consensus_action(&participant_fqsi, action_id, SystemTime::now())
+            .expect("failed to update action");
+        store
+            .update_consensus_event(&participant_fqsi, event_ids[3], SystemTime::now(), 1)
+            .expect("failed to update event");
+
+        assert_eq!(
+            store
+                .prune_consensus_events(&participant_fqsi, event_ids[3])
+                .expect("failed to prune events"),
+            1
+        );
+
+        let events = store
+            .list_consensus_events(&participant_fqsi)
+            .expect("failed to list events");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, event_ids[2]);
+
+        let actions = store
+            .list_consensus_actions(&participant_fqsi)
+            .expect("failed to list actions");
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].id, pending_action_id);
+
+        assert_eq!(
+            store
+                .prune_consensus_events(&participant_fqsi, event_ids[3])
+                .expect("failed to prune events"),
+            0
+        );
+
+        assert_eq!(
+            store
+                .prune_consensus_events(&participant_fqsi, event_ids[3] + 1)
+                .expect("failed to prune events"),
+            1
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_scabbard_store_prune_events() {
+        let pool = create_sqlite_memory_pool();
+
+        let store = DieselScabbardStore::new(pool);
+        scabbard_store_prune_events(&store);
+    }
+
+    #[cfg(feature = "diesel-postgres-tests")]
+    #[test]
+    fn postgres_scabbard_store_prune_events() -> Result<(), Box<dyn std::error::Error>> {
+        run_postgres_test(|url| {
+            let pool = create_postgres_pool(url)?;
+            let store = DieselScabbardStore::new(pool);
+            scabbard_store_prune_events(&store);
+
+            Ok(())
+        })
+    }
+
     /// Test that the scabbard store `get_current_consensus_context` operation is successful.
     ///
     /// 1. Add two services to the database
@@ -1307,6 +1307,7 @@ pub struct Consensus2pcEventModel {
     pub executed_epoch: Option<i64>,
     pub event_type: EventTypeModel,
     pub update_context_action_id: Option<i64>,
+    pub idempotency_key: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, Insertable)]
@@ -1317,6 +1318,7 @@ pub struct InsertableConsensus2pcEventModel {
     pub executed_at: Option<NaiveDateTime>,
     pub event_type: EventTypeModel,
     pub update_context_action_id: Option<i64>,
+    pub idempotency_key: Option<String>,
 }
 
 impl From<&Event> for EventTypeModel {
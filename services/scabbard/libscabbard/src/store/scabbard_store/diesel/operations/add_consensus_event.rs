@@ -48,15 +48,27 @@ pub(in crate::store::scabbard_store::diesel) trait AddEventOperation {
         &self,
         service_id: &FullyQualifiedServiceId,
         event: ConsensusEvent,
+    ) -> Result<i64, ScabbardStoreError> {
+        self.add_consensus_event_with_key(service_id, event, None)
+    }
+
+    /// Adds the event unless the service already has an event with the given idempotency key, in
+    /// which case the ID of the existing event is returned.
+    fn add_consensus_event_with_key(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        event: ConsensusEvent,
+        idempotency_key: Option<&str>,
     ) -> Result<i64, ScabbardStoreError>;
 }
 
 #[cfg(feature = "sqlite")]
 impl<'a> AddEventOperation for ScabbardStoreOperations<'a, SqliteConnection> {
-    fn add_consensus_event(
+    fn add_consensus_event_with_key(
         &self,
         service_id: &FullyQualifiedServiceId,
         event: ConsensusEvent,
+        idempotency_key: Option<&str>,
     ) -> Result<i64, ScabbardStoreError> {
         self.conn.transaction::<_, _, _>(|| {
             let ConsensusEvent::TwoPhaseCommit(event) = event;
@@ -78,12 +90,38 @@ impl<'a> AddEventOperation for ScabbardStoreOperations<'a, SqliteConnection> {
                     )))
                 })?;
 
+            if let Some(idempotency_key) = idempotency_key {
+                let existing_event_id = consensus_2pc_event::table
+                    .filter(
+                        consensus_2pc_event::circuit_id
+                            .eq(service_id.circuit_id().to_string())
+                            .and(
+                                consensus_2pc_event::service_id
+                                    .eq(service_id.service_id().to_string()),
+                            )
+                            .and(consensus_2pc_event::idempotency_key.eq(idempotency_key)),
+                    )
+                    .select(consensus_2pc_event::id)
+                    .first::<i64>(self.conn)
+                    .optional()
+                    .map_err(|err| {
+                        ScabbardStoreError::from_source_with_operation(
+                            err,
+                            OPERATION_NAME.to_string(),
+                        )
+                    })?;
+                if let Some(event_id) = existing_event_id {
+                    return Ok(event_id);
+                }
+            }
+
             let insertable_event = InsertableConsensus2pcEventModel {
                 circuit_id: service_id.circuit_id().to_string(),
                 service_id: service_id.service_id().to_string(),
                 executed_at: None,
                 event_type: EventTypeModel::from(&event),
                 update_context_action_id: None,
+                idempotency_key: idempotency_key.map(String::from),
             };
 
             insert_into(consensus_2pc_event::table)
@@ -183,10 +221,11 @@ impl<'a> AddEventOperation for ScabbardStoreOperations<'a, SqliteConnection> {
 
 #[cfg(feature = "postgres")]
 impl<'a> AddEventOperation for ScabbardStoreOperations<'a, PgConnection> {
-    fn add_consensus_event(
+    fn add_consensus_event_with_key(
         &self,
         service_id: &FullyQualifiedServiceId,
         event: ConsensusEvent,
+        idempotency_key: Option<&str>,
     ) -> Result<i64, ScabbardStoreError> {
         self.conn.transaction::<_, _, _>(|| {
             let ConsensusEvent::TwoPhaseCommit(event) = event;
@@ -208,12 +247,38 @@ impl<'a> AddEventOperation for ScabbardStoreOperations<'a, PgConnection> {
                     )))
                 })?;
 
+            if let Some(idempotency_key) = idempotency_key {
+                let existing_event_id = consensus_2pc_event::table
+                    .filter(
+                        consensus_2pc_event::circuit_id
+                            .eq(service_id.circuit_id().to_string())
+                            .and(
+                                consensus_2pc_event::service_id
+                                    .eq(service_id.service_id().to_string()),
+                            )
+                            .and(consensus_2pc_event::idempotency_key.eq(idempotency_key)),
+                    )
+                    .select(consensus_2pc_event::id)
+                    .first::<i64>(self.conn)
+                    .optional()
+                    .map_err(|err| {
+                        ScabbardStoreError::from_source_with_operation(
+                            err,
+                            OPERATION_NAME.to_string(),
+                        )
+                    })?;
+                if let Some(event_id) = existing_event_id {
+                    return Ok(event_id);
+                }
+            }
+
             let insertable_event = InsertableConsensus2pcEventModel {
                 circuit_id: service_id.circuit_id().to_string(),
                 service_id: service_id.service_id().to_string(),
                 executed_at: None,
                 event_type: EventTypeModel::from(&event),
                 update_context_action_id: None,
+                idempotency_key: idempotency_key.map(String::from),
             };
 
             let event_id: i64 = insert_into(consensus_2pc_event::table)
@@ -26,6 +26,7 @@ pub(super) mod list_consensus_actions;
 pub(super) mod list_consensus_events;
 pub(super) mod list_ready_services;
 pub(super) mod list_supervisor_notifications;
+pub(super) mod prune_consensus_events;
 pub(super) mod remove_service;
 pub(super) mod set_alarm;
 pub(super) mod unset_alarm;
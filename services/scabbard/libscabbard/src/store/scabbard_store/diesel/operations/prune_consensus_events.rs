@@ -0,0 +1,159 @@
+// Copyright 2018-2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "postgres")]
+use diesel::pg::PgConnection;
+#[cfg(feature = "sqlite")]
+use diesel::sqlite::SqliteConnection;
+use diesel::{dsl::delete, prelude::*};
+use splinter::error::InvalidStateError;
+use splinter::service::FullyQualifiedServiceId;
+
+use crate::store::scabbard_store::diesel::operations::get_service::GetServiceOperation;
+use crate::store::scabbard_store::diesel::schema::{
+    consensus_2pc_action, consensus_2pc_event, supervisor_notification,
+};
+use crate::store::scabbard_store::ScabbardStoreError;
+
+use super::ScabbardStoreOperations;
+
+const OPERATION_NAME: &str = "prune_consensus_events";
+
+pub(in crate::store::scabbard_store::diesel) trait PruneEventsOperation {
+    fn prune_consensus_events(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        before_id: i64,
+    ) -> Result<usize, ScabbardStoreError>;
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> PruneEventsOperation for ScabbardStoreOperations<'a, SqliteConnection> {
+    fn prune_consensus_events(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        before_id: i64,
+    ) -> Result<usize, ScabbardStoreError> {
+        self.conn.transaction::<_, _, _>(|| {
+            // check to see if a service with the given service_id exists
+            self.get_service(service_id)?.ok_or_else(|| {
+                ScabbardStoreError::InvalidState(InvalidStateError::with_message(String::from(
+                    "Service does not exist",
+                )))
+            })?;
+
+            let circuit_id = service_id.circuit_id().to_string();
+            let service_id = service_id.service_id().to_string();
+
+            // Deleting an event cascades to the actions it produced and to their supervisor
+            // notifications, so events with any outstanding action or notification are kept
+            let pending_notification_actions = supervisor_notification::table
+                .filter(
+                    supervisor_notification::circuit_id
+                        .eq(&circuit_id)
+                        .and(supervisor_notification::service_id.eq(&service_id))
+                        .and(supervisor_notification::executed_at.is_null()),
+                )
+                .select(supervisor_notification::action_id);
+
+            let pending_events = consensus_2pc_action::table
+                .filter(
+                    consensus_2pc_action::circuit_id
+                        .eq(&circuit_id)
+                        .and(consensus_2pc_action::service_id.eq(&service_id))
+                        .and(
+                            consensus_2pc_action::executed_at
+                                .is_null()
+                                .or(consensus_2pc_action::id.eq_any(pending_notification_actions)),
+                        ),
+                )
+                .select(consensus_2pc_action::event_id);
+
+            delete(
+                consensus_2pc_event::table.filter(
+                    consensus_2pc_event::circuit_id
+                        .eq(&circuit_id)
+                        .and(consensus_2pc_event::service_id.eq(&service_id))
+                        .and(consensus_2pc_event::id.lt(before_id))
+                        .and(consensus_2pc_event::executed_at.is_not_null())
+                        .and(consensus_2pc_event::id.ne_all(pending_events)),
+                ),
+            )
+            .execute(self.conn)
+            .map_err(|err| {
+                ScabbardStoreError::from_source_with_operation(err, OPERATION_NAME.to_string())
+            })
+        })
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> PruneEventsOperation for ScabbardStoreOperations<'a, PgConnection> {
+    fn prune_consensus_events(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        before_id: i64,
+    ) -> Result<usize, ScabbardStoreError> {
+        self.conn.transaction::<_, _, _>(|| {
+            // check to see if a service with the given service_id exists
+            self.get_service(service_id)?.ok_or_else(|| {
+                ScabbardStoreError::InvalidState(InvalidStateError::with_message(String::from(
+                    "Service does not exist",
+                )))
+            })?;
+
+            let circuit_id = service_id.circuit_id().to_string();
+            let service_id = service_id.service_id().to_string();
+
+            // Deleting an event cascades to the actions it produced and to their supervisor
+            // notifications, so events with any outstanding action or notification are kept
+            let pending_notification_actions = supervisor_notification::table
+                .filter(
+                    supervisor_notification::circuit_id
+                        .eq(&circuit_id)
+                        .and(supervisor_notification::service_id.eq(&service_id))
+                        .and(supervisor_notification::executed_at.is_null()),
+                )
+                .select(supervisor_notification::action_id);
+
+            let pending_events = consensus_2pc_action::table
+                .filter(
+                    consensus_2pc_action::circuit_id
+                        .eq(&circuit_id)
+                        .and(consensus_2pc_action::service_id.eq(&service_id))
+                        .and(
+                            consensus_2pc_action::executed_at
+                                .is_null()
+                                .or(consensus_2pc_action::id.eq_any(pending_notification_actions)),
+                        ),
+                )
+                .select(consensus_2pc_action::event_id);
+
+            delete(
+                consensus_2pc_event::table.filter(
+                    consensus_2pc_event::circuit_id
+                        .eq(&circuit_id)
+                        .and(consensus_2pc_event::service_id.eq(&service_id))
+                        .and(consensus_2pc_event::id.lt(before_id))
+                        .and(consensus_2pc_event::executed_at.is_not_null())
+                        .and(consensus_2pc_event::id.ne_all(pending_events)),
+                ),
+            )
+            .execute(self.conn)
+            .map_err(|err| {
+                ScabbardStoreError::from_source_with_operation(err, OPERATION_NAME.to_string())
+            })
+        })
+    }
+}
@@ -142,6 +142,7 @@ table! {
         position -> Integer,
         event_type -> crate::store::scabbard_store::diesel::models::EventTypeModelMapping,
         update_context_action_id -> Nullable<Int8>,
+        idempotency_key -> Nullable<Text>,
     }
 }
 
@@ -18,9 +18,11 @@ use std::convert::{TryFrom, TryInto as _};
 #[cfg(feature = "scabbardv3-consensus")]
 use augrim::{error::InternalError, two_phase_commit::TwoPhaseCommitEvent};
 
+use openssl::sha::sha256;
+
 #[cfg(feature = "scabbardv3-consensus")]
 use crate::service::v3::{ScabbardProcess, ScabbardValue};
-use crate::store::scabbard_store::two_phase_commit::Event;
+use crate::store::scabbard_store::two_phase_commit::{Event, Message};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum ConsensusEvent {
@@ -33,6 +35,76 @@ impl ConsensusEvent {
             Self::TwoPhaseCommit(_) => "two-phase-commit",
         }
     }
+
+    /// Returns a key derived from the content of the event, which may be used as the idempotency
+    /// key when adding the event to a `ScabbardStore`.
+    ///
+    /// The key is the hex-encoded SHA-256 digest of the algorithm name followed by the event's
+    /// fields: a tag byte for the type of the event and of any message it delivers, integers as
+    /// big-endian `u64`s, booleans as a single byte, and byte strings and service IDs prefixed
+    /// with their length as a big-endian `u64`. Events with the same content always have the same
+    /// key.
+    pub fn idempotency_key(&self) -> String {
+        let mut bytes = Vec::new();
+        write_bytes(&mut bytes, self.algorithm_name().as_bytes());
+
+        match self {
+            Self::TwoPhaseCommit(event) => match event {
+                Event::Alarm() => bytes.push(0),
+                Event::Deliver(process, message) => {
+                    bytes.push(1);
+                    write_bytes(&mut bytes, process.as_str().as_bytes());
+                    match message {
+                        Message::VoteRequest(epoch, value) => {
+                            bytes.push(0);
+                            bytes.extend_from_slice(&epoch.to_be_bytes());
+                            write_bytes(&mut bytes, value);
+                        }
+                        Message::Commit(epoch) => {
+                            bytes.push(1);
+                            bytes.extend_from_slice(&epoch.to_be_bytes());
+                        }
+                        Message::Abort(epoch) => {
+                            bytes.push(2);
+                            bytes.extend_from_slice(&epoch.to_be_bytes());
+                        }
+                        Message::DecisionRequest(epoch) => {
+                            bytes.push(3);
+                            bytes.extend_from_slice(&epoch.to_be_bytes());
+                        }
+                        Message::VoteResponse(epoch, vote) => {
+                            bytes.push(4);
+                            bytes.extend_from_slice(&epoch.to_be_bytes());
+                            bytes.push(u8::from(*vote));
+                        }
+                        Message::DecisionAck(epoch) => {
+                            bytes.push(5);
+                            bytes.extend_from_slice(&epoch.to_be_bytes());
+                        }
+                    }
+                }
+                Event::Start(value) => {
+                    bytes.push(2);
+                    write_bytes(&mut bytes, value);
+                }
+                Event::Vote(vote) => {
+                    bytes.push(3);
+                    bytes.push(u8::from(*vote));
+                }
+            },
+        }
+
+        sha256(&bytes)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// Appends the length of `value`, as a big-endian `u64`, followed by `value`
+fn write_bytes(bytes: &mut Vec<u8>, value: &[u8]) {
+    bytes.extend_from_slice(&(value.len() as u64).to_be_bytes());
+    bytes.extend_from_slice(value);
 }
 
 #[cfg(feature = "scabbardv3-consensus")]
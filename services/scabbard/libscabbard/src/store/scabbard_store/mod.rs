@@ -187,6 +187,27 @@ pub trait ScabbardStore {
         event: ConsensusEvent,
     ) -> Result<i64, ScabbardStoreError>;
 
+    /// Add a new consensus event, unless an event with the same idempotency key has already been
+    /// added for the service
+    ///
+    /// Returns the ID of the added event or, if the service already has an event with the same
+    /// key, the ID of that event. Events added without a key are never treated as duplicates, and
+    /// events that have been pruned are no longer considered.
+    ///
+    /// # Arguments
+    ///
+    /// * `service_id` - The combined `CircuitId` and `ServiceId` of the service the event
+    ///    belongs to
+    /// * `event` - The `ConsensusEvent` to be added
+    /// * `idempotency_key` - An optional key identifying the event, such as the key returned by
+    ///    `ConsensusEvent::idempotency_key`
+    fn add_consensus_event_with_key(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        event: ConsensusEvent,
+        idempotency_key: Option<&str>,
+    ) -> Result<i64, ScabbardStoreError>;
+
     /// Update an existing consensus event
     ///
     /// # Arguments
@@ -214,6 +235,23 @@ pub trait ScabbardStore {
         service_id: &FullyQualifiedServiceId,
     ) -> Result<Vec<Identified<ConsensusEvent>>, ScabbardStoreError>;
 
+    /// Remove handled consensus events for a given service with an ID below the given ID,
+    /// returning the number of events removed
+    ///
+    /// Events that have not been executed are always kept, as are events whose actions or
+    /// supervisor notifications have not yet been executed.
+    ///
+    /// # Arguments
+    ///
+    /// * `service_id` - The combined `CircuitId` and `ServiceId` of the service for which events
+    ///    should be pruned
+    /// * `before_id` - Only events with an ID less than this ID are removed
+    fn prune_consensus_events(
+        &self,
+        service_id: &FullyQualifiedServiceId,
+        before_id: i64,
+    ) -> Result<usize, ScabbardStoreError>;
+
     /// Get the current context for a given service
     ///
     /// # Arguments
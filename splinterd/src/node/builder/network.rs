@@ -14,16 +14,25 @@
 
 //! Builder for the NetworkSubsystem
 
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use cylinder::{load_key_from_path, secp256k1::Secp256k1Context, Context, Signer};
 use splinter::error::InternalError;
+use splinter::node_id::NodeId;
 use splinter::transport::multi::MultiTransport;
-use splinter::transport::socket::TcpTransport;
+use splinter::transport::socket::{TcpOptions, TcpTransport};
+use splinter::transport::Transport;
 
 use crate::node::runnable::network::RunnableNetworkSubsystem;
 
 const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// Heartbeat intervals shorter than this are allowed, but are likely a misconfiguration
+const MIN_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Default)]
 pub struct NetworkSubsystemBuilder {
@@ -33,6 +42,8 @@ pub struct NetworkSubsystemBuilder {
     network_endpoints: Option<Vec<String>>,
     signing_context: Option<Arc<Mutex<Box<dyn cylinder::VerifierFactory>>>>,
     signers: Option<Vec<Box<dyn cylinder::Signer>>>,
+    transports: Option<Vec<Box<dyn Transport + Send>>>,
+    tcp_options: Option<TcpOptions>,
 }
 
 impl NetworkSubsystemBuilder {
@@ -40,13 +51,17 @@ impl NetworkSubsystemBuilder {
         Self::default()
     }
 
-    /// Specifies the id for the node. Defaults to a random node id.
+    /// Specifies the id for the node. The id is validated as a [`NodeId`] when the subsystem is
+    /// built.
     pub fn with_node_id(mut self, node_id: String) -> Self {
         self.node_id = Some(node_id);
         self
     }
 
     /// Specifies the heartbeat interval between peer connections. Defaults to 30 seconds.
+    ///
+    /// The subsystem cannot be built with an interval of zero, and a warning is logged for an
+    /// interval of less than one second.
     pub fn with_heartbeat_interval(mut self, heartbeat_interval: Duration) -> Self {
         self.heartbeat_interval = Some(heartbeat_interval);
         self
@@ -80,12 +95,34 @@ impl NetworkSubsystemBuilder {
         self
     }
 
+    /// Specifies the transports the node uses for its network connections, such as TLS or
+    /// Unix-socket transports, in place of the default TCP transport. At least one transport must
+    /// be provided.
+    pub fn with_transports(mut self, transports: Vec<Box<dyn Transport + Send>>) -> Self {
+        self.transports = Some(transports);
+        self
+    }
+
+    /// Specifies the socket options, such as TCP_NODELAY and SO_KEEPALIVE, for the default TCP
+    /// transport. The options are ignored, with a warning, if transports are provided with
+    /// `with_transports`.
+    pub fn with_tcp_options(mut self, tcp_options: TcpOptions) -> Self {
+        self.tcp_options = Some(tcp_options);
+        self
+    }
+
     pub fn build(mut self) -> Result<RunnableNetworkSubsystem, InternalError> {
         let node_id = self.node_id.take().ok_or_else(|| {
             InternalError::with_message(
                 "Cannot build NetworkSubsystem without a node id".to_string(),
             )
         })?;
+        let node_id = NodeId::new(node_id).map_err(|err| {
+            InternalError::from_source_with_prefix(
+                Box::new(err),
+                "Cannot build NetworkSubsystem with an invalid node id".to_string(),
+            )
+        })?;
 
         let signing_context = self.signing_context.take().ok_or_else(|| {
             InternalError::with_message(
@@ -102,11 +139,38 @@ impl NetworkSubsystemBuilder {
             .heartbeat_interval
             .take()
             .unwrap_or(DEFAULT_HEARTBEAT_INTERVAL);
+        if heartbeat_interval == Duration::from_secs(0) {
+            return Err(InternalError::with_message(
+                "Cannot build NetworkSubsystem with a heartbeat interval of zero".to_string(),
+            ));
+        }
+        if heartbeat_interval < MIN_HEARTBEAT_INTERVAL {
+            warn!(
+                "Heartbeat interval of {:?} is less than {:?}; peers will be sent heartbeats \
+                very frequently",
+                heartbeat_interval, MIN_HEARTBEAT_INTERVAL
+            );
+        }
 
-        let transport = MultiTransport::new(vec![Box::new(TcpTransport::default())]);
+        let tcp_options = self.tcp_options.take();
+        let transports = match self.transports.take() {
+            Some(transports) if transports.is_empty() => {
+                return Err(InternalError::with_message(
+                    "Cannot build NetworkSubsystem without any transports".to_string(),
+                ))
+            }
+            Some(transports) => {
+                if tcp_options.is_some() {
+                    warn!("Ignoring TCP options, as the network transports were provided");
+                }
+                transports
+            }
+            None => vec![Box::new(TcpTransport::new(tcp_options.unwrap_or_default()))],
+        };
+        let transport = MultiTransport::new(transports);
 
         Ok(RunnableNetworkSubsystem {
-            node_id,
+            node_id: node_id.to_string(),
             transport,
             heartbeat_interval,
             strict_ref_counts: self.strict_ref_counts,
@@ -116,3 +180,224 @@ impl NetworkSubsystemBuilder {
         })
     }
 }
+
+/// Loads a signer for each private key in the given directory, for use with
+/// `NetworkSubsystemBuilder::with_signers`.
+///
+/// Private keys are the files with a `.priv` extension; any other file is skipped with a warning.
+/// The keys are loaded in file name order, and a key with the same public key as a key that has
+/// already been loaded is skipped. Returns an error if the directory cannot be read or if a
+/// `.priv` file does not contain a valid secp256k1 private key.
+pub fn load_signers_from_dir(key_dir: &Path) -> Result<Vec<Box<dyn Signer>>, InternalError> {
+    let mut paths = fs::read_dir(key_dir)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|err| {
+            InternalError::from_source_with_prefix(
+                Box::new(err),
+                format!("Unable to read key directory {}", key_dir.display()),
+            )
+        })?;
+    paths.sort();
+
+    let context = Secp256k1Context::new();
+    let mut public_keys = HashSet::new();
+    let mut signers = vec![];
+    for path in paths {
+        if !path.is_file() || path.extension() != Some(OsStr::new("priv")) {
+            warn!(
+                "Skipping {}, which is not a private key file",
+                path.display()
+            );
+            continue;
+        }
+
+        let private_key =
+            load_key_from_path(&path).map_err(|err| InternalError::from_source(Box::new(err)))?;
+        let signer = context.new_signer(private_key);
+        let public_key = signer.public_key().map_err(|err| {
+            InternalError::from_source_with_prefix(
+                Box::new(err),
+                format!("Invalid private key in {}", path.display()),
+            )
+        })?;
+
+        if public_keys.insert(public_key) {
+            signers.push(signer);
+        } else {
+            warn!(
+                "Skipping {}, which has the same public key as another key in {}",
+                path.display(),
+                key_dir.display()
+            );
+        }
+    }
+
+    Ok(signers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    use splinter::transport::inproc::InprocTransport;
+    use tempfile::Builder;
+
+    /// Verify that every private key in a directory is loaded once, and that other files and
+    /// duplicate keys are skipped.
+    #[test]
+    fn test_load_signers_from_dir() {
+        let key_dir = Builder::new()
+            .prefix("test_load_signers_from_dir")
+            .tempdir()
+            .expect("Unable to create temp dir");
+
+        let context = Secp256k1Context::new();
+        let key_a = context.new_random_private_key();
+        let key_b = context.new_random_private_key();
+        fs::write(key_dir.path().join("a.priv"), key_a.as_hex()).expect("Unable to write key");
+        fs::write(key_dir.path().join("b.priv"), key_b.as_hex()).expect("Unable to write key");
+        fs::write(key_dir.path().join("c.priv"), key_a.as_hex()).expect("Unable to write key");
+        fs::write(key_dir.path().join("a.pub"), "not a private key").expect("Unable to write file");
+
+        let signers = load_signers_from_dir(key_dir.path()).expect("Unable to load signers");
+        let public_keys = signers
+            .iter()
+            .map(|signer| signer.public_key().expect("Unable to get public key"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            public_keys,
+            vec![
+                context
+                    .get_public_key(&key_a)
+                    .expect("Unable to get public key"),
+                context
+                    .get_public_key(&key_b)
+                    .expect("Unable to get public key"),
+            ]
+        );
+
+        fs::write(key_dir.path().join("d.priv"), "not a key").expect("Unable to write key");
+        assert!(load_signers_from_dir(key_dir.path()).is_err());
+    }
+
+    /// Verify that the subsystem cannot be built with an empty or malformed node id.
+    #[test]
+    fn test_build_invalid_node_id() {
+        for node_id in &["", "node 1", "node/1"] {
+            let signing_context: Arc<Mutex<Box<dyn cylinder::VerifierFactory>>> =
+                Arc::new(Mutex::new(Box::new(Secp256k1Context::new())));
+
+            let result = NetworkSubsystemBuilder::new()
+                .with_node_id(node_id.to_string())
+                .with_signing_context(signing_context)
+                .build();
+
+            assert!(result.is_err(), "node id {:?} was accepted", node_id);
+        }
+    }
+
+    /// Verify that the subsystem cannot be built with a heartbeat interval of zero, and that the
+    /// default interval is used when none is given.
+    #[test]
+    fn test_build_heartbeat_interval() {
+        let signing_context: Arc<Mutex<Box<dyn cylinder::VerifierFactory>>> =
+            Arc::new(Mutex::new(Box::new(Secp256k1Context::new())));
+
+        let result = NetworkSubsystemBuilder::new()
+            .with_node_id("node-1".to_string())
+            .with_signing_context(signing_context.clone())
+            .with_heartbeat_interval(Duration::from_secs(0))
+            .build();
+        assert!(result.is_err());
+
+        let subsystem = NetworkSubsystemBuilder::new()
+            .with_node_id("node-1".to_string())
+            .with_signing_context(signing_context.clone())
+            .build()
+            .expect("Unable to build subsystem");
+        assert_eq!(subsystem.heartbeat_interval, DEFAULT_HEARTBEAT_INTERVAL);
+
+        let subsystem = NetworkSubsystemBuilder::new()
+            .with_node_id("node-1".to_string())
+            .with_signing_context(signing_context)
+            .with_heartbeat_interval(Duration::from_millis(500))
+            .build()
+            .expect("Unable to build subsystem");
+        assert_eq!(subsystem.heartbeat_interval, Duration::from_millis(500));
+    }
+
+    /// Verify that the subsystem can be built with the given transports, but not with an empty
+    /// list of transports.
+    #[test]
+    fn test_build_with_transports() {
+        let signing_context: Arc<Mutex<Box<dyn cylinder::VerifierFactory>>> =
+            Arc::new(Mutex::new(Box::new(Secp256k1Context::new())));
+
+        let result = NetworkSubsystemBuilder::new()
+            .with_node_id("node-1".to_string())
+            .with_signing_context(signing_context.clone())
+            .with_transports(vec![Box::new(TcpTransport::default())])
+            .build();
+        assert!(result.is_ok());
+
+        let result = NetworkSubsystemBuilder::new()
+            .with_node_id("node-1".to_string())
+            .with_signing_context(signing_context)
+            .with_transports(vec![])
+            .build();
+        assert!(result.is_err());
+    }
+
+    /// Verify that the TCP options are used for the default TCP transport, and that they are
+    /// ignored, leaving only the provided transports, when transports are provided.
+    ///
+    /// Whether nodelay and keepalive are set on the transport's connections is verified by the
+    /// `TcpTransport` tests in libsplinter.
+    #[test]
+    fn test_build_with_tcp_options() {
+        let signing_context: Arc<Mutex<Box<dyn cylinder::VerifierFactory>>> =
+            Arc::new(Mutex::new(Box::new(Secp256k1Context::new())));
+        let tcp_options = TcpOptions::new()
+            .with_nodelay(true)
+            .with_keepalive(Duration::from_secs(60));
+
+        let mut subsystem = NetworkSubsystemBuilder::new()
+            .with_node_id("node-1".to_string())
+            .with_signing_context(signing_context.clone())
+            .with_tcp_options(tcp_options.clone())
+            .build()
+            .expect("Unable to build network subsystem");
+        assert!(subsystem.transport.accepts("tcp://127.0.0.1:0"));
+
+        let mut listener = subsystem
+            .transport
+            .listen("tcp://127.0.0.1:0")
+            .expect("Unable to listen");
+        let endpoint = listener.endpoint();
+        let handle = thread::spawn(move || {
+            TcpTransport::default()
+                .connect(&endpoint)
+                .map(|_| ())
+                .expect("Unable to connect")
+        });
+        listener.accept().expect("Unable to accept connection");
+        handle.join().expect("Unable to join connecting thread");
+
+        let subsystem = NetworkSubsystemBuilder::new()
+            .with_node_id("node-1".to_string())
+            .with_signing_context(signing_context)
+            .with_transports(vec![Box::new(InprocTransport::default())])
+            .with_tcp_options(tcp_options)
+            .build()
+            .expect("Unable to build network subsystem");
+        assert!(subsystem.transport.accepts("inproc://node-1"));
+        assert!(!subsystem.transport.accepts("tcp://127.0.0.1:0"));
+    }
+}
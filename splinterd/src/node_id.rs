@@ -15,6 +15,7 @@
 use rand::Rng;
 use splinter::error::InternalError;
 use splinter::node_id::store::NodeIdStore;
+use splinter::node_id::NodeId;
 
 use crate::error::UserError;
 
@@ -33,7 +34,10 @@ fn get_from_store(
     config_node_id: Option<String>,
     node_id_store: Box<dyn NodeIdStore>,
 ) -> Result<String, UserError> {
-    let save_new_node_id = |node_id| -> Result<(), UserError> {
+    let save_new_node_id = |node_id: &str| -> Result<(), UserError> {
+        let node_id = NodeId::new(node_id).map_err(|err| {
+            UserError::InvalidArgument(format!("node_id {} is invalid: {}", node_id, err))
+        })?;
         node_id_store
             .set_node_id(node_id)
             .map_err(|err| UserError::from(InternalError::from_source(Box::new(err))))
@@ -52,12 +56,12 @@ fn get_from_store(
         }
         (Ok(Some(db_node_id)), None) => Ok(db_node_id),
         (Ok(None), Some(config_node_id)) => {
-            save_new_node_id(config_node_id.clone())?;
+            save_new_node_id(&config_node_id)?;
             Ok(config_node_id)
         }
         (Ok(None), None) => {
             let node_id = get_random_node_id();
-            save_new_node_id(node_id.clone())?;
+            save_new_node_id(&node_id)?;
             Ok(node_id)
         }
         (Err(err), _) => Err(UserError::from(InternalError::from_source(Box::new(err)))),